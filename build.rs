@@ -1,17 +1,68 @@
 use std::env;
-use std::fs::File;
+use std::fmt::Write as _;
+use std::fs::{self, File};
 use std::path::Path;
 
 use gl_generator::{Api, Fallbacks, GlobalGenerator, Profile, Registry};
 
+/// Source of the configuration structs.
+const CONFIG_SOURCE: &str = "src/config.rs";
+
 fn main() {
     let dest = env::var("OUT_DIR").unwrap();
     let mut file = File::create(Path::new(&dest).join("gl_bindings.rs")).unwrap();
 
-    Registry::new(Api::Gles2, (2, 0), Profile::Core, Fallbacks::All, [
+    Registry::new(Api::Gles2, (3, 0), Profile::Core, Fallbacks::All, [
         "GL_OES_vertex_array_object",
         "GL_EXT_blend_func_extended",
     ])
     .write_bindings(GlobalGenerator, &mut file)
     .unwrap();
+
+    // Extract config field documentation for the default config template.
+    println!("cargo:rerun-if-changed={CONFIG_SOURCE}");
+    let config = fs::read_to_string(CONFIG_SOURCE).unwrap();
+    fs::write(Path::new(&dest).join("config_docs.rs"), config_docs(&config)).unwrap();
+}
+
+/// Generate a `FieldDoc` array from the doc comments of all config structs.
+fn config_docs(source: &str) -> String {
+    let mut docs = String::from("&[\n");
+    let mut current_struct = None;
+    let mut doc_lines = Vec::new();
+
+    for line in source.lines() {
+        if let Some(name) = line.strip_prefix("pub struct ") {
+            current_struct = name.strip_suffix(" {").map(str::to_owned);
+        } else if line == "}" {
+            current_struct = None;
+        }
+
+        let struct_name = match &current_struct {
+            Some(struct_name) => struct_name,
+            None => continue,
+        };
+
+        let line = line.trim();
+        if let Some(doc) = line.strip_prefix("///") {
+            doc_lines.push(doc.trim().to_owned());
+        } else if line.starts_with("#[") {
+            // Keep the docs of fields with attributes.
+            continue;
+        } else if let Some((name, ty)) =
+            line.strip_prefix("pub ").and_then(|field| field.strip_suffix(',')?.split_once(": "))
+        {
+            let _ = writeln!(
+                docs,
+                "    FieldDoc {{ parent: {struct_name:?}, name: {name:?}, ty: {ty:?}, doc: \
+                 &{doc_lines:?} }},"
+            );
+            doc_lines.clear();
+        } else {
+            doc_lines.clear();
+        }
+    }
+
+    docs.push(']');
+    docs
 }
@@ -1,5 +1,19 @@
 varying mediump vec4 color;
 
+// Pixel position relative to the rectangle's center.
+varying mediump vec2 local;
+
+// Half rectangle width/height and corner radius in pixels.
+varying mediump vec3 shape;
+
 void main() {
-    gl_FragColor = color;
+    // Signed distance to the rounded rectangle's edge.
+    mediump float radius = shape.z;
+    mediump vec2 corner = abs(local) - shape.xy + radius;
+    mediump float distance = length(max(corner, 0.0)) + min(max(corner.x, corner.y), 0.0) - radius;
+
+    // Antialias the edge over a single pixel.
+    mediump float alpha = clamp(0.5 - distance, 0.0, 1.0);
+
+    gl_FragColor = vec4(color.rgb, color.a * alpha);
 }
@@ -1,9 +1,15 @@
 attribute vec2 aPos;
 attribute vec4 aColor;
+attribute vec2 aLocal;
+attribute vec3 aShape;
 
 varying mediump vec4 color;
+varying mediump vec2 local;
+varying mediump vec3 shape;
 
 void main() {
     color = aColor;
+    local = aLocal;
+    shape = aShape;
     gl_Position = vec4(aPos.x, aPos.y, 0.0, 1.0);
 }
@@ -0,0 +1,18 @@
+attribute vec2 aCorner;
+attribute vec4 aRect;
+attribute vec4 aColor;
+attribute vec3 aShape;
+
+varying mediump vec4 color;
+varying mediump vec2 local;
+varying mediump vec3 shape;
+
+void main() {
+    color = aColor;
+    shape = aShape;
+
+    // Corners are relative to the top-left, with Y pointing down.
+    local = (aCorner * 2.0 - 1.0) * aShape.xy;
+    vec2 position = vec2(aRect.x + aCorner.x * aRect.z, aRect.y - aCorner.y * aRect.w);
+    gl_Position = vec4(position, 0.0, 1.0);
+}
@@ -4,6 +4,7 @@
 
 varying mediump vec2 v_UV;
 varying mediump float v_Flags;
+varying mediump float v_Alpha;
 
 uniform sampler2D u_Texture;
 
@@ -11,7 +12,7 @@ void main() {
     if (v_Flags == 1.) {
         // Color glyphs, like emojis.
         gl_FragColor = texture2D(u_Texture, v_UV);
-        gl_SecondaryFragColorEXT = vec4(gl_FragColor.a);
+        gl_SecondaryFragColorEXT = vec4(gl_FragColor.a * v_Alpha);
 
         // Revert alpha premultiplication.
         if (gl_FragColor.a != 0.0) {
@@ -22,7 +23,7 @@ void main() {
     } else {
         // Regular text glyphs.
         mediump vec3 textColor = texture2D(u_Texture, v_UV).rgb;
-        gl_SecondaryFragColorEXT = vec4(textColor, textColor.r);
+        gl_SecondaryFragColorEXT = vec4(textColor, textColor.r) * v_Alpha;
         gl_FragColor = vec4(1.0, 1.0, 1.0, 1.0);
     }
 }
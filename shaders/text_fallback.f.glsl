@@ -0,0 +1,21 @@
+#version 100
+
+varying mediump vec2 v_UV;
+varying mediump float v_Flags;
+varying mediump float v_Alpha;
+
+uniform sampler2D u_Texture;
+
+// Text rendering with premultiplied alpha, for drivers without working
+// GL_EXT_blend_func_extended support.
+void main() {
+    if (v_Flags == 1.) {
+        // Color glyphs, like emojis, are already premultiplied.
+        gl_FragColor = texture2D(u_Texture, v_UV) * v_Alpha;
+    } else {
+        // Regular text glyphs, averaging subpixel coverage.
+        mediump vec3 textColor = texture2D(u_Texture, v_UV).rgb;
+        mediump float coverage = (textColor.r + textColor.g + textColor.b) / 3.0;
+        gl_FragColor = vec4(coverage * v_Alpha);
+    }
+}
@@ -0,0 +1,22 @@
+#version 100
+
+attribute vec2 a_Corner;
+attribute vec4 a_Rect;
+attribute vec4 a_UV;
+attribute float a_Flags;
+attribute float a_Alpha;
+
+varying vec2 v_UV;
+varying float v_Flags;
+varying float v_Alpha;
+
+uniform vec4 u_Projection;
+
+void main() {
+    v_Flags = a_Flags;
+    v_Alpha = a_Alpha;
+    v_UV = a_UV.xy + a_Corner * a_UV.zw;
+    vec2 position = a_Rect.xy + a_Corner * a_Rect.zw;
+    vec2 finalPosition = u_Projection.xy + position * u_Projection.zw;
+    gl_Position = vec4(finalPosition, 0., 1.);
+}
@@ -0,0 +1,43 @@
+//! User-configurable actions.
+
+use catacomb_ipc::{self, DpmsState, IpcMessage};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::module::Toggle;
+use crate::State;
+
+/// Action triggered by a gesture.
+#[derive(Deserialize, Serialize, Copy, Clone, Default, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Do nothing.
+    #[default]
+    None,
+    /// Toggle the flashlight.
+    ToggleFlashlight,
+    /// Turn off the display.
+    DisplayOff,
+}
+
+impl Action {
+    /// Execute the action.
+    pub fn run(self, state: &mut State) {
+        match self {
+            Self::None => return,
+            Self::ToggleFlashlight => {
+                if let Err(err) = state.modules.flashlight.toggle() {
+                    error!(module = "flashlight", "Could not toggle flashlight: {err}");
+                }
+            },
+            Self::DisplayOff => {
+                let msg = IpcMessage::Dpms { state: Some(DpmsState::Off) };
+                let _ = catacomb_ipc::send_message(&msg);
+            },
+        }
+
+        // Confirm the action, since it might not have any visible effect.
+        state.panel().flash();
+        state.request_frame();
+    }
+}
@@ -0,0 +1,152 @@
+//! Command line interface.
+
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::{env, process};
+
+use crate::config::{Config, Mode};
+use crate::ipc::IpcMessage;
+use crate::logging::LogFormat;
+use crate::Size;
+
+/// Command line usage.
+const USAGE: &str = "\
+Usage: epitaph [OPTIONS]
+       epitaph msg <MESSAGE>
+
+Messages:
+  drawer <open|close|toggle>  Change the drawer's visibility
+  module toggle <NAME>        Activate a drawer module
+  modules                     Print the state of all drawer modules as JSON
+  status                      Print drawer visibility and panel sizes as JSON
+
+Options:
+      --screenshot <DIR>  Render the panel and drawer offscreen into PNG files
+      --size <WxH>        Physical screenshot size [default: 720x1440]
+      --scale <FACTOR>    Screenshot scale factor [default: 2]
+      --mode <MODE>       Shown surfaces, \"full\", \"panel\" or \"drawer\"
+      --log-format <FORMAT>
+                          Log output format, \"text\" or \"json\" [default: text]
+      --print-default-config
+                          Print the default configuration file
+  -h, --help              Print help";
+
+/// Command line options.
+#[derive(Debug)]
+pub struct Options {
+    /// Offscreen screenshot options.
+    pub screenshot: Option<ScreenshotOptions>,
+
+    /// Log output format.
+    pub log_format: LogFormat,
+
+    /// Shown surfaces, overriding the configuration file.
+    pub mode: Option<Mode>,
+
+    /// IPC message for the running instance.
+    pub message: Option<IpcMessage>,
+}
+
+impl Options {
+    /// Parse options from the command line arguments.
+    ///
+    /// This will exit the process if the arguments are invalid.
+    pub fn from_args() -> Self {
+        match Self::parse(env::args().skip(1)) {
+            Ok(options) => options,
+            Err(err) => {
+                eprintln!("Error: {err}\n\n{USAGE}");
+                process::exit(2);
+            },
+        }
+    }
+
+    /// Parse options from an argument iterator.
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut screenshot_dir = None;
+        let mut screenshot = ScreenshotOptions::default();
+        let mut log_format = LogFormat::default();
+        let mut mode = None;
+        let mut message = None;
+
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("Missing value for {arg:?}"));
+            match arg.as_str() {
+                "--screenshot" => screenshot_dir = Some(PathBuf::from(value()?)),
+                "--size" => {
+                    let value = value()?;
+                    let (width, height) = value
+                        .split_once('x')
+                        .and_then(|(w, h)| Some((i32::from_str(w).ok()?, i32::from_str(h).ok()?)))
+                        .filter(|(width, height)| *width > 0 && *height > 0)
+                        .ok_or_else(|| format!("Invalid size {value:?}"))?;
+                    screenshot.size = Size::new(width, height);
+                },
+                "--scale" => {
+                    let value = value()?;
+                    screenshot.scale_factor = f64::from_str(&value)
+                        .ok()
+                        .filter(|scale_factor| *scale_factor > 0.)
+                        .ok_or_else(|| format!("Invalid scale factor {value:?}"))?;
+                },
+                "--mode" => {
+                    mode = match value()?.as_str() {
+                        "full" => Some(Mode::Full),
+                        "panel" => Some(Mode::Panel),
+                        "drawer" => Some(Mode::Drawer),
+                        value => return Err(format!("Invalid mode {value:?}")),
+                    };
+                },
+                "--log-format" => {
+                    log_format = match value()?.as_str() {
+                        "text" => LogFormat::Text,
+                        "json" => LogFormat::Json,
+                        value => return Err(format!("Invalid log format {value:?}")),
+                    };
+                },
+                "--print-default-config" => match Config::default_toml() {
+                    Ok(config) => {
+                        print!("{config}");
+                        process::exit(0);
+                    },
+                    Err(err) => {
+                        eprintln!("Error: Could not serialize default config: {err}");
+                        process::exit(1);
+                    },
+                },
+                "msg" => {
+                    message = Some(IpcMessage::parse(&mut args)?);
+                    break;
+                },
+                "-h" | "--help" => {
+                    println!("{USAGE}");
+                    process::exit(0);
+                },
+                _ => return Err(format!("Unexpected argument {arg:?}")),
+            }
+        }
+
+        let screenshot = screenshot_dir.map(|dir| ScreenshotOptions { dir, ..screenshot });
+
+        Ok(Self { screenshot, log_format, mode, message })
+    }
+}
+
+/// Offscreen screenshot options.
+#[derive(Debug)]
+pub struct ScreenshotOptions {
+    /// Output directory for the PNG files.
+    pub dir: PathBuf,
+
+    /// Physical screen size.
+    pub size: Size,
+
+    /// Screen scale factor.
+    pub scale_factor: f64,
+}
+
+impl Default for ScreenshotOptions {
+    fn default() -> Self {
+        Self { size: Size::new(720, 1440), scale_factor: 2., dir: Default::default() }
+    }
+}
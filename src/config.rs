@@ -0,0 +1,757 @@
+//! Configuration options.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::{env, fs, io};
+
+use serde::{Deserialize, Serialize};
+use smithay_client_toolkit::shell::wlr_layer::Layer;
+use tracing::{error, warn};
+
+use crate::action::Action;
+use crate::module::{Alignment, ModuleGroup};
+
+/// Name of the configuration file.
+const CONFIG_FILE: &str = "epitaph.toml";
+
+/// Documentation of all configuration fields, extracted from this file.
+const FIELD_DOCS: &[FieldDoc] = include!(concat!(env!("OUT_DIR"), "/config_docs.rs"));
+
+/// Epitaph configuration.
+#[derive(Deserialize, Serialize, Default, PartialEq, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Surfaces shown by Epitaph.
+    pub mode: Mode,
+
+    /// Replace all animations with instant state changes.
+    ///
+    /// This overrides the individual animation options, keeps notification
+    /// popups in place while swiping and hides the WiFi signal graph.
+    pub reduced_motion: bool,
+
+    /// Panel options.
+    pub panel: PanelConfig,
+
+    /// Drawer options.
+    pub drawer: DrawerConfig,
+
+    /// Font rendering options.
+    pub text: TextConfig,
+
+    /// Custom drawer modules backed by DBus.
+    pub dbus: Vec<DbusModuleConfig>,
+
+    /// Shared libraries with out-of-tree drawer modules.
+    pub plugins: Vec<PathBuf>,
+
+    /// Module-specific options.
+    pub module: ModuleConfigs,
+
+    /// Diagnostic options.
+    pub diagnostics: DiagnosticsConfig,
+
+    /// Log filter directives, overriding `RUST_LOG`.
+    ///
+    /// This is reloaded when receiving SIGHUP.
+    pub log_filter: Option<String>,
+}
+
+impl Config {
+    /// Load the configuration file.
+    ///
+    /// This will fall back to the default configuration if the file is missing
+    /// or invalid.
+    pub fn load() -> Self {
+        let path = match config_path() {
+            Some(path) => path,
+            None => return Self::default(),
+        };
+
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Self::default(),
+            Err(err) => {
+                error!(module = "config", "Could not read config {path:?}: {err}");
+                return Self::default();
+            },
+        };
+
+        let mut config = match Self::from_toml(&content) {
+            Ok(config) => config,
+            Err(err) => {
+                error!(module = "config", "Invalid config {path:?}: {err}");
+                return Self::default();
+            },
+        };
+
+        // Disable all individual animations.
+        if config.reduced_motion {
+            config.module.battery.charging_animation = false;
+            config.panel.attention_pulse = false;
+            config.panel.icon_fade = false;
+        }
+
+        config
+    }
+
+    /// Parse the configuration from TOML.
+    ///
+    /// Deprecated top-level module sections are moved to `[module.<name>]`,
+    /// with options in `[module.<name>]` taking precedence. The same applies
+    /// to `drawer.self_metrics`, which is read as `diagnostics.enabled`.
+    fn from_toml(content: &str) -> Result<Self, toml::de::Error> {
+        let mut table: toml::Table = toml::from_str(content)?;
+
+        for name in ["battery", "flashlight"] {
+            let deprecated = match table.remove(name) {
+                Some(toml::Value::Table(deprecated)) => deprecated,
+                Some(value) => {
+                    table.insert(name.into(), value);
+                    continue;
+                },
+                None => continue,
+            };
+
+            warn!(module = "config", "[{name}] is deprecated, use [module.{name}] instead");
+
+            let modules = table.entry("module").or_insert_with(|| toml::Table::new().into());
+            if let Some(modules) = modules.as_table_mut() {
+                let module = modules.entry(name).or_insert_with(|| toml::Table::new().into());
+                if let Some(module) = module.as_table_mut() {
+                    for (key, value) in deprecated {
+                        module.entry(key).or_insert(value);
+                    }
+                }
+            }
+        }
+
+        let drawer = table.get_mut("drawer").and_then(|drawer| drawer.as_table_mut());
+        if let Some(self_metrics) = drawer.and_then(|drawer| drawer.remove("self_metrics")) {
+            warn!(module = "config", "drawer.self_metrics is deprecated, use diagnostics.enabled");
+
+            let diagnostics =
+                table.entry("diagnostics").or_insert_with(|| toml::Table::new().into());
+            if let Some(diagnostics) = diagnostics.as_table_mut() {
+                diagnostics.entry("enabled").or_insert(self_metrics);
+            }
+        }
+
+        Self::deserialize(table)
+    }
+
+    /// Default configuration as commented TOML.
+    pub fn default_toml() -> Result<String, toml::ser::Error> {
+        let table = toml::Table::try_from(Self::default())?;
+        let mut toml = String::new();
+        write_table(&mut toml, "", "Config", &table);
+        Ok(toml)
+    }
+}
+
+/// Module-specific options.
+#[derive(Deserialize, Serialize, Default, PartialEq, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModuleConfigs {
+    /// Battery options.
+    pub battery: BatteryModuleConfig,
+
+    /// Cellular options.
+    pub cellular: CellularModuleConfig,
+
+    /// Clock options.
+    pub clock: ClockModuleConfig,
+
+    /// Flashlight options.
+    pub flashlight: FlashlightModuleConfig,
+}
+
+/// Options of the `[module.battery]` section.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct BatteryModuleConfig {
+    /// Show the capacity as text instead of an icon.
+    pub show_percentage: bool,
+
+    /// Animate the battery icon while charging.
+    pub charging_animation: bool,
+
+    /// Capacity below which the power saver profile is activated.
+    ///
+    /// The previous profile is restored once the battery starts charging.
+    pub power_saver_threshold: Option<u8>,
+
+    /// Charger input current limit in µA while slow charging.
+    pub slow_charge_limit: u32,
+
+    /// Capacity at which the battery is considered critical.
+    pub critical_capacity: u8,
+
+    /// Action taken once the battery is critical and not charging.
+    pub critical_action: CriticalAction,
+
+    /// Seconds before the critical action is taken, giving time to cancel it.
+    pub critical_grace: u64,
+}
+
+impl Default for BatteryModuleConfig {
+    fn default() -> Self {
+        Self {
+            show_percentage: false,
+            charging_animation: true,
+            power_saver_threshold: None,
+            slow_charge_limit: 500_000,
+            critical_capacity: 5,
+            critical_action: CriticalAction::None,
+            critical_grace: 60,
+        }
+    }
+}
+
+/// Action taken on critical battery.
+#[derive(Deserialize, Serialize, Copy, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum CriticalAction {
+    None,
+    Suspend,
+    Hibernate,
+    Poweroff,
+}
+
+impl CriticalAction {
+    /// `systemctl` command performing the action.
+    pub fn systemctl_verb(&self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Suspend => Some("suspend"),
+            Self::Hibernate => Some("hibernate"),
+            Self::Poweroff => Some("poweroff"),
+        }
+    }
+
+    /// Progressive description of the action.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::None => "",
+            Self::Suspend => "Suspending",
+            Self::Hibernate => "Hibernating",
+            Self::Poweroff => "Powering off",
+        }
+    }
+}
+
+/// Options of the `[module.cellular]` section.
+#[derive(Deserialize, Serialize, Default, Clone, PartialEq, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct CellularModuleConfig {
+    /// Signal strength shown as text next to the icon.
+    pub signal_text: SignalText,
+}
+
+/// Options of the `[module.clock]` section.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClockModuleConfig {
+    /// Clock format, using strftime syntax.
+    pub format: String,
+
+    /// Clock format while seconds are shown after tapping the clock.
+    pub seconds_format: String,
+}
+
+impl Default for ClockModuleConfig {
+    fn default() -> Self {
+        Self { format: "%H:%M".into(), seconds_format: "%H:%M:%S".into() }
+    }
+}
+
+/// Options of the `[module.flashlight]` section.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct FlashlightModuleConfig {
+    /// Intensity while running on battery, relative to the maximum brightness.
+    pub battery_intensity: f64,
+
+    /// Intensity while charging, relative to the maximum brightness.
+    pub charging_intensity: f64,
+}
+
+impl Default for FlashlightModuleConfig {
+    fn default() -> Self {
+        Self { battery_intensity: 0.5, charging_intensity: 1. }
+    }
+}
+
+/// Panel options.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct PanelConfig {
+    /// Module priorities, overriding the defaults.
+    ///
+    /// Modules with the lowest priority are hidden first when the panel is
+    /// overflowing.
+    pub priorities: HashMap<String, u8>,
+
+    /// Panel height in pixels with a scale factor of 1.
+    pub height: u32,
+
+    /// Padding between modules.
+    pub module_padding: f64,
+
+    /// Padding to the screen edges.
+    pub edge_padding: f64,
+
+    /// Padding overrides for center-aligned modules.
+    pub center: PaddingOverrides,
+
+    /// Padding overrides for right-aligned modules.
+    pub right: PaddingOverrides,
+
+    /// Font for text modules, also used by the drawer and notification popups.
+    pub font: FontConfig,
+
+    /// Module fonts, overriding the panel font.
+    pub fonts: HashMap<String, FontConfig>,
+
+    /// Seconds before data of a stopped module backend is shown as stale.
+    pub stale_timeout: u64,
+
+    /// Hide the panel while the focused window is fullscreen.
+    pub auto_conceal: bool,
+
+    /// Layer shell layer of the panel.
+    pub layer: SurfaceLayer,
+
+    /// Reserve space for the panel, preventing windows from overlapping it.
+    pub exclusive_zone: bool,
+
+    /// Cross-fade between icons when a module's icon changes.
+    pub icon_fade: bool,
+
+    /// Pulse modules requesting attention, like a critically low battery.
+    pub attention_pulse: bool,
+
+    /// Action when swiping left on the panel.
+    pub swipe_left: Action,
+
+    /// Action when swiping right on the panel.
+    pub swipe_right: Action,
+
+    /// Connectivity icons shown while both WiFi and cellular are connected.
+    pub connectivity_icons: ConnectivityIcons,
+}
+
+impl Default for PanelConfig {
+    fn default() -> Self {
+        Self {
+            height: 20,
+            module_padding: 5.,
+            edge_padding: 5.,
+            priorities: Default::default(),
+            center: Default::default(),
+            right: Default::default(),
+            fonts: Default::default(),
+            font: Default::default(),
+            stale_timeout: 60,
+            auto_conceal: true,
+            layer: SurfaceLayer::Bottom,
+            exclusive_zone: true,
+            icon_fade: false,
+            attention_pulse: true,
+            swipe_left: Action::None,
+            swipe_right: Action::None,
+            connectivity_icons: ConnectivityIcons::Both,
+        }
+    }
+}
+
+impl PanelConfig {
+    /// Padding between modules with the specified alignment.
+    pub fn module_padding(&self, alignment: Alignment) -> f64 {
+        self.overrides(alignment).module_padding.unwrap_or(self.module_padding)
+    }
+
+    /// Screen edge padding of modules with the specified alignment.
+    pub fn edge_padding(&self, alignment: Alignment) -> f64 {
+        self.overrides(alignment).edge_padding.unwrap_or(self.edge_padding)
+    }
+
+    /// Font of the module with the specified name.
+    pub fn font(&self, name: &str) -> &FontConfig {
+        self.fonts.get(name).unwrap_or(&self.font)
+    }
+
+    /// Get padding overrides for an alignment.
+    fn overrides(&self, alignment: Alignment) -> &PaddingOverrides {
+        match alignment {
+            Alignment::Center => &self.center,
+            Alignment::Right => &self.right,
+        }
+    }
+}
+
+/// Alignment-specific panel padding.
+#[derive(Deserialize, Serialize, Default, Clone, PartialEq, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct PaddingOverrides {
+    /// Padding between modules.
+    pub module_padding: Option<f64>,
+
+    /// Padding to the screen edges.
+    pub edge_padding: Option<f64>,
+}
+
+/// Font options.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Hash, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct FontConfig {
+    /// Font family name.
+    pub family: String,
+
+    /// Use bold font weight.
+    pub bold: bool,
+
+    /// Use italic font slant.
+    pub italic: bool,
+}
+
+impl Default for FontConfig {
+    fn default() -> Self {
+        Self { family: String::from("Sans"), bold: false, italic: false }
+    }
+}
+
+/// Diagnostic options.
+#[derive(Deserialize, Serialize, Default, Copy, Clone, PartialEq, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiagnosticsConfig {
+    /// Collect diagnostics about epitaph itself.
+    ///
+    /// This logs the presentation latency of every frame at the debug level,
+    /// and tapping the drawer's diagnostics shows epitaph's own memory, thread
+    /// and file descriptor usage.
+    pub enabled: bool,
+}
+
+/// Font rendering options.
+///
+/// Unset options fall back to the system's fontconfig settings.
+#[derive(Deserialize, Serialize, Default, Copy, Clone, PartialEq, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct TextConfig {
+    /// Enable font hinting.
+    pub hinting: Option<bool>,
+
+    /// Use FreeType's autohinter instead of the font's hinting instructions.
+    pub autohint: Option<bool>,
+
+    /// Amount of hinting applied.
+    pub hint_style: Option<HintStyle>,
+
+    /// Subpixel order of the display.
+    pub subpixel: Option<Subpixel>,
+
+    /// Blend text using `GL_EXT_blend_func_extended`.
+    ///
+    /// This is used by default if the driver supports it. Disabling it renders
+    /// text with grayscale antialiasing, which avoids invisible text on drivers
+    /// with broken support.
+    pub dual_source_blending: Option<bool>,
+}
+
+impl TextConfig {
+    /// Check if any fontconfig option was changed.
+    pub fn is_empty(&self) -> bool {
+        self.hinting.is_none()
+            && self.autohint.is_none()
+            && self.hint_style.is_none()
+            && self.subpixel.is_none()
+    }
+}
+
+/// Surfaces shown by Epitaph.
+#[derive(Deserialize, Serialize, Copy, Clone, Default, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// Panel with a drawer opened by dragging it down.
+    #[default]
+    Full,
+    /// Panel without a drawer.
+    Panel,
+    /// Drawer opened through DBus, without a panel.
+    Drawer,
+}
+
+/// Layer shell surface layer.
+#[derive(Deserialize, Serialize, Copy, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum SurfaceLayer {
+    Background,
+    Bottom,
+    Top,
+    /// Above fullscreen windows.
+    Overlay,
+}
+
+impl From<SurfaceLayer> for Layer {
+    fn from(layer: SurfaceLayer) -> Self {
+        match layer {
+            SurfaceLayer::Background => Layer::Background,
+            SurfaceLayer::Bottom => Layer::Bottom,
+            SurfaceLayer::Top => Layer::Top,
+            SurfaceLayer::Overlay => Layer::Overlay,
+        }
+    }
+}
+
+/// Connectivity icon policy while both WiFi and cellular are connected.
+#[derive(Deserialize, Serialize, Copy, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectivityIcons {
+    /// Show both icons.
+    Both,
+    /// Hide the cellular icon.
+    PreferWifi,
+    /// Show only the icon of the connection with the default route.
+    DefaultRoute,
+}
+
+/// Cellular signal strength text format.
+#[derive(Deserialize, Serialize, Default, Copy, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum SignalText {
+    /// Show only the icon.
+    #[default]
+    None,
+    /// Signal quality in percent.
+    Percentage,
+    /// Number of signal bars, out of five.
+    Bars,
+}
+
+/// Font hinting strength.
+#[derive(Deserialize, Serialize, Copy, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum HintStyle {
+    None,
+    Slight,
+    Medium,
+    Full,
+}
+
+/// Display subpixel order.
+#[derive(Deserialize, Serialize, Copy, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Subpixel {
+    /// Grayscale antialiasing.
+    None,
+    Rgb,
+    Bgr,
+    Vrgb,
+    Vbgr,
+}
+
+/// Custom DBus drawer module.
+///
+/// The module displays a property's value and calls a method on tap.
+#[derive(Deserialize, Serialize, Default, Clone, PartialEq, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct DbusModuleConfig {
+    /// Use the system bus instead of the session bus.
+    pub system_bus: bool,
+
+    /// Bus name of the service.
+    pub service: String,
+
+    /// Object path.
+    pub path: String,
+
+    /// Interface of the method and property.
+    pub interface: String,
+
+    /// Method called without arguments when the module is tapped.
+    pub method: Option<String>,
+
+    /// Property displayed as text.
+    pub property: String,
+}
+
+/// Drawer options.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct DrawerConfig {
+    /// Padding between modules.
+    pub module_padding: f64,
+
+    /// Padding to the screen edges.
+    pub edge_padding: f64,
+
+    /// Opacity of the black overlay behind the open drawer.
+    pub dim_alpha: f32,
+
+    /// Corner radius of module backgrounds.
+    pub corner_radius: f64,
+
+    /// Show names below toggle buttons.
+    pub titles: bool,
+
+    /// Layer shell layer of the drawer.
+    pub layer: SurfaceLayer,
+
+    /// Cover the space reserved by other surfaces, like the panel.
+    pub overlap_panels: bool,
+
+    /// Module groups shown under a header, in order.
+    ///
+    /// Modules of other groups are shown last, without a header.
+    pub groups: Vec<ModuleGroup>,
+
+    /// Show a text summary of the panel modules above the drawer modules.
+    pub summary: bool,
+
+    /// Emergency contact and owner information, shown at the top of the drawer.
+    ///
+    /// Every entry is rendered as a separate line in bold.
+    pub emergency_info: Vec<String>,
+
+    /// List open windows in the drawer, tap to focus and swipe to close them.
+    pub task_switcher: bool,
+}
+
+impl Default for DrawerConfig {
+    fn default() -> Self {
+        Self {
+            module_padding: 16.,
+            edge_padding: 24.,
+            dim_alpha: 0.5,
+            corner_radius: 8.,
+            titles: true,
+            groups: Default::default(),
+            layer: SurfaceLayer::Overlay,
+            overlap_panels: true,
+            summary: true,
+            emergency_info: Default::default(),
+            task_switcher: false,
+        }
+    }
+}
+
+/// Documentation of a configuration struct field.
+struct FieldDoc {
+    /// Name of the struct containing the field.
+    parent: &'static str,
+    /// Field name.
+    name: &'static str,
+    /// Field type.
+    ty: &'static str,
+    /// Doc comment lines.
+    doc: &'static [&'static str],
+}
+
+/// Write the fields of a config struct as commented TOML.
+///
+/// Unset optional fields are omitted, since they have no default value.
+fn write_table(toml: &mut String, path: &str, ty: &str, table: &toml::Table) {
+    let fields = FIELD_DOCS.iter().filter(|field| field.parent == ty);
+
+    // Write plain values first, since they cannot follow nested tables.
+    let mut tables = Vec::new();
+    for field in fields {
+        match table.get(field.name) {
+            Some(toml::Value::Table(nested)) => tables.push((field, nested)),
+            Some(value) => {
+                write_doc(toml, field.doc);
+                let _ = writeln!(toml, "{} = {value}\n", field.name);
+            },
+            None => (),
+        }
+    }
+
+    for (field, nested) in tables {
+        let path =
+            if path.is_empty() { field.name.into() } else { format!("{path}.{}", field.name) };
+        write_doc(toml, field.doc);
+        let _ = writeln!(toml, "[{path}]");
+
+        // Maps have no field docs, so their entries are written directly.
+        if field.ty.starts_with("HashMap<") {
+            for (key, value) in nested {
+                let _ = writeln!(toml, "{key} = {value}");
+            }
+            toml.push('\n');
+        } else {
+            write_table(toml, &path, field.ty, nested);
+        }
+    }
+}
+
+/// Write doc comment lines as TOML comments.
+fn write_doc(toml: &mut String, doc: &[&str]) {
+    for line in doc {
+        if line.is_empty() {
+            toml.push_str("#\n");
+        } else {
+            let _ = writeln!(toml, "# {line}");
+        }
+    }
+}
+
+/// Location of the configuration file.
+fn config_path() -> Option<PathBuf> {
+    let config_home = match env::var_os("XDG_CONFIG_HOME") {
+        Some(config_home) => PathBuf::from(config_home),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+
+    Some(config_home.join("epitaph").join(CONFIG_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config_round_trip() {
+        let toml = Config::default_toml().unwrap();
+        let config = Config::from_toml(&toml).unwrap();
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn default_toml_contains_all_fields() {
+        let toml: toml::Table = toml::from_str(&Config::default_toml().unwrap()).unwrap();
+        assert_eq!(toml, toml::Table::try_from(Config::default()).unwrap());
+    }
+
+    #[test]
+    fn all_fields_documented() {
+        for field in FIELD_DOCS {
+            assert!(!field.doc.is_empty(), "{}::{} has no docs", field.parent, field.name);
+        }
+    }
+
+    #[test]
+    fn deprecated_module_sections() {
+        let toml = "[battery]\nshow_percentage = true\ncritical_grace = 5\n\n[module.battery]\n\
+                    critical_grace = 10\n";
+        let config = Config::from_toml(toml).unwrap();
+        assert!(config.module.battery.show_percentage);
+        assert_eq!(config.module.battery.critical_grace, 10);
+    }
+
+    #[test]
+    fn deprecated_self_metrics() {
+        let config = Config::from_toml("[drawer]\nself_metrics = true\n").unwrap();
+        assert!(config.diagnostics.enabled);
+
+        let toml = "[drawer]\nself_metrics = true\n\n[diagnostics]\nenabled = false\n";
+        assert!(!Config::from_toml(toml).unwrap().diagnostics.enabled);
+    }
+
+    #[test]
+    fn unknown_module_options() {
+        assert!(Config::from_toml("[module.clock]\nformt = \"%H\"\n").is_err());
+        assert!(Config::from_toml("[module.unknown]\n").is_err());
+    }
+}
@@ -0,0 +1,85 @@
+//! User-defined DBus integrations.
+
+use std::error::Error;
+use std::thread;
+
+use calloop::channel::Channel;
+use tokio::runtime::Builder;
+use tracing::error;
+use zbus::export::futures_util::stream::StreamExt;
+use zbus::zvariant::{OwnedValue, Value};
+use zbus::{Connection, Proxy};
+
+use crate::config::DbusModuleConfig;
+use crate::supervisor::{self, BackendEvent, BackendSender};
+
+/// Call the configured method without any arguments.
+pub fn call_method(config: DbusModuleConfig) {
+    // Async function for calling the method.
+    let call = |config: DbusModuleConfig| async move {
+        let proxy = proxy(&config).await?;
+        if let Some(method) = &config.method {
+            if let Err(err) = proxy.call_method(method.as_str(), &()).await {
+                error!(module = "custom", "DBus method {method:?} failed: {err}");
+            }
+        }
+        Ok::<(), zbus::Error>(())
+    };
+
+    // Spawn async executor for the method call on a new thread.
+    thread::spawn(move || {
+        let mut builder = Builder::new_current_thread();
+        let runtime = builder.enable_all().build().expect("create tokio runtime");
+        let service = config.service.clone();
+        if let Err(err) = runtime.block_on(call(config)) {
+            error!(module = "custom", "Could not connect to {service:?}: {err}");
+        }
+    });
+}
+
+/// Get calloop channel for changes of the configured property.
+pub fn property_listener(config: DbusModuleConfig) -> Channel<BackendEvent<String>> {
+    supervisor::spawn("custom DBus module", move |tx| run_dbus_loop(config.clone(), tx))
+}
+
+/// Run the DBus property event loop.
+async fn run_dbus_loop(
+    config: DbusModuleConfig,
+    tx: BackendSender<String>,
+) -> Result<(), Box<dyn Error>> {
+    let proxy = proxy(&config).await?;
+
+    // Send the current value before waiting for changes.
+    let value: OwnedValue = proxy.get_property(&config.property).await?;
+    tx.send(format_value(&value))?;
+
+    let mut property_stream = proxy.receive_property_changed::<OwnedValue>(&config.property).await;
+    while let Some(change) = property_stream.next().await {
+        let value: OwnedValue = change.get().await?;
+        tx.send(format_value(&value))?;
+    }
+
+    Ok(())
+}
+
+/// Create a proxy for the configured DBus object.
+async fn proxy(config: &DbusModuleConfig) -> zbus::Result<Proxy<'_>> {
+    let connection =
+        if config.system_bus { Connection::system().await? } else { Connection::session().await? };
+
+    Proxy::new(
+        &connection,
+        config.service.as_str(),
+        config.path.as_str(),
+        config.interface.as_str(),
+    )
+    .await
+}
+
+/// Convert a property value to text.
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::Str(text) => text.to_string(),
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,104 @@
+//! Feedbackd DBus interface.
+
+use std::error::Error;
+use std::thread;
+
+use calloop::channel::Channel;
+use tokio::runtime::Builder;
+use tracing::error;
+use zbus::export::futures_util::stream::StreamExt;
+use zbus::{proxy, Connection};
+
+use crate::supervisor::{self, BackendEvent, BackendSender};
+
+/// Feedbackd event feedback profile.
+#[derive(PartialEq, Eq, Default, Copy, Clone, Debug)]
+pub enum FeedbackProfile {
+    /// Sound, vibration and LED feedback.
+    #[default]
+    Full,
+    /// Vibration and LED feedback only.
+    Quiet,
+    /// LED feedback only.
+    Silent,
+}
+
+impl FeedbackProfile {
+    /// Get the feedbackd name of the profile.
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Full => "full",
+            Self::Quiet => "quiet",
+            Self::Silent => "silent",
+        }
+    }
+
+    /// Parse feedbackd profile name.
+    fn from_str(profile: &str) -> Option<Self> {
+        match profile {
+            "full" => Some(Self::Full),
+            "quiet" => Some(Self::Quiet),
+            "silent" => Some(Self::Silent),
+            _ => None,
+        }
+    }
+}
+
+/// Set the global feedbackd profile.
+pub fn set_profile(profile: FeedbackProfile) {
+    // Async function for updating the feedback profile.
+    let set_feedback_profile = |profile: FeedbackProfile| async move {
+        let connection = Connection::session().await?;
+        let feedback = FeedbackProxy::new(&connection).await?;
+        if let Err(err) = feedback.set_profile(profile.as_str()).await {
+            error!(module = "feedbackd", "Feedback profile change failed: {err}");
+        }
+        Ok::<(), zbus::Error>(())
+    };
+
+    // Spawn async executor for the profile update on a new thread.
+    thread::spawn(move || {
+        let mut builder = Builder::new_current_thread();
+        let runtime = builder.enable_all().build().expect("create tokio runtime");
+        if let Err(err) = runtime.block_on(set_feedback_profile(profile)) {
+            error!(module = "feedbackd", "Could not connect to feedbackd: {err}");
+        }
+    });
+}
+
+/// Get calloop channel for feedback profile changes.
+pub fn profile_listener() -> Channel<BackendEvent<FeedbackProfile>> {
+    supervisor::spawn("feedbackd", run_dbus_loop)
+}
+
+/// Run the DBus feedback profile event loop.
+async fn run_dbus_loop(tx: BackendSender<FeedbackProfile>) -> Result<(), Box<dyn Error>> {
+    let connection = Connection::session().await?;
+    let feedback = FeedbackProxy::new(&connection).await?;
+
+    // Stream yields the current value first, followed by all changes.
+    let mut profile_stream = feedback.receive_profile_changed().await;
+    while let Some(profile) = profile_stream.next().await {
+        let profile = profile.get().await?;
+        if let Some(profile) = FeedbackProfile::from_str(&profile) {
+            tx.send(profile)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[proxy(
+    interface = "org.sigxcpu.Feedback",
+    default_service = "org.sigxcpu.Feedback",
+    default_path = "/org/sigxcpu/Feedback"
+)]
+trait Feedback {
+    /// The currently active global feedback profile.
+    #[zbus(property)]
+    fn profile(&self) -> zbus::Result<String>;
+
+    /// Set the global feedback profile.
+    #[zbus(property)]
+    fn set_profile(&self, profile: &str) -> zbus::Result<()>;
+}
@@ -0,0 +1,91 @@
+//! Logind DBus interface.
+
+use std::error::Error;
+use std::thread;
+
+use calloop::channel::Channel;
+use tokio::runtime::Builder;
+use tokio::sync::oneshot::{self, Sender};
+use tracing::error;
+use zbus::export::futures_util::stream::StreamExt;
+use zbus::zvariant::OwnedFd;
+use zbus::{proxy, Connection};
+
+use crate::supervisor::{self, BackendEvent, BackendSender};
+
+/// Lock preventing system suspend.
+///
+/// The lock is released when this is dropped.
+#[derive(Debug)]
+pub struct SleepInhibitor {
+    _release: Sender<()>,
+}
+
+/// Prevent system suspend until the returned inhibitor is dropped.
+pub fn inhibit_sleep(why: &'static str) -> SleepInhibitor {
+    let (release_tx, release_rx) = oneshot::channel();
+
+    // Spawn async executor holding the inhibitor on a new thread.
+    thread::spawn(move || {
+        let mut builder = Builder::new_current_thread();
+        let runtime = builder.enable_all().build().expect("create tokio runtime");
+        runtime.block_on(async move {
+            let inhibitor = match take_inhibitor(why).await {
+                Ok(inhibitor) => inhibitor,
+                Err(err) => {
+                    error!(module = "logind", "Could not inhibit suspend: {err}");
+                    return;
+                },
+            };
+
+            // Hold the lock until the inhibitor is dropped.
+            let _ = release_rx.await;
+            drop(inhibitor);
+        });
+    });
+
+    SleepInhibitor { _release: release_tx }
+}
+
+/// Get calloop channel for system resume events.
+pub fn resume_listener() -> Channel<BackendEvent<()>> {
+    supervisor::spawn("logind", run_dbus_loop)
+}
+
+/// Run the DBus resume event loop.
+async fn run_dbus_loop(tx: BackendSender<()>) -> Result<(), Box<dyn Error>> {
+    let connection = Connection::system().await?;
+    let manager = ManagerProxy::new(&connection).await?;
+
+    // Report the end of every suspend.
+    let mut sleep_stream = manager.receive_prepare_for_sleep().await?;
+    while let Some(signal) = sleep_stream.next().await {
+        if !signal.args()?.start {
+            tx.send(())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Take a logind sleep inhibitor lock.
+async fn take_inhibitor(why: &str) -> zbus::Result<OwnedFd> {
+    let connection = Connection::system().await?;
+    let manager = ManagerProxy::new(&connection).await?;
+    manager.inhibit("sleep", "Epitaph", why, "block").await
+}
+
+#[proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait Manager {
+    /// Take an inhibitor lock, which is released when the file descriptor is
+    /// closed.
+    fn inhibit(&self, what: &str, who: &str, why: &str, mode: &str) -> zbus::Result<OwnedFd>;
+
+    /// Emitted before suspend with `start` set, and after resume without it.
+    #[zbus(signal)]
+    fn prepare_for_sleep(&self, start: bool) -> zbus::Result<()>;
+}
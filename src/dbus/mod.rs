@@ -1,4 +1,11 @@
 //! DBus interface.
 
+pub mod custom;
+pub mod feedbackd;
+pub mod logind;
 pub mod modem_manager;
+pub mod mpris;
 pub mod network_manager;
+pub mod notifications;
+pub mod power_profiles;
+pub mod status;
@@ -1,16 +1,20 @@
 //! ModemManager DBus interface.
 
+use std::collections::HashMap;
 use std::error::Error;
 use std::thread;
 
-use calloop::channel::{self, Channel, Sender};
+use calloop::channel::Channel;
 use tokio::runtime::Builder;
+use tracing::{error, warn};
 use zbus::export::futures_util::stream::StreamExt;
 use zbus::fdo::ObjectManagerProxy;
 use zbus::proxy::PropertyStream;
-use zbus::zvariant::{OwnedObjectPath, OwnedValue, Type};
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Type, Value};
 use zbus::{proxy, Connection};
 
+use crate::supervisor::{self, BackendEvent, BackendSender};
+
 /// Cellular connection status.
 #[derive(PartialEq, Eq, Default, Copy, Clone, Debug)]
 pub struct ModemConnection {
@@ -22,6 +26,21 @@ pub struct ModemConnection {
 
     /// Modem state is at least 'registered'.
     pub registered: bool,
+
+    /// Network registration restrictions.
+    pub restriction: Option<Restriction>,
+
+    /// Modem is present and reporting its state.
+    pub available: bool,
+
+    /// Allowed access technologies.
+    pub mode: NetworkMode,
+
+    /// Number of SIM slots.
+    pub sim_slots: usize,
+
+    /// Active SIM slot number, starting at 1.
+    pub primary_sim_slot: u32,
 }
 
 impl ModemConnection {
@@ -32,25 +51,191 @@ impl ModemConnection {
 
         // Get 3gpp registration status.
         let registration_state = modem3gpp.registration_state().await.ok()?;
-        let registered = registration_state > RegistrationState::Idle;
+        let registered = registration_state.is_registered();
+        let restriction = match registration_state {
+            RegistrationState::Denied => Some(Restriction::Denied),
+            RegistrationState::EmergencyOnly => Some(Restriction::EmergencyOnly),
+            _ => None,
+        };
 
         // Get modem status.
         let modem_state = modem.modem_state().await.ok()?;
         let enabled = modem_state >= ModemState::Enabled;
 
-        Some(Self { strength, registered, enabled })
+        // Get allowed access technologies.
+        let current_modes = modem.current_modes().await;
+        let mode = current_modes.map(|(allowed, _)| NetworkMode::from_allowed(allowed));
+        let mode = mode.unwrap_or_default();
+
+        // Get SIM slots, which are empty for modems without multiple slots.
+        let sim_slots = modem.sim_slots().await.map_or(0, |slots| slots.len());
+        let primary_sim_slot = modem.primary_sim_slot().await.unwrap_or_default();
+
+        Some(Self {
+            strength,
+            registered,
+            restriction,
+            enabled,
+            mode,
+            sim_slots,
+            primary_sim_slot,
+            available: true,
+        })
+    }
+}
+
+/// Connection profiles of the modem.
+#[derive(PartialEq, Eq, Default, Clone, Debug)]
+pub struct ModemProfiles {
+    /// Profiles stored on the modem.
+    pub profiles: Vec<ModemProfile>,
+
+    /// ID of the profile used by the connected bearer.
+    pub active: Option<i32>,
+}
+
+impl ModemProfiles {
+    /// Get the current connection profiles.
+    async fn new(
+        connection: &Connection,
+        modem: &ModemProxy<'_>,
+        profile_manager: &ProfileManagerProxy<'_>,
+    ) -> Self {
+        let profiles = match profile_manager.list().await {
+            Ok(profiles) => profiles.iter().filter_map(ModemProfile::from_properties).collect(),
+            Err(_) => return Self::default(),
+        };
+
+        // Find the profile of the first connected bearer.
+        let mut active = None;
+        for path in modem.bearers().await.unwrap_or_default() {
+            let bearer = match BearerProxy::builder(connection).path(path) {
+                Ok(builder) => builder.build().await,
+                Err(err) => Err(err),
+            };
+            let bearer = match bearer {
+                Ok(bearer) => bearer,
+                Err(_) => continue,
+            };
+
+            if bearer.connected().await.unwrap_or_default() {
+                let properties = bearer.properties().await.unwrap_or_default();
+                active = properties.get("profile-id").and_then(|id| id.downcast_ref().ok());
+                break;
+            }
+        }
+
+        Self { profiles, active }
+    }
+}
+
+/// Connection profile stored on the modem.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ModemProfile {
+    /// ModemManager profile ID.
+    pub id: i32,
+
+    /// Profile name, falling back to the APN.
+    pub name: String,
+}
+
+impl ModemProfile {
+    /// Parse a profile from its ModemManager properties.
+    fn from_properties(properties: &HashMap<String, OwnedValue>) -> Option<Self> {
+        let id = properties.get("profile-id")?.downcast_ref().ok()?;
+
+        let string = |key: &str| {
+            let value = properties.get(key)?.downcast_ref::<String>().ok()?;
+            (!value.is_empty()).then_some(value)
+        };
+        let name = string("profile-name").or_else(|| string("apn"))?;
+
+        Some(Self { id, name })
+    }
+}
+
+/// Network registration restriction.
+///
+/// This usually indicates a problem with the SIM card or mobile plan.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Restriction {
+    /// Registration was denied by the network.
+    Denied,
+    /// Only emergency calls are possible.
+    EmergencyOnly,
+}
+
+/// Modem access technology selection.
+#[derive(PartialEq, Eq, Default, Copy, Clone, Debug)]
+pub enum NetworkMode {
+    /// Allow all supported technologies.
+    #[default]
+    Auto,
+    /// 5G only.
+    FiveG,
+    /// 4G only.
+    FourG,
+    /// 3G only.
+    ThreeG,
+    /// 2G only.
+    TwoG,
+}
+
+impl NetworkMode {
+    /// Get ModemManager mode bits of the technology.
+    fn bits(&self) -> u32 {
+        match self {
+            Self::Auto => MODE_ANY,
+            Self::FiveG => MODE_5G,
+            Self::FourG => MODE_4G,
+            Self::ThreeG => MODE_3G,
+            Self::TwoG => MODE_2G,
+        }
+    }
+
+    /// Get network mode from ModemManager's allowed modes.
+    fn from_allowed(allowed: u32) -> Self {
+        // Ignore circuit-switched support, since it doesn't limit the generation.
+        match allowed & !MODE_CS {
+            MODE_5G => Self::FiveG,
+            MODE_4G => Self::FourG,
+            MODE_3G => Self::ThreeG,
+            MODE_2G => Self::TwoG,
+            _ => Self::Auto,
+        }
+    }
+
+    /// Find the best matching combination of allowed and preferred modes.
+    fn select(&self, supported: &[(u32, u32)]) -> Option<(u32, u32)> {
+        let mut supported = supported.iter().copied();
+        match self {
+            // Allow as many technologies as possible, without any preference.
+            Self::Auto => supported
+                .max_by_key(|(allowed, preferred)| (allowed.count_ones(), *preferred == MODE_NONE)),
+            _ => supported.find(|(allowed, _)| allowed & !MODE_CS == self.bits()),
+        }
     }
 }
 
+/// Location sources based on satellite positioning.
+///
+/// Cell tower sources are ignored, since ModemManager commonly enables them
+/// by default.
+const GPS_SOURCES: u32 = 0x02 | 0x04 | 0x10 | 0x20 | 0x40;
+
+/// Get calloop channel for GPS location status changes.
+pub fn location_listener() -> Channel<BackendEvent<bool>> {
+    supervisor::spawn("ModemManager location", run_location_loop)
+}
+
+/// Get calloop channel for connection profile changes.
+pub fn profile_listener() -> Channel<BackendEvent<ModemProfiles>> {
+    supervisor::spawn("ModemManager profiles", run_profile_loop)
+}
+
 /// Get calloop channel for cellular signal strength changes.
-pub fn modem_listener() -> Result<Channel<ModemConnection>, Box<dyn Error>> {
-    let (tx, rx) = channel::channel();
-    thread::spawn(|| {
-        let mut builder = Builder::new_current_thread();
-        let runtime = builder.enable_all().build().expect("create tokio runtime");
-        runtime.block_on(run_dbus_loop(tx)).expect("execute tokio runtime");
-    });
-    Ok(rx)
+pub fn modem_listener() -> Channel<BackendEvent<ModemConnection>> {
+    supervisor::spawn("ModemManager", run_dbus_loop)
 }
 
 /// Set ModemManager modem states.
@@ -67,13 +252,13 @@ pub fn set_enabled(enabled: bool) {
             // Ensure modem's power state is `On` before enabling it.
             if enabled {
                 if let Err(err) = modem.set_power_state(PowerState::On as u32).await {
-                    eprintln!("Could not power modem on: {err}");
+                    error!(module = "modem_manager", "Could not power modem on: {err}");
                 }
             }
 
             // Set the modem state.
             if let Err(err) = modem.enable(enabled).await {
-                eprintln!("Modem state change failed: {err}");
+                error!(module = "modem_manager", "Modem state change failed: {err}");
             }
 
             // Set modem to lowest powerstate it can recover from.
@@ -82,7 +267,7 @@ pub fn set_enabled(enabled: bool) {
             // future.
             if !enabled {
                 if let Err(err) = modem.set_power_state(PowerState::Low as u32).await {
-                    eprintln!("Could not power modem off: {err}");
+                    error!(module = "modem_manager", "Could not power modem off: {err}");
                 }
             }
         }
@@ -94,12 +279,130 @@ pub fn set_enabled(enabled: bool) {
     thread::spawn(move || {
         let mut builder = Builder::new_current_thread();
         let runtime = builder.enable_all().build().expect("create tokio runtime");
-        runtime.block_on(set_modem_state()).expect("execute tokio runtime");
+        if let Err(err) = runtime.block_on(set_modem_state()) {
+            error!(module = "modem_manager", "Could not connect to ModemManager: {err}");
+        }
+    });
+}
+
+/// Set ModemManager allowed access technologies.
+pub fn set_network_mode(mode: NetworkMode) {
+    // Async function for updating the modes of every modem.
+    let set_modem_modes = move || async move {
+        // Get all active modems.
+        let connection = Connection::system().await?;
+        let object_manager = object_manager(&connection).await?;
+        let modems = active_modems(&connection, &object_manager).await;
+
+        // Set the modes for each one.
+        for (modem, _) in modems {
+            let supported_modes = modem.supported_modes().await?;
+            let modes = match mode.select(&supported_modes) {
+                Some(modes) => modes,
+                None => {
+                    warn!(module = "modem_manager", "Network mode {mode:?} not supported by modem");
+                    continue;
+                },
+            };
+
+            if let Err(err) = modem.set_current_modes(&modes).await {
+                error!(module = "modem_manager", "Network mode change failed: {err}");
+            }
+        }
+
+        Ok::<(), zbus::Error>(())
+    };
+
+    // Spawn async executor for the mode update on a new thread.
+    thread::spawn(move || {
+        let mut builder = Builder::new_current_thread();
+        let runtime = builder.enable_all().build().expect("create tokio runtime");
+        if let Err(err) = runtime.block_on(set_modem_modes()) {
+            error!(module = "modem_manager", "Could not set network mode: {err}");
+        }
+    });
+}
+
+/// Set the primary SIM slot of the modem.
+///
+/// The slot number starts at 1.
+pub fn set_primary_sim_slot(slot: u32) {
+    // Async function for updating the SIM slot of the first modem.
+    let set_sim_slot = move || async move {
+        let connection = Connection::system().await?;
+        let object_manager = object_manager(&connection).await?;
+        let modems = active_modems(&connection, &object_manager).await;
+
+        if let Some((modem, _)) = modems.first() {
+            if let Err(err) = modem.set_primary_sim_slot(slot).await {
+                error!(module = "modem_manager", "SIM slot change failed: {err}");
+            }
+        }
+
+        Ok::<(), zbus::Error>(())
+    };
+
+    // Spawn async executor for the SIM slot update on a new thread.
+    thread::spawn(move || {
+        let mut builder = Builder::new_current_thread();
+        let runtime = builder.enable_all().build().expect("create tokio runtime");
+        if let Err(err) = runtime.block_on(set_sim_slot()) {
+            error!(module = "modem_manager", "Could not set SIM slot: {err}");
+        }
+    });
+}
+
+/// Connect the modem using a different profile.
+pub fn connect_profile(id: i32) {
+    // Async function for reconnecting the first modem.
+    let connect = move || async move {
+        let connection = Connection::system().await?;
+        let object_manager = object_manager(&connection).await?;
+        let modems = active_modems(&connection, &object_manager).await;
+
+        if let Some((modem, _)) = modems.first() {
+            let path = modem.inner().path().to_owned();
+            let simple = SimpleProxy::builder(&connection).path(path)?.build().await?;
+
+            // Disconnect bearers of other profiles before switching to the new profile.
+            for path in modem.bearers().await? {
+                let bearer = BearerProxy::builder(&connection).path(&path)?.build().await?;
+                if !bearer.connected().await.unwrap_or_default() {
+                    continue;
+                }
+
+                let properties = bearer.properties().await.unwrap_or_default();
+                let profile_id = properties.get("profile-id").and_then(|id| id.downcast_ref().ok());
+                if profile_id == Some(id) {
+                    return Ok(());
+                }
+
+                if let Err(err) = simple.disconnect(&path).await {
+                    error!(module = "modem_manager", "Bearer disconnect failed: {err}");
+                }
+            }
+
+            let properties = HashMap::from([("profile-id", Value::from(id))]);
+            if let Err(err) = simple.connect(properties).await {
+                error!(module = "modem_manager", "Profile connection failed: {err}");
+            }
+        }
+
+        Ok::<(), zbus::Error>(())
+    };
+
+    // Spawn async executor for the connection update on a new thread.
+    thread::spawn(move || {
+        let mut builder = Builder::new_current_thread();
+        let runtime = builder.enable_all().build().expect("create tokio runtime");
+        if let Err(err) = runtime.block_on(connect()) {
+            error!(module = "modem_manager", "Could not switch profile: {err}");
+        }
     });
 }
 
 /// Run the DBus cellular event loop.
-async fn run_dbus_loop(tx: Sender<ModemConnection>) -> Result<(), Box<dyn Error>> {
+async fn run_dbus_loop(tx: BackendSender<ModemConnection>) -> Result<(), Box<dyn Error>> {
     let connection = Connection::system().await?;
 
     // Create object manager for modem changes.
@@ -119,11 +422,19 @@ async fn run_dbus_loop(tx: Sender<ModemConnection>) -> Result<(), Box<dyn Error>
         // Extract optional streams, since async Rust sucks.
         let modem_future = async {
             match &mut modem_streams {
-                Some((registration_stream, connectivity_stream, quality_stream)) => {
+                Some((
+                    registration_stream,
+                    connectivity_stream,
+                    quality_stream,
+                    modes_stream,
+                    sim_slot_stream,
+                )) => {
                     tokio::select! {
                         _ = registration_stream.next() => Some(()),
                         _ = connectivity_stream.next() => Some(()),
                         _ = quality_stream.next() => Some(()),
+                        _ = modes_stream.next() => Some(()),
+                        _ = sim_slot_stream.next() => Some(()),
                     }
                 },
                 None => None,
@@ -162,6 +473,159 @@ async fn run_dbus_loop(tx: Sender<ModemConnection>) -> Result<(), Box<dyn Error>
     }
 }
 
+/// Run the DBus modem location event loop.
+async fn run_location_loop(tx: BackendSender<bool>) -> Result<(), Box<dyn Error>> {
+    let connection = Connection::system().await?;
+
+    // Create object manager for modem changes.
+    let object_manager = object_manager(&connection).await?;
+
+    // Get stream for modem changes.
+    let mut modem_added_stream = object_manager.receive_interfaces_added().await?;
+    let mut modem_removed_stream = object_manager.receive_interfaces_removed().await?;
+
+    let mut location = primary_location(&connection, &object_manager).await;
+    let mut enabled_stream = match &location {
+        Some(location) => Some(location.receive_enabled_changed().await),
+        None => None,
+    };
+
+    loop {
+        // Report GPS status of the primary modem.
+        let enabled = match &location {
+            Some(location) => location.enabled().await.unwrap_or_default(),
+            None => 0,
+        };
+        tx.send(enabled & GPS_SOURCES != 0)?;
+
+        // Extract optional stream, since async Rust sucks.
+        let enabled_future = async {
+            match &mut enabled_stream {
+                Some(enabled_stream) => enabled_stream.next().await,
+                None => None,
+            }
+        };
+
+        tokio::select! {
+            // Wait for location source changes.
+            Some(_) = enabled_future => (),
+
+            // Wait for new/removed modems.
+            Some(_) = modem_added_stream.next() => {
+                location = primary_location(&connection, &object_manager).await;
+                enabled_stream = match &location {
+                    Some(location) => Some(location.receive_enabled_changed().await),
+                    None => None,
+                };
+            },
+            Some(_) = modem_removed_stream.next() => {
+                location = primary_location(&connection, &object_manager).await;
+                enabled_stream = match &location {
+                    Some(location) => Some(location.receive_enabled_changed().await),
+                    None => None,
+                };
+            },
+
+            else => continue,
+        };
+    }
+}
+
+/// Run the DBus connection profile event loop.
+async fn run_profile_loop(tx: BackendSender<ModemProfiles>) -> Result<(), Box<dyn Error>> {
+    let connection = Connection::system().await?;
+
+    // Create object manager for modem changes.
+    let object_manager = object_manager(&connection).await?;
+
+    // Get stream for modem changes.
+    let mut modem_added_stream = object_manager.receive_interfaces_added().await?;
+    let mut modem_removed_stream = object_manager.receive_interfaces_removed().await?;
+
+    let mut modems = active_modems(&connection, &object_manager).await;
+    let mut profile_manager = primary_profile_manager(&connection, &modems).await;
+    let mut profile_streams = primary_profile_streams(&modems, &profile_manager).await;
+
+    loop {
+        // Report profiles of the primary modem.
+        let profiles = match (modems.first(), &profile_manager) {
+            (Some((modem, _)), Some(profile_manager)) => {
+                ModemProfiles::new(&connection, modem, profile_manager).await
+            },
+            _ => ModemProfiles::default(),
+        };
+        tx.send(profiles)?;
+
+        // Extract optional streams, since async Rust sucks.
+        let profile_future = async {
+            match &mut profile_streams {
+                Some((updated_stream, bearers_stream, state_stream)) => {
+                    tokio::select! {
+                        _ = updated_stream.next() => Some(()),
+                        _ = bearers_stream.next() => Some(()),
+                        _ = state_stream.next() => Some(()),
+                    }
+                },
+                None => None,
+            }
+        };
+
+        tokio::select! {
+            // Wait for profile or bearer changes.
+            Some(_) = profile_future => (),
+
+            // Wait for new/removed modems.
+            Some(_) = modem_added_stream.next() => {
+                modems = active_modems(&connection, &object_manager).await;
+                profile_manager = primary_profile_manager(&connection, &modems).await;
+                profile_streams = primary_profile_streams(&modems, &profile_manager).await;
+            },
+            Some(_) = modem_removed_stream.next() => {
+                modems = active_modems(&connection, &object_manager).await;
+                profile_manager = primary_profile_manager(&connection, &modems).await;
+                profile_streams = primary_profile_streams(&modems, &profile_manager).await;
+            },
+
+            else => continue,
+        };
+    }
+}
+
+/// Get the profile manager interface of the first active modem.
+async fn primary_profile_manager<'a>(
+    connection: &'a Connection,
+    modems: &[(ModemProxy<'a>, Modem3gppProxy<'a>)],
+) -> Option<ProfileManagerProxy<'a>> {
+    let (modem, _) = modems.first()?;
+    let path = modem.inner().path().to_owned();
+    ProfileManagerProxy::builder(connection).path(path).ok()?.build().await.ok()
+}
+
+/// Get profile update/bearer/modem state streams.
+async fn primary_profile_streams<'a>(
+    modems: &[(ModemProxy<'a>, Modem3gppProxy<'a>)],
+    profile_manager: &Option<ProfileManagerProxy<'a>>,
+) -> Option<(UpdatedStream, PropertyStream<'a, Vec<OwnedObjectPath>>, PropertyStream<'a, ModemState>)>
+{
+    let (modem, _) = modems.first()?;
+    let updated_stream = profile_manager.as_ref()?.receive_updated().await.ok()?;
+    let bearers_stream = modem.receive_bearers_changed().await;
+    let state_stream = modem.receive_modem_state_changed().await;
+    Some((updated_stream, bearers_stream, state_stream))
+}
+
+/// Get the location interface of the first active modem.
+async fn primary_location<'a>(
+    connection: &'a Connection,
+    object_manager: &ObjectManagerProxy<'_>,
+) -> Option<LocationProxy<'a>> {
+    let managed_objects = object_manager.get_managed_objects().await.ok()?;
+    let path = managed_objects
+        .into_keys()
+        .find(|path| path.starts_with("/org/freedesktop/ModemManager1/Modem/"))?;
+    LocationProxy::builder(connection).path(path).ok()?.build().await.ok()
+}
+
 /// Create object manager for tracking DBus modem objects
 async fn object_manager(connection: &Connection) -> zbus::Result<ObjectManagerProxy> {
     ObjectManagerProxy::builder(connection)
@@ -195,21 +659,25 @@ async fn active_modems<'a>(
     modems
 }
 
-/// Get modem state/signal quality streams.
+/// Get modem state/signal quality/access technology/SIM slot streams.
 async fn primary_modem_streams<'a>(
     modems: &[(ModemProxy<'a>, Modem3gppProxy<'a>)],
 ) -> Option<(
     PropertyStream<'a, RegistrationState>,
     PropertyStream<'a, ModemState>,
     PropertyStream<'a, (u32, bool)>,
+    PropertyStream<'a, (u32, u32)>,
+    PropertyStream<'a, u32>,
 )> {
     let (modem, modem3gpp) = modems.first()?;
 
     let registration_stream = modem3gpp.receive_registration_state_changed().await;
     let connectivity_stream = modem.receive_modem_state_changed().await;
     let quality_stream = modem.receive_signal_quality_changed().await;
+    let modes_stream = modem.receive_current_modes_changed().await;
+    let sim_slot_stream = modem.receive_primary_sim_slot_changed().await;
 
-    Some((registration_stream, connectivity_stream, quality_stream))
+    Some((registration_stream, connectivity_stream, quality_stream, modes_stream, sim_slot_stream))
 }
 
 /// Try and convert a DBus device path to modem.
@@ -697,6 +1165,21 @@ trait ProfileManager {
     fn index_field(&self) -> zbus::Result<String>;
 }
 
+#[proxy(
+    interface = "org.freedesktop.ModemManager1.Bearer",
+    default_service = "org.freedesktop.ModemManager1",
+    default_path = "/org/freedesktop/ModemManager1/Bearer/0"
+)]
+trait Bearer {
+    /// Connected property
+    #[zbus(property)]
+    fn connected(&self) -> zbus::Result<bool>;
+
+    /// Properties property
+    #[zbus(property)]
+    fn properties(&self) -> zbus::Result<std::collections::HashMap<String, OwnedValue>>;
+}
+
 #[proxy(
     interface = "org.freedesktop.ModemManager1.Modem.Sar",
     default_service = "org.freedesktop.ModemManager1",
@@ -880,6 +1363,21 @@ trait Voice {
     fn emergency_only(&self) -> zbus::Result<bool>;
 }
 
+/// No access technology.
+const MODE_NONE: u32 = 0;
+/// Circuit-switched access technology.
+const MODE_CS: u32 = 1 << 0;
+/// 2G access technology.
+const MODE_2G: u32 = 1 << 1;
+/// 3G access technology.
+const MODE_3G: u32 = 1 << 2;
+/// 4G access technology.
+const MODE_4G: u32 = 1 << 3;
+/// 5G access technology.
+const MODE_5G: u32 = 1 << 4;
+/// Any access technology.
+const MODE_ANY: u32 = u32::MAX;
+
 /// ModemManager modem 3gpp state.
 #[derive(Type, OwnedValue, PartialEq, Debug, PartialOrd)]
 #[repr(u32)]
@@ -912,6 +1410,22 @@ pub enum RegistrationState {
     AttachedRlos = 11,
 }
 
+impl RegistrationState {
+    /// Check if the modem is registered with a network.
+    fn is_registered(&self) -> bool {
+        matches!(
+            self,
+            Self::Home
+                | Self::Roaming
+                | Self::HomeSmsOnly
+                | Self::RoamingSmsOnly
+                | Self::HomeCsfbNotPreferred
+                | Self::RoamingCsfbNotPreferred
+                | Self::AttachedRlos
+        )
+    }
+}
+
 /// Power state of the modem.
 #[derive(Type, OwnedValue, PartialEq, Debug, PartialOrd)]
 #[repr(u32)]
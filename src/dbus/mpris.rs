@@ -0,0 +1,270 @@
+//! MPRIS media player DBus interface.
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::sync::Arc;
+
+use calloop::channel::Channel;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::sync::Mutex;
+use tracing::error;
+use zbus::export::futures_util::stream::{BoxStream, SelectAll, StreamExt};
+use zbus::fdo::DBusProxy;
+use zbus::message::Type as MessageType;
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::OwnedValue;
+use zbus::{proxy, Connection, MatchRule, MessageStream};
+
+use crate::module::MediaAction;
+use crate::supervisor::{self, BackendEvent, BackendSender};
+
+/// Bus name prefix of all MPRIS players.
+const PLAYER_PREFIX: &str = "org.mpris.MediaPlayer2.";
+
+/// Object path of the MPRIS player interface.
+const PLAYER_PATH: &str = "/org/mpris/MediaPlayer2";
+
+/// Maximum number of queued property change signals.
+const MAX_QUEUED_CHANGES: usize = 16;
+
+/// Media player status.
+#[derive(PartialEq, Eq, Default, Clone, Debug)]
+pub struct MediaPlayer {
+    /// Bus name of the player.
+    pub name: String,
+
+    /// Title of the current track.
+    pub title: String,
+
+    /// Player is currently playing.
+    pub playing: bool,
+}
+
+impl MediaPlayer {
+    /// Get the current status of a player.
+    async fn new(connection: &Connection, name: String) -> zbus::Result<Self> {
+        let player = PlayerProxy::builder(connection)
+            .destination(name.as_str())?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?;
+
+        let playing = player.playback_status().await? == "Playing";
+
+        // Fall back to the player's name for tracks without title.
+        let metadata = player.metadata().await.unwrap_or_default();
+        let title = metadata.get("xesam:title").and_then(|title| <&str>::try_from(title).ok());
+        let title = match title {
+            Some(title) if !title.is_empty() => title.into(),
+            _ => {
+                let player_name = name.trim_start_matches(PLAYER_PREFIX);
+                player_name.split('.').next().unwrap_or_default().into()
+            },
+        };
+
+        Ok(Self { name, title, playing })
+    }
+}
+
+/// Update from the MPRIS backend.
+#[derive(Debug)]
+pub enum MprisEvent {
+    /// Player controlled by the drawer changed.
+    ///
+    /// The player is `None` while no MPRIS player is running.
+    Player(Option<MediaPlayer>),
+    /// Playback command could not be delivered to the player.
+    ControlFailed(String),
+}
+
+/// Sender for playback commands.
+#[derive(Clone, Debug)]
+pub struct MediaControl(UnboundedSender<(String, MediaAction)>);
+
+impl MediaControl {
+    /// Send a playback command to a player.
+    ///
+    /// Commands are sent through the backend's DBus connection, failures are
+    /// reported as [`MprisEvent::ControlFailed`].
+    pub fn send(&self, name: String, action: MediaAction) -> Result<(), &'static str> {
+        self.0.send((name, action)).map_err(|_| "media player backend is gone")
+    }
+}
+
+/// Get calloop channel for media player changes, and a sender for commands.
+pub fn player_listener() -> (Channel<BackendEvent<MprisEvent>>, MediaControl) {
+    let (control_tx, control_rx) = mpsc::unbounded_channel();
+
+    // Share the command receiver with every restart of the backend.
+    let control_rx = Arc::new(Mutex::new(control_rx));
+    let rx = supervisor::spawn("MPRIS", move |tx| run_dbus_loop(tx, control_rx.clone()));
+
+    (rx, MediaControl(control_tx))
+}
+
+/// Run the DBus media player event loop.
+async fn run_dbus_loop(
+    tx: BackendSender<MprisEvent>,
+    control_rx: Arc<Mutex<UnboundedReceiver<(String, MediaAction)>>>,
+) -> Result<(), Box<dyn Error>> {
+    let mut control_rx = control_rx.lock().await;
+
+    let connection = Connection::session().await?;
+    let dbus = DBusProxy::new(&connection).await?;
+
+    // Get stream for players appearing and disappearing.
+    let mut owner_stream = dbus.receive_name_owner_changed().await?;
+
+    // Get the current state of all players.
+    let mut players = BTreeMap::new();
+    for name in dbus.list_names().await? {
+        if !name.as_str().starts_with(PLAYER_PREFIX) {
+            continue;
+        }
+
+        let owner = match dbus.get_name_owner(name.inner().clone()).await {
+            Ok(owner) => owner.to_string(),
+            Err(_) => continue,
+        };
+        update_player(&connection, &mut players, name.to_string(), owner).await;
+    }
+
+    let mut properties_stream = property_changes(&connection, &players).await?;
+
+    // Send the current state before waiting for changes.
+    let mut active = active_player(&players);
+    tx.send(MprisEvent::Player(active.clone()))?;
+
+    loop {
+        tokio::select! {
+            Some(change) = owner_stream.next() => {
+                // Ignore changes of unrelated bus names.
+                let args = change.args()?;
+                let name = args.name().to_string();
+                if !name.starts_with(PLAYER_PREFIX) {
+                    continue;
+                }
+
+                match args.new_owner().as_ref() {
+                    Some(owner) => {
+                        update_player(&connection, &mut players, name, owner.to_string()).await
+                    },
+                    None => {
+                        players.remove(&name);
+                    },
+                }
+
+                properties_stream = property_changes(&connection, &players).await?;
+            },
+            // Only refresh the player which reported the change.
+            Some(name) = properties_stream.next() => {
+                if let Some((_, owner)) = players.remove(&name) {
+                    update_player(&connection, &mut players, name, owner).await;
+                }
+            },
+            Some((name, action)) = control_rx.recv() => {
+                if let Err(err) = control(&connection, name, action).await {
+                    error!(module = "mpris", "Media player command failed: {err}");
+                    tx.send(MprisEvent::ControlFailed(err.to_string()))?;
+                }
+                continue;
+            },
+            else => return Ok(()),
+        }
+
+        let new_active = active_player(&players);
+        if new_active != active {
+            active = new_active;
+            tx.send(MprisEvent::Player(active.clone()))?;
+        }
+    }
+}
+
+/// Reload the status of a player.
+///
+/// Players which fail to report their status are removed.
+async fn update_player(
+    connection: &Connection,
+    players: &mut BTreeMap<String, (MediaPlayer, String)>,
+    name: String,
+    owner: String,
+) {
+    match MediaPlayer::new(connection, name.clone()).await {
+        Ok(player) => {
+            players.insert(name, (player, owner));
+        },
+        Err(_) => {
+            players.remove(&name);
+        },
+    }
+}
+
+/// Get a stream of property changes for all players.
+///
+/// The stream yields the bus name of the player whose properties changed.
+async fn property_changes(
+    connection: &Connection,
+    players: &BTreeMap<String, (MediaPlayer, String)>,
+) -> zbus::Result<SelectAll<BoxStream<'static, String>>> {
+    let mut streams = SelectAll::new();
+    for (name, (_, owner)) in players {
+        // Signals are sent by the unique name owning the player's bus name.
+        let rule = MatchRule::builder()
+            .msg_type(MessageType::Signal)
+            .sender(owner.as_str())?
+            .interface("org.freedesktop.DBus.Properties")?
+            .member("PropertiesChanged")?
+            .path(PLAYER_PATH)?
+            .build();
+        let stream =
+            MessageStream::for_match_rule(rule, connection, Some(MAX_QUEUED_CHANGES)).await?;
+
+        let name = name.clone();
+        streams.push(stream.map(move |_| name.clone()).boxed());
+    }
+    Ok(streams)
+}
+
+/// Send a playback command to a player.
+async fn control(connection: &Connection, name: String, action: MediaAction) -> zbus::Result<()> {
+    let player = PlayerProxy::builder(connection)
+        .destination(name)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+
+    match action {
+        MediaAction::Previous => player.previous().await,
+        MediaAction::PlayPause => player.play_pause().await,
+        MediaAction::Next => player.next().await,
+    }
+}
+
+/// Get the player which should be controlled from the drawer.
+///
+/// Playing players are preferred, otherwise the first player is used.
+fn active_player(players: &BTreeMap<String, (MediaPlayer, String)>) -> Option<MediaPlayer> {
+    let mut players = players.values().map(|(player, _)| player);
+    let first = players.clone().next();
+    players.find(|player| player.playing).or(first).cloned()
+}
+
+#[proxy(interface = "org.mpris.MediaPlayer2.Player", default_path = "/org/mpris/MediaPlayer2")]
+trait Player {
+    /// Skip to the next track.
+    fn next(&self) -> zbus::Result<()>;
+
+    /// Skip to the previous track.
+    fn previous(&self) -> zbus::Result<()>;
+
+    /// Pause playback, or resume it if already paused.
+    fn play_pause(&self) -> zbus::Result<()>;
+
+    /// The current playback status.
+    #[zbus(property)]
+    fn playback_status(&self) -> zbus::Result<String>;
+
+    /// Metadata of the current track.
+    #[zbus(property)]
+    fn metadata(&self) -> zbus::Result<HashMap<String, OwnedValue>>;
+}
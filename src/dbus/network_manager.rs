@@ -1,15 +1,29 @@
 //! NetworkManager DBus interface.
 
+use std::cmp::Reverse;
+use std::collections::HashMap;
 use std::error::Error;
 use std::thread;
+use std::time::Duration;
 
-use calloop::channel::{self, Channel, Sender};
+use calloop::channel::{Channel, Sender};
 use tokio::runtime::Builder;
+use tokio::sync::oneshot::{self, Sender as OneshotSender};
+use tokio::time;
+use tracing::error;
 use zbus::export::futures_util::stream::StreamExt;
 use zbus::proxy::{PropertyChanged, PropertyStream};
-use zbus::zvariant::{OwnedObjectPath, OwnedValue, Type};
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Type, Value};
 use zbus::{proxy, Connection};
 
+use crate::supervisor::{self, BackendEvent, BackendSender};
+
+/// Access point supports some form of security.
+const AP_FLAG_PRIVACY: u32 = 0x1;
+
+/// Maximum time to wait for a WiFi scan to complete.
+const SCAN_TIMEOUT: Duration = Duration::from_secs(30);
+
 /// Wifi connection quality.
 #[derive(PartialEq, Eq, Default, Copy, Clone, Debug)]
 pub struct WifiConnection {
@@ -21,6 +35,9 @@ pub struct WifiConnection {
 
     /// Connection has internet access.
     pub connected: bool,
+
+    /// Wireless device is present and reporting its state.
+    pub available: bool,
 }
 
 impl WifiConnection {
@@ -30,29 +47,195 @@ impl WifiConnection {
         network_manager: &NetworkManagerProxy<'_>,
         wireless_device: &WirelessDeviceProxy<'_>,
     ) -> Option<Self> {
+        // Get enabled status.
+        let enabled = network_manager.wireless_enabled().await.ok()?;
+        let mut wifi_connection = Self { enabled, available: true, ..Self::default() };
+
         // Get the active access point.
         let active_ap = match wireless_device.active_access_point().await {
             // Filter out fallback AP `/`.
             Ok(path) if path.len() != 1 => {
                 AccessPointProxy::builder(connection).path(path).ok()?.build().await.ok()?
             },
-            _ => return None,
+            _ => return Some(wifi_connection),
         };
 
         // Get signal strength from AP.
-        let strength = active_ap.strength().await.ok()?;
+        wifi_connection.strength = active_ap.strength().await.ok()?;
 
         // Get connection status from NM.
         let connectivity = network_manager.connectivity().await.ok()?;
-        let connected = connectivity == ConnectivityState::Full;
+        wifi_connection.connected = connectivity == ConnectivityState::Full;
 
-        // Get enabled status.
-        let enabled = network_manager.wireless_enabled().await.ok()?;
+        Some(wifi_connection)
+    }
+}
+
+/// Type of the connection with the default route.
+#[derive(PartialEq, Eq, Default, Copy, Clone, Debug)]
+pub enum PrimaryConnection {
+    /// No active connection.
+    #[default]
+    None,
+    Wifi,
+    Cellular,
+    /// Connection of any other type, like ethernet or VPN.
+    Other,
+}
 
-        Some(Self { strength, connected, enabled })
+impl PrimaryConnection {
+    /// Get the type of the current primary connection.
+    async fn new(
+        connection: &Connection,
+        network_manager: &NetworkManagerProxy<'_>,
+    ) -> zbus::Result<Self> {
+        // Filter out fallback connection `/`, without any active connection.
+        let primary_connection = network_manager.primary_connection().await?;
+        if primary_connection.len() == 1 {
+            return Ok(Self::None);
+        }
+
+        let active_connection =
+            ActiveConnectionProxy::builder(connection).path(primary_connection)?.build().await?;
+        let primary_connection = match active_connection.connection_type().await?.as_str() {
+            "802-11-wireless" => Self::Wifi,
+            "gsm" | "cdma" => Self::Cellular,
+            _ => Self::Other,
+        };
+
+        Ok(primary_connection)
     }
 }
 
+/// WiFi network found by a scan.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct WifiNetwork {
+    /// Network name.
+    pub ssid: String,
+
+    /// Signal strength of the strongest AP in percent.
+    pub strength: u8,
+
+    /// Network requires authentication.
+    pub secured: bool,
+}
+
+impl WifiNetwork {
+    /// Get network details of an access point.
+    async fn new(connection: &Connection, path: OwnedObjectPath) -> zbus::Result<Self> {
+        let ap = AccessPointProxy::builder(connection).path(path)?.build().await?;
+
+        let ssid = String::from_utf8_lossy(&ap.ssid().await?).into_owned();
+        let strength = ap.strength().await?;
+
+        // Networks with privacy flag or any WPA/RSN security are secured.
+        let secured = ap.flags().await? & AP_FLAG_PRIVACY != 0
+            || ap.wpa_flags().await? != 0
+            || ap.rsn_flags().await? != 0;
+
+        Ok(Self { ssid, strength, secured })
+    }
+}
+
+/// Running WiFi scan.
+///
+/// The scan is cancelled when this is dropped.
+#[derive(Debug)]
+pub struct WifiScan {
+    _cancel: OneshotSender<()>,
+}
+
+/// Scan for WiFi networks.
+///
+/// The known networks are sent immediately, followed by an update once the
+/// scan is complete.
+pub fn request_scan(tx: Sender<Vec<WifiNetwork>>) -> WifiScan {
+    let (cancel_tx, cancel_rx) = oneshot::channel();
+
+    // Spawn async executor for the scan on a new thread.
+    thread::spawn(move || {
+        let mut builder = Builder::new_current_thread();
+        let runtime = builder.enable_all().build().expect("create tokio runtime");
+        runtime.block_on(async move {
+            tokio::select! {
+                result = time::timeout(SCAN_TIMEOUT, scan(tx)) => match result {
+                    Ok(Err(err)) => error!(module = "network_manager", "WiFi scan failed: {err}"),
+                    Err(_) => error!(module = "network_manager", "WiFi scan timed out"),
+                    Ok(Ok(())) => (),
+                },
+                // Abort once the scan results are no longer needed.
+                _ = cancel_rx => (),
+            }
+        });
+    });
+
+    WifiScan { _cancel: cancel_tx }
+}
+
+/// Run a WiFi scan.
+async fn scan(tx: Sender<Vec<WifiNetwork>>) -> Result<(), Box<dyn Error>> {
+    let connection = Connection::system().await?;
+    let network_manager = NetworkManagerProxy::new(&connection).await?;
+    let (wireless_device, _) = match active_wireless_device(&connection, &network_manager).await {
+        Some(wireless_device) => wireless_device,
+        None => return Ok(()),
+    };
+
+    // Send cached networks while the scan is running.
+    tx.send(networks(&connection, &wireless_device).await?)?;
+
+    // Scan completion is signaled by a change of the last scan time.
+    let last_scan = wireless_device.last_scan().await?;
+    let mut last_scan_stream = wireless_device.receive_last_scan_changed().await;
+
+    // NetworkManager rejects scans if the last one was recent.
+    if wireless_device.request_scan(HashMap::new()).await.is_err() {
+        return Ok(());
+    }
+
+    while let Some(change) = last_scan_stream.next().await {
+        if change.get().await? != last_scan {
+            tx.send(networks(&connection, &wireless_device).await?)?;
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Get all visible WiFi networks.
+///
+/// Networks are sorted by signal strength, with only the strongest AP
+/// reported for each SSID.
+async fn networks(
+    connection: &Connection,
+    wireless_device: &WirelessDeviceProxy<'_>,
+) -> zbus::Result<Vec<WifiNetwork>> {
+    let mut networks: Vec<WifiNetwork> = Vec::new();
+    for path in wireless_device.get_all_access_points().await? {
+        // Ignore APs which disappeared during the scan.
+        let network = match WifiNetwork::new(connection, path).await {
+            Ok(network) => network,
+            Err(_) => continue,
+        };
+
+        // Skip hidden networks.
+        if network.ssid.is_empty() {
+            continue;
+        }
+
+        match networks.iter_mut().find(|known| known.ssid == network.ssid) {
+            Some(known) if known.strength < network.strength => *known = network,
+            Some(_) => (),
+            None => networks.push(network),
+        }
+    }
+
+    networks.sort_by_key(|network| Reverse(network.strength));
+
+    Ok(networks)
+}
+
 /// Set NetworkManager WiFi state.
 pub fn set_enabled(enabled: bool) {
     // Async function for updating the WiFi state.
@@ -60,7 +243,7 @@ pub fn set_enabled(enabled: bool) {
         let connection = Connection::system().await?;
         let network_manager = NetworkManagerProxy::new(&connection).await?;
         if let Err(err) = network_manager.set_wireless_enabled(enabled).await {
-            eprintln!("WiFi state change failed: {err}");
+            error!(module = "network_manager", "WiFi state change failed: {err}");
         }
         Ok::<(), zbus::Error>(())
     };
@@ -69,23 +252,113 @@ pub fn set_enabled(enabled: bool) {
     thread::spawn(move || {
         let mut builder = Builder::new_current_thread();
         let runtime = builder.enable_all().build().expect("create tokio runtime");
-        runtime.block_on(set_wifi_state(enabled)).expect("execute tokio runtime");
+        if let Err(err) = runtime.block_on(set_wifi_state(enabled)) {
+            error!(module = "network_manager", "Could not connect to NetworkManager: {err}");
+        }
     });
 }
 
-/// Get calloop channel for wifi signal strength changes.
-pub fn wifi_listener() -> Result<Channel<WifiConnection>, Box<dyn Error>> {
-    let (tx, rx) = channel::channel();
-    thread::spawn(|| {
+/// Set the metered state of the primary connection.
+///
+/// The change is applied to the active connection only, without modifying the
+/// stored connection profile.
+pub fn set_metered(metered: bool) {
+    // Async function for updating the metered state.
+    let set_metered = |metered: bool| async move {
+        let connection = Connection::system().await?;
+        let network_manager = NetworkManagerProxy::new(&connection).await?;
+
+        // Ignore fallback connection `/`, without any active connection.
+        let primary_connection = network_manager.primary_connection().await?;
+        if primary_connection.len() == 1 {
+            return Ok(());
+        }
+
+        // Get the device of the primary connection.
+        let active_connection =
+            ActiveConnectionProxy::builder(&connection).path(primary_connection)?.build().await?;
+        let device_path = match active_connection.devices().await?.into_iter().next() {
+            Some(device_path) => device_path,
+            None => return Ok(()),
+        };
+        let device = DeviceProxy::builder(&connection).path(device_path)?.build().await?;
+
+        // Reapply the device's settings with the new metered state.
+        let (mut settings, version_id) = device.get_applied_connection(0).await?;
+        let metered = if metered { Metered::Yes } else { Metered::No };
+        let metered = OwnedValue::try_from(Value::from(metered as i32))?;
+        settings.entry("connection".into()).or_default().insert("metered".into(), metered);
+        if let Err(err) = device.reapply(settings, version_id, 0).await {
+            error!(module = "network_manager", "Metered state change failed: {err}");
+        }
+
+        Ok::<(), zbus::Error>(())
+    };
+
+    // Spawn async executor for the metered update on a new thread.
+    thread::spawn(move || {
         let mut builder = Builder::new_current_thread();
         let runtime = builder.enable_all().build().expect("create tokio runtime");
-        runtime.block_on(run_dbus_loop(tx)).expect("execute tokio runtime");
+        if let Err(err) = runtime.block_on(set_metered(metered)) {
+            error!(module = "network_manager", "Could not connect to NetworkManager: {err}");
+        }
     });
-    Ok(rx)
+}
+
+/// Get calloop channel for metered connection changes.
+///
+/// The state is `None` while the metered state of the primary connection is
+/// unknown.
+pub fn metered_listener() -> Channel<BackendEvent<Option<bool>>> {
+    supervisor::spawn("NetworkManager metered", run_metered_loop)
+}
+
+/// Run the DBus metered connection event loop.
+async fn run_metered_loop(tx: BackendSender<Option<bool>>) -> Result<(), Box<dyn Error>> {
+    let connection = Connection::system().await?;
+    let network_manager = NetworkManagerProxy::new(&connection).await?;
+
+    // Send the current state before waiting for changes.
+    tx.send(network_manager.metered().await?.is_metered())?;
+
+    let mut metered_stream = network_manager.receive_metered_changed().await;
+    while let Some(change) = metered_stream.next().await {
+        tx.send(change.get().await?.is_metered())?;
+    }
+
+    Ok(())
+}
+
+/// Get calloop channel for default route changes.
+pub fn primary_connection_listener() -> Channel<BackendEvent<PrimaryConnection>> {
+    supervisor::spawn("NetworkManager primary connection", run_primary_connection_loop)
+}
+
+/// Run the DBus primary connection event loop.
+async fn run_primary_connection_loop(
+    tx: BackendSender<PrimaryConnection>,
+) -> Result<(), Box<dyn Error>> {
+    let connection = Connection::system().await?;
+    let network_manager = NetworkManagerProxy::new(&connection).await?;
+
+    // Send the current state before waiting for changes.
+    tx.send(PrimaryConnection::new(&connection, &network_manager).await?)?;
+
+    let mut primary_stream = network_manager.receive_primary_connection_changed().await;
+    while primary_stream.next().await.is_some() {
+        tx.send(PrimaryConnection::new(&connection, &network_manager).await?)?;
+    }
+
+    Ok(())
+}
+
+/// Get calloop channel for wifi signal strength changes.
+pub fn wifi_listener() -> Channel<BackendEvent<WifiConnection>> {
+    supervisor::spawn("NetworkManager", run_dbus_loop)
 }
 
 /// Run the DBus WiFi event loop.
-async fn run_dbus_loop(tx: Sender<WifiConnection>) -> Result<(), Box<dyn Error>> {
+async fn run_dbus_loop(tx: BackendSender<WifiConnection>) -> Result<(), Box<dyn Error>> {
     let connection = Connection::system().await?;
 
     // Get network manager interface.
@@ -233,6 +506,15 @@ trait NetworkManager {
     #[zbus(property)]
     fn connectivity(&self) -> zbus::Result<ConnectivityState>;
 
+    /// The object path of the "primary" active connection being used to access
+    /// the network.
+    #[zbus(property)]
+    fn primary_connection(&self) -> zbus::Result<OwnedObjectPath>;
+
+    /// Indicates whether the connectivity is metered.
+    #[zbus(property)]
+    fn metered(&self) -> zbus::Result<Metered>;
+
     /// DeviceAdded signal
     #[zbus(signal)]
     fn device_added(&self, device_path: zbus::zvariant::ObjectPath<'_>) -> zbus::Result<()>;
@@ -251,6 +533,37 @@ trait Device {
     /// The general type of the network device; ie Ethernet, Wi-Fi, etc.
     #[zbus(property)]
     fn device_type(&self) -> zbus::Result<DeviceType>;
+
+    /// Get the currently applied connection on the device.
+    fn get_applied_connection(
+        &self,
+        flags: u32,
+    ) -> zbus::Result<(HashMap<String, HashMap<String, OwnedValue>>, u64)>;
+
+    /// Attempts to update the configuration of a device without deactivating
+    /// it.
+    fn reapply(
+        &self,
+        connection: HashMap<String, HashMap<String, OwnedValue>>,
+        version_id: u64,
+        flags: u32,
+    ) -> zbus::Result<()>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.Connection.Active",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager/ActiveConnection"
+)]
+trait ActiveConnection {
+    /// Array of object paths representing devices which are part of this
+    /// active connection.
+    #[zbus(property)]
+    fn devices(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    /// The type of the connection, like "802-11-wireless" or "gsm".
+    #[zbus(property, name = "Type")]
+    fn connection_type(&self) -> zbus::Result<String>;
 }
 
 #[proxy(
@@ -262,6 +575,19 @@ trait WirelessDevice {
     /// Object path of the access point currently used by the wireless device.
     #[zbus(property)]
     fn active_access_point(&self) -> zbus::Result<OwnedObjectPath>;
+
+    /// The timestamp (in CLOCK_BOOTTIME milliseconds) for the last finished
+    /// network scan. A value of -1 means the device never scanned for access
+    /// points.
+    #[zbus(property)]
+    fn last_scan(&self) -> zbus::Result<i64>;
+
+    /// Get the list of all access points visible to this device, including
+    /// hidden ones for which the SSID is not yet known.
+    fn get_all_access_points(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    /// Request the device to scan.
+    fn request_scan(&self, options: HashMap<&str, zbus::zvariant::Value<'_>>) -> zbus::Result<()>;
 }
 
 #[proxy(
@@ -285,6 +611,19 @@ trait AccessPoint {
     /// The current signal quality of the access point, in percent.
     #[zbus(property)]
     fn strength(&self) -> zbus::Result<u8>;
+
+    /// Flags describing the capabilities of the access point.
+    #[zbus(property)]
+    fn flags(&self) -> zbus::Result<u32>;
+
+    /// Flags describing the access point's capabilities according to WPA.
+    #[zbus(property)]
+    fn wpa_flags(&self) -> zbus::Result<u32>;
+
+    /// Flags describing the access point's capabilities according to the RSN
+    /// protocol.
+    #[zbus(property)]
+    fn rsn_flags(&self) -> zbus::Result<u32>;
 }
 
 /// NMDeviceType values indicate the type of hardware represented by a device
@@ -296,6 +635,35 @@ pub enum DeviceType {
     Modem = 8,
 }
 
+/// NetworkManager metered state.
+#[derive(Type, OwnedValue, PartialEq, Copy, Clone, Debug)]
+#[repr(u32)]
+pub enum Metered {
+    /// The metered status is unknown.
+    Unknown = 0,
+    /// Metered, the value was explicitly configured.
+    Yes = 1,
+    /// Not metered, the value was explicitly configured.
+    No = 2,
+    /// Metered, the value was guessed.
+    GuessYes = 3,
+    /// Not metered, the value was guessed.
+    GuessNo = 4,
+}
+
+impl Metered {
+    /// Check if the connection is metered.
+    ///
+    /// Returns `None` if the metered state is unknown.
+    fn is_metered(self) -> Option<bool> {
+        match self {
+            Self::Yes | Self::GuessYes => Some(true),
+            Self::No | Self::GuessNo => Some(false),
+            Self::Unknown => None,
+        }
+    }
+}
+
 /// NetworkManager connectivity state.
 #[derive(Type, OwnedValue, PartialEq, Debug)]
 #[repr(u32)]
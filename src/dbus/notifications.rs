@@ -0,0 +1,178 @@
+//! Freedesktop notification server DBus interface.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::thread;
+use std::time::Duration;
+
+use calloop::channel::{self, Channel, Sender};
+use tokio::runtime::Builder;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tracing::{error, warn};
+use zbus::fdo::{RequestNameFlags, RequestNameReply};
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::OwnedValue;
+use zbus::{connection, interface};
+
+/// DBus name of the notification server.
+const NAME: &str = "org.freedesktop.Notifications";
+
+/// DBus object path of the notification server.
+const PATH: &str = "/org/freedesktop/Notifications";
+
+/// Notification submitted by a client.
+#[derive(Clone, Debug)]
+pub struct Notification {
+    /// Unique notification ID.
+    pub id: u32,
+
+    /// Summary line.
+    pub summary: String,
+
+    /// Notification body.
+    pub body: String,
+
+    /// Time until the notification expires.
+    ///
+    /// Uses the server's default timeout if `None`.
+    pub timeout: Option<Duration>,
+
+    /// Notification will never expire.
+    pub persistent: bool,
+}
+
+/// Notification server request.
+#[derive(Debug)]
+pub enum NotificationEvent {
+    /// Show or replace a notification.
+    Notify(Notification),
+
+    /// Close a notification.
+    Close(u32),
+}
+
+/// Reason for closing a notification.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CloseReason {
+    Expired = 1,
+    Dismissed = 2,
+    Closed = 3,
+}
+
+/// Handle for reporting closed notifications to clients.
+#[derive(Clone, Debug)]
+pub struct NotificationCloser {
+    tx: UnboundedSender<(u32, CloseReason)>,
+}
+
+impl NotificationCloser {
+    /// Notify clients that a notification was closed.
+    pub fn closed(&self, id: u32, reason: CloseReason) {
+        let _ = self.tx.send((id, reason));
+    }
+}
+
+/// Get calloop channel for incoming notifications.
+pub fn notification_listener(
+) -> Result<(Channel<NotificationEvent>, NotificationCloser), Box<dyn Error>> {
+    let (tx, rx) = channel::channel();
+    let (close_tx, close_rx) = mpsc::unbounded_channel();
+    thread::spawn(|| {
+        let mut builder = Builder::new_current_thread();
+        let runtime = builder.enable_all().build().expect("create tokio runtime");
+        if let Err(err) = runtime.block_on(run_dbus_loop(tx, close_rx)) {
+            error!(module = "notifications", "Notification server failed: {err}");
+        }
+    });
+    Ok((rx, NotificationCloser { tx: close_tx }))
+}
+
+/// Run the DBus notification server.
+async fn run_dbus_loop(
+    tx: Sender<NotificationEvent>,
+    mut close_rx: UnboundedReceiver<(u32, CloseReason)>,
+) -> Result<(), Box<dyn Error>> {
+    let server = NotificationServer { tx, next_id: 1 };
+    let connection = connection::Builder::session()?.serve_at(PATH, server)?.build().await?;
+
+    // Leave existing notification daemons alone, only showing local popups.
+    let flags = RequestNameFlags::DoNotQueue.into();
+    if connection.request_name_with_flags(NAME, flags).await? == RequestNameReply::Exists {
+        warn!(module = "notifications", "Another notification server is running");
+        return Ok(());
+    }
+
+    // Forward closed notifications to clients.
+    let object_server = connection.object_server();
+    let interface = object_server.interface::<_, NotificationServer>(PATH).await?;
+    while let Some((id, reason)) = close_rx.recv().await {
+        let emitter = interface.signal_emitter();
+        NotificationServer::notification_closed(emitter, id, reason as u32).await?;
+    }
+
+    Ok(())
+}
+
+/// DBus notification server implementation.
+struct NotificationServer {
+    tx: Sender<NotificationEvent>,
+    next_id: u32,
+}
+
+#[interface(name = "org.freedesktop.Notifications")]
+impl NotificationServer {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &mut self,
+        _app_name: String,
+        replaces_id: u32,
+        _app_icon: String,
+        summary: String,
+        body: String,
+        _actions: Vec<String>,
+        _hints: HashMap<String, OwnedValue>,
+        expire_timeout: i32,
+    ) -> u32 {
+        // Reuse the ID of the replaced notification.
+        let id = if replaces_id != 0 {
+            replaces_id
+        } else {
+            let id = self.next_id;
+            self.next_id = self.next_id.checked_add(1).unwrap_or(1);
+            id
+        };
+
+        // Negative timeouts request the server's default.
+        let persistent = expire_timeout == 0;
+        let timeout = u64::try_from(expire_timeout)
+            .ok()
+            .filter(|timeout| *timeout > 0)
+            .map(Duration::from_millis);
+
+        let notification = Notification { id, summary, body, timeout, persistent };
+        let _ = self.tx.send(NotificationEvent::Notify(notification));
+
+        id
+    }
+
+    fn close_notification(&self, id: u32) {
+        let _ = self.tx.send(NotificationEvent::Close(id));
+    }
+
+    fn get_capabilities(&self) -> Vec<String> {
+        vec!["body".into()]
+    }
+
+    #[zbus(out_args("name", "vendor", "version", "spec_version"))]
+    fn get_server_information(&self) -> (String, String, String, String) {
+        let version = env!("CARGO_PKG_VERSION").into();
+        ("Epitaph".into(), "Catacombing".into(), version, "1.2".into())
+    }
+
+    #[zbus(signal)]
+    async fn notification_closed(
+        emitter: &SignalEmitter<'_>,
+        id: u32,
+        reason: u32,
+    ) -> zbus::Result<()>;
+}
@@ -0,0 +1,97 @@
+//! Power profiles daemon DBus interface.
+
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use tokio::runtime::Builder;
+use tracing::error;
+use zbus::{proxy, Connection};
+
+/// Name of the power saver profile.
+const POWER_SAVER: &str = "power-saver";
+
+/// Profile restored when the previous profile is unknown.
+const DEFAULT_PROFILE: &str = "balanced";
+
+/// Active power saver profile.
+#[derive(Debug)]
+pub struct PowerSaver {
+    /// Profile active before the power saver was enabled.
+    previous: Arc<Mutex<Option<String>>>,
+}
+
+impl PowerSaver {
+    /// Switch to the power saver profile.
+    pub fn enable() -> Self {
+        let previous = Arc::new(Mutex::new(None));
+
+        let previous_profile = previous.clone();
+        run(async move {
+            let connection = Connection::system().await?;
+            let power_profiles = PowerProfilesProxy::new(&connection).await?;
+
+            // Remember the profile to restore later.
+            let active = power_profiles.active_profile().await?;
+            if active != POWER_SAVER {
+                *previous_profile.lock().unwrap() = Some(active);
+            }
+
+            if let Err(err) = power_profiles.set_active_profile(POWER_SAVER).await {
+                error!(module = "power_profiles", "Power profile change failed: {err}");
+            }
+            Ok(())
+        });
+
+        Self { previous }
+    }
+
+    /// Restore the profile active before the power saver was enabled.
+    ///
+    /// Profiles changed while the power saver was active are left alone.
+    pub fn disable(self) {
+        run(async move {
+            let connection = Connection::system().await?;
+            let power_profiles = PowerProfilesProxy::new(&connection).await?;
+            if power_profiles.active_profile().await? != POWER_SAVER {
+                return Ok(());
+            }
+
+            let previous = self.previous.lock().unwrap().take();
+            let profile = previous.as_deref().unwrap_or(DEFAULT_PROFILE);
+            if let Err(err) = power_profiles.set_active_profile(profile).await {
+                error!(module = "power_profiles", "Power profile change failed: {err}");
+            }
+            Ok(())
+        });
+    }
+}
+
+/// Spawn async executor for a profile update on a new thread.
+fn run<F>(update: F)
+where
+    F: Future<Output = zbus::Result<()>> + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut builder = Builder::new_current_thread();
+        let runtime = builder.enable_all().build().expect("create tokio runtime");
+        if let Err(err) = runtime.block_on(update) {
+            error!(module = "power_profiles", "Could not connect to power-profiles-daemon: {err}");
+        }
+    });
+}
+
+#[proxy(
+    interface = "net.hadess.PowerProfiles",
+    default_service = "net.hadess.PowerProfiles",
+    default_path = "/net/hadess/PowerProfiles"
+)]
+trait PowerProfiles {
+    /// The currently active power profile.
+    #[zbus(property)]
+    fn active_profile(&self) -> zbus::Result<String>;
+
+    /// Set the active power profile.
+    #[zbus(property)]
+    fn set_active_profile(&self, profile: &str) -> zbus::Result<()>;
+}
@@ -0,0 +1,213 @@
+//! Panel status DBus interface for external widgets.
+
+use std::thread;
+
+use calloop::channel::{self, Event, Sender};
+use calloop::LoopHandle;
+use tokio::runtime::Builder;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tracing::error;
+use zbus::{connection, interface};
+
+use crate::{Result, State};
+
+/// DBus name of the status interface.
+const NAME: &str = "org.catacombing.Epitaph";
+
+/// DBus object path of the status interface.
+const PATH: &str = "/org/catacombing/Epitaph";
+
+/// Module values published over DBus.
+#[derive(PartialEq, Eq, Default, Copy, Clone, Debug)]
+pub struct PanelStatus {
+    /// Battery capacity in percent.
+    pub battery_capacity: u8,
+
+    /// Battery is charging.
+    pub battery_charging: bool,
+
+    /// WiFi signal strength in percent, zero while WiFi is disabled.
+    pub wifi_strength: u8,
+
+    /// WiFi connection has internet access.
+    pub wifi_connected: bool,
+
+    /// Cellular signal strength in percent, zero while the modem is disabled.
+    pub cellular_strength: u8,
+
+    /// Modem is registered to a network.
+    pub cellular_registered: bool,
+
+    /// Notification popups are suppressed.
+    pub do_not_disturb: bool,
+}
+
+/// State change requested over DBus.
+#[derive(Copy, Clone, Debug)]
+enum StatusRequest {
+    OpenDrawer,
+    CloseDrawer,
+    ToggleDrawer,
+    DoNotDisturb(bool),
+}
+
+/// Handle for publishing panel status updates.
+#[derive(Debug)]
+pub struct StatusPublisher {
+    tx: UnboundedSender<PanelStatus>,
+    status: Option<PanelStatus>,
+}
+
+impl StatusPublisher {
+    /// Start the DBus status server.
+    pub fn new(event_loop: &LoopHandle<'static, State>) -> Result<Self> {
+        // Forward requests to the event loop.
+        let (request_tx, request_rx) = channel::channel();
+        event_loop.insert_source(request_rx, |event, _, state| {
+            let open = match event {
+                Event::Msg(StatusRequest::OpenDrawer) => true,
+                Event::Msg(StatusRequest::CloseDrawer) => false,
+                Event::Msg(StatusRequest::ToggleDrawer) => !state.drawer().visible(),
+                Event::Msg(StatusRequest::DoNotDisturb(enabled)) => {
+                    state.modules.do_not_disturb.set_enabled(enabled);
+                    state.request_frame();
+                    return;
+                },
+                Event::Closed => return,
+            };
+            state.request_drawer(open);
+        })?;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        thread::spawn(|| {
+            let mut builder = Builder::new_current_thread();
+            let runtime = builder.enable_all().build().expect("create tokio runtime");
+            if let Err(err) = runtime.block_on(run_dbus_loop(rx, request_tx)) {
+                error!(module = "status", "Status server failed: {err}");
+            }
+        });
+
+        Ok(Self { tx, status: None })
+    }
+
+    /// Publish the current panel status.
+    ///
+    /// Updates which do not change any value are ignored.
+    pub fn update(&mut self, status: PanelStatus) {
+        if self.status != Some(status) {
+            self.status = Some(status);
+            let _ = self.tx.send(status);
+        }
+    }
+}
+
+/// Run the DBus status server.
+async fn run_dbus_loop(
+    mut rx: UnboundedReceiver<PanelStatus>,
+    request_tx: Sender<StatusRequest>,
+) -> Result<()> {
+    let server = StatusServer { status: Default::default(), request_tx };
+    let connection =
+        connection::Builder::session()?.name(NAME)?.serve_at(PATH, server)?.build().await?;
+
+    // Emit `PropertiesChanged` for every modified value.
+    let object_server = connection.object_server();
+    let interface = object_server.interface::<_, StatusServer>(PATH).await?;
+    while let Some(status) = rx.recv().await {
+        let mut server = interface.get_mut().await;
+        let old_status = server.status;
+        server.status = status;
+
+        let emitter = interface.signal_emitter();
+        if old_status.battery_capacity != status.battery_capacity {
+            server.battery_capacity_changed(emitter).await?;
+        }
+        if old_status.battery_charging != status.battery_charging {
+            server.battery_charging_changed(emitter).await?;
+        }
+        if old_status.wifi_strength != status.wifi_strength {
+            server.wifi_strength_changed(emitter).await?;
+        }
+        if old_status.wifi_connected != status.wifi_connected {
+            server.wifi_connected_changed(emitter).await?;
+        }
+        if old_status.cellular_strength != status.cellular_strength {
+            server.cellular_strength_changed(emitter).await?;
+        }
+        if old_status.cellular_registered != status.cellular_registered {
+            server.cellular_registered_changed(emitter).await?;
+        }
+        if old_status.do_not_disturb != status.do_not_disturb {
+            server.do_not_disturb_changed(emitter).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// DBus status server implementation.
+struct StatusServer {
+    status: PanelStatus,
+    request_tx: Sender<StatusRequest>,
+}
+
+#[interface(name = "org.catacombing.Epitaph")]
+impl StatusServer {
+    /// Open the drawer.
+    fn open_drawer(&self) {
+        let _ = self.request_tx.send(StatusRequest::OpenDrawer);
+    }
+
+    /// Close the drawer.
+    fn close_drawer(&self) {
+        let _ = self.request_tx.send(StatusRequest::CloseDrawer);
+    }
+
+    /// Open the drawer if it is closed, otherwise close it.
+    fn toggle_drawer(&self) {
+        let _ = self.request_tx.send(StatusRequest::ToggleDrawer);
+    }
+
+    #[zbus(property)]
+    fn battery_capacity(&self) -> u8 {
+        self.status.battery_capacity
+    }
+
+    #[zbus(property)]
+    fn battery_charging(&self) -> bool {
+        self.status.battery_charging
+    }
+
+    #[zbus(property)]
+    fn wifi_strength(&self) -> u8 {
+        self.status.wifi_strength
+    }
+
+    #[zbus(property)]
+    fn wifi_connected(&self) -> bool {
+        self.status.wifi_connected
+    }
+
+    #[zbus(property)]
+    fn cellular_strength(&self) -> u8 {
+        self.status.cellular_strength
+    }
+
+    #[zbus(property)]
+    fn cellular_registered(&self) -> bool {
+        self.status.cellular_registered
+    }
+
+    #[zbus(property)]
+    fn do_not_disturb(&self) -> bool {
+        self.status.do_not_disturb
+    }
+
+    /// Suppress notification popups.
+    ///
+    /// The change is announced once it was applied by the event loop.
+    #[zbus(property)]
+    fn set_do_not_disturb(&mut self, enabled: bool) {
+        let _ = self.request_tx.send(StatusRequest::DoNotDisturb(enabled));
+    }
+}
@@ -1,122 +1,183 @@
 //! Drawer window state.
 
-use std::num::NonZeroU32;
-use std::ptr::NonNull;
-
-use glutin::api::egl::config::Config;
-use glutin::config::GetGlConfig;
-use glutin::context::{ContextApi, ContextAttributesBuilder, Version};
-use glutin::display::GetGlDisplay;
-use glutin::prelude::*;
-use glutin::surface::{SurfaceAttributesBuilder, WindowSurface};
-use raw_window_handle::{RawWindowHandle, WaylandWindowHandle};
-use smithay_client_toolkit::compositor::{CompositorState, Region};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+use smithay_client_toolkit::compositor::CompositorState;
+use smithay_client_toolkit::reexports::client::protocol::wl_output::WlOutput;
 use smithay_client_toolkit::reexports::client::protocol::wl_surface::WlSurface;
-use smithay_client_toolkit::reexports::client::{Proxy, QueueHandle};
-use smithay_client_toolkit::reexports::protocols::wp::viewporter::client::wp_viewport::WpViewport;
+use smithay_client_toolkit::reexports::client::QueueHandle;
 use smithay_client_toolkit::shell::wlr_layer::{
-    Anchor, Layer, LayerShell, LayerSurface, LayerSurfaceConfigure,
+    Anchor, KeyboardInteractivity, LayerShell, LayerSurfaceConfigure,
 };
-use smithay_client_toolkit::shell::WaylandSurface;
+use tracing::error;
 
-use crate::module::{DrawerModule, Module, Slider, Toggle};
-use crate::panel::PANEL_HEIGHT;
+use crate::config::{DrawerConfig, FontConfig};
+use crate::module::{
+    Cycle, DrawerModule, Info, Media, MediaAction, ModuleGroup, ModuleList, Slider, Toggle,
+};
 use crate::protocols::fractional_scale::FractionalScaleManager;
 use crate::protocols::viewporter::Viewporter;
 use crate::renderer::{RectRenderer, Renderer, TextRenderer};
+use crate::surface::WindowSurface;
 use crate::text::{GlRasterizer, GlSubTexture, Svg};
-use crate::vertex::{RectVertex, VertexBatcher};
-use crate::{gl, Result, Size, State};
+use crate::theme::Theme;
+use crate::vertex::{Rect, RectQuad, VertexBatcher};
+use crate::{geometry, gl, Result, Size, State};
 
 /// Height of the handle for single-tap closing the drawer.
 pub const HANDLE_HEIGHT: u32 = 32;
 
-/// Slider module height.
-///
-/// This should be less than `MODULE_SIZE`.
-const SLIDER_HEIGHT: f64 = (MODULE_SIZE - 16) as f64;
+/// Color of the badge on modules whose last action failed.
+const ERROR_COLOR: [u8; 4] = [204, 68, 68, 255];
 
-/// Color of slider handle and active buttons,
-const MODULE_COLOR_FG: [u8; 4] = [85, 85, 85, 255];
+/// Diameter of the error badge.
+const BADGE_SIZE: u32 = 12;
 
-/// Color of the slider tray and inactive buttons.
-const MODULE_COLOR_BG: [u8; 4] = [51, 51, 51, 255];
+/// Color of the history graph on toggle buttons.
+const GRAPH_COLOR: [u8; 4] = [136, 136, 136, 255];
 
-/// Padding between drawer modules.
-const MODULE_PADDING: f64 = 16.;
-
-/// Drawer padding to the screen edges.
-const EDGE_PADDING: f64 = 24.;
+/// Height of the history graph on toggle buttons.
+const GRAPH_HEIGHT: u32 = 8;
 
 /// Drawer module width and height.
-const MODULE_SIZE: u32 = 64;
+pub const MODULE_SIZE: u32 = 64;
 
 /// Drawer module icon height.
 const ICON_HEIGHT: u32 = 32;
 
+/// Drawer module icon height for buttons with a label.
+const LABELED_ICON_HEIGHT: u32 = 24;
+
+/// Height of the title below toggle buttons.
+const TITLE_HEIGHT: u32 = 20;
+
+/// Height of the header above a module group.
+const HEADER_HEIGHT: u32 = 24;
+
+/// Font size of the emergency information.
+const EMERGENCY_FONT_SIZE: f32 = 16.;
+
+/// Separator between module summaries.
+const SUMMARY_SEPARATOR: &str = " · ";
+
+/// Minimum horizontal distance before a module touch is considered a swipe.
+const MIN_SWIPE_DISTANCE: f64 = 48.;
+
+/// Opacity of info text with stale content.
+const STALE_ALPHA: f32 = 0.5;
+
+/// Maximum number of lines in info modules.
+const INFO_LINES: usize = 2;
+
+/// Number of buttons of the media controls.
+const MEDIA_BUTTONS: i16 = 3;
+
+/// Media control button icon height.
+const MEDIA_ICON_HEIGHT: u32 = 20;
+
+/// Slider value change per scroll wheel step.
+const SCROLL_STEP: f64 = 0.05;
+
+/// Drawer window visibility.
+///
+/// Frames are only requested and drawn while the drawer is not hidden.
+#[derive(Deserialize, Serialize, Copy, Clone, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    /// Window is unmapped.
+    #[default]
+    Hidden,
+    /// Window is mapped and moving towards its fully open position.
+    Opening,
+    /// Window is fully open.
+    Open,
+    /// Window is mapped and moving towards its hidden position.
+    Closing,
+}
+
 pub struct Drawer {
-    /// Current drawer Y-offset.
-    pub offset: f64,
     /// Drawer currently in the process of being opened/closed.
     pub offsetting: bool,
 
+    /// Current drawer Y-offset.
+    offset: f64,
+    visibility: Visibility,
+
     opening_icon: Option<GlSubTexture>,
     closing_icon: Option<GlSubTexture>,
-    viewport: Option<WpViewport>,
-    window: Option<LayerSurface>,
     queue: QueueHandle<State>,
     touch_module: Option<usize>,
     touch_position: (f64, f64),
+    touch_start: (f64, f64),
+
+    /// Touch position inside the touched module, relative to its width.
+    touch_module_x: f64,
+
+    surface: WindowSurface,
+    output: Option<WlOutput>,
     touch_id: Option<i32>,
-    frame_pending: bool,
-    renderer: Renderer,
-    scale_factor: f64,
-    size: Size,
-}
+    long_pressed: bool,
+    config: DrawerConfig,
 
-impl Drawer {
-    pub fn new(queue: QueueHandle<State>, egl_config: &Config) -> Result<Self> {
-        // Default to 1x1 initial size since 0x0 EGL surfaces are illegal.
-        let size = Size { width: 1, height: 1 };
+    /// Height of the panel above the drawer modules.
+    panel_height: u32,
 
-        let context_attribules = ContextAttributesBuilder::new()
-            .with_context_api(ContextApi::Gles(Some(Version::new(2, 0))))
-            .build(None);
+    /// Height of the wrapped emergency information in physical pixels.
+    emergency_height: i16,
 
-        let egl_context =
-            unsafe { egl_config.display().create_context(egl_config, &context_attribules)? };
+    /// Failed actions, by module index.
+    errors: HashMap<usize, ModuleAction>,
 
-        // Initialize the renderer.
-        let renderer = Renderer::new(egl_context, 1.)?;
+    /// Color scheme of the last frame, which determines the module layout.
+    theme: &'static Theme,
+}
+
+impl Drawer {
+    pub fn new(
+        queue: QueueHandle<State>,
+        renderer: Rc<RefCell<Renderer>>,
+        config: DrawerConfig,
+        panel_height: u32,
+    ) -> Result<Self> {
+        let surface = WindowSurface::new(queue.clone(), renderer);
 
         Ok(Self {
-            renderer,
+            surface,
+            config,
+            panel_height,
             queue,
-            size,
-            scale_factor: 1.,
-            frame_pending: Default::default(),
+            output: Default::default(),
             touch_position: Default::default(),
+            touch_start: Default::default(),
             touch_module: Default::default(),
+            touch_module_x: Default::default(),
             opening_icon: Default::default(),
             closing_icon: Default::default(),
             offsetting: Default::default(),
-            viewport: Default::default(),
+            visibility: Default::default(),
+            long_pressed: Default::default(),
             touch_id: Default::default(),
+            emergency_height: Default::default(),
+            errors: Default::default(),
             offset: Default::default(),
-            window: Default::default(),
+            theme: Theme::select(false),
         })
     }
 
-    /// Create the window.
+    /// Create the window on the specified output.
     pub fn show(
         &mut self,
         fractional_scale: &FractionalScaleManager,
         compositor: &CompositorState,
         viewporter: &Viewporter,
         layer: &LayerShell,
+        output: Option<&WlOutput>,
     ) -> Result<()> {
         // Ensure the window is not mapped yet.
-        if self.window.is_some() {
+        if self.visibility != Visibility::Hidden {
             return Ok(());
         }
 
@@ -124,71 +185,105 @@ impl Drawer {
         let surface = compositor.create_surface(&self.queue);
 
         // Setup layer shell surface.
+        let layer_type = self.config.layer.into();
         let window =
-            layer.create_layer_surface(&self.queue, surface, Layer::Overlay, Some("panel"), None);
+            layer.create_layer_surface(&self.queue, surface, layer_type, Some("panel"), output);
         window.set_anchor(Anchor::LEFT | Anchor::TOP | Anchor::RIGHT | Anchor::BOTTOM);
-        window.set_exclusive_zone(-1);
-
-        // Initialize fractional scaling protocol.
-        fractional_scale.fractional_scaling(&self.queue, window.wl_surface());
-
-        // Initialize viewporter protocol.
-        let viewport = viewporter.viewport(&self.queue, window.wl_surface());
+        window.set_exclusive_zone(if self.config.overlap_panels { -1 } else { 0 });
 
-        // Set initial viewport size based on last resize.
-        let logical_size = self.size / self.scale_factor;
-        viewport.set_destination(logical_size.width, logical_size.height);
+        // Accept keyboard focus once clicked, to allow closing it with Escape.
+        window.set_keyboard_interactivity(KeyboardInteractivity::OnDemand);
 
-        // Reset frame request tracking since we created a new surface.
-        self.frame_pending = false;
-
-        self.viewport = Some(viewport);
-        self.window = Some(window);
+        self.surface.show(fractional_scale, viewporter, window);
+        self.visibility = Visibility::Opening;
+        self.output = output.cloned();
 
         Ok(())
     }
 
     /// Destroy the window.
     pub fn hide(&mut self) {
-        self.renderer.set_surface(None);
-        self.window = None;
+        self.visibility = Visibility::Hidden;
+        self.offset = 0.;
+        self.output = None;
+        self.surface.hide();
+    }
+
+    /// Check if the window is mapped.
+    pub fn visible(&self) -> bool {
+        self.visibility != Visibility::Hidden
+    }
+
+    /// Current drawer visibility.
+    pub fn visibility(&self) -> Visibility {
+        self.visibility
+    }
+
+    /// Current drawer Y-offset.
+    pub fn offset(&self) -> f64 {
+        self.offset
+    }
+
+    /// Move the drawer to a new Y-offset.
+    ///
+    /// This is ignored while the drawer is hidden.
+    pub fn set_offset(&mut self, offset: f64) {
+        if self.visibility == Visibility::Hidden {
+            return;
+        }
+
+        self.visibility = if offset >= self.max_offset() {
+            Visibility::Open
+        } else if offset < self.offset || offset <= 0. {
+            Visibility::Closing
+        } else if offset > self.offset {
+            Visibility::Opening
+        } else {
+            self.visibility
+        };
+        self.offset = offset;
     }
 
     /// Render the panel.
     pub fn draw(
         &mut self,
         compositor: &CompositorState,
-        modules: &mut [&mut dyn Module],
+        modules: &mut impl ModuleList,
+        theme: &'static Theme,
         opening: bool,
     ) -> Result<()> {
-        self.frame_pending = false;
+        self.surface.frame_done();
+        self.theme = theme;
+
+        // Ignore frame callbacks racing with the window's destruction.
+        if self.visibility == Visibility::Hidden {
+            return Ok(());
+        }
+
+        let scale_factor = self.surface.scale_factor();
+        let size = self.surface.size();
 
         // Clamp offset, to ensure minimize works immediately.
         let max_offset = self.max_offset();
         self.offset = self.offset.min(max_offset).max(0.);
 
         // Calculate drawer offset.
-        let offset = (self.offset * self.scale_factor).min(self.size.height as f64);
-        let y_offset = self.size.height - offset.round() as i32;
+        let offset = geometry::to_physical(self.offset, scale_factor).min(size.height);
+        let y_offset = size.height - offset;
 
         // Skip rendering if there's nothing to draw.
-        if y_offset >= self.size.height {
+        if y_offset >= size.height {
             return Ok(());
         }
 
         // Update opaque region.
-        let region = Region::new(compositor).ok();
-        if let Some((window, region)) = self.window.as_ref().zip(region) {
-            // Calculate vertical opaque region start.
-            let logical_size = self.size / self.scale_factor;
-            let drawer_height = logical_size.height - PANEL_HEIGHT;
-            let y = (self.offset - drawer_height as f64).max(0.).round() as i32;
+        let logical_size = self.surface.logical_size();
+        let drawer_height = logical_size.height - self.panel_height as i32;
+        let y = (self.offset - drawer_height as f64).max(0.).round() as i32;
+        let height = self.offset.round() as i32;
+        self.surface.set_opaque_region(compositor, 0, y, logical_size.width, height);
 
-            region.add(0, y, logical_size.width, self.offset.round() as i32);
-            window.wl_surface().set_opaque_region(Some(region.wl_region()));
-        }
-
-        self.renderer.draw(|renderer| unsafe {
+        self.surface.draw(|renderer| unsafe {
             // Dynamically initialize icons on first draw.
             if self.opening_icon.is_none() {
                 let texture =
@@ -200,80 +295,189 @@ impl Drawer {
                 self.closing_icon = texture.ok();
             }
 
-            // Transparently clear entire screen.
+            // Dim the screen behind the drawer, fading in while it's opening.
+            let dim_alpha = self.config.dim_alpha * (self.offset / max_offset) as f32;
             gl::Disable(gl::SCISSOR_TEST);
-            gl::Viewport(0, 0, self.size.width, self.size.height);
-            gl::ClearColor(0.0, 0.0, 0.0, 0.0);
+            gl::Viewport(0, 0, size.width, size.height);
+            gl::ClearColor(0.0, 0.0, 0.0, dim_alpha);
             gl::Clear(gl::COLOR_BUFFER_BIT);
 
             // Setup drawer to render at correct offset.
-            let panel_height = (PANEL_HEIGHT as f64 * renderer.scale_factor).round() as i32;
+            let content_height =
+                content_height(logical_size.height, self.panel_height, renderer.scale_factor);
             gl::Enable(gl::SCISSOR_TEST);
-            gl::Scissor(0, y_offset, self.size.width, self.size.height - panel_height);
-            gl::Viewport(0, y_offset, self.size.width, self.size.height);
+            gl::Scissor(0, y_offset, size.width, content_height);
+            gl::Viewport(0, y_offset, size.width, size.height);
 
             // Draw background for the offset viewport.
-            gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+            theme.set_clear_color();
             gl::Clear(gl::COLOR_BUFFER_BIT);
 
-            // Add modules to rendering batch.
-            let mut run = DrawerRun::new(renderer);
-            for module in modules.iter_mut().filter_map(|module| module.drawer_module()) {
-                run.batch(module);
-            }
+            self.emergency_height = Self::draw_modules(
+                renderer,
+                modules,
+                &self.config,
+                theme,
+                self.panel_height,
+                &self.errors,
+            );
 
             // Add drawer handle to rendering batch.
             let opening = opening && self.offset != max_offset;
             let handle_icon = if opening { &self.opening_icon } else { &self.closing_icon };
             if let Some(handle_icon) = handle_icon {
-                let handle_height = (HANDLE_HEIGHT as f64 * self.scale_factor).round() as i16;
-                let handle_x = (self.size.width as i16 - handle_height) / 2;
-                let handle_y = self.size.height as i16 - handle_height;
-                for vertex in handle_icon.vertices(handle_x, handle_y).into_iter().flatten() {
-                    run.text_batcher.push(handle_icon.texture_id, vertex);
+                let handle_height = geometry::to_physical(HANDLE_HEIGHT, scale_factor) as i16;
+                let handle_x = (size.width as i16 - handle_height) / 2;
+                let handle_y = size.height as i16 - handle_height;
+                if let Some(quad) = handle_icon.quad(handle_x, handle_y) {
+                    renderer.text_batcher.push(handle_icon.texture_id, quad);
                 }
             }
 
-            // Draw batched textures.
-            run.draw();
+            // Draw drawer handle.
+            renderer.rasterizer.flush_uploads();
+            let mut batches = renderer.text_batcher.batches();
+            while let Some(batch) = batches.next() {
+                batch.draw();
+            }
 
             Ok(())
         })
     }
 
+    /// Render just the drawer modules.
+    ///
+    /// Returns the height of the emergency information, which offsets all
+    /// modules below it.
+    ///
+    /// Modules with failed actions in `errors` are marked with a badge.
+    pub fn draw_modules(
+        renderer: &mut Renderer,
+        modules: &mut impl ModuleList,
+        config: &DrawerConfig,
+        theme: &Theme,
+        panel_height: u32,
+        errors: &HashMap<usize, ModuleAction>,
+    ) -> i16 {
+        let size = renderer.size;
+        let mut positioner =
+            ModulePositioner::new(size, renderer.scale_factor, panel_height, config, theme);
+        let emergency =
+            EmergencyText::new(&mut renderer.rasterizer, &config.emergency_info, &positioner)
+                .unwrap_or_default();
+        positioner.emergency_height = emergency.height();
+        let layout = positioner.layout(modules);
+
+        let mut run = DrawerRun::new(renderer, positioner, theme);
+        for item in layout {
+            match item {
+                LayoutItem::Emergency { y } => run.batch_emergency(&emergency, y),
+                LayoutItem::Summary { y } => {
+                    let _ = run.batch_line(&panel_summary(modules), y);
+                },
+                LayoutItem::Header { group, y } => {
+                    let _ = run.batch_line(group.title(), y);
+                },
+                LayoutItem::Module { index, x, y, width } => {
+                    if let Some(module) = modules.drawer_module(index) {
+                        run.batch(module, x, y);
+                    }
+                    if errors.contains_key(&index) {
+                        run.batch_badge(x, y, width);
+                    }
+                },
+            }
+        }
+        run.draw();
+
+        emergency.height()
+    }
+
+    /// Mark a module's asynchronously failed action with a badge.
+    pub fn set_error(&mut self, index: usize, action: ModuleAction) {
+        self.errors.insert(index, action);
+        self.request_frame();
+    }
+
     /// Check if the panel owns this surface.
     pub fn owns_surface(&self, surface: &WlSurface) -> bool {
-        self.window.as_ref().is_some_and(|window| window.wl_surface() == surface)
+        self.surface.owns_surface(surface)
     }
 
     /// Update the DPI scale factor.
     pub fn set_scale_factor(&mut self, scale_factor: f64) {
-        let factor_change = scale_factor / self.scale_factor;
-        self.scale_factor = scale_factor;
+        self.surface.set_scale_factor(scale_factor);
+        self.resized();
+    }
 
-        self.resize(self.size * factor_change);
+    /// Output the drawer is shown on.
+    ///
+    /// This is `None` while hidden, or if the compositor picked the output.
+    pub fn output(&self) -> Option<&WlOutput> {
+        self.output.as_ref()
+    }
+
+    /// Check if the drawer is shown on an output.
+    ///
+    /// This also covers the compositor's choice of output, once the drawer
+    /// surface entered it.
+    pub fn on_output(&self, output: &WlOutput) -> bool {
+        self.output.as_ref() == Some(output) || self.surface.on_output(output)
+    }
+
+    /// Update the outputs the drawer surface is visible on.
+    pub fn set_entered(&mut self, output: &WlOutput, entered: bool) {
+        self.surface.set_entered(output, entered);
+    }
+
+    /// Resize and redraw the drawer after its output changed.
+    ///
+    /// The `output_size` is the new logical size of the output, if known, and
+    /// `panel_zone` the exclusive zone reserved by the panel on it. The
+    /// `rescale` flag indicates a change of its mode, scale or transform.
+    pub fn output_changed(
+        &mut self,
+        fractional_scale: &FractionalScaleManager,
+        output_size: Option<Size>,
+        panel_zone: i32,
+        rescale: bool,
+    ) {
+        if self.visibility == Visibility::Hidden {
+            return;
+        }
+
+        let size = output_size.map(|mut size| {
+            if !self.config.overlap_panels {
+                size.height -= panel_zone;
+            }
+            size
+        });
+        self.surface.output_changed(fractional_scale, size, rescale);
+        self.resized();
+        self.request_frame();
     }
 
     /// Reconfigure the window.
     pub fn reconfigure(&mut self, configure: LayerSurfaceConfigure) {
-        let new_width = configure.new_size.0 as i32;
-        let new_height = configure.new_size.1 as i32;
-        let size = Size::new(new_width, new_height) * self.scale_factor;
-        self.resize(size);
+        self.surface.reconfigure(configure, None);
+        self.resized();
     }
 
     /// Request a new frame.
     pub fn request_frame(&mut self) {
-        // Ensure window is mapped without pending frame.
-        let window = match &self.window {
-            Some(window) if !self.frame_pending => window,
-            _ => return,
-        };
-        self.frame_pending = true;
+        if self.visibility != Visibility::Hidden {
+            self.surface.request_frame();
+        }
+    }
+
+    /// Handle a frame callback from the compositor.
+    pub fn frame_received(&mut self) {
+        self.surface.frame_received();
+    }
 
-        let surface = window.wl_surface();
-        surface.frame(&self.queue, surface.clone());
-        surface.commit();
+    /// Recreate the window's EGL surface if it stopped receiving frames.
+    pub fn recover_stall(&mut self) -> Option<WlSurface> {
+        self.surface.recover_stall()
     }
 
     /// Handle touch press events.
@@ -281,21 +485,24 @@ impl Drawer {
         &mut self,
         id: i32,
         position: (f64, f64),
-        modules: &mut [&mut dyn Module],
+        modules: &mut impl ModuleList,
     ) -> TouchStart {
-        self.touch_position = scale_touch(position, self.scale_factor);
+        self.touch_position = geometry::to_physical_position(position, self.surface.scale_factor());
+        self.touch_start = self.touch_position;
+        self.long_pressed = false;
         self.touch_id = Some(id);
 
         // Find touched module.
-        let positioner = ModulePositioner::new(self.size.into(), self.scale_factor);
+        let positioner = self.positioner();
         let (index, x) = match positioner.module_position(modules, self.touch_position) {
             Some((index, x, _)) => (index, x),
             None => return TouchStart { requires_redraw: false, module_touched: false },
         };
         self.touch_module = Some(index);
+        self.touch_module_x = x;
 
         // Update sliders.
-        let requires_redraw = match modules[index].drawer_module() {
+        let requires_redraw = match modules.drawer_module(index) {
             Some(DrawerModule::Slider(slider)) => {
                 let _ = slider.set_value(x.clamp(0., 1.));
                 true
@@ -311,16 +518,16 @@ impl Drawer {
         &mut self,
         id: i32,
         position: (f64, f64),
-        modules: &mut [&mut dyn Module],
+        modules: &mut impl ModuleList,
     ) -> bool {
         if Some(id) != self.touch_id {
             return false;
         }
-        self.touch_position = scale_touch(position, self.scale_factor);
+        self.touch_position = geometry::to_physical_position(position, self.surface.scale_factor());
 
         // Update slider position.
-        let positioner = ModulePositioner::new(self.size.into(), self.scale_factor);
-        match self.touch_module.and_then(|module| modules[module].drawer_module()) {
+        let positioner = self.positioner();
+        match self.touch_module.and_then(|index| modules.drawer_module(index)) {
             Some(DrawerModule::Slider(slider)) => {
                 let relative_x = self.touch_position.0 - positioner.edge_padding as f64;
                 let fractional_x = relative_x / positioner.slider_size.width as f64;
@@ -333,88 +540,174 @@ impl Drawer {
         }
     }
 
+    /// Handle long-press of the active touch.
+    pub fn long_press(&mut self, modules: &mut impl ModuleList) -> bool {
+        let index = match self.touch_module {
+            Some(index) => index,
+            None => return false,
+        };
+
+        // Trigger secondary action of the touched button.
+        let handled = match modules.drawer_module(index) {
+            Some(DrawerModule::Toggle(toggle)) => match toggle.long_press() {
+                Ok(handled) => {
+                    if handled {
+                        self.errors.remove(&index);
+                    }
+                    handled
+                },
+                Err(err) => {
+                    error!(module = "drawer", "Drawer module long-press failed: {err}");
+                    self.errors.insert(index, ModuleAction::LongPress);
+                    true
+                },
+            },
+            _ => false,
+        };
+
+        // Prevent toggling the button on touch release.
+        self.long_pressed |= handled;
+
+        handled
+    }
+
     /// Handle touch release events.
-    pub fn touch_up(&mut self, id: i32, modules: &mut [&mut dyn Module]) -> bool {
+    pub fn touch_up(&mut self, id: i32, modules: &mut impl ModuleList) -> TouchEnd {
+        let mut touch_end = TouchEnd::default();
         if Some(id) != self.touch_id {
-            return false;
+            return touch_end;
         }
 
-        // Handle button toggles on touch up.
-        let mut dirty = false;
-        match self.touch_module.and_then(|module| modules[module].drawer_module()) {
-            Some(DrawerModule::Toggle(toggle)) => {
-                let _ = toggle.toggle();
-                dirty = true;
-            },
-            Some(DrawerModule::Slider(slider)) => {
-                let _ = slider.on_touch_up();
-                dirty = true;
-            },
-            _ => (),
+        // Activate the touched module, retrying its last failed action.
+        if let Some(index) = self.touch_module {
+            let failed = self.errors.get(&index).copied();
+            let media_action = self.positioner().media_action(self.touch_module_x);
+            match modules.drawer_module(index) {
+                Some(DrawerModule::Toggle(_)) if self.long_pressed => (),
+                Some(DrawerModule::Info(info)) if self.swiped() => {
+                    if let Err(err) = info.swipe() {
+                        error!(module = "drawer", "Drawer module swipe failed: {err}");
+                        touch_end.error = Some(err.to_string());
+                    }
+                    touch_end.requires_redraw = true;
+                },
+                Some(module) => {
+                    // Redraw even for info modules, since taps can change their text.
+                    let (action, result) = activate(module, failed, media_action);
+                    touch_end.requires_redraw = true;
+
+                    match result {
+                        Ok(()) => {
+                            self.errors.remove(&index);
+                        },
+                        Err(err) => {
+                            error!(module = "drawer", "Drawer module action failed: {err}");
+                            self.errors.insert(index, action);
+                            touch_end.error = Some(err.to_string());
+                        },
+                    }
+                },
+                None => (),
+            }
         }
 
         // Reset touch state.
         self.touch_module = None;
         self.touch_id = None;
 
-        dirty
+        touch_end
     }
 
-    /// Drawer offset when fully visible.
-    pub fn max_offset(&self) -> f64 {
-        self.size.height as f64 / self.scale_factor
+    /// Check if the active touch moved far enough horizontally to be a swipe.
+    fn swiped(&self) -> bool {
+        let x_delta = (self.touch_position.0 - self.touch_start.0).abs();
+        let y_delta = (self.touch_position.1 - self.touch_start.1).abs();
+        let min_distance = MIN_SWIPE_DISTANCE * self.surface.scale_factor();
+        x_delta >= min_distance && x_delta > y_delta * 2.
     }
 
-    /// Resize the window.
-    fn resize(&mut self, size: Size) {
-        self.size = size;
-
-        self.resize_surface(size);
-
-        // Update viewporter buffer target size.
-        let logical_size = size / self.scale_factor;
-        if let Some(viewport) = &self.viewport {
-            viewport.set_destination(logical_size.width, logical_size.height);
+    /// Handle touch cancellation.
+    ///
+    /// Slider changes are kept, but no button is activated.
+    pub fn touch_cancel(&mut self, modules: &mut impl ModuleList) -> bool {
+        // Finish slider updates, since their value was already applied.
+        let mut dirty = false;
+        if let Some(DrawerModule::Slider(slider)) =
+            self.touch_module.and_then(|index| modules.drawer_module(index))
+        {
+            let _ = slider.on_touch_up();
+            dirty = true;
         }
 
-        // Ensure drawer stays fully open after resize.
-        if !self.offsetting && self.offset > 0. {
-            self.offset = self.max_offset();
-        }
+        // Reset touch state.
+        self.long_pressed = false;
+        self.touch_module = None;
+        self.touch_id = None;
+
+        dirty
     }
 
-    /// Resize EGL surface, dynamically initializing it on first resize.
-    fn resize_surface(&mut self, size: Size) {
-        // Resize if the surface exists already.
-        if self.renderer.has_surface() {
-            let _ = self.renderer.resize(size, self.scale_factor);
-            self.closing_icon = None;
-            self.opening_icon = None;
-            return;
+    /// Handle scroll wheel steps.
+    ///
+    /// This adjusts the slider below the pointer, returning `true` if a redraw
+    /// is required.
+    pub fn scroll(
+        &mut self,
+        position: (f64, f64),
+        steps: f64,
+        modules: &mut impl ModuleList,
+    ) -> bool {
+        let position = geometry::to_physical_position(position, self.surface.scale_factor());
+        let index = match self.positioner().module_position(modules, position) {
+            Some((index, ..)) => index,
+            None => return false,
+        };
+
+        let slider = match modules.drawer_module(index) {
+            Some(DrawerModule::Slider(slider)) => slider,
+            _ => return false,
+        };
+
+        let value = (slider.get_value() + steps * SCROLL_STEP).clamp(0., 1.);
+        if let Err(err) = slider.set_value(value).and_then(|_| slider.on_touch_up()) {
+            error!(module = "drawer", "Drawer slider update failed: {err}");
         }
 
-        // Otherwise create a new EGL surface of the desired size.
+        true
+    }
 
-        let window = match &self.window {
-            Some(window) => window,
-            None => return,
-        };
+    /// Check if a pointer position is above a module.
+    pub fn hovers_module(&self, position: (f64, f64), modules: &mut impl ModuleList) -> bool {
+        let position = geometry::to_physical_position(position, self.surface.scale_factor());
+        self.positioner().module_position(modules, position).is_some()
+    }
+
+    /// Drawer offset when fully visible.
+    pub fn max_offset(&self) -> f64 {
+        self.surface.logical_size().height as f64
+    }
 
-        // Get raw window handle.
-        let window = NonNull::new(window.wl_surface().id().as_ptr().cast()).unwrap();
-        let wayland_window_handle = WaylandWindowHandle::new(window);
-        let raw_window_handle = RawWindowHandle::Wayland(wayland_window_handle);
+    /// Get module positioner for the current window size.
+    fn positioner(&self) -> ModulePositioner {
+        let size = self.surface.size().into();
+        let scale_factor = self.surface.scale_factor();
+        let mut positioner =
+            ModulePositioner::new(size, scale_factor, self.panel_height, &self.config, self.theme);
+        positioner.emergency_height = self.emergency_height;
+        positioner
+    }
 
-        let config = self.renderer.egl_context().config();
-        let surface_attributes = SurfaceAttributesBuilder::<WindowSurface>::new().build(
-            raw_window_handle,
-            NonZeroU32::new(size.width as u32).unwrap(),
-            NonZeroU32::new(size.height as u32).unwrap(),
-        );
+    /// Update drawer state after a window resize.
+    fn resized(&mut self) {
+        // Re-rasterize icons at the new scale.
+        self.closing_icon = None;
+        self.opening_icon = None;
 
-        let display = config.display();
-        let egl_surface = unsafe { display.create_window_surface(&config, &surface_attributes) };
-        self.renderer.set_surface(egl_surface.ok());
+        // Ensure drawer stays fully open after resize.
+        if !self.offsetting && self.offset > 0. {
+            self.offset = self.max_offset();
+            self.visibility = Visibility::Open;
+        }
     }
 }
 
@@ -425,40 +718,156 @@ pub struct TouchStart {
     pub module_touched: bool,
 }
 
+/// Drawer touch release status.
+#[derive(Default)]
+pub struct TouchEnd {
+    pub requires_redraw: bool,
+
+    /// Error of the activated module's action.
+    pub error: Option<String>,
+}
+
+/// Drawer module action, stored to retry it after a failure.
+#[derive(Copy, Clone)]
+pub enum ModuleAction {
+    /// Switch a toggle to the specified state.
+    Toggle(bool),
+    /// Trigger a toggle's secondary action.
+    LongPress,
+    /// Apply a slider's value.
+    Slider,
+    /// Switch a cycle button to the specified state.
+    Cycle(usize),
+    /// Tap an info module.
+    Info,
+    /// Send a command to a media player.
+    Media,
+}
+
+/// Activate a module, or retry its failed action.
+///
+/// Media commands are never retried, since they depend on the touched button.
+///
+/// Returns the performed action and its result.
+fn activate(
+    module: DrawerModule,
+    failed: Option<ModuleAction>,
+    media_action: MediaAction,
+) -> (ModuleAction, Result<()>) {
+    match module {
+        DrawerModule::Toggle(toggle) if matches!(failed, Some(ModuleAction::LongPress)) => {
+            (ModuleAction::LongPress, toggle.long_press().map(|_| ()))
+        },
+        DrawerModule::Toggle(toggle) => {
+            let enabled = match failed {
+                Some(ModuleAction::Toggle(enabled)) => enabled,
+                _ => !toggle.enabled(),
+            };
+
+            // Toggles might report the new state despite failing to apply it,
+            // so the target state is re-applied directly.
+            (ModuleAction::Toggle(enabled), toggle.set_enabled(enabled))
+        },
+        DrawerModule::Slider(slider) => (ModuleAction::Slider, slider.on_touch_up()),
+        DrawerModule::Cycle(cycle) => {
+            let next = match failed {
+                Some(ModuleAction::Cycle(next)) => next,
+                _ => (cycle.active() + 1) % cycle.states().len().max(1),
+            };
+            (ModuleAction::Cycle(next), cycle.set_active(next))
+        },
+        DrawerModule::Info(info) => (ModuleAction::Info, info.tap()),
+        DrawerModule::Media(media) => (ModuleAction::Media, media.control(media_action)),
+    }
+}
+
 /// Batched drawer module rendering.
 struct DrawerRun<'a> {
     text_batcher: &'a mut VertexBatcher<TextRenderer>,
     rect_batcher: &'a mut VertexBatcher<RectRenderer>,
     rasterizer: &'a mut GlRasterizer,
     positioner: ModulePositioner,
-    column: i16,
-    row: i16,
+    theme: &'a Theme,
 }
 
 impl<'a> DrawerRun<'a> {
-    fn new(renderer: &'a mut Renderer) -> Self {
+    fn new(renderer: &'a mut Renderer, positioner: ModulePositioner, theme: &'a Theme) -> Self {
         Self {
-            positioner: ModulePositioner::new(renderer.size, renderer.scale_factor),
             rasterizer: &mut renderer.rasterizer,
             text_batcher: &mut renderer.text_batcher,
             rect_batcher: &mut renderer.rect_batcher,
-            column: 0,
-            row: 0,
+            positioner,
+            theme,
         }
     }
 
-    /// Add a drawer module to the run.
-    fn batch(&mut self, module: DrawerModule) {
+    /// Add a drawer module at the specified origin to the run.
+    fn batch(&mut self, module: DrawerModule, x: i16, y: i16) {
         let _ = match module {
-            DrawerModule::Toggle(toggle) => self.batch_toggle(toggle),
-            DrawerModule::Slider(slider) => self.batch_slider(slider),
+            DrawerModule::Toggle(toggle) => self.batch_toggle(toggle, x, y),
+            DrawerModule::Slider(slider) => self.batch_slider(slider, x, y),
+            DrawerModule::Cycle(cycle) => self.batch_cycle(cycle, x, y),
+            DrawerModule::Info(info) => self.batch_info(info, x, y),
+            DrawerModule::Media(media) => self.batch_media(media, x, y),
         };
     }
 
+    /// Add an error badge to the top-right corner of a module.
+    fn batch_badge(&mut self, x: i16, y: i16, width: i16) {
+        let window_size = self.positioner.size;
+
+        let size = self.positioner.badge_size;
+        let badge_x = x + width - size - size / 2;
+        let badge_y = y + size / 2;
+        let radius = size / 2;
+        let badge = RectQuad::new(
+            window_size,
+            Rect::new(badge_x, badge_y, size, size),
+            radius,
+            &ERROR_COLOR,
+        );
+        self.rect_batcher.push(0, badge);
+    }
+
+    /// Add a single line of text, like a group header, to the drawer.
+    fn batch_line(&mut self, text: &str, y: i16) -> Result<()> {
+        let metrics = self.rasterizer.metrics()?;
+        let line_height = metrics.line_height as i16;
+        let baseline = (metrics.line_height + metrics.descent as f64) as i16;
+        let header_y = y + (self.positioner.header_height - line_height) / 2 + baseline;
+
+        // Left-align the header with the modules below it.
+        let width = self.positioner.slider_size.width;
+        let lines = self.rasterizer.layout_string(text, width, 1);
+        let mut glyph_x = self.positioner.edge_padding;
+        for glyph in lines.into_iter().flatten() {
+            if let Some(quad) = glyph.quad(glyph_x, header_y) {
+                self.text_batcher.push(glyph.texture_id, quad);
+            }
+            glyph_x += glyph.advance.0 as i16;
+        }
+
+        Ok(())
+    }
+
+    /// Add the wrapped emergency information to the drawer.
+    fn batch_emergency(&mut self, emergency: &EmergencyText, y: i16) {
+        let mut line_y = y + emergency.baseline;
+        for line in &emergency.lines {
+            let mut glyph_x = self.positioner.edge_padding;
+            for glyph in line {
+                if let Some(quad) = glyph.quad(glyph_x, line_y) {
+                    self.text_batcher.push(glyph.texture_id, quad);
+                }
+                glyph_x += glyph.advance.0 as i16;
+            }
+            line_y += emergency.line_height;
+        }
+    }
+
     /// Add a slider to the drawer.
-    fn batch_slider(&mut self, slider: &dyn Slider) -> Result<()> {
-        let window_width = self.positioner.size.width;
-        let window_height = self.positioner.size.height;
+    fn batch_slider(&mut self, slider: &dyn Slider, x: i16, mut y: i16) -> Result<()> {
+        let window_size = self.positioner.size;
 
         let width = self.positioner.slider_size.width;
         let height = self.positioner.slider_size.height;
@@ -466,85 +875,254 @@ impl<'a> DrawerRun<'a> {
         // Rasterize slider icon.
         let icon = self.rasterizer.rasterize_svg(slider.svg(), ICON_HEIGHT, None)?;
 
-        // Ensure we're in an empty row.
-        if self.column != 0 {
-            self.column = 0;
-            self.row += 1;
-        }
-
-        // Calculate origin point.
-        let (x, mut y) = self.positioner.position(self.column, self.row);
+        // Center slider vertically in its row.
         y += (self.positioner.module_size - self.positioner.slider_size.height) / 2;
 
-        // Update active row.
-        self.row += 1;
-
-        // Stage tray vertices.
-        let tray =
-            RectVertex::new(window_width, window_height, x, y, width, height, &MODULE_COLOR_BG);
-        for vertex in tray {
-            self.rect_batcher.push(0, vertex);
-        }
+        // Stage tray quad.
+        let radius = self.positioner.corner_radius;
+        let tray = RectQuad::new(
+            window_size,
+            Rect::new(x, y, width, height),
+            radius,
+            &self.theme.module_bg,
+        );
+        self.rect_batcher.push(0, tray);
 
-        // Stage slider vertices.
+        // Stage slider quad.
         let slider_width = (width as f64 * slider.get_value()) as i16;
-        let slider = RectVertex::new(
-            window_width,
-            window_height,
-            x,
-            y,
-            slider_width,
-            height,
-            &MODULE_COLOR_FG,
+        let slider = RectQuad::new(
+            window_size,
+            Rect::new(x, y, slider_width, height),
+            radius,
+            &self.theme.module_fg,
         );
-        for vertex in slider {
-            self.rect_batcher.push(0, vertex);
-        }
+        self.rect_batcher.push(0, slider);
 
         // Calculate icon origin.
         let icon_x = x + (self.positioner.slider_size.width - icon.width) / 2;
         let icon_y = y + (self.positioner.slider_size.height - icon.height) / 2;
 
-        for vertex in icon.vertices(icon_x, icon_y).into_iter().flatten() {
-            self.text_batcher.push(icon.texture_id, vertex);
+        if let Some(quad) = icon.quad(icon_x, icon_y) {
+            self.text_batcher.push(icon.texture_id, quad);
         }
 
         Ok(())
     }
 
     /// Add a toggle button to the drawer.
-    fn batch_toggle(&mut self, toggle: &dyn Toggle) -> Result<()> {
-        let window_width = self.positioner.size.width;
-        let window_height = self.positioner.size.height;
+    fn batch_toggle(&mut self, toggle: &dyn Toggle, x: i16, y: i16) -> Result<()> {
+        self.batch_button(toggle.svg(), toggle.label(), toggle.enabled(), x, y)?;
+
+        // Batch history graph in the space below the icon.
+        if let Some(history) = toggle.history() {
+            self.batch_graph(history, x, y);
+        }
+
+        // Batch title centered in the space below the button.
+        let title_height = self.positioner.title_height;
+        if title_height > 0 {
+            let metrics = self.rasterizer.metrics()?;
+            let size = self.positioner.module_size;
+            let line_height = metrics.line_height as i16;
+            let baseline = (metrics.line_height + metrics.descent as f64) as i16;
+            let title_y = y + size + (title_height - line_height) / 2 + baseline;
+            self.batch_centered(toggle.title(), x, size, title_y);
+        }
+
+        Ok(())
+    }
+
+    /// Batch a bar graph at the bottom of a drawer button.
+    ///
+    /// Values are expected to be in the range `0.0..=1.0`, with the most
+    /// recent value on the right.
+    fn batch_graph(&mut self, values: &[f32], x: i16, y: i16) {
+        let window_size = self.positioner.size;
 
         let size = self.positioner.module_size;
+        let height = self.positioner.graph_height;
+        let padding = self.positioner.corner_radius.max(height / 2);
+        let width = size - 2 * padding;
+        if values.is_empty() || width <= 0 {
+            return;
+        }
+
+        // Ensure every bar is at least one pixel wide.
+        let len = values.len().min(width as usize);
+        let values = &values[values.len() - len..];
+        let bar_width = width as f32 / len as f32;
+
+        let bottom = y + size - height / 2;
+        for (i, value) in values.iter().enumerate() {
+            let bar_x = x + padding + (i as f32 * bar_width) as i16;
+            let next_x = x + padding + ((i + 1) as f32 * bar_width) as i16;
+            let bar_height = ((value.clamp(0., 1.) * height as f32).round() as i16).max(1);
+            let bar = RectQuad::new(
+                window_size,
+                Rect::new(bar_x, bottom - bar_height, next_x - bar_x, bar_height),
+                0,
+                &GRAPH_COLOR,
+            );
+            self.rect_batcher.push(0, bar);
+        }
+    }
+
+    /// Add a cycle button to the drawer.
+    fn batch_cycle(&mut self, cycle: &dyn Cycle, x: i16, y: i16) -> Result<()> {
+        match cycle.states().get(cycle.active()) {
+            Some(state) => self.batch_button(state.svg, Some(&state.label), cycle.enabled(), x, y),
+            None => Ok(()),
+        }
+    }
+
+    /// Add a button with an optional label to the drawer.
+    fn batch_button(
+        &mut self,
+        svg: Svg,
+        label: Option<&str>,
+        active: bool,
+        x: i16,
+        y: i16,
+    ) -> Result<()> {
+        let window_size = self.positioner.size;
 
-        let svg = self.rasterizer.rasterize_svg(toggle.svg(), None, ICON_HEIGHT)?;
+        let size = self.positioner.module_size;
 
-        // Calculate module origin point.
-        let (x, y) = self.positioner.position(self.column, self.row);
+        // Shrink the icon to make space for the label.
+        let icon_height = if label.is_some() { LABELED_ICON_HEIGHT } else { ICON_HEIGHT };
+        let svg = self.rasterizer.rasterize_svg(svg, None, icon_height)?;
+        let metrics = self.rasterizer.metrics()?;
+        let label_height = if label.is_some() { metrics.line_height as i16 } else { 0 };
 
-        // Calculate icon origin point.
+        // Center icon and label together inside the button.
+        let content_height = svg.height + label_height;
         let icon_x = x + (size - svg.width) / 2;
-        let icon_y = y + (size - svg.height) / 2;
+        let icon_y = y + (size - content_height) / 2;
+
+        // Batch icon backdrop.
+        let color = if active { self.theme.module_fg } else { self.theme.module_bg };
+        let radius = self.positioner.corner_radius;
+        let backdrop = RectQuad::new(window_size, Rect::new(x, y, size, size), radius, &color);
+        self.rect_batcher.push(0, backdrop);
 
-        // Update active column/row.
-        self.column += 1;
-        if self.column >= self.positioner.columns {
-            self.column = 0;
-            self.row += 1;
+        // Batch icon.
+        if let Some(quad) = svg.quad(icon_x, icon_y) {
+            self.text_batcher.push(svg.texture_id, quad);
         }
 
-        // Batch icon backdrop.
-        let color = if toggle.enabled() { MODULE_COLOR_FG } else { MODULE_COLOR_BG };
-        let backdrop = RectVertex::new(window_width, window_height, x, y, size, size, &color);
-        for vertex in backdrop {
-            self.rect_batcher.push(0, vertex);
+        // Batch centered label below the icon.
+        if let Some(label) = label {
+            let label_y =
+                icon_y + svg.height + (metrics.line_height + metrics.descent as f64) as i16;
+            self.batch_centered(label, x, size, label_y);
         }
 
-        // Batch icon.
-        for vertex in svg.vertices(icon_x, icon_y).into_iter().flatten() {
-            self.text_batcher.push(svg.texture_id, vertex);
+        Ok(())
+    }
+
+    /// Add a single line of text, horizontally centered within `width`.
+    fn batch_centered(&mut self, text: &str, x: i16, width: i16, baseline: i16) {
+        let lines = self.rasterizer.layout_string(text, width, 1);
+        let glyphs = lines.into_iter().next().unwrap_or_default();
+        let text_width: i16 = glyphs.iter().map(|glyph| glyph.advance.0 as i16).sum();
+        let mut glyph_x = x + (width - text_width) / 2;
+        for glyph in glyphs {
+            if let Some(quad) = glyph.quad(glyph_x, baseline) {
+                self.text_batcher.push(glyph.texture_id, quad);
+            }
+            glyph_x += glyph.advance.0 as i16;
+        }
+    }
+
+    /// Add an info module to the drawer.
+    fn batch_info(&mut self, info: &dyn Info, x: i16, y: i16) -> Result<()> {
+        let window_size = self.positioner.size;
+
+        let width = self.positioner.slider_size.width;
+        let height = self.positioner.module_size;
+
+        // Batch text backdrop.
+        let radius = self.positioner.corner_radius;
+        let backdrop = RectQuad::new(
+            window_size,
+            Rect::new(x, y, width, height),
+            radius,
+            &self.theme.module_bg,
+        );
+        self.rect_batcher.push(0, backdrop);
+
+        // Batch text lines, centered vertically.
+        let padding = self.positioner.module_padding;
+        let metrics = self.rasterizer.metrics()?;
+        let lines = self.rasterizer.layout_string(info.text(), width - 2 * padding, INFO_LINES);
+        let line_height = metrics.line_height as i16;
+        let text_height = lines.len() as i16 * line_height;
+        let baseline = (metrics.line_height + metrics.descent as f64) as i16;
+        let mut line_y = y + (height - text_height) / 2 + baseline;
+        let alpha = if info.stale() { STALE_ALPHA } else { 1. };
+        for line in lines {
+            let mut glyph_x = x + padding;
+            for glyph in line {
+                if let Some(mut quad) = glyph.quad(glyph_x, line_y) {
+                    quad.alpha = alpha;
+                    self.text_batcher.push(glyph.texture_id, quad);
+                }
+                glyph_x += glyph.advance.0 as i16;
+            }
+            line_y += line_height;
+        }
+
+        Ok(())
+    }
+
+    /// Add media playback controls to the drawer.
+    fn batch_media(&mut self, media: &dyn Media, x: i16, y: i16) -> Result<()> {
+        let window_size = self.positioner.size;
+
+        let width = self.positioner.slider_size.width;
+        let height = self.positioner.module_size;
+
+        // Batch controls backdrop.
+        let radius = self.positioner.corner_radius;
+        let backdrop = RectQuad::new(
+            window_size,
+            Rect::new(x, y, width, height),
+            radius,
+            &self.theme.module_bg,
+        );
+        self.rect_batcher.push(0, backdrop);
+
+        // Batch buttons in square areas at the right edge.
+        let play_svg = if media.playing() { Svg::MediaPause } else { Svg::MediaPlay };
+        let buttons = [Svg::MediaPrevious, play_svg, Svg::MediaNext];
+        let buttons_x = x + width - MEDIA_BUTTONS * height;
+        for (i, svg) in buttons.into_iter().enumerate() {
+            let icon = self.rasterizer.rasterize_svg(svg, None, MEDIA_ICON_HEIGHT)?;
+            let icon_x = buttons_x + i as i16 * height + (height - icon.width) / 2;
+            let icon_y = y + (height - icon.height) / 2;
+            if let Some(quad) = icon.quad(icon_x, icon_y) {
+                self.text_batcher.push(icon.texture_id, quad);
+            }
+        }
+
+        // Batch title in the remaining space, centered vertically.
+        let padding = self.positioner.module_padding;
+        let title_width = buttons_x - x - 2 * padding;
+        let metrics = self.rasterizer.metrics()?;
+        let lines = self.rasterizer.layout_string(media.title(), title_width, INFO_LINES);
+        let line_height = metrics.line_height as i16;
+        let text_height = lines.len() as i16 * line_height;
+        let baseline = (metrics.line_height + metrics.descent as f64) as i16;
+        let mut line_y = y + (height - text_height) / 2 + baseline;
+        for line in lines {
+            let mut glyph_x = x + padding;
+            for glyph in line {
+                if let Some(quad) = glyph.quad(glyph_x, line_y) {
+                    self.text_batcher.push(glyph.texture_id, quad);
+                }
+                glyph_x += glyph.advance.0 as i16;
+            }
+            line_y += line_height;
         }
 
         Ok(())
@@ -552,6 +1130,8 @@ impl<'a> DrawerRun<'a> {
 
     /// Draw all modules in this run.
     fn draw(self) {
+        self.rasterizer.flush_uploads();
+
         let mut rect_batches = self.rect_batcher.batches();
         while let Some(rect_batch) = rect_batches.next() {
             rect_batch.draw();
@@ -564,93 +1144,342 @@ impl<'a> DrawerRun<'a> {
     }
 }
 
+/// Element of the drawer layout.
+#[derive(Copy, Clone)]
+enum LayoutItem {
+    /// Drawer module with its origin and width.
+    Module { index: usize, x: i16, y: i16, width: i16 },
+    /// Header above a module group.
+    Header { group: ModuleGroup, y: i16 },
+    /// Text summary of the panel modules.
+    Summary { y: i16 },
+    /// Wrapped emergency information.
+    Emergency { y: i16 },
+}
+
+/// Emergency information wrapped to the drawer's width.
+#[derive(Default)]
+struct EmergencyText {
+    lines: Vec<Vec<GlSubTexture>>,
+    line_height: i16,
+    baseline: i16,
+}
+
+impl EmergencyText {
+    /// Rasterize the emergency information in a large bold font.
+    ///
+    /// Entries are wrapped over as many lines as fit on the page below the
+    /// panel, truncating everything beyond that.
+    fn new(
+        rasterizer: &mut GlRasterizer,
+        info: &[String],
+        positioner: &ModulePositioner,
+    ) -> Result<Self> {
+        if info.is_empty() {
+            return Ok(Self::default());
+        }
+
+        let font = FontConfig { bold: true, ..rasterizer.default_font().clone() };
+        rasterizer.set_font(&font);
+        rasterizer.set_size(Some(EMERGENCY_FONT_SIZE));
+
+        let result = Self::layout(rasterizer, info, positioner);
+
+        rasterizer.reset_font();
+        rasterizer.set_size(None);
+
+        result
+    }
+
+    /// Wrap all entries with the active font.
+    fn layout(
+        rasterizer: &mut GlRasterizer,
+        info: &[String],
+        positioner: &ModulePositioner,
+    ) -> Result<Self> {
+        let metrics = rasterizer.metrics()?;
+        let line_height = (metrics.line_height as i16).max(1);
+        let baseline = (metrics.line_height + metrics.descent as f64) as i16;
+
+        let page_height = positioner.size.height - positioner.panel_height;
+        let mut max_lines = ((page_height - 2 * positioner.edge_padding) / line_height).max(0);
+
+        let width = positioner.slider_size.width;
+        let mut lines = Vec::new();
+        for entry in info {
+            if max_lines <= 0 {
+                break;
+            }
+
+            let entry_lines = rasterizer.layout_string(entry, width, max_lines as usize);
+            max_lines -= entry_lines.len() as i16;
+            lines.extend(entry_lines);
+        }
+
+        Ok(Self { lines, line_height, baseline })
+    }
+
+    /// Total height of all lines.
+    fn height(&self) -> i16 {
+        self.lines.len() as i16 * self.line_height
+    }
+}
+
 /// Module position calculator.
 struct ModulePositioner {
+    groups: Vec<ModuleGroup>,
+    summary: bool,
+    emergency_height: i16,
+    header_height: i16,
     slider_size: Size<i16>,
     module_padding: i16,
     edge_padding: i16,
     panel_height: i16,
+    corner_radius: i16,
+    title_height: i16,
     module_size: i16,
+    badge_size: i16,
+    graph_height: i16,
     size: Size<i16>,
     columns: i16,
 }
 
 impl ModulePositioner {
-    pub fn new(size: Size<f32>, scale_factor: f64) -> Self {
+    pub fn new(
+        size: Size<f32>,
+        scale_factor: f64,
+        panel_height: u32,
+        config: &DrawerConfig,
+        theme: &Theme,
+    ) -> Self {
         let size = Size::new(size.width as i16, size.height as i16);
 
         // Scale constants by DPI scale factor.
-        let panel_height = (PANEL_HEIGHT as f64 * scale_factor).round() as i16;
-        let module_size = (MODULE_SIZE as f64 * scale_factor).round() as i16;
-        let module_padding = (MODULE_PADDING * scale_factor).round() as i16;
-        let slider_height = (SLIDER_HEIGHT * scale_factor).round() as i16;
-        let edge_padding = (EDGE_PADDING * scale_factor).round() as i16;
+        let scale = |logical: f64| geometry::to_physical(logical, scale_factor) as i16;
+        let panel_height = scale(panel_height as f64);
+        let module_size = scale(MODULE_SIZE as f64);
+        let module_padding = scale(config.module_padding);
+        let slider_height = scale(theme.slider_height);
+        let edge_padding = scale(config.edge_padding);
+        let corner_radius = scale(config.corner_radius);
+        let title_height = if config.titles { scale(TITLE_HEIGHT as f64) } else { 0 };
+        let header_height = scale(HEADER_HEIGHT as f64);
+        let badge_size = scale(BADGE_SIZE as f64);
+        let graph_height = scale(GRAPH_HEIGHT as f64);
 
         let content_width = size.width - edge_padding * 2;
         let padded_module_size = module_size + module_padding;
         let columns = (content_width + module_padding) / padded_module_size;
         let edge_padding = (size.width + module_padding - columns * padded_module_size) / 2;
 
-        let slider_width = size.width - 2 * edge_padding;
+        // Align sliders with the toggle grid, even if it can't be centered exactly.
+        let slider_width = columns * padded_module_size - module_padding;
         let slider_size = Size::new(slider_width, slider_height);
 
-        Self { module_padding, edge_padding, panel_height, slider_size, module_size, columns, size }
+        Self {
+            groups: config.groups.clone(),
+            summary: config.summary,
+            emergency_height: 0,
+            module_padding,
+            header_height,
+            corner_radius,
+            title_height,
+            edge_padding,
+            panel_height,
+            slider_size,
+            module_size,
+            badge_size,
+            graph_height,
+            columns,
+            size,
+        }
     }
 
-    /// Get cell origin point.
-    fn position(&self, column: i16, row: i16) -> (i16, i16) {
+    /// Calculate the position of all drawer modules and group headers.
+    ///
+    /// Modules are ordered by their group's position in the configuration,
+    /// with modules of unlisted groups placed last.
+    fn layout(&self, modules: &mut impl ModuleList) -> Vec<LayoutItem> {
+        // Collect drawer modules with their group order.
+        let mut entries: Vec<_> = modules
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, module)| {
+                let group = module.group();
+                let full_width = match module.drawer_module()? {
+                    DrawerModule::Toggle(_) | DrawerModule::Cycle(_) => false,
+                    DrawerModule::Slider(_) | DrawerModule::Info(_) | DrawerModule::Media(_) => {
+                        true
+                    },
+                };
+                let order = self.groups.iter().position(|g| *g == group);
+                Some((order.unwrap_or(self.groups.len()), index, full_width))
+            })
+            .collect();
+        entries.sort_by_key(|(order, ..)| *order);
+
         let padded_module_size = self.module_size + self.module_padding;
-        let x = self.edge_padding + column * padded_module_size;
-        let y = self.panel_height + self.edge_padding + row * padded_module_size;
+        let mut y = self.panel_height + self.edge_padding;
+        let mut current_order = None;
+        let mut column = 0;
 
-        (x, y)
+        let mut items = Vec::new();
+
+        // Add emergency information above everything else.
+        if self.emergency_height > 0 {
+            items.push(LayoutItem::Emergency { y });
+            y += self.emergency_height + self.module_padding;
+        }
+
+        // Add panel module summary above all drawer modules.
+        if self.summary && !panel_summary(modules).is_empty() {
+            items.push(LayoutItem::Summary { y });
+            y += self.header_height;
+        }
+
+        for (order, index, full_width) in entries {
+            // Start a new row for every group and full-width module.
+            let group_changed = current_order != Some(order);
+            if column != 0 && (group_changed || full_width) {
+                column = 0;
+                y += self.row_height();
+            }
+
+            // Add header above configured groups.
+            if group_changed {
+                if let Some(&group) = self.groups.get(order) {
+                    items.push(LayoutItem::Header { group, y });
+                    y += self.header_height;
+                }
+                current_order = Some(order);
+            }
+
+            let x = self.edge_padding + column * padded_module_size;
+            let width = if full_width { self.slider_size.width } else { self.module_size };
+            items.push(LayoutItem::Module { index, x, y, width });
+
+            // Update active column/row.
+            column += 1;
+            if full_width || column >= self.columns {
+                column = 0;
+                y += self.row_height();
+            }
+        }
+
+        items
     }
 
     /// Get relative position inside a module.
     fn module_position(
         &self,
-        modules: &mut [&mut dyn Module],
+        modules: &mut impl ModuleList,
         position: (f64, f64),
     ) -> Option<(usize, f64, f64)> {
         let x = position.0 as i16;
         let y = position.1 as i16;
-        let mut start_x = self.edge_padding;
-        let mut start_y = self.panel_height + self.edge_padding;
-
-        for (i, module) in modules.iter_mut().enumerate() {
-            // Only check drawer modules.
-            let module = match module.drawer_module() {
-                Some(module) => module,
-                None => continue,
-            };
 
-            // Calculate module end.
-            let end_x = match module {
-                DrawerModule::Toggle(_) => start_x + self.module_size,
-                DrawerModule::Slider(_) => start_x + self.slider_size.width,
+        self.layout(modules).into_iter().find_map(|item| {
+            let (index, start_x, start_y, width) = match item {
+                LayoutItem::Module { index, x, y, width } => (index, x, y, width),
+                LayoutItem::Header { .. }
+                | LayoutItem::Summary { .. }
+                | LayoutItem::Emergency { .. } => return None,
             };
-            let end_y = start_y + self.module_size;
 
             // Check if position is within this module.
-            if x >= start_x && y >= start_y && x < end_x && y < end_y {
-                let fractional_x = (position.0 - start_x as f64) / (end_x - start_x) as f64;
-                let fractional_y = (position.1 - start_y as f64) / (end_y - start_y) as f64;
-                return Some((i, fractional_x, fractional_y));
+            let end_x = start_x + width;
+            let end_y = start_y + self.module_size;
+            if x < start_x || y < start_y || x >= end_x || y >= end_y {
+                return None;
             }
 
-            // Calculate next module start.
-            start_x = end_x + self.module_padding;
-            if start_x >= self.size.width - self.edge_padding {
-                start_x = self.edge_padding;
-                start_y = end_y + self.module_padding;
-            }
+            let fractional_x = (position.0 - start_x as f64) / width as f64;
+            let fractional_y = (position.1 - start_y as f64) / self.module_size as f64;
+            Some((index, fractional_x, fractional_y))
+        })
+    }
+
+    /// Get the media command of a touch inside the media controls.
+    ///
+    /// Touches outside of the buttons toggle playback.
+    fn media_action(&self, x: f64) -> MediaAction {
+        let width = self.slider_size.width as f64;
+        let buttons_width = (MEDIA_BUTTONS * self.module_size) as f64;
+        let button_x = x * width - (width - buttons_width);
+        match (button_x / self.module_size as f64).floor() as i16 {
+            0 => MediaAction::Previous,
+            2 => MediaAction::Next,
+            _ => MediaAction::PlayPause,
         }
+    }
 
-        None
+    /// Vertical distance between the origins of two rows.
+    fn row_height(&self) -> i16 {
+        self.module_size + self.title_height + self.module_padding
     }
 }
 
-/// Scale touch position by scale factor.
-fn scale_touch(position: (f64, f64), scale_factor: f64) -> (f64, f64) {
-    (position.0 * scale_factor, position.1 * scale_factor)
+/// Physical height of the drawer content below the panel.
+///
+/// The content ends at the bottom of the window, with its top edge at the
+/// panel's bottom edge.
+fn content_height(logical_height: i32, panel_height: u32, scale_factor: f64) -> i32 {
+    let drawer_height = logical_height - panel_height as i32;
+    geometry::physical_size(panel_height, drawer_height, scale_factor)
+}
+
+/// Get the text summary of all panel modules.
+fn panel_summary(modules: &impl ModuleList) -> String {
+    let summaries: Vec<_> = modules
+        .iter()
+        .filter_map(|module| module.panel_module())
+        .filter_map(|module| module.summary())
+        .collect();
+    summaries.join(SUMMARY_SEPARATOR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_starts_at_panel_edge() {
+        let logical_size = Size::new(393, 720);
+        let config = DrawerConfig::default();
+        let theme = Theme::select(false);
+
+        for (scale_factor, panel_edge) in [(1.25, 16), (1.5, 20), (1.75, 23), (2.5, 33)] {
+            let size = geometry::buffer_size(logical_size, scale_factor);
+
+            // The scissored content must start exactly below the panel.
+            let content_height = content_height(logical_size.height, 13, scale_factor);
+            assert_eq!(size.height - content_height, panel_edge, "scale {scale_factor}");
+
+            let positioner = ModulePositioner::new(size.into(), scale_factor, 13, &config, theme);
+            assert_eq!(positioner.panel_height as i32, panel_edge, "scale {scale_factor}");
+        }
+    }
+
+    #[test]
+    fn sliders_align_with_toggles() {
+        let logical_size = Size::new(393, 720);
+        let config = DrawerConfig::default();
+        let theme = Theme::select(false);
+
+        for (scale_factor, right_edge) in [(1.25, 435), (1.5, 523), (1.75, 610), (2.5, 871)] {
+            let size = geometry::buffer_size(logical_size, scale_factor);
+            let positioner = ModulePositioner::new(size.into(), scale_factor, 13, &config, theme);
+
+            // Get the right edge of the last toggle column.
+            let padded_module_size = positioner.module_size + positioner.module_padding;
+            let last_toggle_x =
+                positioner.edge_padding + (positioner.columns - 1) * padded_module_size;
+            let toggle_end = last_toggle_x + positioner.module_size;
+            assert_eq!(toggle_end, right_edge, "scale {scale_factor}");
+
+            // Sliders must end at the right edge of the last toggle column.
+            let slider_end = positioner.edge_padding + positioner.slider_size.width;
+            assert_eq!(slider_end, right_edge, "scale {scale_factor}");
+        }
+    }
 }
@@ -0,0 +1,111 @@
+//! Fontconfig rendering overrides.
+//!
+//! Crossfont reads hinting and subpixel options from fontconfig's font
+//! patterns, so the only way to change them is through fontconfig itself.
+
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::Write as _;
+use std::path::PathBuf;
+use std::{env, process};
+
+use crate::config::{HintStyle, Subpixel, TextConfig};
+use crate::Result;
+
+/// Fontconfig environment variable for the configuration file.
+const FONTCONFIG_FILE_ENV: &str = "FONTCONFIG_FILE";
+
+/// Default fontconfig configuration file.
+const DEFAULT_FONTCONFIG_FILE: &str = "/etc/fonts/fonts.conf";
+
+/// Generated fontconfig configuration file.
+///
+/// The file is removed when this is dropped.
+pub struct FontconfigFile {
+    path: PathBuf,
+}
+
+impl Drop for FontconfigFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Apply font rendering options to fontconfig.
+///
+/// This writes a fontconfig configuration which includes the system
+/// configuration and overrides the configured options, then points fontconfig
+/// at it through `FONTCONFIG_FILE`. The file must be kept alive while fonts
+/// are loaded.
+///
+/// Since this modifies the environment, it must be called before any threads
+/// are spawned and before the first font is loaded.
+pub fn apply(config: &TextConfig) -> Result<Option<FontconfigFile>> {
+    if config.is_empty() {
+        return Ok(None);
+    }
+
+    let base_config =
+        env::var(FONTCONFIG_FILE_ENV).unwrap_or_else(|_| DEFAULT_FONTCONFIG_FILE.into());
+
+    let mut edits = String::new();
+    if let Some(hinting) = config.hinting {
+        write_edit(&mut edits, "hinting", &format!("<bool>{hinting}</bool>"));
+    }
+    if let Some(autohint) = config.autohint {
+        write_edit(&mut edits, "autohint", &format!("<bool>{autohint}</bool>"));
+    }
+    if let Some(hint_style) = config.hint_style {
+        let hint_style = match hint_style {
+            HintStyle::None => "hintnone",
+            HintStyle::Slight => "hintslight",
+            HintStyle::Medium => "hintmedium",
+            HintStyle::Full => "hintfull",
+        };
+        write_edit(&mut edits, "hintstyle", &format!("<const>{hint_style}</const>"));
+    }
+    if let Some(subpixel) = config.subpixel {
+        let rgba = match subpixel {
+            Subpixel::None => "none",
+            Subpixel::Rgb => "rgb",
+            Subpixel::Bgr => "bgr",
+            Subpixel::Vrgb => "vrgb",
+            Subpixel::Vbgr => "vbgr",
+        };
+        write_edit(&mut edits, "rgba", &format!("<const>{rgba}</const>"));
+    }
+
+    let content = format!(
+        "<?xml version=\"1.0\"?>
+<!DOCTYPE fontconfig SYSTEM \"urn:fontconfig:fonts.dtd\">
+<fontconfig>
+  <include ignore_missing=\"yes\">{base_config}</include>
+  <match target=\"font\">
+{edits}  </match>
+</fontconfig>
+"
+    );
+
+    // Replace leftovers of a previous process with the same PID.
+    let path = config_path();
+    let _ = fs::remove_file(&path);
+    let mut file = OpenOptions::new().write(true).create_new(true).open(&path)?;
+    file.write_all(content.as_bytes())?;
+
+    env::set_var(FONTCONFIG_FILE_ENV, &path);
+
+    Ok(Some(FontconfigFile { path }))
+}
+
+/// Append a fontconfig property assignment.
+fn write_edit(edits: &mut String, name: &str, value: &str) {
+    let _ = writeln!(edits, "    <edit name=\"{name}\" mode=\"assign\">{value}</edit>");
+}
+
+/// Location of the generated fontconfig file.
+///
+/// Files are separated by process, to avoid conflicts between instances.
+fn config_path() -> PathBuf {
+    let runtime_dir = env::var_os("XDG_RUNTIME_DIR").map_or_else(env::temp_dir, PathBuf::from);
+    runtime_dir.join(format!("epitaph-fonts-{}.conf", process::id()))
+}
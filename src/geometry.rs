@@ -0,0 +1,137 @@
+//! Conversion between logical and physical coordinates.
+//!
+//! All conversions to physical pixels round to the nearest pixel, so elements
+//! sharing an edge in logical coordinates also share it after scaling.
+
+use crate::Size;
+
+/// Convert a logical length to physical pixels.
+pub fn to_physical(logical: impl Into<f64>, scale_factor: f64) -> i32 {
+    (logical.into() * scale_factor).round() as i32
+}
+
+/// Get the physical size of an element at a logical position.
+///
+/// Both edges are rounded independently, rather than the size itself, so the
+/// element ends exactly where its logical neighbour begins.
+pub fn physical_size(position: impl Into<f64>, size: impl Into<f64>, scale_factor: f64) -> i32 {
+    let position = position.into();
+    to_physical(position + size.into(), scale_factor) - to_physical(position, scale_factor)
+}
+
+/// Convert a physical length to logical coordinates.
+pub fn to_logical(physical: impl Into<f64>, scale_factor: f64) -> f64 {
+    physical.into() / scale_factor
+}
+
+/// Convert a logical position to physical coordinates.
+///
+/// Positions are not rounded, to retain sub-pixel precision for touch input.
+pub fn to_physical_position(position: (f64, f64), scale_factor: f64) -> (f64, f64) {
+    (position.0 * scale_factor, position.1 * scale_factor)
+}
+
+/// Get the buffer size of a surface with the specified logical size.
+///
+/// Buffers are at least one pixel in each dimension, since empty EGL surfaces
+/// are illegal.
+pub fn buffer_size(logical_size: Size, scale_factor: f64) -> Size {
+    let width = to_physical(logical_size.width, scale_factor).max(1);
+    let height = to_physical(logical_size.height, scale_factor).max(1);
+    Size::new(width, height)
+}
+
+/// Get the viewport destination of a buffer.
+///
+/// This is the inverse of [`buffer_size`], so a buffer created for a logical
+/// size always maps back onto that exact size. The destination is at least one
+/// pixel in each dimension, since empty viewport destinations are a protocol
+/// error.
+pub fn viewport_destination(buffer_size: Size, scale_factor: f64) -> Size {
+    let width = to_logical(buffer_size.width, scale_factor).round() as i32;
+    let height = to_logical(buffer_size.height, scale_factor).round() as i32;
+    Size::new(width.max(1), height.max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Scale factors commonly used on phones and laptops.
+    const SCALE_FACTORS: [f64; 8] = [1., 1.25, 1.5, 1.75, 2., 2.25, 2.5, 3.];
+
+    #[test]
+    fn physical_rounds_to_nearest() {
+        assert_eq!(to_physical(20, 1.25), 25);
+        assert_eq!(to_physical(13, 1.25), 16);
+        assert_eq!(to_physical(14, 1.25), 18);
+        assert_eq!(to_physical(0.5, 1.), 1);
+        assert_eq!(to_physical(16., 1.75), 28);
+    }
+
+    #[test]
+    fn viewport_matches_logical_size() {
+        for scale_factor in SCALE_FACTORS {
+            for width in 1..=1080 {
+                let logical_size = Size::new(width, 20);
+                let buffer_size = buffer_size(logical_size, scale_factor);
+                let destination = viewport_destination(buffer_size, scale_factor);
+                assert_eq!(destination.width, logical_size.width, "scale {scale_factor}");
+                assert_eq!(destination.height, logical_size.height, "scale {scale_factor}");
+            }
+        }
+    }
+
+    #[test]
+    fn adjacent_edges_stay_adjacent() {
+        // Three neighbouring 13px wide elements and their expected physical edges.
+        for (scale_factor, edges) in [
+            (1.25, [0, 16, 33, 49]),
+            (1.5, [0, 20, 39, 59]),
+            (1.75, [0, 23, 46, 68]),
+            (2.5, [0, 33, 65, 98]),
+        ] {
+            for (i, position) in [0, 13, 26].into_iter().enumerate() {
+                let start = to_physical(position, scale_factor);
+                let end = start + physical_size(position, 13, scale_factor);
+                assert_eq!((start, end), (edges[i], edges[i + 1]), "scale {scale_factor}");
+            }
+        }
+    }
+
+    #[test]
+    fn physical_size_depends_on_position() {
+        // Identical logical sizes cover different pixel counts, so they tile without gaps.
+        for (scale_factor, sizes) in
+            [(1.25, [1, 2, 1, 1]), (1.5, [2, 1, 2, 1]), (1.75, [2, 2, 1, 2]), (2.5, [3, 2, 3, 2])]
+        {
+            for (position, size) in sizes.into_iter().enumerate() {
+                assert_eq!(
+                    physical_size(position as f64, 1, scale_factor),
+                    size,
+                    "scale {scale_factor}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn empty_sizes_are_clamped() {
+        for scale_factor in SCALE_FACTORS {
+            let buffer_size = buffer_size(Size::new(0, 0), scale_factor);
+            assert_eq!((buffer_size.width, buffer_size.height), (1, 1));
+
+            let destination = viewport_destination(Size::new(0, 0), scale_factor);
+            assert_eq!((destination.width, destination.height), (1, 1));
+        }
+    }
+
+    #[test]
+    fn position_round_trip() {
+        for scale_factor in SCALE_FACTORS {
+            let (x, y) = to_physical_position((12.5, 300.25), scale_factor);
+            assert!((to_logical(x, scale_factor) - 12.5).abs() < f64::EPSILON * 100.);
+            assert!((to_logical(y, scale_factor) - 300.25).abs() < f64::EPSILON * 1000.);
+        }
+    }
+}
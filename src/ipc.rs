@@ -0,0 +1,387 @@
+//! Unix socket interface for scripting.
+
+use std::cell::Cell;
+use std::io::{self, Read, Write};
+use std::net::Shutdown;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::result::Result as StdResult;
+use std::time::Duration;
+use std::{env, fs};
+
+use calloop::generic::Generic;
+use calloop::timer::{TimeoutAction, Timer};
+use calloop::{Interest, LoopHandle, Mode, PostAction, RegistrationToken};
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+
+use crate::drawer::Visibility;
+use crate::module::DrawerModule;
+use crate::{Result, State};
+
+/// Maximum size of a client's message in bytes.
+const MAX_MESSAGE_SIZE: usize = 4096;
+
+/// Time a client has to send its message and receive the reply.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Source of the client's current read or write operation.
+type ClientSource = Rc<Cell<Option<RegistrationToken>>>;
+
+/// Message sent to a running Epitaph instance.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum IpcMessage {
+    /// Change the drawer's visibility.
+    Drawer { state: DrawerState },
+    /// Activate a drawer toggle, or advance a cycle button.
+    Toggle { module: String },
+    /// Get the state of all drawer modules.
+    Modules,
+    /// Get the state of Epitaph's windows.
+    Status,
+}
+
+impl IpcMessage {
+    /// Parse a message from command line arguments.
+    pub fn parse(mut args: impl Iterator<Item = String>) -> StdResult<Self, String> {
+        let message = match args.next().as_deref() {
+            Some("drawer") => {
+                let state = match args.next().as_deref() {
+                    Some("open") => DrawerState::Open,
+                    Some("close") => DrawerState::Close,
+                    Some("toggle") => DrawerState::Toggle,
+                    Some(state) => return Err(format!("Invalid drawer state {state:?}")),
+                    None => return Err("Missing drawer state".into()),
+                };
+                Self::Drawer { state }
+            },
+            Some("module") => match args.next().as_deref() {
+                Some("toggle") => {
+                    let module = args.next().ok_or("Missing module name")?;
+                    Self::Toggle { module }
+                },
+                Some(action) => return Err(format!("Invalid module action {action:?}")),
+                None => return Err("Missing module action".into()),
+            },
+            Some("modules") => Self::Modules,
+            Some("status") => Self::Status,
+            Some(message) => return Err(format!("Invalid message {message:?}")),
+            None => return Err("Missing message".into()),
+        };
+
+        match args.next() {
+            Some(arg) => Err(format!("Unexpected argument {arg:?}")),
+            None => Ok(message),
+        }
+    }
+}
+
+/// Requested drawer visibility.
+#[derive(Deserialize, Serialize, Copy, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum DrawerState {
+    Open,
+    Close,
+    Toggle,
+}
+
+/// Response to an IPC message.
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum IpcReply {
+    /// Message was handled successfully.
+    Ok,
+    /// State of all drawer modules.
+    Modules(Vec<ModuleStatus>),
+    /// State of Epitaph's windows.
+    Status(WindowStatus),
+    /// Message could not be handled.
+    Error(String),
+}
+
+/// State of Epitaph's windows.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct WindowStatus {
+    /// Drawer visibility.
+    pub drawer: Visibility,
+
+    /// Logical size of every panel, in the order they were created.
+    pub panels: Vec<PanelSize>,
+}
+
+/// Panel window size.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct PanelSize {
+    pub width: i32,
+    pub height: i32,
+    pub scale_factor: f64,
+}
+
+/// Drawer module state.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ModuleStatus {
+    /// Module name used for IPC messages.
+    pub name: String,
+
+    #[serde(flatten)]
+    pub state: ModuleState,
+}
+
+/// State of a drawer module, depending on its type.
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ModuleState {
+    Toggle { enabled: bool },
+    Slider { value: f64 },
+    Cycle { state: String },
+    Info { text: String },
+    Media { title: String, playing: bool },
+}
+
+impl ModuleState {
+    fn new(module: DrawerModule) -> Self {
+        match module {
+            DrawerModule::Toggle(toggle) => Self::Toggle { enabled: toggle.enabled() },
+            DrawerModule::Slider(slider) => Self::Slider { value: slider.get_value() },
+            DrawerModule::Cycle(cycle) => {
+                let state = cycle.states().get(cycle.active()).map(|state| state.label.to_string());
+                Self::Cycle { state: state.unwrap_or_default() }
+            },
+            DrawerModule::Info(info) => Self::Info { text: info.text().into() },
+            DrawerModule::Media(media) => {
+                Self::Media { title: media.title().into(), playing: media.playing() }
+            },
+        }
+    }
+}
+
+/// Listening IPC socket.
+///
+/// The socket file is removed when this is dropped.
+pub struct IpcSocket {
+    path: PathBuf,
+}
+
+impl Drop for IpcSocket {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Start listening for IPC messages.
+///
+/// This fails if another instance is already listening on the socket.
+pub fn listen(event_loop: &LoopHandle<'static, State>) -> Result<IpcSocket> {
+    // Avoid stealing the socket from a running instance.
+    let path = socket_path();
+    if UnixStream::connect(&path).is_ok() {
+        return Err(format!("socket {path:?} is in use by another instance").into());
+    }
+
+    // Remove the socket of a previous instance which did not shut down cleanly.
+    let _ = fs::remove_file(&path);
+
+    let listener = UnixListener::bind(&path)?;
+    listener.set_nonblocking(true)?;
+
+    let source = Generic::new(listener, Interest::READ, Mode::Level);
+    event_loop.insert_source(source, |_, listener, state| {
+        loop {
+            match listener.as_ref().accept() {
+                Ok((stream, _)) => {
+                    if let Err(err) = accept_client(&state.event_loop, stream) {
+                        error!(module = "ipc", "IPC client failed: {err}");
+                    }
+                },
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(PostAction::Continue)
+    })?;
+
+    Ok(IpcSocket { path })
+}
+
+/// Send a message to the running Epitaph instance.
+pub fn send(message: &IpcMessage) -> Result<IpcReply> {
+    let mut stream = UnixStream::connect(socket_path())?;
+
+    stream.write_all(&serde_json::to_vec(message)?)?;
+    stream.shutdown(Shutdown::Write)?;
+
+    Ok(serde_json::from_reader(stream)?)
+}
+
+/// Read a client's message without blocking the event loop.
+///
+/// The message is handled once the client closed its write half. Clients
+/// which do not finish within [`CLIENT_TIMEOUT`] are disconnected.
+fn accept_client(event_loop: &LoopHandle<'static, State>, stream: UnixStream) -> Result<()> {
+    stream.set_nonblocking(true)?;
+
+    let client_source = ClientSource::default();
+    let read_source = client_source.clone();
+
+    let mut message = Vec::new();
+    let source = Generic::new(stream, Interest::READ, Mode::Level);
+    let token = event_loop.insert_source(source, move |_, stream, state| {
+        let mut buffer = [0; 1024];
+        loop {
+            match stream.as_ref().read(&mut buffer) {
+                Ok(0) => break,
+                Ok(len) if message.len() + len > MAX_MESSAGE_SIZE => {
+                    error!(module = "ipc", "IPC message exceeds {MAX_MESSAGE_SIZE} bytes");
+                    read_source.set(None);
+                    return Ok(PostAction::Remove);
+                },
+                Ok(len) => message.extend_from_slice(&buffer[..len]),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    return Ok(PostAction::Continue);
+                },
+                Err(err) => {
+                    error!(module = "ipc", "Could not read IPC message: {err}");
+                    read_source.set(None);
+                    return Ok(PostAction::Remove);
+                },
+            }
+        }
+        read_source.set(None);
+
+        let reply = match serde_json::from_slice(&message) {
+            Ok(message) => handle_message(state, message),
+            Err(err) => IpcReply::Error(format!("Invalid message: {err}")),
+        };
+
+        let result = serde_json::to_vec(&reply).map_err(Into::into).and_then(|reply| {
+            let stream = stream.as_ref().try_clone()?;
+            send_reply(&state.event_loop, stream, reply, read_source.clone())
+        });
+        if let Err(err) = result {
+            error!(module = "ipc", "Could not send IPC reply: {err}");
+        }
+
+        Ok(PostAction::Remove)
+    })?;
+    client_source.set(Some(token));
+
+    // Disconnect clients which neither send their message nor read the reply.
+    let timer = Timer::from_duration(CLIENT_TIMEOUT);
+    event_loop.insert_source(timer, move |_, _, state| {
+        if let Some(token) = client_source.take() {
+            warn!(module = "ipc", "IPC client timed out");
+            state.event_loop.remove(token);
+        }
+        TimeoutAction::Drop
+    })?;
+
+    Ok(())
+}
+
+/// Write the reply to a client without blocking the event loop.
+fn send_reply(
+    event_loop: &LoopHandle<'static, State>,
+    stream: UnixStream,
+    reply: Vec<u8>,
+    client_source: ClientSource,
+) -> Result<()> {
+    let mut written = 0;
+    let write_source = client_source.clone();
+    let source = Generic::new(stream, Interest::WRITE, Mode::Level);
+    let token = event_loop.insert_source(source, move |_, stream, _| {
+        while written < reply.len() {
+            match stream.as_ref().write(&reply[written..]) {
+                Ok(len) => written += len,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    return Ok(PostAction::Continue);
+                },
+                Err(err) => {
+                    error!(module = "ipc", "Could not send IPC reply: {err}");
+                    break;
+                },
+            }
+        }
+
+        write_source.set(None);
+        Ok(PostAction::Remove)
+    })?;
+    client_source.set(Some(token));
+
+    Ok(())
+}
+
+/// Route a message to its target.
+fn handle_message(state: &mut State, message: IpcMessage) -> IpcReply {
+    match message {
+        IpcMessage::Drawer { state: drawer_state } => {
+            let open = match drawer_state {
+                DrawerState::Open => true,
+                DrawerState::Close => false,
+                DrawerState::Toggle => !state.drawer().visible(),
+            };
+            state.request_drawer(open);
+        },
+        IpcMessage::Toggle { module: name } => {
+            let mut modules = state.modules.named_mut();
+            let module = match modules.iter_mut().find(|(module_name, _)| *module_name == name) {
+                Some((_, module)) => module,
+                None => return IpcReply::Error(format!("Unknown module {name:?}")),
+            };
+
+            let result = match module.drawer_module() {
+                Some(DrawerModule::Toggle(toggle)) => toggle.toggle(),
+                Some(DrawerModule::Cycle(cycle)) => {
+                    let next = (cycle.active() + 1) % cycle.states().len().max(1);
+                    cycle.set_active(next)
+                },
+                Some(_) => return IpcReply::Error(format!("Module {name:?} cannot be toggled")),
+                None => return IpcReply::Error(format!("Module {name:?} is unavailable")),
+            };
+
+            if let Err(err) = result {
+                return IpcReply::Error(format!("Could not toggle {name:?}: {err}"));
+            }
+
+            state.request_frame();
+        },
+        IpcMessage::Modules => {
+            let modules = state
+                .modules
+                .named_mut()
+                .into_iter()
+                .filter_map(|(name, module)| {
+                    let state = ModuleState::new(module.drawer_module()?);
+                    Some(ModuleStatus { name: name.into(), state })
+                })
+                .collect();
+            return IpcReply::Modules(modules);
+        },
+        IpcMessage::Status => {
+            let panels = state
+                .panels
+                .iter()
+                .map(|panel| {
+                    let size = panel.logical_size();
+                    let scale_factor = panel.scale_factor();
+                    PanelSize { width: size.width, height: size.height, scale_factor }
+                })
+                .collect();
+            let drawer = state.drawer().visibility();
+            return IpcReply::Status(WindowStatus { drawer, panels });
+        },
+    }
+
+    IpcReply::Ok
+}
+
+/// Path of the IPC socket.
+///
+/// Sockets are separated by Wayland display, to allow multiple compositors.
+fn socket_path() -> PathBuf {
+    let runtime_dir = env::var_os("XDG_RUNTIME_DIR").map_or_else(env::temp_dir, PathBuf::from);
+    let display = env::var("WAYLAND_DISPLAY").unwrap_or_else(|_| "wayland-0".into());
+    runtime_dir.join(format!("epitaph-{display}.sock"))
+}
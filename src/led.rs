@@ -0,0 +1,162 @@
+//! Sysfs LED devices.
+
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use tracing::error;
+use udev::{Device, Enumerator};
+
+use crate::Result;
+
+/// Trigger disabling automatic LED control.
+const TRIGGER_NONE: &str = "none";
+
+/// Sysfs LED class device.
+pub struct Led {
+    max_brightness: u32,
+    device: Device,
+}
+
+impl Led {
+    /// Find the first LED with the specified name.
+    ///
+    /// The name may contain shell-style wildcards.
+    pub fn find(name: &str) -> Result<Option<Self>> {
+        let mut enumerator = Enumerator::new()?;
+        enumerator.match_subsystem("leds")?;
+        enumerator.match_sysname(name)?;
+        let devices = enumerator.scan_devices()?;
+
+        Ok(devices.into_iter().find_map(Self::from_device))
+    }
+
+    /// Convert udev device to LED.
+    fn from_device(device: Device) -> Option<Self> {
+        let max_brightness = attribute(&device, "max_brightness")?;
+        Some(Self { max_brightness, device })
+    }
+
+    /// Maximum brightness supported by the LED.
+    pub fn max_brightness(&self) -> u32 {
+        self.max_brightness
+    }
+
+    /// Current LED brightness.
+    pub fn brightness(&self) -> Option<u32> {
+        attribute(&self.device, "brightness")
+    }
+
+    /// Update the LED brightness.
+    pub fn set_brightness(&mut self, brightness: u32) -> Result<()> {
+        self.device.set_attribute_value("brightness", brightness.to_string())?;
+        Ok(())
+    }
+
+    /// Take exclusive control over the LED.
+    ///
+    /// Since LED controllers are commonly shared between multiple LEDs, like
+    /// flash and torch, the state of every LED on the same controller is
+    /// restored once the claim is dropped.
+    pub fn claim(mut self) -> Result<LedClaim> {
+        // Save state of all LEDs attached to the same controller.
+        let mut saved = Vec::new();
+        match self.device.parent() {
+            Some(controller) => {
+                let mut enumerator = Enumerator::new()?;
+                enumerator.match_subsystem("leds")?;
+                enumerator.match_parent(&controller)?;
+                saved.extend(enumerator.scan_devices()?.filter_map(SavedLed::new));
+            },
+            None => saved.extend(SavedLed::new(self.device.clone())),
+        }
+
+        // Stop triggers from overriding our brightness.
+        if selected_trigger(&self.device).is_some_and(|trigger| trigger != TRIGGER_NONE) {
+            self.device.set_attribute_value("trigger", TRIGGER_NONE)?;
+        }
+
+        Ok(LedClaim { led: self, saved })
+    }
+}
+
+/// LED under exclusive control.
+pub struct LedClaim {
+    led: Led,
+    saved: Vec<SavedLed>,
+}
+
+impl Drop for LedClaim {
+    fn drop(&mut self) {
+        if let Err(err) = self.led.set_brightness(0) {
+            error!(module = "led", "Could not turn off LED: {err}");
+        }
+
+        for saved in &self.saved {
+            if let Err(err) = saved.restore() {
+                error!(module = "led", "Could not restore LED state: {err}");
+            }
+        }
+    }
+}
+
+impl Deref for LedClaim {
+    type Target = Led;
+
+    fn deref(&self) -> &Self::Target {
+        &self.led
+    }
+}
+
+impl DerefMut for LedClaim {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.led
+    }
+}
+
+/// LED state before it was claimed.
+struct SavedLed {
+    syspath: PathBuf,
+    brightness: u32,
+    trigger: Option<String>,
+}
+
+impl SavedLed {
+    fn new(device: Device) -> Option<Self> {
+        let brightness = attribute(&device, "brightness")?;
+        let trigger = selected_trigger(&device);
+        Some(Self { syspath: device.syspath().to_owned(), brightness, trigger })
+    }
+
+    /// Restore the saved LED state.
+    fn restore(&self) -> Result<()> {
+        let mut device = Device::from_syspath(&self.syspath)?;
+
+        // Triggers take over brightness control, so only restore one of them.
+        match &self.trigger {
+            Some(trigger) if trigger != TRIGGER_NONE => {
+                device.set_attribute_value("trigger", trigger)?
+            },
+            _ => device.set_attribute_value("brightness", self.brightness.to_string())?,
+        }
+
+        Ok(())
+    }
+}
+
+/// Read a numeric sysfs attribute.
+fn attribute(device: &Device, name: &str) -> Option<u32> {
+    let value = device.attribute_value(name)?.to_string_lossy();
+    u32::from_str(value.trim()).ok()
+}
+
+/// Get the active trigger of an LED.
+///
+/// The trigger attribute lists all available triggers, with the active one
+/// surrounded by brackets.
+fn selected_trigger(device: &Device) -> Option<String> {
+    let triggers = device.attribute_value("trigger")?.to_string_lossy();
+    let start = triggers.find('[')?;
+    let end = start + triggers[start..].find(']')?;
+    Some(triggers[start + 1..end].to_owned())
+}
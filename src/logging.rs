@@ -0,0 +1,85 @@
+//! Log output setup.
+
+use calloop::signals::{Signal, Signals};
+use calloop::LoopHandle;
+use tracing::error;
+use tracing_subscriber::filter::{EnvFilter, LevelFilter};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, Registry};
+
+use crate::config::Config;
+use crate::{Result, State};
+
+/// Handle for replacing the active log filter.
+pub type FilterHandle = reload::Handle<EnvFilter, Registry>;
+
+/// Log output format.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub enum LogFormat {
+    /// Human-readable text.
+    #[default]
+    Text,
+    /// One JSON object per line.
+    Json,
+}
+
+/// Setup logging, configurable through `RUST_LOG`.
+pub fn init(format: LogFormat) -> FilterHandle {
+    let (filter, handle) = reload::Layer::new(env_filter());
+    let registry = tracing_subscriber::registry().with(filter);
+    match format {
+        LogFormat::Text => registry.with(fmt::layer().with_writer(std::io::stderr)).init(),
+        LogFormat::Json => {
+            let layer = fmt::layer().json().flatten_event(true).with_writer(std::io::stderr);
+            registry.with(layer).init();
+        },
+    }
+    handle
+}
+
+/// Apply the configured log filter and reload it on SIGHUP.
+///
+/// This must be called before spawning any threads, since SIGHUP would
+/// otherwise terminate the process when delivered to them.
+pub fn watch_config(
+    event_loop: &LoopHandle<'static, State>,
+    handle: FilterHandle,
+    config: &Config,
+) -> Result<()> {
+    if config.log_filter.is_some() {
+        set_filter(&handle, config.log_filter.as_deref());
+    }
+
+    let signals = Signals::new(&[Signal::SIGHUP])?;
+    event_loop.insert_source(signals, move |_, _, _| {
+        let config = Config::load();
+        set_filter(&handle, config.log_filter.as_deref());
+    })?;
+
+    Ok(())
+}
+
+/// Replace the active log filter.
+///
+/// Without filter directives, this falls back to `RUST_LOG`.
+fn set_filter(handle: &FilterHandle, directives: Option<&str>) {
+    let filter = match directives {
+        Some(directives) => filter_builder().parse_lossy(directives),
+        None => env_filter(),
+    };
+
+    if let Err(err) = handle.reload(filter) {
+        error!(module = "logging", "Could not update log filter: {err}");
+    }
+}
+
+/// Log filter based on `RUST_LOG`.
+fn env_filter() -> EnvFilter {
+    filter_builder().from_env_lossy()
+}
+
+/// Log filter builder, defaulting to warnings and errors.
+fn filter_builder() -> tracing_subscriber::filter::Builder {
+    EnvFilter::builder().with_default_directive(LevelFilter::WARN.into())
+}
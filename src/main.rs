@@ -1,11 +1,15 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::error::Error;
-use std::ffi::CString;
-use std::ops::{Div, Mul};
-use std::process;
+use std::ffi::{CString, OsString};
 use std::ptr::NonNull;
+use std::rc::Rc;
 use std::result::Result as StdResult;
 use std::time::{Duration, Instant};
+use std::{env, mem, process};
 
+use calloop::channel::Event;
+use calloop::signals::{Signal, Signals};
 use calloop::timer::{TimeoutAction, Timer};
 use calloop::{EventLoop, LoopHandle, RegistrationToken};
 use calloop_wayland_source::WaylandSource;
@@ -15,14 +19,24 @@ use glutin::config::ConfigTemplateBuilder;
 use glutin::prelude::*;
 use raw_window_handle::{RawDisplayHandle, WaylandDisplayHandle};
 use smithay_client_toolkit::compositor::{CompositorHandler, CompositorState};
-use smithay_client_toolkit::output::{OutputHandler, OutputState};
+use smithay_client_toolkit::output::{OutputHandler, OutputInfo, OutputState};
 use smithay_client_toolkit::reexports::client::globals::{self, GlobalList};
 use smithay_client_toolkit::reexports::client::protocol::wl_output::{Transform, WlOutput};
+use smithay_client_toolkit::reexports::client::protocol::wl_keyboard::WlKeyboard;
+use smithay_client_toolkit::reexports::client::protocol::wl_pointer::WlPointer;
 use smithay_client_toolkit::reexports::client::protocol::wl_seat::WlSeat;
 use smithay_client_toolkit::reexports::client::protocol::wl_surface::WlSurface;
 use smithay_client_toolkit::reexports::client::protocol::wl_touch::WlTouch;
-use smithay_client_toolkit::reexports::client::{Connection, EventQueue, QueueHandle};
+use smithay_client_toolkit::reexports::client::backend::ObjectId;
+use smithay_client_toolkit::reexports::client::{Connection, EventQueue, Proxy, QueueHandle};
+use smithay_client_toolkit::reexports::protocols::wp::cursor_shape::v1::client::wp_cursor_shape_device_v1::Shape;
 use smithay_client_toolkit::registry::{ProvidesRegistryState, RegistryState};
+use smithay_client_toolkit::seat::keyboard::{
+    KeyEvent, KeyboardHandler, Keysym, Modifiers,
+};
+use smithay_client_toolkit::seat::pointer::{
+    PointerEvent, PointerEventKind, PointerHandler, BTN_LEFT,
+};
 use smithay_client_toolkit::seat::touch::TouchHandler;
 use smithay_client_toolkit::seat::{Capability, SeatHandler, SeatState};
 use smithay_client_toolkit::shell::wlr_layer::{
@@ -30,33 +44,82 @@ use smithay_client_toolkit::shell::wlr_layer::{
 };
 use smithay_client_toolkit::shell::WaylandSurface;
 use smithay_client_toolkit::{
-    delegate_compositor, delegate_layer, delegate_output, delegate_registry, delegate_seat,
-    delegate_touch, registry_handlers,
+    delegate_compositor, delegate_keyboard, delegate_layer, delegate_output, delegate_pointer,
+    delegate_registry, delegate_seat, delegate_touch, registry_handlers,
 };
-
-use crate::drawer::{Drawer, HANDLE_HEIGHT};
+use tracing::{debug, error};
+
+use crate::action::Action;
+use crate::cli::Options;
+use crate::config::{Config, ConnectivityIcons, Mode, PanelConfig};
+use crate::dbus::network_manager::{self, PrimaryConnection};
+use crate::dbus::notifications::CloseReason;
+use crate::dbus::status::{PanelStatus, StatusPublisher};
+use crate::drawer::{Drawer, ModuleAction, Visibility, HANDLE_HEIGHT};
+use crate::ipc::{IpcReply, IpcSocket};
+use crate::logging::FilterHandle;
 use crate::module::battery::Battery;
 use crate::module::brightness::Brightness;
 use crate::module::cellular::Cellular;
+use crate::module::cellular_profile::CellularProfile;
+use crate::module::charge_limit::ChargeLimit;
 use crate::module::clock::Clock;
+use crate::module::custom::CustomDbus;
+use crate::module::diagnostics::Diagnostics;
+use crate::module::do_not_disturb::DoNotDisturb;
 use crate::module::flashlight::Flashlight;
+use crate::module::high_contrast::HighContrast;
+use crate::module::keyboard_backlight::KeyboardBacklight;
+use crate::module::location::Location;
+use crate::module::metered::Metered;
+use crate::module::network_mode::NetworkMode;
 use crate::module::orientation::Orientation;
+use crate::module::playback::Playback;
+use crate::module::plugin::Plugin;
+use crate::module::ring_mode::RingMode;
 use crate::module::scale::Scale;
+use crate::module::sim_slot::SimSlot;
+use crate::module::task::Task;
 use crate::module::wifi::Wifi;
-use crate::module::Module;
-use crate::panel::{Panel, PANEL_HEIGHT};
+use crate::module::{Module, ModuleList};
+use crate::notification::Notifications;
+use crate::panel::Panel;
+use crate::persistence::StatusCache;
+use crate::protocols::cursor_shape::{CursorShapeManager, PointerCursor};
+use crate::protocols::data_control::DataControlManager;
+use crate::protocols::foreign_toplevel::{ForeignToplevelHandler, ForeignToplevelManager};
 use crate::protocols::fractional_scale::{FractionalScaleHandler, FractionalScaleManager};
+use crate::protocols::presentation_time::{Presentation, PresentationHandler};
 use crate::protocols::viewporter::Viewporter;
 use crate::reaper::Reaper;
+use crate::recording::{TouchEvent, TouchRecorder, TouchTarget};
+use crate::renderer::Renderer;
+use crate::supervisor::BackendEvent;
 
+mod action;
+mod cli;
+mod config;
 mod dbus;
 mod drawer;
+mod fontconfig;
+mod geometry;
+mod ipc;
+mod led;
+mod logging;
 mod module;
+mod notification;
+mod offscreen;
 mod panel;
+mod persistence;
 mod protocols;
 mod reaper;
+mod recording;
 mod renderer;
+mod supervisor;
+mod surface;
 mod text;
+mod theme;
+mod time;
 mod vertex;
 
 mod gl {
@@ -64,12 +127,18 @@ mod gl {
     include!(concat!(env!("OUT_DIR"), "/gl_bindings.rs"));
 }
 
-/// Time between drawer animation updates.
+/// Interval for checking windows for missing frame callbacks.
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Time between drawer animation updates, until the refresh rate is known.
 const ANIMATION_INTERVAL: Duration = Duration::from_millis(1000 / 120);
 
 /// Maximum time between taps to be considered a double-tap.
 const MAX_DOUBLE_TAP_DURATION: Duration = Duration::from_millis(200);
 
+/// Minimum touch duration before a press is considered a long-press.
+const LONG_PRESS_DURATION: Duration = Duration::from_millis(500);
+
 /// Square of the maximum distance before a touch input is considered a drag.
 const MAX_TAP_DISTANCE: f64 = 400.;
 
@@ -77,18 +146,79 @@ const MAX_TAP_DISTANCE: f64 = 400.;
 /// of closing.
 const ANIMATION_THRESHOLD: f64 = 0.25;
 
-/// Step size for drawer animation.
+/// Step size for drawer animation, per [`ANIMATION_INTERVAL`].
 const ANIMATION_STEP: f64 = 20.;
 
+/// Touch ID used for emulating touch input with the pointer.
+///
+/// This is negative to avoid collisions with the compositor's touch IDs.
+const POINTER_TOUCH_ID: i32 = -1;
+
 /// Convenience result wrapper.
 pub type Result<T> = StdResult<T, Box<dyn Error>>;
 
 fn main() {
+    let options = Options::from_args();
+
+    // Setup logging, configurable through `RUST_LOG`.
+    let log_filter = logging::init(options.log_format);
+
+    // Send IPC message to the running instance.
+    if let Some(message) = &options.message {
+        match ipc::send(message) {
+            Ok(IpcReply::Ok) => (),
+            Ok(IpcReply::Modules(modules)) => match serde_json::to_string(&modules) {
+                Ok(modules) => println!("{modules}"),
+                Err(err) => {
+                    eprintln!("Error: Could not serialize modules: {err}");
+                    process::exit(1);
+                },
+            },
+            Ok(IpcReply::Status(status)) => match serde_json::to_string(&status) {
+                Ok(status) => println!("{status}"),
+                Err(err) => {
+                    eprintln!("Error: Could not serialize status: {err}");
+                    process::exit(1);
+                },
+            },
+            Ok(IpcReply::Error(err)) => {
+                eprintln!("Error: {err}");
+                process::exit(1);
+            },
+            Err(err) => {
+                eprintln!("Error: Could not send IPC message: {err}");
+                process::exit(1);
+            },
+        }
+        return;
+    }
+
+    // Load configuration file.
+    let config = Config::load();
+
+    // Apply font rendering options before any threads are spawned.
+    let _fontconfig_file = match fontconfig::apply(&config.text) {
+        Ok(fontconfig_file) => fontconfig_file,
+        Err(err) => {
+            error!(module = "fontconfig", "Could not apply font rendering options: {err}");
+            None
+        },
+    };
+
+    // Render screenshots without connecting to a compositor.
+    if let Some(screenshot) = &options.screenshot {
+        if let Err(err) = offscreen::screenshot(screenshot, &config) {
+            error!(module = "screenshot", "Screenshot failed: {err}");
+            process::exit(1);
+        }
+        return;
+    }
+
     // Initialize Wayland connection.
     let connection = match Connection::connect_to_env() {
         Ok(connection) => connection,
         Err(err) => {
-            eprintln!("Error: {err}");
+            error!(module = "wayland", "Could not connect to Wayland: {err}");
             process::exit(1);
         },
     };
@@ -99,8 +229,16 @@ fn main() {
     let mut event_loop = EventLoop::try_new().expect("initialize event loop");
 
     // Setup shared state.
-    let mut state =
-        State::new(&connection, &globals, &queue, event_loop.handle()).expect("state setup");
+    let mut state = State::new(
+        &connection,
+        &globals,
+        &queue,
+        event_loop.handle(),
+        log_filter,
+        options.mode,
+        config,
+    )
+    .expect("state setup");
 
     // Insert wayland source into calloop loop.
     let wayland_source = WaylandSource::new(connection, queue);
@@ -118,9 +256,14 @@ pub struct State {
     event_loop: LoopHandle<'static, Self>,
     protocol_states: ProtocolStates,
     modules: Modules,
+    status: StatusPublisher,
+    _ipc_socket: Option<IpcSocket>,
     terminated: bool,
     reaper: Reaper,
+    status_cache: StatusCache,
 
+    long_press_timeout: Option<RegistrationToken>,
+    panel_animation: Option<RegistrationToken>,
     tap_timeout: Option<RegistrationToken>,
     active_touch: Option<i32>,
     panel_height: Option<u32>,
@@ -128,10 +271,39 @@ pub struct State {
     touch_start: (f64, f64),
     drawer_opening: bool,
     last_touch_y: f64,
-
+    animation_interval: Duration,
+    pending_swipe: Option<Action>,
+    panel_long_pressed: bool,
+    swipe_left: Action,
+    swipe_right: Action,
+    connectivity_icons: ConnectivityIcons,
+    mode: Mode,
+    reduced_motion: bool,
+    task_switcher: bool,
+    diagnostics: bool,
+    primary_connection: PrimaryConnection,
+
+    touch_recorder: Option<TouchRecorder>,
+    touch_replay: Option<OsString>,
+    notifications: Option<Notifications>,
     touch: Option<WlTouch>,
+    keyboard: Option<WlKeyboard>,
+    pointer: Option<WlPointer>,
+    pointer_pressed: bool,
+    cursor: Option<PointerCursor>,
     drawer: Option<Drawer>,
-    panel: Option<Panel>,
+
+    /// Panel windows, one per output.
+    panels: Vec<Panel>,
+
+    /// Index of the last touched panel.
+    active_panel: usize,
+
+    /// Last known properties of each output.
+    output_properties: HashMap<ObjectId, OutputProperties>,
+
+    renderer: Option<Rc<RefCell<Renderer>>>,
+    panel_config: PanelConfig,
 }
 
 impl State {
@@ -140,42 +312,125 @@ impl State {
         globals: &GlobalList,
         queue: &EventQueue<Self>,
         event_loop: LoopHandle<'static, Self>,
+        log_filter: FilterHandle,
+        mode: Option<Mode>,
+        config: Config,
     ) -> Result<Self> {
         // Setup globals.
         let queue_handle = queue.handle();
         let protocol_states = ProtocolStates::new(globals, &queue_handle);
 
+        // Apply log filter overrides, before backend threads are spawned.
+        logging::watch_config(&event_loop, log_filter, &config)?;
+
+        // Shut down cleanly on termination, to remove the IPC socket.
+        //
+        // Signals are only blocked for threads spawned after this, so it must
+        // happen before any module backends are started.
+        let signals = Signals::new(&[Signal::SIGTERM, Signal::SIGINT])?;
+        event_loop.insert_source(signals, |_, _, state| state.terminated = true)?;
+
+        // Accept scripting commands over IPC.
+        let ipc_socket = ipc::listen(&event_loop)
+            .map_err(|err| error!(module = "ipc", "Could not create IPC socket: {err}"))
+            .ok();
+
         // Initialize panel modules.
-        let modules = Modules::new(&event_loop)?;
+        let modules = Modules::new(&event_loop, &config)?;
+
+        // Publish module values over DBus.
+        let status = StatusPublisher::new(&event_loop)?;
 
         // Create process reaper.
         let reaper = Reaper::new(&event_loop)?;
 
+        // Batch module status writes.
+        let status_cache = StatusCache::new(&event_loop);
+
+        // Setup touch event recording for debugging.
+        let touch_recorder = env::var_os(recording::RECORD_ENV).and_then(|path| {
+            TouchRecorder::new(&path)
+                .map_err(|err| {
+                    error!(module = "recording", "Could not record touch to {path:?}: {err}")
+                })
+                .ok()
+        });
+
         let mut state = Self {
             protocol_states,
             event_loop,
             modules,
+            status,
             reaper,
+            status_cache,
+            touch_recorder,
+            touch_replay: env::var_os(recording::REPLAY_ENV),
+            _ipc_socket: ipc_socket,
+            animation_interval: ANIMATION_INTERVAL,
+            swipe_left: config.panel.swipe_left,
+            swipe_right: config.panel.swipe_right,
+            connectivity_icons: config.panel.connectivity_icons,
+            mode: mode.unwrap_or(config.mode),
+            reduced_motion: config.reduced_motion,
+            task_switcher: config.drawer.task_switcher,
+            diagnostics: config.diagnostics.enabled,
+            panel_config: config.panel.clone(),
+            primary_connection: Default::default(),
+            output_properties: Default::default(),
+            pending_swipe: Default::default(),
+            panel_long_pressed: Default::default(),
+            long_press_timeout: Default::default(),
+            panel_animation: Default::default(),
             drawer_opening: Default::default(),
             active_touch: Default::default(),
             panel_height: Default::default(),
             last_touch_y: Default::default(),
             touch_start: Default::default(),
             tap_timeout: Default::default(),
+            notifications: Default::default(),
             terminated: Default::default(),
             last_tap: Default::default(),
             drawer: Default::default(),
             touch: Default::default(),
-            panel: Default::default(),
+            keyboard: Default::default(),
+            pointer: Default::default(),
+            pointer_pressed: Default::default(),
+            cursor: Default::default(),
+            active_panel: Default::default(),
+            renderer: Default::default(),
+            panels: Default::default(),
         };
 
-        state.init_windows(connection, queue)?;
+        state.init_windows(connection, queue, &config)?;
+
+        // Periodically check for windows with a frozen render loop.
+        let watchdog = Timer::from_duration(WATCHDOG_INTERVAL);
+        state.event_loop.insert_source(watchdog, |_, _, state| {
+            state.recover_stalled_windows();
+            TimeoutAction::ToDuration(WATCHDOG_INTERVAL)
+        })?;
+
+        // Track the default route for hiding redundant connectivity icons.
+        if state.connectivity_icons == ConnectivityIcons::DefaultRoute {
+            let rx = network_manager::primary_connection_listener();
+            state.event_loop.insert_source(rx, |event, _, state| {
+                if let Event::Msg(BackendEvent::Update(primary_connection)) = event {
+                    state.primary_connection = primary_connection;
+                    state.update_connectivity_icons();
+                }
+            })?;
+        }
 
         Ok(state)
     }
 
     /// Initialize the panel/drawer windows and their EGL surfaces.
-    fn init_windows(&mut self, connection: &Connection, queue: &EventQueue<Self>) -> Result<()> {
+    fn init_windows(
+        &mut self,
+        connection: &Connection,
+        queue: &EventQueue<Self>,
+        config: &Config,
+    ) -> Result<()> {
         let display = NonNull::new(connection.backend().display_ptr().cast()).unwrap();
         let wayland_display = WaylandDisplayHandle::new(display);
         let raw_display_handle = RawDisplayHandle::Wayland(wayland_display);
@@ -199,261 +454,339 @@ impl State {
             gl_display.get_proc_address(symbol.as_c_str()).cast()
         });
 
-        // Setup panel window.
-        self.panel = Some(Panel::new(
-            &self.protocol_states.fractional_scale,
-            &self.protocol_states.compositor,
-            &self.protocol_states.viewporter,
-            queue.handle(),
-            &self.protocol_states.layer,
-            &egl_config,
-        )?);
+        // Create a single renderer shared by all windows.
+        let egl_context = renderer::create_context(&gl_display, &egl_config)?;
+        let renderer = Rc::new(RefCell::new(Renderer::new(
+            egl_context,
+            1.,
+            config.panel.font.clone(),
+            config.text.dual_source_blending,
+        )?));
+
+        self.renderer = Some(renderer.clone());
+
+        // Setup a panel window for every known output.
+        let queue_handle = queue.handle();
+        let outputs: Vec<_> = self.protocol_states.output.outputs().collect();
+        if self.mode == Mode::Drawer || outputs.is_empty() {
+            self.add_panel(&queue_handle, None)?;
+        } else {
+            for output in outputs {
+                self.add_panel(&queue_handle, Some(output))?;
+            }
+        }
 
         // Setup drawer window.
-        self.drawer = Some(Drawer::new(queue.handle(), &egl_config)?);
+        let drawer_config = config.drawer.clone();
+        let drawer =
+            Drawer::new(queue.handle(), renderer.clone(), drawer_config, config.panel.height)?;
+        self.drawer = Some(drawer);
+
+        // Setup notification popups.
+        let notifications =
+            Notifications::new(&self.event_loop, queue.handle(), renderer, self.reduced_motion)?;
+        self.notifications = Some(notifications);
 
         Ok(())
     }
 
     /// Draw window associated with the surface.
     fn draw(&mut self, surface: &WlSurface) {
-        if self.panel().owns_surface(surface) {
-            if let Err(error) = self.panel.as_mut().unwrap().draw(&self.modules.as_slice()) {
-                eprintln!("Panel rendering failed: {error:?}");
+        let is_panel = self.panels.iter().any(|panel| panel.owns_surface(surface));
+
+        // Track presentation latency of the new frame.
+        //
+        // Outside of diagnostics, only the clock's latency needs to be measured.
+        let measure_latency =
+            self.diagnostics || (is_panel && self.modules.clock.take_latency_request());
+        if measure_latency {
+            if let Some(presentation) = &self.protocol_states.presentation {
+                presentation.feedback(surface);
+            }
+        }
+
+        if let Some(panel) = self.panels.iter_mut().find(|panel| panel.owns_surface(surface)) {
+            let theme = self.modules.high_contrast.theme();
+            if let Err(error) = panel.draw(&self.modules, theme) {
+                error!(module = "panel", "Panel rendering failed: {error:?}");
             }
+            self.schedule_panel_animation();
         } else if self.drawer().owns_surface(surface) {
             let compositor = &self.protocol_states.compositor;
-            let modules = &mut self.modules.as_slice_mut();
+            let theme = self.modules.high_contrast.theme();
+            let modules = &mut self.modules;
             let drawer = self.drawer.as_mut().unwrap();
-            if let Err(error) = drawer.draw(compositor, modules, self.drawer_opening) {
-                eprintln!("Drawer rendering failed: {error:?}");
+            if let Err(error) = drawer.draw(compositor, modules, theme, self.drawer_opening) {
+                error!(module = "drawer", "Drawer rendering failed: {error:?}");
             }
+        } else if let Err(error) =
+            self.notifications.as_mut().unwrap().draw(surface, self.modules.high_contrast.theme())
+        {
+            error!(module = "notifications", "Notification rendering failed: {error:?}");
+        }
+    }
+
+    /// Redraw windows which stopped receiving frame callbacks.
+    fn recover_stalled_windows(&mut self) {
+        let mut stalled: Vec<_> =
+            self.panels.iter_mut().filter_map(|panel| panel.recover_stall()).collect();
+        stalled.extend(self.drawer().recover_stall());
+        for surface in stalled {
+            self.draw(&surface);
         }
     }
 
     /// Request new frame for all windows.
     fn request_frame(&mut self) {
         self.drawer().request_frame();
-        self.panel().request_frame();
+        self.request_panel_frame();
+
+        // Publish module changes to external widgets.
+        self.status.update(self.modules.status());
+    }
+
+    /// Request new frame for all panels.
+    fn request_panel_frame(&mut self) {
+        for panel in &mut self.panels {
+            panel.request_frame();
+        }
+    }
+
+    /// Schedule a panel redraw for the next module animation frame.
+    ///
+    /// The timer is only rearmed by drawing a panel, so animations stop
+    /// waking up the event loop while all panels are hidden.
+    fn schedule_panel_animation(&mut self) {
+        if self.panel_animation.is_some() {
+            return;
+        }
+
+        let modules = &self.modules;
+        let next_frame =
+            modules.iter().filter_map(|module| module.panel_module()?.next_animation_frame()).min();
+        let next_frame = match next_frame {
+            Some(next_frame) => next_frame,
+            None => return,
+        };
+
+        let timer = Timer::from_deadline(next_frame);
+        let source = self.event_loop.insert_source(timer, |_, _, state| {
+            state.panel_animation = None;
+            state.request_panel_frame();
+            TimeoutAction::Drop
+        });
+        self.panel_animation = source.ok();
+    }
+
+    /// Create a panel window.
+    ///
+    /// Without an output, the compositor picks the output for the panel.
+    fn add_panel(&mut self, queue: &QueueHandle<Self>, output: Option<WlOutput>) -> Result<()> {
+        let renderer = match &self.renderer {
+            Some(renderer) => renderer.clone(),
+            None => return Ok(()),
+        };
+
+        let panel = Panel::new(
+            &self.protocol_states,
+            queue.clone(),
+            renderer,
+            &self.panel_config,
+            output,
+            self.mode != Mode::Drawer,
+            self.reduced_motion,
+        )?;
+        self.panels.push(panel);
+
+        // Conceal panels added while their output is already fullscreen.
+        self.fullscreen_changed();
+
+        Ok(())
     }
 
     /// Set drawer status without animation.
     fn set_drawer_status(&mut self, open: bool) {
         let drawer = self.drawer.as_mut().unwrap();
-        if open {
+        if open && drawer.visible() {
             // Show drawer on panel single-tap with drawer closed.
-            drawer.offset = drawer.max_offset();
+            drawer.set_offset(drawer.max_offset());
             drawer.request_frame();
-        } else {
+        } else if !open {
             // Hide drawer on single-tap of panel or drawer handle.
-            drawer.offset = 0.;
-            drawer.hide();
+            self.hide_drawer();
         }
     }
 
-    fn drawer(&mut self) -> &mut Drawer {
-        self.drawer.as_mut().expect("Drawer window access before initialization")
-    }
+    /// Open or close the drawer on external request.
+    fn request_drawer(&mut self, open: bool) {
+        if self.mode == Mode::Panel {
+            return;
+        }
 
-    fn panel(&mut self) -> &mut Panel {
-        self.panel.as_mut().expect("Panel window access before initialization")
+        if open {
+            self.show_drawer();
+        }
+        self.set_drawer_status(open);
     }
-}
 
-impl ProvidesRegistryState for State {
-    registry_handlers![OutputState, SeatState];
+    /// Map the drawer window, without changing its offset.
+    fn show_drawer(&mut self) {
+        let drawer = self.drawer.as_mut().unwrap();
+        let was_visible = drawer.visible();
 
-    fn registry(&mut self) -> &mut RegistryState {
-        &mut self.protocol_states.registry
-    }
-}
+        let fractional_scale = &self.protocol_states.fractional_scale;
+        let compositor = &self.protocol_states.compositor;
+        let viewporter = &self.protocol_states.viewporter;
+        let layer_state = &mut self.protocol_states.layer;
 
-impl CompositorHandler for State {
-    fn scale_factor_changed(
-        &mut self,
-        _connection: &Connection,
-        _queue: &QueueHandle<Self>,
-        _surface: &WlSurface,
-        _factor: i32,
-    ) {
-        // NOTE: We exclusively use fractional scaling.
-    }
+        // Open the drawer on the output of the touched panel.
+        let output = self.panels.get(self.active_panel).and_then(|panel| panel.output());
 
-    fn frame(
-        &mut self,
-        _connection: &Connection,
-        _queue: &QueueHandle<Self>,
-        surface: &WlSurface,
-        _time: u32,
-    ) {
-        self.draw(surface);
+        if let Err(err) = drawer.show(fractional_scale, compositor, viewporter, layer_state, output)
+        {
+            error!(module = "drawer", "Couldn't open drawer: {err}");
+        } else if !was_visible {
+            self.drawer_visibility_changed(true);
+        }
     }
 
-    fn transform_changed(
-        &mut self,
-        _: &Connection,
-        _: &QueueHandle<Self>,
-        _: &WlSurface,
-        _: Transform,
-    ) {
-    }
+    /// Forward a panel tap to the module at the tap position.
+    ///
+    /// Returns `true` if the module handled the tap.
+    fn panel_tap(&mut self, x: f64) -> bool {
+        let index = match self.panel().module_at(x) {
+            Some(index) => index,
+            None => return false,
+        };
 
-    fn surface_enter(
-        &mut self,
-        _: &Connection,
-        _: &QueueHandle<Self>,
-        _: &WlSurface,
-        _: &WlOutput,
-    ) {
-    }
+        let handled = self.modules.get_mut(index).is_some_and(|module| module.panel_tap());
+        if handled {
+            self.request_frame();
+        }
 
-    fn surface_leave(
-        &mut self,
-        _: &Connection,
-        _: &QueueHandle<Self>,
-        _: &WlSurface,
-        _: &WlOutput,
-    ) {
+        handled
     }
-}
 
-impl FractionalScaleHandler for State {
-    fn scale_factor_changed(
-        &mut self,
-        _connection: &Connection,
-        _queue: &QueueHandle<Self>,
-        surface: &WlSurface,
-        factor: f64,
-    ) {
-        if self.panel().owns_surface(surface) {
-            self.panel.as_mut().unwrap().set_scale_factor(&self.protocol_states.compositor, factor);
-        } else if self.drawer().owns_surface(surface) {
-            self.drawer().set_scale_factor(factor);
+    /// Handle long-presses on the panel.
+    ///
+    /// Returns `false` if the module at the long-press has no long-press
+    /// action.
+    fn panel_long_press(&mut self, x: f64) -> bool {
+        let text = match self.panel().module_at(x) {
+            Some(index) => self.modules.get_mut(index).and_then(|module| module.panel_long_press()),
+            None => None,
+        };
+        let text = match text {
+            Some(text) => text,
+            None => return false,
+        };
+
+        // Copy to clipboard, flashing the panel for confirmation.
+        match &self.protocol_states.data_control {
+            Some(data_control) if data_control.copy(text) => {
+                self.panel().flash();
+                self.request_frame();
+            },
+            _ => error!(module = "clipboard", "Clipboard is not available"),
         }
-        self.draw(surface);
-    }
-}
 
-impl OutputHandler for State {
-    fn output_state(&mut self) -> &mut OutputState {
-        &mut self.protocol_states.output
+        true
     }
 
-    fn new_output(
-        &mut self,
-        _connection: &Connection,
-        _queue: &QueueHandle<Self>,
-        _output: WlOutput,
-    ) {
-    }
+    /// Hide redundant connectivity icons based on the configured policy.
+    fn update_connectivity_icons(&mut self) {
+        let wifi = self.modules.wifi.connection();
+        let cellular = self.modules.cellular.connection();
+        let both_connected =
+            wifi.enabled && wifi.strength > 0 && cellular.enabled && cellular.registered;
+
+        let (hide_wifi, hide_cellular) = match (self.connectivity_icons, self.primary_connection) {
+            _ if !both_connected => (false, false),
+            (ConnectivityIcons::PreferWifi, _) => (false, true),
+            (ConnectivityIcons::DefaultRoute, PrimaryConnection::Wifi) => (false, true),
+            (ConnectivityIcons::DefaultRoute, PrimaryConnection::Cellular) => (true, false),
+            _ => (false, false),
+        };
 
-    fn update_output(
-        &mut self,
-        _connection: &Connection,
-        _queue: &QueueHandle<Self>,
-        _output: WlOutput,
-    ) {
+        let wifi_changed = self.modules.wifi.set_hidden(hide_wifi);
+        let cellular_changed = self.modules.cellular.set_hidden(hide_cellular);
+        if wifi_changed || cellular_changed {
+            self.request_panel_frame();
+        }
     }
 
-    fn output_destroyed(
-        &mut self,
-        _connection: &Connection,
-        _queue: &QueueHandle<Self>,
-        _output: WlOutput,
-    ) {
+    /// Destroy the drawer window.
+    fn hide_drawer(&mut self) {
+        let drawer = self.drawer();
+        if drawer.visible() {
+            drawer.hide();
+            self.drawer_visibility_changed(false);
+        }
     }
-}
 
-impl LayerShellHandler for State {
-    fn closed(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _layer: &LayerSurface) {
-        self.terminated = true;
-    }
+    /// Badge the playback module after a media player command failed.
+    fn media_control_failed(&mut self, error: String) {
+        let playback = &self.modules.playback as &dyn Module as *const dyn Module as *const ();
+        let index = self
+            .modules
+            .iter()
+            .position(|module| module as *const dyn Module as *const () == playback);
 
-    fn configure(
-        &mut self,
-        _conn: &Connection,
-        _queue: &QueueHandle<Self>,
-        layer: &LayerSurface,
-        configure: LayerSurfaceConfigure,
-        _serial: u32,
-    ) {
-        let surface = layer.wl_surface();
-        if self.panel().owns_surface(surface) {
-            self.panel.as_mut().unwrap().reconfigure(&self.protocol_states.compositor, configure);
-        } else if self.drawer().owns_surface(surface) {
-            self.panel_height = Some(configure.new_size.1);
-            self.drawer().reconfigure(configure);
+        if let Some((drawer, index)) = self.drawer.as_mut().zip(index) {
+            drawer.set_error(index, ModuleAction::Media);
         }
-        self.draw(surface);
-    }
-}
 
-impl SeatHandler for State {
-    fn seat_state(&mut self) -> &mut SeatState {
-        &mut self.protocol_states.seat
+        if let Some(notifications) = &mut self.notifications {
+            notifications.notify_local(&self.protocol_states, "Action failed".into(), error);
+        }
     }
 
-    fn new_seat(&mut self, _: &Connection, _: &QueueHandle<Self>, _: WlSeat) {}
-
-    fn new_capability(
-        &mut self,
-        _connection: &Connection,
-        queue: &QueueHandle<Self>,
-        seat: WlSeat,
-        capability: Capability,
-    ) {
-        if capability == Capability::Touch && self.touch.is_none() {
-            self.touch = self.protocol_states.seat.get_touch(queue, &seat).ok();
+    /// Notify modules about drawer visibility changes.
+    fn drawer_visibility_changed(&mut self, visible: bool) {
+        for module in self.modules.iter_mut() {
+            module.drawer_visibility_changed(visible);
         }
     }
 
-    fn remove_capability(
-        &mut self,
-        _connection: &Connection,
-        _queue: &QueueHandle<Self>,
-        _seat: WlSeat,
-        capability: Capability,
-    ) {
-        if capability != Capability::Touch {
-            if let Some(touch) = self.touch.take() {
-                touch.release();
+    /// Get the touch target owning a surface.
+    ///
+    /// This also marks touched panels as active.
+    fn touch_target(&mut self, surface: &WlSurface) -> Option<TouchTarget> {
+        let panel_index = self.panels.iter().position(|panel| panel.owns_surface(surface));
+        if let Some(index) = panel_index {
+            // Ignore panels on other outputs while another touch is active.
+            if self.active_touch.is_none() {
+                self.active_panel = index;
             }
+            Some(TouchTarget::Panel)
+        } else if self.drawer().owns_surface(surface) {
+            Some(TouchTarget::Drawer)
+        } else {
+            self.notifications().owns_surface(surface).map(TouchTarget::Notification)
         }
     }
 
-    fn remove_seat(&mut self, _: &Connection, _: &QueueHandle<Self>, _: WlSeat) {}
-}
-
-impl TouchHandler for State {
-    fn down(
-        &mut self,
-        _connection: &Connection,
-        _queue: &QueueHandle<Self>,
-        _touch: &WlTouch,
-        _serial: u32,
-        _time: u32,
-        surface: WlSurface,
-        id: i32,
-        position: (f64, f64),
-    ) {
-        let drawer = self.drawer.as_mut().unwrap();
-        let panel = self.panel.as_ref().unwrap();
+    /// Handle touch press events.
+    fn touch_down(&mut self, target: TouchTarget, id: i32, position: (f64, f64)) {
+        if let Some(touch_recorder) = &mut self.touch_recorder {
+            touch_recorder.record(TouchEvent::Down { target, id, position });
+        }
 
-        if self.active_touch.is_none() && panel.owns_surface(&surface) {
-            let fractional_scale = &self.protocol_states.fractional_scale;
-            let compositor = &self.protocol_states.compositor;
-            let viewporter = &self.protocol_states.viewporter;
-            let layer_state = &mut self.protocol_states.layer;
-            if let Err(err) = drawer.show(fractional_scale, compositor, viewporter, layer_state) {
-                eprintln!("Error: Couldn't open drawer: {err}");
+        if self.active_touch.is_none() && target == TouchTarget::Panel {
+            if self.mode != Mode::Panel {
+                self.show_drawer();
             }
 
             self.last_touch_y = position.1;
             self.touch_start = position;
             self.active_touch = Some(id);
             self.drawer_opening = true;
-        } else if drawer.owns_surface(&surface) {
-            let touch_start = drawer.touch_down(id, position, &mut self.modules.as_slice_mut());
+
+            // Wait for long-press on panel modules.
+            self.stage_panel_long_press();
+        } else if target == TouchTarget::Drawer {
+            let drawer = self.drawer.as_mut().unwrap();
+            let touch_start = drawer.touch_down(id, position, &mut self.modules);
 
             // Check drawer touch status.
             if !touch_start.module_touched {
@@ -465,19 +798,47 @@ impl TouchHandler for State {
             } else if touch_start.requires_redraw {
                 // Redraw if slider was touched.
                 self.request_frame();
+            } else {
+                // Wait for long-press on buttons.
+                self.stage_long_press();
             }
+        } else if let TouchTarget::Notification(notification_id) = target {
+            self.notifications().touch_down(notification_id, id, position);
         }
     }
 
-    fn up(
-        &mut self,
-        _connection: &Connection,
-        _queue: &QueueHandle<Self>,
-        _touch: &WlTouch,
-        _serial: u32,
-        _time: u32,
-        id: i32,
-    ) {
+    /// Handle touch release events.
+    fn touch_up(&mut self, id: i32) {
+        if let Some(touch_recorder) = &mut self.touch_recorder {
+            touch_recorder.record(TouchEvent::Up { id });
+        }
+
+        // Handle notification swipes.
+        let notifications = self.notifications.as_mut().unwrap();
+        if notifications.touch_up(&self.protocol_states, id) {
+            return;
+        }
+
+        // Run the action of completed panel swipes.
+        if self.active_touch == Some(id) {
+            if let Some(action) = self.pending_swipe.take() {
+                self.active_touch = None;
+                self.hide_drawer();
+                action.run(self);
+                return;
+            }
+        }
+
+        // Ignore release of completed panel long-presses.
+        if self.active_touch == Some(id) && self.panel_long_pressed {
+            self.panel_long_pressed = false;
+            self.active_touch = None;
+            self.hide_drawer();
+            return;
+        }
+
+        self.cancel_long_press();
+
         let drawer = self.drawer.as_mut().unwrap();
 
         // Handle non-module touch events.
@@ -494,16 +855,19 @@ impl TouchHandler for State {
                     }
 
                     // Turn off display on panel double-tap.
-                    if self.touch_start.1 <= PANEL_HEIGHT as f64 {
+                    if self.touch_start.1 <= self.panel_config.height as f64 {
                         let msg = IpcMessage::Dpms { state: Some(DpmsState::Off) };
                         let _ = catacomb_ipc::send_message(&msg);
                     }
-                } else if self.touch_start.1 <= PANEL_HEIGHT as f64 {
+                } else if self.touch_start.1 <= self.panel_config.height as f64 {
                     // Stage delayed single-tap for taps on the top panel.
                     let drawer_opening = self.drawer_opening;
+                    let x = self.touch_start.0;
                     let timer = Timer::from_duration(MAX_DOUBLE_TAP_DURATION);
                     let source = self.event_loop.insert_source(timer, move |_, _, state| {
-                        state.set_drawer_status(drawer_opening);
+                        // Let modules handle the tap instead of opening the drawer.
+                        let module_tapped = drawer_opening && state.panel_tap(x);
+                        state.set_drawer_status(drawer_opening && !module_tapped);
                         TimeoutAction::Drop
                     });
                     self.tap_timeout = source.ok();
@@ -522,45 +886,72 @@ impl TouchHandler for State {
             }
         // Handle module touch events.
         } else {
-            let dirty = drawer.touch_up(id, &mut self.modules.as_slice_mut());
+            let touch_end = drawer.touch_up(id, &mut self.modules);
 
-            if dirty {
-                self.request_frame();
+            // Show popup for failed module actions.
+            if let Some(error) = touch_end.error {
+                let notifications = self.notifications.as_mut().unwrap();
+                notifications.notify_local(&self.protocol_states, "Action failed".into(), error);
+            }
+
+            if touch_end.requires_redraw {
+                self.request_frame();
             }
         }
     }
 
-    fn motion(
-        &mut self,
-        _connection: &Connection,
-        _queue: &QueueHandle<Self>,
-        _touch: &WlTouch,
-        _time: u32,
-        id: i32,
-        position: (f64, f64),
-    ) {
+    /// Handle touch motion events.
+    fn touch_motion(&mut self, id: i32, position: (f64, f64)) {
+        if let Some(touch_recorder) = &mut self.touch_recorder {
+            touch_recorder.record(TouchEvent::Motion { id, position });
+        }
+
+        // Handle notification swipes.
+        if self.notifications().touch_motion(id, position) {
+            return;
+        }
+
         if self.active_touch == Some(id) {
+            // Ignore further motion once a panel swipe or long-press was detected.
+            if self.pending_swipe.is_some() || self.panel_long_pressed {
+                return;
+            }
+
             // Ignore touch motion until drag threshold is reached.
             let x_delta = position.0 - self.touch_start.0;
             let y_delta = position.1 - self.touch_start.1;
             if x_delta.powi(2) + y_delta.powi(2) <= MAX_TAP_DISTANCE {
                 return;
             }
+            self.cancel_long_press();
+
+            // Detect horizontal panel swipes, instead of dragging the drawer.
+            if self.drawer_opening
+                && !self.drawer().offsetting
+                && x_delta.abs() > y_delta.abs() * 2.
+            {
+                let action = if x_delta > 0. { self.swipe_right } else { self.swipe_left };
+                if action != Action::None {
+                    self.pending_swipe = Some(action);
+                    return;
+                }
+            }
+
+            // Ignore drags without a visible drawer, like in panel-only mode.
+            if !self.drawer().visible() {
+                return;
+            }
 
             let delta = position.1 - self.last_touch_y;
 
             let drawer = self.drawer();
             drawer.offsetting = true;
-            drawer.offset += delta;
+            drawer.set_offset(drawer.offset() + delta);
             drawer.request_frame();
 
             self.last_touch_y = position.1;
         } else {
-            let dirty = self.drawer.as_mut().unwrap().touch_motion(
-                id,
-                position,
-                &mut self.modules.as_slice_mut(),
-            );
+            let dirty = self.drawer.as_mut().unwrap().touch_motion(id, position, &mut self.modules);
 
             if dirty {
                 self.request_frame();
@@ -568,7 +959,571 @@ impl TouchHandler for State {
         }
     }
 
-    fn cancel(&mut self, _connection: &Connection, _queue: &QueueHandle<Self>, _touch: &WlTouch) {}
+    /// Handle recorded touch events.
+    fn replay_touch(&mut self, event: TouchEvent) {
+        match event {
+            TouchEvent::Down { target, id, position } => self.touch_down(target, id, position),
+            TouchEvent::Up { id } => self.touch_up(id),
+            TouchEvent::Motion { id, position } => self.touch_motion(id, position),
+            TouchEvent::Cancel => self.touch_cancel(),
+        }
+    }
+
+    /// Handle compositor touch cancellation.
+    ///
+    /// This discards all active touches without triggering taps, while
+    /// completing any drawer drag.
+    fn touch_cancel(&mut self) {
+        if let Some(touch_recorder) = &mut self.touch_recorder {
+            touch_recorder.record(TouchEvent::Cancel);
+        }
+
+        self.cancel_long_press();
+        self.notifications().touch_cancel();
+
+        // Discard panel swipes and long-presses.
+        let long_pressed = mem::take(&mut self.panel_long_pressed);
+        if self.pending_swipe.take().is_some() || long_pressed {
+            self.active_touch = None;
+            self.hide_drawer();
+        }
+
+        // Settle the drawer in its closest position.
+        let drawer = self.drawer.as_mut().unwrap();
+        if self.active_touch.take().is_some() && drawer.offsetting {
+            let _ = self.event_loop.insert_source(Timer::immediate(), animate_drawer);
+            drawer.offsetting = false;
+        }
+
+        if drawer.touch_cancel(&mut self.modules) {
+            self.request_frame();
+        }
+    }
+
+    /// Stage delayed long-press for the active drawer module touch.
+    fn stage_long_press(&mut self) {
+        self.cancel_long_press();
+
+        let timer = Timer::from_duration(LONG_PRESS_DURATION);
+        let source = self.event_loop.insert_source(timer, |_, _, state| {
+            state.long_press_timeout = None;
+
+            if state.drawer.as_mut().unwrap().long_press(&mut state.modules) {
+                state.request_frame();
+            }
+
+            TimeoutAction::Drop
+        });
+        self.long_press_timeout = source.ok();
+    }
+
+    /// Stage delayed long-press for the active panel touch.
+    fn stage_panel_long_press(&mut self) {
+        self.cancel_long_press();
+
+        let timer = Timer::from_duration(LONG_PRESS_DURATION);
+        let source = self.event_loop.insert_source(timer, |_, _, state| {
+            state.long_press_timeout = None;
+            state.panel_long_pressed = state.panel_long_press(state.touch_start.0);
+            TimeoutAction::Drop
+        });
+        self.long_press_timeout = source.ok();
+    }
+
+    /// Cancel pending long-press callback.
+    fn cancel_long_press(&mut self) {
+        if let Some(source) = self.long_press_timeout.take() {
+            self.event_loop.remove(source);
+        }
+    }
+
+    /// Track which outputs a window's surface is visible on.
+    fn surface_output_changed(&mut self, surface: &WlSurface, output: &WlOutput, entered: bool) {
+        if let Some(panel) = self.panels.iter_mut().find(|panel| panel.owns_surface(surface)) {
+            panel.set_entered(output, entered);
+        } else if let Some(drawer) =
+            self.drawer.as_mut().filter(|drawer| drawer.owns_surface(surface))
+        {
+            drawer.set_entered(output, entered);
+        }
+    }
+
+    /// Match the drawer animation rate to the fastest output's refresh rate.
+    ///
+    /// Updating the animation more often than the output can present frames
+    /// only causes redundant draws, while updating it less often makes the
+    /// animation stutter.
+    fn update_animation_interval(&mut self, removed: Option<&WlOutput>) {
+        let output_state = &self.protocol_states.output;
+        let max_refresh = output_state
+            .outputs()
+            .filter(|output| Some(output) != removed)
+            .filter_map(|output| output_state.info(&output))
+            .flat_map(|info| info.modes.into_iter().filter(|mode| mode.current))
+            .map(|mode| mode.refresh_rate)
+            .filter(|refresh| *refresh > 0)
+            .max();
+
+        // Refresh rate is reported in mHz.
+        self.animation_interval = match max_refresh {
+            Some(refresh) => Duration::from_secs(1000) / refresh as u32,
+            None => ANIMATION_INTERVAL,
+        };
+    }
+
+    fn drawer(&mut self) -> &mut Drawer {
+        self.drawer.as_mut().expect("Drawer window access before initialization")
+    }
+
+    /// Get the last touched panel.
+    fn panel(&mut self) -> &mut Panel {
+        self.panels.get_mut(self.active_panel).expect("Panel window access before initialization")
+    }
+
+    fn notifications(&mut self) -> &mut Notifications {
+        self.notifications.as_mut().expect("Notification access before initialization")
+    }
+}
+
+impl ProvidesRegistryState for State {
+    registry_handlers![OutputState, SeatState];
+
+    fn registry(&mut self) -> &mut RegistryState {
+        &mut self.protocol_states.registry
+    }
+}
+
+impl CompositorHandler for State {
+    fn scale_factor_changed(
+        &mut self,
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+        _surface: &WlSurface,
+        _factor: i32,
+    ) {
+        // NOTE: We exclusively use fractional scaling.
+    }
+
+    fn frame(
+        &mut self,
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+        surface: &WlSurface,
+        _time: u32,
+    ) {
+        // Track frame callbacks separately from other redraws, for stall detection.
+        if let Some(panel) = self.panels.iter_mut().find(|panel| panel.owns_surface(surface)) {
+            panel.frame_received();
+        } else if let Some(drawer) =
+            self.drawer.as_mut().filter(|drawer| drawer.owns_surface(surface))
+        {
+            drawer.frame_received();
+        }
+
+        self.draw(surface);
+    }
+
+    fn transform_changed(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        surface: &WlSurface,
+        _: Transform,
+    ) {
+        // Redraw to avoid showing stale content after output rotation.
+        if let Some(panel) = self.panels.iter_mut().find(|panel| panel.owns_surface(surface)) {
+            panel.request_frame();
+        } else if let Some(drawer) =
+            self.drawer.as_mut().filter(|drawer| drawer.owns_surface(surface))
+        {
+            drawer.request_frame();
+        }
+    }
+
+    fn surface_enter(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        surface: &WlSurface,
+        output: &WlOutput,
+    ) {
+        self.surface_output_changed(surface, output, true);
+    }
+
+    fn surface_leave(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        surface: &WlSurface,
+        output: &WlOutput,
+    ) {
+        self.surface_output_changed(surface, output, false);
+    }
+}
+
+impl FractionalScaleHandler for State {
+    fn scale_factor_changed(
+        &mut self,
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+        surface: &WlSurface,
+        factor: f64,
+    ) {
+        if let Some(panel) = self.panels.iter_mut().find(|panel| panel.owns_surface(surface)) {
+            panel.set_scale_factor(&self.protocol_states.compositor, factor);
+        } else if self.drawer().owns_surface(surface) {
+            self.drawer().set_scale_factor(factor);
+        } else {
+            self.notifications().set_scale_factor(surface, factor);
+        }
+        self.draw(surface);
+    }
+}
+
+impl ForeignToplevelHandler for State {
+    fn fullscreen_changed(&mut self) {
+        let manager = match &self.protocol_states.foreign_toplevel {
+            Some(manager) => manager,
+            None => return,
+        };
+
+        // Only conceal panels on outputs with a fullscreen window.
+        let was_visible = self.panels.iter().any(|panel| !panel.concealed());
+        for panel in &mut self.panels {
+            panel.set_concealed(manager.has_fullscreen(panel.output()));
+        }
+        let visible = self.panels.iter().any(|panel| !panel.concealed());
+
+        // Close the drawer when its output switches to a fullscreen window.
+        let drawer_covered = self
+            .drawer
+            .as_ref()
+            .is_some_and(|drawer| drawer.visible() && manager.has_fullscreen(drawer.output()));
+        if drawer_covered {
+            self.hide_drawer();
+        }
+
+        if visible != was_visible {
+            for module in self.modules.iter_mut() {
+                module.panel_visibility_changed(visible);
+            }
+        }
+    }
+
+    fn toplevels_changed(&mut self) {
+        if !self.task_switcher {
+            return;
+        }
+
+        let manager = match &self.protocol_states.foreign_toplevel {
+            Some(manager) => manager,
+            None => return,
+        };
+
+        let old_len = self.modules.tasks.len();
+        let seat = manager.seat();
+        self.modules.tasks =
+            manager.toplevels().iter().map(|toplevel| Task::new(toplevel, seat.cloned())).collect();
+
+        if let Some(drawer) = &mut self.drawer {
+            // Abort module touches, since the touched module's index might be gone.
+            if old_len != self.modules.tasks.len() {
+                drawer.touch_cancel(&mut self.modules);
+            }
+
+            drawer.request_frame();
+        }
+    }
+}
+
+impl PresentationHandler for State {
+    fn presented(&mut self, surface: &WlSurface, latency: Duration) {
+        let is_panel = self.panels.iter().any(|panel| panel.owns_surface(surface));
+        if is_panel {
+            self.modules.clock.set_latency(latency);
+        }
+
+        if !self.diagnostics {
+            return;
+        }
+
+        if is_panel {
+            debug!(module = "presentation", "Panel presented after {latency:?}");
+        } else if self.drawer().owns_surface(surface) {
+            debug!(module = "presentation", "Drawer presented after {latency:?}");
+        }
+    }
+}
+
+impl OutputHandler for State {
+    fn output_state(&mut self) -> &mut OutputState {
+        &mut self.protocol_states.output
+    }
+
+    fn new_output(
+        &mut self,
+        _connection: &Connection,
+        queue: &QueueHandle<Self>,
+        output: WlOutput,
+    ) {
+        self.update_animation_interval(None);
+
+        if let Some(info) = self.protocol_states.output.info(&output) {
+            self.output_properties.insert(output.id(), OutputProperties::from(&info));
+        }
+
+        // Skip panel creation before initialization and for known outputs.
+        if self.mode == Mode::Drawer
+            || self.renderer.is_none()
+            || self.panels.iter().any(|panel| panel.output() == Some(&output))
+        {
+            return;
+        }
+
+        // Replace the panel on the compositor's choice of output.
+        self.panels.retain(|panel| panel.output().is_some());
+        self.active_panel = 0;
+
+        if let Err(err) = self.add_panel(queue, Some(output)) {
+            error!(module = "panel", "Could not create panel for new output: {err}");
+        }
+    }
+
+    fn update_output(
+        &mut self,
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+        output: WlOutput,
+    ) {
+        self.update_animation_interval(None);
+
+        let info = self.protocol_states.output.info(&output);
+
+        // Only re-request the fractional scale if it might have changed.
+        let rescale = info.as_ref().is_some_and(|info| {
+            let properties = OutputProperties::from(info);
+            self.output_properties.insert(output.id(), properties) != Some(properties)
+        });
+
+        // Resize windows immediately, instead of waiting for the next configure.
+        let output_size =
+            info.and_then(|info| info.logical_size).map(|(width, height)| Size::new(width, height));
+
+        let fractional_scale = &self.protocol_states.fractional_scale;
+        let compositor = &self.protocol_states.compositor;
+        let mut panel_zone = 0;
+        for panel in self.panels.iter_mut().filter(|panel| panel.on_output(&output)) {
+            let width = output_size.map(|size| size.width);
+            panel.output_changed(fractional_scale, compositor, width, rescale);
+            panel_zone = panel.exclusive_zone();
+        }
+
+        if let Some(drawer) = self.drawer.as_mut().filter(|drawer| drawer.on_output(&output)) {
+            drawer.output_changed(fractional_scale, output_size, panel_zone, rescale);
+        }
+    }
+
+    fn output_destroyed(
+        &mut self,
+        _connection: &Connection,
+        queue: &QueueHandle<Self>,
+        output: WlOutput,
+    ) {
+        self.update_animation_interval(Some(&output));
+        self.output_properties.remove(&output.id());
+
+        let old_len = self.panels.len();
+        self.panels.retain(|panel| panel.output() != Some(&output));
+        if self.panels.len() == old_len {
+            return;
+        }
+        self.active_panel = 0;
+
+        // Fall back to letting the compositor pick the output.
+        if self.panels.is_empty() {
+            if let Err(err) = self.add_panel(queue, None) {
+                error!(module = "panel", "Could not create panel: {err}");
+            }
+        }
+    }
+}
+
+impl LayerShellHandler for State {
+    fn closed(&mut self, _conn: &Connection, queue: &QueueHandle<Self>, layer: &LayerSurface) {
+        let surface = layer.wl_surface();
+
+        // Remove notifications closed by the compositor.
+        if let Some(id) = self.notifications().owns_surface(surface) {
+            let notifications = self.notifications.as_mut().unwrap();
+            notifications.close(&self.protocol_states, id, CloseReason::Dismissed);
+            return;
+        }
+
+        // Only hide the drawer, it is recreated on the next panel touch.
+        if self.drawer().owns_surface(surface) {
+            self.hide_drawer();
+            return;
+        }
+
+        // Remove panels whose output went away.
+        let old_len = self.panels.len();
+        self.panels.retain(|panel| !panel.owns_surface(surface));
+        if self.panels.len() == old_len {
+            self.terminated = true;
+            return;
+        }
+        self.active_panel = 0;
+
+        // Fall back to letting the compositor pick the output.
+        if self.panels.is_empty() {
+            if let Err(err) = self.add_panel(queue, None) {
+                error!(module = "panel", "Could not create panel: {err}");
+            }
+        }
+    }
+
+    fn configure(
+        &mut self,
+        _conn: &Connection,
+        _queue: &QueueHandle<Self>,
+        layer: &LayerSurface,
+        configure: LayerSurfaceConfigure,
+        _serial: u32,
+    ) {
+        let surface = layer.wl_surface();
+        if let Some(panel) = self.panels.iter_mut().find(|panel| panel.owns_surface(surface)) {
+            panel.reconfigure(&self.protocol_states.compositor, configure);
+
+            // Replay recorded touch events for debugging, once touches can be mapped.
+            if let Some(path) = self.touch_replay.take() {
+                if let Err(err) = recording::replay(&self.event_loop, &path) {
+                    error!(module = "recording", "Could not replay touch from {path:?}: {err}");
+                }
+            }
+        } else if self.drawer().owns_surface(surface) {
+            self.panel_height = Some(configure.new_size.1);
+            self.drawer().reconfigure(configure);
+        } else {
+            self.notifications().reconfigure(surface, configure);
+        }
+        self.draw(surface);
+    }
+}
+
+impl SeatHandler for State {
+    fn seat_state(&mut self) -> &mut SeatState {
+        &mut self.protocol_states.seat
+    }
+
+    fn new_seat(&mut self, _: &Connection, _: &QueueHandle<Self>, _: WlSeat) {}
+
+    fn new_capability(
+        &mut self,
+        _connection: &Connection,
+        queue: &QueueHandle<Self>,
+        seat: WlSeat,
+        capability: Capability,
+    ) {
+        // Setup clipboard access, if the compositor supports it.
+        if let Some(data_control) = &mut self.protocol_states.data_control {
+            data_control.add_seat(&seat);
+        }
+
+        // Allow focusing windows from the task switcher.
+        if let Some(foreign_toplevel) = &mut self.protocol_states.foreign_toplevel {
+            foreign_toplevel.add_seat(&seat);
+        }
+
+        if capability == Capability::Touch && self.touch.is_none() {
+            self.touch = self.protocol_states.seat.get_touch(queue, &seat).ok();
+        } else if capability == Capability::Pointer && self.pointer.is_none() {
+            self.pointer = self.protocol_states.seat.get_pointer(queue, &seat).ok();
+
+            // Setup cursor shapes, if the compositor supports it.
+            let cursor_shape = self.protocol_states.cursor_shape.as_ref();
+            self.cursor = cursor_shape
+                .zip(self.pointer.as_ref())
+                .map(|(manager, pointer)| PointerCursor::new(manager.device(queue, pointer)));
+        } else if capability == Capability::Keyboard && self.keyboard.is_none() {
+            self.keyboard = self.protocol_states.seat.get_keyboard(queue, &seat, None).ok();
+        }
+    }
+
+    fn remove_capability(
+        &mut self,
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+        _seat: WlSeat,
+        capability: Capability,
+    ) {
+        if capability == Capability::Touch {
+            if let Some(touch) = self.touch.take() {
+                touch.release();
+            }
+        } else if capability == Capability::Pointer {
+            self.cursor = None;
+            if let Some(pointer) = self.pointer.take() {
+                pointer.release();
+            }
+
+            // Release emulated touches, since the button release will never arrive.
+            if mem::take(&mut self.pointer_pressed) {
+                self.touch_cancel();
+            }
+        } else if capability == Capability::Keyboard {
+            if let Some(keyboard) = self.keyboard.take() {
+                keyboard.release();
+            }
+        }
+    }
+
+    fn remove_seat(&mut self, _: &Connection, _: &QueueHandle<Self>, _: WlSeat) {}
+}
+
+impl TouchHandler for State {
+    fn down(
+        &mut self,
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+        _touch: &WlTouch,
+        _serial: u32,
+        _time: u32,
+        surface: WlSurface,
+        id: i32,
+        position: (f64, f64),
+    ) {
+        if let Some(target) = self.touch_target(&surface) {
+            self.touch_down(target, id, position);
+        }
+    }
+
+    fn up(
+        &mut self,
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+        _touch: &WlTouch,
+        _serial: u32,
+        _time: u32,
+        id: i32,
+    ) {
+        self.touch_up(id);
+    }
+
+    fn motion(
+        &mut self,
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+        _touch: &WlTouch,
+        _time: u32,
+        id: i32,
+        position: (f64, f64),
+    ) {
+        self.touch_motion(id, position);
+    }
+
+    fn cancel(&mut self, _connection: &Connection, _queue: &QueueHandle<Self>, _touch: &WlTouch) {
+        self.touch_cancel();
+    }
 
     fn shape(
         &mut self,
@@ -592,17 +1547,168 @@ impl TouchHandler for State {
     }
 }
 
+impl PointerHandler for State {
+    fn pointer_frame(
+        &mut self,
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+        _pointer: &WlPointer,
+        events: &[PointerEvent],
+    ) {
+        for event in events {
+            // Emulate touch input, to reuse the panel and drawer gestures.
+            match event.kind {
+                PointerEventKind::Press { button: BTN_LEFT, .. } if !self.pointer_pressed => {
+                    if let Some(target) = self.touch_target(&event.surface) {
+                        self.pointer_pressed = true;
+                        self.touch_down(target, POINTER_TOUCH_ID, event.position);
+                    }
+                },
+                PointerEventKind::Release { button: BTN_LEFT, .. } if self.pointer_pressed => {
+                    self.pointer_pressed = false;
+                    self.touch_up(POINTER_TOUCH_ID);
+                },
+                PointerEventKind::Motion { .. } if self.pointer_pressed => {
+                    self.touch_motion(POINTER_TOUCH_ID, event.position);
+                },
+                PointerEventKind::Axis { vertical, .. } if !self.pointer_pressed => {
+                    let drawer = self.drawer.as_mut().unwrap();
+                    if !drawer.owns_surface(&event.surface) {
+                        continue;
+                    }
+
+                    // Scrolling up increases the value, like on a vertical slider.
+                    let steps = if vertical.discrete != 0 {
+                        -vertical.discrete as f64
+                    } else {
+                        -vertical.absolute / 10.
+                    };
+
+                    if drawer.scroll(event.position, steps, &mut self.modules) {
+                        self.request_frame();
+                    }
+                },
+                _ => (),
+            }
+
+            let cursor = match &mut self.cursor {
+                Some(cursor) => cursor,
+                None => continue,
+            };
+
+            match event.kind {
+                PointerEventKind::Enter { serial } => cursor.enter(serial),
+                PointerEventKind::Motion { .. } => (),
+                _ => continue,
+            }
+
+            // Indicate interactive drawer modules and drawer dragging.
+            let drawer = self.drawer.as_ref().unwrap();
+            let shape = if !drawer.owns_surface(&event.surface) {
+                Shape::Default
+            } else if drawer.offsetting {
+                Shape::Grabbing
+            } else if drawer.hovers_module(event.position, &mut self.modules) {
+                Shape::Pointer
+            } else {
+                Shape::Default
+            };
+            cursor.set_shape(shape);
+        }
+    }
+}
+
+impl KeyboardHandler for State {
+    fn press_key(
+        &mut self,
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+        _keyboard: &WlKeyboard,
+        _serial: u32,
+        event: KeyEvent,
+    ) {
+        // Close the drawer with Escape.
+        if event.keysym == Keysym::Escape && self.drawer().visible() {
+            self.set_drawer_status(false);
+        }
+    }
+
+    fn enter(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &WlKeyboard,
+        _: &WlSurface,
+        _: u32,
+        _: &[u32],
+        _: &[Keysym],
+    ) {
+    }
+
+    fn leave(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &WlKeyboard,
+        _: &WlSurface,
+        _: u32,
+    ) {
+    }
+
+    fn release_key(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &WlKeyboard,
+        _: u32,
+        _: KeyEvent,
+    ) {
+    }
+
+    fn update_modifiers(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &WlKeyboard,
+        _: u32,
+        _: Modifiers,
+        _: u32,
+    ) {
+    }
+}
+
 delegate_compositor!(State);
 delegate_output!(State);
 delegate_layer!(State);
 delegate_seat!(State);
 delegate_touch!(State);
+delegate_pointer!(State);
+delegate_keyboard!(State);
 
 delegate_registry!(State);
 
+/// Output properties affecting the preferred fractional scale.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+struct OutputProperties {
+    mode: Option<(i32, i32)>,
+    scale_factor: i32,
+    transform: Transform,
+}
+
+impl From<&OutputInfo> for OutputProperties {
+    fn from(info: &OutputInfo) -> Self {
+        let mode = info.modes.iter().find(|mode| mode.current).map(|mode| mode.dimensions);
+        Self { mode, scale_factor: info.scale_factor, transform: info.transform }
+    }
+}
+
 #[derive(Debug)]
 struct ProtocolStates {
+    foreign_toplevel: Option<ForeignToplevelManager>,
+    data_control: Option<DataControlManager>,
+    cursor_shape: Option<CursorShapeManager>,
     fractional_scale: FractionalScaleManager,
+    presentation: Option<Presentation>,
     compositor: CompositorState,
     registry: RegistryState,
     viewporter: Viewporter,
@@ -622,66 +1728,192 @@ impl ProtocolStates {
             layer: LayerShell::bind(globals, queue).expect("missing wlr_layer_shell"),
             output: OutputState::new(globals, queue),
             seat: SeatState::new(globals, queue),
+            cursor_shape: CursorShapeManager::new(globals, queue).ok(),
+            presentation: Presentation::new(globals, queue).ok(),
+            foreign_toplevel: ForeignToplevelManager::new(globals, queue).ok(),
+            data_control: DataControlManager::new(globals, queue).ok(),
         }
     }
 }
 
 /// Panel modules.
 struct Modules {
+    diagnostics: Diagnostics,
     orientation: Orientation,
+    high_contrast: HighContrast,
     brightness: Brightness,
+    keyboard_backlight: KeyboardBacklight,
     flashlight: Flashlight,
+    charge_limit: ChargeLimit,
+    ring_mode: RingMode,
+    do_not_disturb: DoNotDisturb,
+    playback: Playback,
+    network_mode: NetworkMode,
+    sim_slot: SimSlot,
+    cellular_profile: CellularProfile,
+    location: Location,
+    metered: Metered,
     cellular: Cellular,
     battery: Battery,
     scale: Scale,
+    custom: Vec<CustomDbus>,
+    plugins: Vec<Plugin>,
+    tasks: Vec<Task>,
     clock: Clock,
     wifi: Wifi,
 }
 
 impl Modules {
-    fn new(event_loop: &LoopHandle<'static, State>) -> Result<Self> {
+    fn new(event_loop: &LoopHandle<'static, State>, config: &Config) -> Result<Self> {
+        let max_age = Duration::from_secs(config.panel.stale_timeout);
+
+        let mut custom = Vec::new();
+        for (index, dbus_config) in config.dbus.iter().enumerate() {
+            custom.push(CustomDbus::new(event_loop, dbus_config.clone(), index, max_age)?);
+        }
+
+        // Skip plugins which fail to load, instead of aborting startup.
+        let mut plugins = Vec::new();
+        for path in &config.plugins {
+            match Plugin::load(path) {
+                Ok(plugin) => plugins.push(plugin),
+                Err(err) => error!(module = "plugin", "Could not load plugin {path:?}: {err}"),
+            }
+        }
+
         Ok(Self {
+            diagnostics: Diagnostics::new(config.diagnostics.enabled),
             orientation: Orientation::new(),
+            high_contrast: Default::default(),
+            do_not_disturb: DoNotDisturb::new(),
             brightness: Brightness::new()?,
-            flashlight: Flashlight::new(),
-            cellular: Cellular::new(event_loop)?,
-            battery: Battery::new(event_loop)?,
-            clock: Clock::new(event_loop)?,
-            wifi: Wifi::new(event_loop)?,
+            keyboard_backlight: KeyboardBacklight::new(),
+            flashlight: Flashlight::new(event_loop, config.module.flashlight.clone()),
+            charge_limit: ChargeLimit::new(config.module.battery.slow_charge_limit),
+            ring_mode: RingMode::new(event_loop, max_age)?,
+            playback: Playback::new(event_loop)?,
+            network_mode: NetworkMode::new(),
+            sim_slot: SimSlot::new(),
+            cellular_profile: CellularProfile::new(event_loop)?,
+            location: Location::new(event_loop)?,
+            metered: Metered::new(event_loop)?,
+            cellular: Cellular::new(event_loop, max_age, config.module.cellular.clone())?,
+            battery: Battery::new(event_loop, config.module.battery.clone())?,
+            clock: Clock::new(event_loop, config.module.clock.clone())?,
+            wifi: Wifi::new(event_loop, max_age, config.reduced_motion)?,
             scale: Scale::new(),
+            custom,
+            plugins,
+            tasks: Vec::new(),
         })
     }
 
-    /// Get all modules as sorted immutable slice.
-    fn as_slice(&self) -> [&dyn Module; 8] {
-        [
+    /// Get module values for external widgets.
+    fn status(&self) -> PanelStatus {
+        let wifi = self.wifi.connection();
+        let cellular = self.cellular.connection();
+
+        PanelStatus {
+            battery_capacity: self.battery.capacity(),
+            battery_charging: self.battery.charging(),
+            wifi_strength: if wifi.enabled { wifi.strength } else { 0 },
+            wifi_connected: wifi.connected,
+            cellular_strength: if cellular.enabled { cellular.strength } else { 0 },
+            cellular_registered: cellular.registered,
+            do_not_disturb: self.do_not_disturb.enabled(),
+        }
+    }
+
+    /// Get all built-in modules with their IPC names.
+    fn named_mut(&mut self) -> Vec<(&'static str, &mut dyn Module)> {
+        vec![
+            ("brightness", &mut self.brightness),
+            ("keyboard_backlight", &mut self.keyboard_backlight),
+            ("scale", &mut self.scale),
+            ("clock", &mut self.clock),
+            ("ring_mode", &mut self.ring_mode),
+            ("do_not_disturb", &mut self.do_not_disturb),
+            ("playback", &mut self.playback),
+            ("location", &mut self.location),
+            ("metered", &mut self.metered),
+            ("cellular", &mut self.cellular),
+            ("sim_slot", &mut self.sim_slot),
+            ("cellular_profile", &mut self.cellular_profile),
+            ("wifi", &mut self.wifi),
+            ("battery", &mut self.battery),
+            ("orientation", &mut self.orientation),
+            ("high_contrast", &mut self.high_contrast),
+            ("flashlight", &mut self.flashlight),
+            ("charge_limit", &mut self.charge_limit),
+            ("network_mode", &mut self.network_mode),
+            ("diagnostics", &mut self.diagnostics),
+        ]
+    }
+}
+
+impl ModuleList for Modules {
+    fn iter(&self) -> impl Iterator<Item = &dyn Module> {
+        let fixed: [&dyn Module; 20] = [
             &self.brightness,
+            &self.keyboard_backlight,
             &self.scale,
             &self.clock,
+            &self.ring_mode,
+            &self.do_not_disturb,
+            &self.playback,
+            &self.location,
+            &self.metered,
             &self.cellular,
+            &self.sim_slot,
+            &self.cellular_profile,
             &self.wifi,
             &self.battery,
             &self.orientation,
+            &self.high_contrast,
             &self.flashlight,
-        ]
+            &self.charge_limit,
+            &self.network_mode,
+            &self.diagnostics,
+        ];
+        fixed
+            .into_iter()
+            .chain(self.custom.iter().map(|module| module as &dyn Module))
+            .chain(self.plugins.iter().map(|module| module as &dyn Module))
+            .chain(self.tasks.iter().map(|module| module as &dyn Module))
     }
 
-    /// Get all modules as sorted mutable slice.
-    fn as_slice_mut(&mut self) -> [&mut dyn Module; 8] {
-        [
+    fn iter_mut(&mut self) -> impl Iterator<Item = &mut dyn Module> {
+        let fixed: [&mut dyn Module; 20] = [
             &mut self.brightness,
+            &mut self.keyboard_backlight,
             &mut self.scale,
             &mut self.clock,
+            &mut self.ring_mode,
+            &mut self.do_not_disturb,
+            &mut self.playback,
+            &mut self.location,
+            &mut self.metered,
             &mut self.cellular,
+            &mut self.sim_slot,
+            &mut self.cellular_profile,
             &mut self.wifi,
             &mut self.battery,
             &mut self.orientation,
+            &mut self.high_contrast,
             &mut self.flashlight,
-        ]
+            &mut self.charge_limit,
+            &mut self.network_mode,
+            &mut self.diagnostics,
+        ];
+        fixed
+            .into_iter()
+            .chain(self.custom.iter_mut().map(|module| module as &mut dyn Module))
+            .chain(self.plugins.iter_mut().map(|module| module as &mut dyn Module))
+            .chain(self.tasks.iter_mut().map(|module| module as &mut dyn Module))
     }
 }
 
-#[derive(Copy, Clone, Default, Debug)]
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
 pub struct Size<T = i32> {
     pub width: T,
     pub height: T,
@@ -699,32 +1931,19 @@ impl From<Size> for Size<f32> {
     }
 }
 
-impl Mul<f64> for Size {
-    type Output = Self;
-
-    fn mul(mut self, factor: f64) -> Self {
-        self.width = (self.width as f64 * factor) as i32;
-        self.height = (self.height as f64 * factor) as i32;
-        self
-    }
-}
-
-impl Div<f64> for Size {
-    type Output = Self;
-
-    fn div(mut self, factor: f64) -> Self {
-        self.width = (self.width as f64 / factor).round() as i32;
-        self.height = (self.height as f64 / factor).round() as i32;
-        self
-    }
-}
-
 /// Drawer animation frame.
 fn animate_drawer(now: Instant, _: &mut (), state: &mut State) -> TimeoutAction {
     let drawer_opening = state.drawer_opening;
+    let interval = state.animation_interval;
+    let reduced_motion = state.reduced_motion;
     let drawer = state.drawer();
     let max_offset = drawer.max_offset();
 
+    // Stop animating once the drawer was hidden.
+    if drawer.visibility() == Visibility::Hidden {
+        return TimeoutAction::Drop;
+    }
+
     // Compute threshold beyond which motion will automatically be completed.
     let threshold = if drawer_opening {
         max_offset * ANIMATION_THRESHOLD
@@ -732,24 +1951,29 @@ fn animate_drawer(now: Instant, _: &mut (), state: &mut State) -> TimeoutAction
         max_offset - max_offset * ANIMATION_THRESHOLD
     };
 
-    // Update drawer position.
-    if drawer.offset >= threshold {
-        drawer.offset += ANIMATION_STEP;
+    // Update drawer position, keeping its speed independent of the interval.
+    let step = ANIMATION_STEP * interval.as_secs_f64() / ANIMATION_INTERVAL.as_secs_f64();
+    let offset = drawer.offset();
+    if reduced_motion {
+        // Skip straight to the final position.
+        drawer.set_offset(if offset >= threshold { max_offset } else { 0. });
+    } else if offset >= threshold {
+        drawer.set_offset(offset + step);
     } else {
-        drawer.offset -= ANIMATION_STEP;
+        drawer.set_offset(offset - step);
     }
 
-    if drawer.offset <= 0. {
-        drawer.hide();
+    if drawer.offset() <= 0. {
+        state.hide_drawer();
 
         TimeoutAction::Drop
-    } else if drawer.offset >= max_offset {
+    } else if drawer.visibility() == Visibility::Open {
         drawer.request_frame();
 
         TimeoutAction::Drop
     } else {
         drawer.request_frame();
 
-        TimeoutAction::ToInstant(now + ANIMATION_INTERVAL)
+        TimeoutAction::ToInstant(now + interval)
     }
 }
@@ -1,27 +1,73 @@
 //! Battery status and capacity.
 
+use std::process::Command;
 use std::str::FromStr;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use calloop::generic::Generic;
 use calloop::timer::{TimeoutAction, Timer};
-use calloop::{Interest, LoopHandle, Mode, PostAction};
+use calloop::{Interest, LoopHandle, Mode, PostAction, RegistrationToken};
+use tracing::{error, warn};
 use udev::{Enumerator, MonitorBuilder};
 
+use crate::config::{BatteryModuleConfig, CriticalAction};
+use crate::dbus::notifications::{CloseReason, Notification};
+use crate::dbus::power_profiles::PowerSaver;
 use crate::module::{Alignment, Module, PanelModule, PanelModuleContent};
+use crate::persistence::PersistentState;
 use crate::text::Svg;
 use crate::{Result, State};
 
 /// Refresh interval for capacity updates.
 const UPDATE_INTERVAL: Duration = Duration::from_secs(60);
 
+/// Time between charging animation frames.
+const CHARGING_ANIMATION_INTERVAL: Duration = Duration::from_millis(750);
+
+/// Interval between updates of the critical battery countdown.
+const COUNTDOWN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Charging icons from lowest to highest capacity.
+const CHARGING_SVGS: [Svg; 5] = [
+    Svg::BatteryCharging20,
+    Svg::BatteryCharging40,
+    Svg::BatteryCharging60,
+    Svg::BatteryCharging80,
+    Svg::BatteryCharging100,
+];
+
 pub struct Battery {
+    power_saver_threshold: Option<u8>,
+    show_percentage: bool,
+    power_saver: Option<PowerSaver>,
+    charging_animation: bool,
+    animation_start: Option<Instant>,
     charging: bool,
     capacity: u8,
+    stale: bool,
+
+    /// Charging stopped at the charge control end threshold.
+    limited: bool,
+
+    /// Capacity at which the battery module starts requesting attention.
+    critical_capacity: u8,
+    critical_action: CriticalAction,
+    critical_grace: Duration,
+
+    /// Pending critical battery action.
+    countdown: Option<Countdown>,
+
+    /// Critical battery action was cancelled by the user.
+    ///
+    /// This is reset once the battery is no longer critical.
+    countdown_cancelled: bool,
 }
 
 impl Battery {
-    pub fn new(event_loop: &LoopHandle<'static, State>) -> Result<Self> {
+    pub fn new(
+        event_loop: &LoopHandle<'static, State>,
+        config: BatteryModuleConfig,
+    ) -> Result<Self> {
         // Create Udev device enumerator.
         let mut socket_enumerator = Enumerator::new()?;
         socket_enumerator.match_subsystem("power_supply")?;
@@ -51,7 +97,25 @@ impl Battery {
             TimeoutAction::ToInstant(now + UPDATE_INTERVAL)
         })?;
 
-        Ok(Self { charging: false, capacity: 100 })
+        // Show the last known status until the battery is read.
+        let persistent_state = PersistentState::load();
+
+        Ok(Self {
+            power_saver_threshold: config.power_saver_threshold,
+            charging_animation: config.charging_animation,
+            critical_capacity: config.critical_capacity,
+            critical_action: config.critical_action,
+            critical_grace: Duration::from_secs(config.critical_grace),
+            show_percentage: config.show_percentage,
+            capacity: persistent_state.battery_capacity.unwrap_or(100),
+            charging: persistent_state.battery_charging.unwrap_or_default(),
+            stale: persistent_state.battery_capacity.is_some(),
+            limited: Default::default(),
+            power_saver: Default::default(),
+            animation_start: Default::default(),
+            countdown: Default::default(),
+            countdown_cancelled: Default::default(),
+        })
     }
 
     /// Update battery status from udev attributes.
@@ -68,15 +132,210 @@ impl Battery {
                 .attribute_value("capacity")
                 .and_then(|capacity| u8::from_str(&capacity.to_string_lossy()).ok());
 
-            let new_charging = device.attribute_value("status").map(|status| status == "Charging");
+            let status = device.attribute_value("status");
+            let new_charging = status.map(|status| status == "Charging");
 
-            new_capacity.zip(new_charging)
+            // Check if charging was stopped by the charge threshold.
+            let threshold = device
+                .attribute_value("charge_control_end_threshold")
+                .and_then(|threshold| u8::from_str(&threshold.to_string_lossy()).ok());
+            let not_charging = status.is_some_and(|status| status == "Not charging");
+
+            new_capacity.zip(new_charging).map(|(capacity, charging)| {
+                let limited = not_charging
+                    && threshold.is_some_and(|threshold| threshold < 100 && capacity >= threshold);
+                (capacity, charging, limited)
+            })
         });
 
         // Update charging status.
-        if let Some((new_capacity, new_charging)) = battery {
-            state.modules.battery.capacity = new_capacity;
-            state.modules.battery.charging = new_charging;
+        if let Some((new_capacity, new_charging, new_limited)) = battery {
+            let battery = &mut state.modules.battery;
+            let changed = battery.capacity != new_capacity || battery.charging != new_charging;
+            let limited_changed = battery.limited != new_limited;
+
+            battery.capacity = new_capacity;
+            battery.charging = new_charging;
+            battery.limited = new_limited;
+            battery.stale = false;
+
+            // Cache status for the next startup.
+            if changed {
+                state.status_cache.update(move |state| {
+                    state.battery_capacity = Some(new_capacity);
+                    state.battery_charging = Some(new_charging);
+                });
+            }
+
+            // Show threshold indicator immediately, since it doesn't change with the clock.
+            if limited_changed {
+                state.request_panel_frame();
+            }
+        }
+
+        // Switch flashlight to the intensity of the current power source.
+        let charging = state.modules.battery.charging;
+        state.modules.flashlight.set_charging(charging);
+
+        state.modules.battery.update_power_profile();
+        Self::update_animation(state);
+        Self::update_countdown(state);
+    }
+
+    /// Battery capacity in percent.
+    pub fn capacity(&self) -> u8 {
+        self.capacity
+    }
+
+    /// Check if the battery is charging.
+    pub fn charging(&self) -> bool {
+        self.charging
+    }
+
+    /// Toggle the power saver profile based on the battery status.
+    fn update_power_profile(&mut self) {
+        let threshold = match self.power_saver_threshold {
+            Some(threshold) if !self.stale => threshold,
+            _ => return,
+        };
+
+        let active = self.power_saver.is_some();
+        let wanted = power_saver_wanted(active, self.charging, self.capacity, threshold);
+        match self.power_saver.take() {
+            Some(power_saver) if !wanted => power_saver.disable(),
+            None if wanted => self.power_saver = Some(PowerSaver::enable()),
+            power_saver => self.power_saver = power_saver,
+        }
+    }
+
+    /// Check if the battery is critical.
+    fn critical(&self) -> bool {
+        !self.charging && self.capacity <= self.critical_capacity && !self.stale
+    }
+
+    /// Start or stop the critical battery countdown.
+    fn update_countdown(state: &mut State) {
+        let battery = &mut state.modules.battery;
+        if battery.critical_action == CriticalAction::None {
+            return;
+        }
+
+        let critical = battery.critical();
+        if !critical {
+            battery.countdown_cancelled = false;
+        }
+
+        match (critical, battery.countdown.is_some()) {
+            (true, false) if !battery.countdown_cancelled => {
+                // Show warning until the action is taken or cancelled.
+                //
+                // Without notification popups, the action is still taken
+                // after the grace period, but cannot be cancelled.
+                let deadline = Instant::now() + battery.critical_grace;
+                let body = countdown_text(battery.critical_action, battery.critical_grace);
+                let notification_id = match state.notifications.as_mut() {
+                    Some(notifications) => Some(notifications.notify_local_persistent(
+                        &state.protocol_states,
+                        "Battery critical".into(),
+                        body,
+                    )),
+                    None => {
+                        warn!(module = "battery", "Battery critical: {body}");
+                        None
+                    },
+                };
+
+                let timer = Timer::from_duration(COUNTDOWN_INTERVAL);
+                let timer = state
+                    .event_loop
+                    .insert_source(timer, |now, _, state| Self::tick_countdown(state, now));
+
+                match timer {
+                    Ok(timer) => {
+                        battery.countdown = Some(Countdown { timer, notification_id, deadline })
+                    },
+                    Err(err) => {
+                        error!(module = "battery", "Could not start battery countdown: {err}")
+                    },
+                }
+            },
+            (false, true) => {
+                let countdown = battery.countdown.take().unwrap();
+                state.event_loop.remove(countdown.timer);
+                countdown.close(state);
+            },
+            _ => (),
+        }
+    }
+
+    /// Update the critical battery countdown.
+    fn tick_countdown(state: &mut State, now: Instant) -> TimeoutAction {
+        let battery = &mut state.modules.battery;
+        let countdown = match &battery.countdown {
+            Some(countdown) => *countdown,
+            None => return TimeoutAction::Drop,
+        };
+
+        let remaining = countdown.deadline.saturating_duration_since(now);
+        if let Some((id, notifications)) =
+            countdown.notification_id.zip(state.notifications.as_mut())
+        {
+            // Cancel the action once the warning was dismissed.
+            if !notifications.is_open(id) {
+                battery.countdown = None;
+                battery.countdown_cancelled = true;
+                return TimeoutAction::Drop;
+            }
+
+            if !remaining.is_zero() {
+                let notification = Notification {
+                    id,
+                    summary: "Battery critical".into(),
+                    body: countdown_text(battery.critical_action, remaining),
+                    timeout: None,
+                    persistent: true,
+                };
+                notifications.notify(&state.protocol_states, notification);
+            }
+        }
+
+        if !remaining.is_zero() {
+            return TimeoutAction::ToInstant(now + COUNTDOWN_INTERVAL);
+        }
+
+        // Only act once, until the battery recovers.
+        battery.countdown = None;
+        battery.countdown_cancelled = true;
+        countdown.close(state);
+
+        if let Some(verb) = state.modules.battery.critical_action.systemctl_verb() {
+            let mut command = Command::new("systemctl");
+            command.arg(verb);
+            state.reaper.watch(
+                command,
+                Box::new(|_, output| {
+                    if !output.status.success() {
+                        let stderr = String::from_utf8_lossy(&output.stderr);
+                        error!(module = "battery", "Critical battery action failed: {stderr}");
+                    }
+                }),
+            );
+        }
+
+        TimeoutAction::Drop
+    }
+
+    /// Start or stop the charging animation.
+    ///
+    /// The animation is advanced by the panel's animation timer, which is
+    /// not rearmed while the panel is hidden.
+    fn update_animation(state: &mut State) {
+        let battery = &mut state.modules.battery;
+        let animate = battery.charging_animation && battery.charging;
+
+        if animate != battery.animation_start.is_some() {
+            battery.animation_start = animate.then(Instant::now);
+            state.request_panel_frame();
         }
     }
 }
@@ -88,22 +347,119 @@ impl Module for Battery {
 }
 
 impl PanelModule for Battery {
+    fn name(&self) -> &'static str {
+        "battery"
+    }
+
     fn alignment(&self) -> Alignment {
         Alignment::Right
     }
 
     fn content(&self) -> PanelModuleContent {
-        PanelModuleContent::Svg(match (self.charging, self.capacity) {
-            (true, 80..) => Svg::BatteryCharging100,
-            (true, 60..=79) => Svg::BatteryCharging80,
-            (true, 40..=59) => Svg::BatteryCharging60,
-            (true, 20..=39) => Svg::BatteryCharging40,
-            (true, 0..=19) => Svg::BatteryCharging20,
-            (false, 80..) => Svg::Battery100,
-            (false, 60..=79) => Svg::Battery80,
-            (false, 40..=59) => Svg::Battery60,
-            (false, 20..=39) => Svg::Battery40,
-            (false, 0..=19) => Svg::Battery20,
+        if self.show_percentage {
+            return PanelModuleContent::Text(format!("{}%", self.capacity));
+        }
+
+        // Fill up segments above the current capacity while charging.
+        if self.charging {
+            let level = (self.capacity.min(99) / 20) as usize;
+            let frames = CHARGING_SVGS.len() - level;
+            let step = self.animation_frame().unwrap_or_default();
+            return PanelModuleContent::Svg(CHARGING_SVGS[level + step % frames]);
+        }
+
+        if self.limited {
+            return PanelModuleContent::Svg(Svg::BatteryLimited);
+        }
+
+        PanelModuleContent::Svg(match self.capacity {
+            80.. => Svg::Battery100,
+            60..=79 => Svg::Battery80,
+            40..=59 => Svg::Battery60,
+            20..=39 => Svg::Battery40,
+            0..=19 => Svg::Battery20,
         })
     }
+
+    fn attention(&self) -> bool {
+        self.critical()
+    }
+
+    fn animation_frame(&self) -> Option<usize> {
+        let elapsed = self.animation_start?.elapsed();
+        Some((elapsed.as_millis() / CHARGING_ANIMATION_INTERVAL.as_millis()) as usize)
+    }
+
+    fn next_animation_frame(&self) -> Option<Instant> {
+        let next_frame = self.animation_frame()? as u32 + 1;
+        Some(self.animation_start? + CHARGING_ANIMATION_INTERVAL * next_frame)
+    }
+
+    fn stale(&self) -> bool {
+        self.stale
+    }
+
+    fn priority(&self) -> u8 {
+        30
+    }
+
+    fn summary(&self) -> Option<String> {
+        let status = if self.charging {
+            "charging"
+        } else if self.limited {
+            "charge limit reached"
+        } else {
+            "discharging"
+        };
+        Some(format!("Battery {}%, {status}", self.capacity))
+    }
+}
+
+/// Pending critical battery action.
+#[derive(Copy, Clone)]
+struct Countdown {
+    timer: RegistrationToken,
+    notification_id: Option<u32>,
+    deadline: Instant,
+}
+
+impl Countdown {
+    /// Close the countdown's warning.
+    fn close(self, state: &mut State) {
+        if let Some((id, notifications)) = self.notification_id.zip(state.notifications.as_mut()) {
+            notifications.close(&state.protocol_states, id, CloseReason::Closed);
+        }
+    }
+}
+
+/// Check if the power saver profile should be active.
+///
+/// The power saver is enabled once the capacity drops below the threshold and
+/// stays active until the battery is charging again.
+fn power_saver_wanted(active: bool, charging: bool, capacity: u8, threshold: u8) -> bool {
+    !charging && (active || capacity < threshold)
+}
+
+/// Critical battery warning text.
+fn countdown_text(action: CriticalAction, remaining: Duration) -> String {
+    let seconds = remaining.as_secs_f64().ceil() as u64;
+    format!("{} in {seconds} seconds, dismiss to cancel.", action.description())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn power_saver_threshold() {
+        // Enable once discharging below the threshold.
+        assert!(!power_saver_wanted(false, false, 20, 20));
+        assert!(power_saver_wanted(false, false, 19, 20));
+        assert!(!power_saver_wanted(false, true, 19, 20));
+
+        // Stay enabled until charging.
+        assert!(power_saver_wanted(true, false, 19, 20));
+        assert!(power_saver_wanted(true, false, 25, 20));
+        assert!(!power_saver_wanted(true, true, 19, 20));
+    }
 }
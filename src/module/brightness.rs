@@ -4,7 +4,7 @@ use std::str::FromStr;
 
 use udev::Enumerator;
 
-use crate::module::{DrawerModule, Module, Slider};
+use crate::module::{DrawerModule, Module, ModuleGroup, Slider};
 use crate::text::Svg;
 use crate::Result;
 
@@ -47,6 +47,10 @@ impl Module for Brightness {
     fn drawer_module(&mut self) -> Option<DrawerModule> {
         Some(DrawerModule::Slider(self))
     }
+
+    fn group(&self) -> ModuleGroup {
+        ModuleGroup::Display
+    }
 }
 
 impl Slider for Brightness {
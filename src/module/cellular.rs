@@ -1,10 +1,18 @@
 //! Cellular status and signal strength.
 
+use std::time::Duration;
+
 use calloop::channel::Event;
 use calloop::LoopHandle;
 
-use crate::dbus::modem_manager::{self, ModemConnection};
-use crate::module::{Alignment, DrawerModule, Module, PanelModule, PanelModuleContent, Toggle};
+use crate::config::{CellularModuleConfig, SignalText};
+use crate::dbus::modem_manager::{self, ModemConnection, Restriction};
+use crate::module::{
+    Alignment, DrawerModule, Freshness, Module, ModuleGroup, PanelModule, PanelModuleContent,
+    Toggle,
+};
+use crate::persistence::PersistentState;
+use crate::supervisor::BackendEvent;
 use crate::text::Svg;
 use crate::{Result, State};
 
@@ -14,18 +22,48 @@ pub struct Cellular {
 
     /// Desired connectivity state.
     desired_enabled: bool,
+
+    /// Desired connectivity state from the previous run.
+    persisted_enabled: Option<bool>,
+
+    /// Connection state is restored from the previous run.
+    stale: bool,
+
+    /// Icon is hidden from the panel in favor of another connection.
+    hidden: bool,
+
+    /// Age of the ModemManager data.
+    freshness: Freshness,
+
+    /// Signal strength text format.
+    signal_text: SignalText,
 }
 
 impl Cellular {
-    pub fn new(event_loop: &LoopHandle<'static, State>) -> Result<Self> {
+    pub fn new(
+        event_loop: &LoopHandle<'static, State>,
+        max_age: Duration,
+        module_config: CellularModuleConfig,
+    ) -> Result<Self> {
         // Subscribe to ModemManager DBus events.
-        let rx = modem_manager::modem_listener()?;
+        let rx = modem_manager::modem_listener();
         event_loop.insert_source(rx, move |event, _, state| {
             let connection = match event {
-                Event::Msg(connection) => connection,
-                Event::Closed => return,
+                Event::Msg(BackendEvent::Update(connection)) => connection,
+                Event::Msg(BackendEvent::Stopped) | Event::Closed => {
+                    state.modules.cellular.freshness.stop(&state.event_loop);
+                    return;
+                },
             };
 
+            // Redraw modules which were outdated before this update.
+            let freshness = &mut state.modules.cellular.freshness;
+            let outdated = freshness.is_outdated();
+            freshness.update();
+            if outdated {
+                state.request_frame();
+            }
+
             // Ignore updates that change nothing.
             let module = &mut state.modules.cellular;
             if connection == module.connection {
@@ -33,46 +71,214 @@ impl Cellular {
             }
 
             let old_enabled = module.desired_enabled;
+            let old_present = module.modem_present();
+            let old_stale = module.stale;
             let old_svg = module.svg();
+            let old_text = module.signal_text();
+            let old_mode = module.connection.mode;
+            let old_restriction = module.connection.restriction;
+            let old_sim_slots = (module.connection.sim_slots, module.connection.primary_sim_slot);
 
             // Update connection status.
             module.desired_enabled = connection.enabled;
             module.connection = connection;
+            module.stale = false;
+            state.update_connectivity_icons();
+            let module = &mut state.modules.cellular;
+
+            // Restore the desired state from the previous run once the modem is available.
+            if connection.available {
+                if let Some(enabled) = module.persisted_enabled.take() {
+                    if enabled != connection.enabled {
+                        module.desired_enabled = enabled;
+                        modem_manager::set_enabled(enabled);
+                    }
+                }
+            }
 
-            // Request redraw only if SVG changed.
-            if old_svg != state.modules.wifi.svg() || old_enabled != connection.enabled {
+            // Update the SIM slot selector.
+            if old_sim_slots != (connection.sim_slots, connection.primary_sim_slot) {
+                let sim_slot = &mut state.modules.sim_slot;
+                sim_slot.set_current(connection.sim_slots, connection.primary_sim_slot);
+                state.request_frame();
+            }
+
+            // Update the network mode selector.
+            if old_mode != connection.mode {
+                state.modules.network_mode.set_current(connection.mode);
+                state.request_frame();
+            }
+
+            // Cache the icon's status for the next startup.
+            let content_changed = old_svg != state.modules.cellular.svg()
+                || old_text != state.modules.cellular.signal_text();
+            if content_changed {
+                state.status_cache.update(move |state| {
+                    state.cellular_strength = Some(connection.strength);
+                    state.cellular_registered = Some(connection.registered);
+                });
+            }
+
+            // Request redraw only if SVG, text or label changed.
+            if content_changed
+                || old_enabled != state.modules.cellular.desired_enabled
+                || old_restriction != connection.restriction
+                || old_present != state.modules.cellular.modem_present()
+                || old_stale
+            {
                 state.request_frame();
             }
         })?;
 
-        Ok(Self { connection: ModemConnection::default(), desired_enabled: false })
+        // Show the last known status until ModemManager reports the connection.
+        let persistent_state = PersistentState::load();
+        let persisted_enabled = persistent_state.cellular_enabled;
+        let connection = ModemConnection {
+            enabled: persisted_enabled.unwrap_or_default(),
+            strength: persistent_state.cellular_strength.unwrap_or_default(),
+            registered: persistent_state.cellular_registered.unwrap_or_default(),
+            restriction: None,
+            available: false,
+            primary_sim_slot: Default::default(),
+            sim_slots: Default::default(),
+            mode: Default::default(),
+        };
+
+        Ok(Self {
+            connection,
+            persisted_enabled,
+            desired_enabled: connection.enabled,
+            stale: persistent_state.cellular_strength.is_some(),
+            freshness: Freshness::new(max_age),
+            signal_text: module_config.signal_text,
+            hidden: false,
+        })
+    }
+
+    /// Current connection state.
+    pub fn connection(&self) -> ModemConnection {
+        self.connection
+    }
+
+    /// Check if the device has a modem.
+    ///
+    /// Until ModemManager reports the modem state, a modem is assumed to be
+    /// present if one was found in the previous run.
+    fn modem_present(&self) -> bool {
+        self.connection.available || self.stale
+    }
+
+    /// Number of signal bars shown by the icon.
+    fn bars(&self) -> u8 {
+        match self.connection.strength {
+            90.. => 5,
+            70.. => 4,
+            50.. => 3,
+            30.. => 2,
+            10.. => 1,
+            _ => 0,
+        }
+    }
+
+    /// Signal strength text shown next to the icon.
+    ///
+    /// Text is only shown while registered to a network, since the strength
+    /// is meaningless otherwise.
+    fn signal_text(&self) -> Option<String> {
+        let connection = &self.connection;
+        if !connection.enabled || connection.restriction.is_some() || !connection.registered {
+            return None;
+        }
+
+        match self.signal_text {
+            SignalText::None => None,
+            SignalText::Percentage => Some(format!("{}%", connection.strength)),
+            SignalText::Bars => Some(self.bars().to_string()),
+        }
+    }
+
+    /// Hide the icon from the panel.
+    ///
+    /// Returns `true` if the visibility changed.
+    pub fn set_hidden(&mut self, hidden: bool) -> bool {
+        let changed = self.hidden != hidden;
+        self.hidden = hidden;
+        changed
     }
 }
 
 impl Module for Cellular {
     fn panel_module(&self) -> Option<&dyn PanelModule> {
-        Some(self)
+        // Don't reserve space on devices without a modem.
+        self.modem_present().then_some(self)
     }
 
     fn drawer_module(&mut self) -> Option<DrawerModule> {
-        Some(DrawerModule::Toggle(self))
+        if self.modem_present() {
+            Some(DrawerModule::Toggle(self))
+        } else {
+            None
+        }
+    }
+
+    fn group(&self) -> ModuleGroup {
+        ModuleGroup::Connectivity
     }
 }
 
 impl PanelModule for Cellular {
+    fn name(&self) -> &'static str {
+        "cellular"
+    }
+
     fn alignment(&self) -> Alignment {
         Alignment::Right
     }
 
     fn content(&self) -> PanelModuleContent {
-        PanelModuleContent::Svg(self.svg())
+        match self.signal_text() {
+            Some(text) => PanelModuleContent::Composite(self.svg(), text),
+            None => PanelModuleContent::Svg(self.svg()),
+        }
+    }
+
+    fn hidden(&self) -> bool {
+        self.hidden
+    }
+
+    fn stale(&self) -> bool {
+        self.stale || self.freshness.is_outdated()
+    }
+
+    fn priority(&self) -> u8 {
+        20
+    }
+
+    fn summary(&self) -> Option<String> {
+        let connection = &self.connection;
+        let summary = if !connection.available {
+            return None;
+        } else if !connection.enabled {
+            "Cellular off".into()
+        } else if connection.restriction == Some(Restriction::EmergencyOnly) {
+            "Cellular emergency only".into()
+        } else if !connection.registered {
+            "Cellular no service".into()
+        } else {
+            format!("Cellular {}%", connection.strength)
+        };
+        Some(summary)
     }
 }
 
 impl Toggle for Cellular {
-    fn toggle(&mut self) -> Result<()> {
-        self.desired_enabled = !self.desired_enabled;
-        modem_manager::set_enabled(self.desired_enabled);
+    fn set_enabled(&mut self, enabled: bool) -> Result<()> {
+        self.desired_enabled = enabled;
+        modem_manager::set_enabled(enabled);
+
+        // Persist desired state for restoring it after reboot.
+        PersistentState::update(|state| state.cellular_enabled = Some(enabled));
+
         Ok(())
     }
 
@@ -82,16 +288,20 @@ impl Toggle for Cellular {
             return Svg::CellularDisabled;
         }
 
+        if self.connection.restriction.is_some() {
+            return Svg::CellularRestricted;
+        }
+
         if !self.connection.registered {
             return Svg::Cellular0;
         }
 
-        match self.connection.strength {
-            90.. => Svg::Cellular100,
-            70.. => Svg::Cellular80,
-            50.. => Svg::Cellular60,
-            30.. => Svg::Cellular40,
-            10.. => Svg::Cellular20,
+        match self.bars() {
+            5 => Svg::Cellular100,
+            4 => Svg::Cellular80,
+            3 => Svg::Cellular60,
+            2 => Svg::Cellular40,
+            1 => Svg::Cellular20,
             _ => Svg::Cellular0,
         }
     }
@@ -99,4 +309,15 @@ impl Toggle for Cellular {
     fn enabled(&self) -> bool {
         self.desired_enabled
     }
+
+    fn label(&self) -> Option<&'static str> {
+        match self.connection.restriction? {
+            Restriction::Denied => Some("Denied"),
+            Restriction::EmergencyOnly => Some("SOS only"),
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        "Mobile Data"
+    }
 }
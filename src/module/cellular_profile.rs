@@ -0,0 +1,124 @@
+//! Cellular connection profile selection.
+
+use calloop::channel::Event;
+use calloop::LoopHandle;
+
+use crate::dbus::modem_manager::{self, ModemProfiles};
+use crate::module::{DrawerModule, Info, Module, ModuleGroup};
+use crate::supervisor::BackendEvent;
+use crate::{Result, State};
+
+pub struct CellularProfile {
+    /// Profiles of the primary modem.
+    profiles: ModemProfiles,
+
+    /// Index of the profile connected on tap.
+    selected: usize,
+
+    /// Drawer module text.
+    text: String,
+}
+
+impl CellularProfile {
+    pub fn new(event_loop: &LoopHandle<'static, State>) -> Result<Self> {
+        // Subscribe to ModemManager profile changes.
+        let rx = modem_manager::profile_listener();
+        event_loop.insert_source(rx, |event, _, state| {
+            let profiles = match event {
+                Event::Msg(BackendEvent::Update(profiles)) => profiles,
+                Event::Msg(BackendEvent::Stopped) | Event::Closed => return,
+            };
+
+            let module = &mut state.modules.cellular_profile;
+            if module.profiles != profiles {
+                module.profiles = profiles;
+                module.selected = module.active_index().unwrap_or_default();
+                module.update_text();
+                state.request_frame();
+            }
+        })?;
+
+        Ok(Self {
+            profiles: Default::default(),
+            selected: Default::default(),
+            text: Default::default(),
+        })
+    }
+
+    /// Get the index of the active profile.
+    fn active_index(&self) -> Option<usize> {
+        let active = self.profiles.active?;
+        self.profiles.profiles.iter().position(|profile| profile.id == active)
+    }
+
+    /// Update the drawer text from the current profiles.
+    ///
+    /// Profiles are browsed one at a time, since the drawer only shows two
+    /// lines of text.
+    fn update_text(&mut self) {
+        let profiles = &self.profiles.profiles;
+        let selected = match profiles.get(self.selected) {
+            Some(selected) => selected,
+            None => {
+                self.text.clear();
+                return;
+            },
+        };
+
+        let position = format!("{}/{}", self.selected + 1, profiles.len());
+        self.text = match self.active_index() {
+            Some(active) if active == self.selected => {
+                format!("{} ({position}, active)\nSwipe for other profiles", selected.name)
+            },
+            Some(active) => {
+                let active = &profiles[active].name;
+                format!("{} ({position})\nTap to connect, {active} is active", selected.name)
+            },
+            None => format!("{} ({position})\nTap to connect", selected.name),
+        };
+    }
+}
+
+impl Module for CellularProfile {
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        // Only offer switching with multiple profiles to choose from.
+        if self.profiles.profiles.len() > 1 {
+            Some(DrawerModule::Info(self))
+        } else {
+            None
+        }
+    }
+
+    fn group(&self) -> ModuleGroup {
+        ModuleGroup::Connectivity
+    }
+}
+
+impl Info for CellularProfile {
+    fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn tap(&mut self) -> Result<()> {
+        // Ignore taps on the profile which is already connected.
+        if self.active_index() == Some(self.selected) {
+            return Ok(());
+        }
+
+        let profile = self.profiles.profiles.get(self.selected).ok_or("no profile selected")?;
+        modem_manager::connect_profile(profile.id);
+
+        // Optimistically update the active profile until the modem reconnects.
+        self.profiles.active = Some(profile.id);
+        self.update_text();
+
+        Ok(())
+    }
+
+    fn swipe(&mut self) -> Result<()> {
+        self.selected = (self.selected + 1) % self.profiles.profiles.len().max(1);
+        self.update_text();
+
+        Ok(())
+    }
+}
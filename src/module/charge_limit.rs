@@ -0,0 +1,152 @@
+//! Charging current limit.
+
+use std::fs;
+use std::str::FromStr;
+
+use udev::{Device, Enumerator};
+
+use crate::module::{DrawerModule, Module, Toggle};
+use crate::persistence::PersistentState;
+use crate::text::Svg;
+use crate::Result;
+
+/// Sysfs attribute controlling the charger's input current in µA.
+const LIMIT_ATTRIBUTE: &str = "input_current_limit";
+
+/// Random ID regenerated by the kernel on every boot.
+const BOOT_ID_PATH: &str = "/proc/sys/kernel/random/boot_id";
+
+pub struct ChargeLimit {
+    /// Input current while slow charging.
+    slow_limit: u32,
+
+    /// Input current before slow charging was enabled.
+    normal_limit: Option<u32>,
+
+    /// Charger exposing a configurable current limit.
+    available: bool,
+
+    enabled: bool,
+}
+
+impl ChargeLimit {
+    pub fn new(slow_limit: u32) -> Self {
+        let limit = charger().and_then(|charger| current_limit(&charger));
+
+        // Use the persisted limit if slow charging was enabled before a restart.
+        //
+        // The kernel restores the default limit on reboot, so limits persisted
+        // during a previous boot are outdated.
+        let state = PersistentState::load();
+        let boot_id = boot_id();
+        let persisted_limit = state
+            .charge_current_limit
+            .filter(|_| boot_id.is_some() && state.charge_limit_boot_id == boot_id);
+
+        Self {
+            normal_limit: persisted_limit.or(limit),
+            enabled: persisted_limit.is_some(),
+            available: limit.is_some(),
+            slow_limit,
+        }
+    }
+
+    /// Write a new charger current limit.
+    ///
+    /// Returns the limit read back from the charger after the update.
+    fn set_limit(limit: u32) -> Result<u32> {
+        let mut charger = charger().ok_or("charger with input current limit disappeared")?;
+        charger.set_attribute_value(LIMIT_ATTRIBUTE, limit.to_string())?;
+
+        // Read the value back, since drivers may clamp or reject it.
+        let charger = charger.syspath().to_owned();
+        let limit = Device::from_syspath(&charger).ok().as_ref().and_then(current_limit);
+        limit.ok_or_else(|| "could not read back input current limit".into())
+    }
+}
+
+impl Module for ChargeLimit {
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        if self.available {
+            Some(DrawerModule::Toggle(self))
+        } else {
+            None
+        }
+    }
+}
+
+impl Toggle for ChargeLimit {
+    fn set_enabled(&mut self, enabled: bool) -> Result<()> {
+        if !enabled {
+            // Restore the previous limit.
+            let normal_limit = match self.normal_limit {
+                Some(normal_limit) => normal_limit,
+                None => return Err("unknown input current limit before slow charging".into()),
+            };
+            if Self::set_limit(normal_limit)? != normal_limit {
+                return Err("charger did not restore the input current limit".into());
+            }
+
+            self.enabled = false;
+            PersistentState::update(|state| {
+                state.charge_current_limit = None;
+                state.charge_limit_boot_id = None;
+            });
+        } else {
+            // Remember the default limit for restoring it later.
+            if !self.enabled {
+                let charger = charger().ok_or("charger with input current limit disappeared")?;
+                self.normal_limit = current_limit(&charger);
+            }
+            let normal_limit = match self.normal_limit {
+                Some(normal_limit) => normal_limit,
+                None => return Err("could not read input current limit".into()),
+            };
+
+            // Drivers may clamp the limit, so only check that charging was slowed down.
+            if Self::set_limit(self.slow_limit)? >= normal_limit {
+                return Err("charger rejected the slow charging limit".into());
+            }
+
+            self.enabled = true;
+            PersistentState::update(|state| {
+                state.charge_current_limit = Some(normal_limit);
+                state.charge_limit_boot_id = boot_id();
+            });
+        }
+
+        Ok(())
+    }
+
+    fn svg(&self) -> Svg {
+        Svg::BatteryCharging20
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn title(&self) -> &'static str {
+        "Slow Charge"
+    }
+}
+
+/// Find the first `power_supply` device with a current limit.
+fn charger() -> Option<Device> {
+    let mut enumerator = Enumerator::new().ok()?;
+    enumerator.match_subsystem("power_supply").ok()?;
+    let mut devices = enumerator.scan_devices().ok()?;
+    devices.find(|device| current_limit(device).is_some())
+}
+
+/// Get a charger's current limit.
+fn current_limit(device: &Device) -> Option<u32> {
+    let limit = device.attribute_value(LIMIT_ATTRIBUTE)?;
+    u32::from_str(limit.to_string_lossy().trim()).ok()
+}
+
+/// Get the ID of the current boot.
+fn boot_id() -> Option<String> {
+    let boot_id = fs::read_to_string(BOOT_ID_PATH).ok()?;
+    Some(boot_id.trim().into())
+}
@@ -1,46 +1,191 @@
 //! Nice clock.
 
-use std::time::{Duration, UNIX_EPOCH};
+use std::mem;
+use std::time::{Duration, Instant, UNIX_EPOCH};
 
+use calloop::channel::Event;
+use calloop::generic::Generic;
 use calloop::timer::{TimeoutAction, Timer};
-use calloop::LoopHandle;
+use calloop::{Interest, LoopHandle, Mode, PostAction, RegistrationToken};
+use chrono::format::{Item, StrftimeItems};
 use chrono::offset::Local;
+use tracing::warn;
 
+use crate::config::ClockModuleConfig;
+use crate::dbus::logind;
 use crate::module::{Alignment, Module, PanelModule, PanelModuleContent};
+use crate::supervisor::BackendEvent;
+use crate::time::ClockChangeTimer;
 use crate::{Result, State};
 
+/// Delay after the minute boundary before the clock is presented.
+///
+/// This accounts for timer and latency imprecision, ensuring the new minute is
+/// always shown.
+const PRESENTATION_MARGIN: Duration = Duration::from_millis(10);
+
+/// Clock update interval.
+const MINUTE: Duration = Duration::from_secs(60);
+
+/// Clock update interval while seconds are shown.
+const SECOND: Duration = Duration::from_secs(1);
+
+/// Duration seconds are shown for after tapping the clock.
+const SECONDS_DURATION: Duration = Duration::from_secs(10);
+
 pub struct Clock {
-    _new: (),
+    event_loop: LoopHandle<'static, State>,
+    config: ClockModuleConfig,
+
+    /// Time between panel commit and presentation.
+    latency: Duration,
+
+    /// Presentation latency should be measured for the next panel frame.
+    latency_requested: bool,
+
+    /// Minute update timer.
+    timer: Option<RegistrationToken>,
+
+    /// Deadline for showing seconds.
+    seconds_until: Option<Instant>,
 }
 
 impl Clock {
-    pub fn new(event_loop: &LoopHandle<'static, State>) -> Result<Self> {
-        event_loop.insert_source(Timer::immediate(), move |now, _, state| {
-            state.request_frame();
+    pub fn new(
+        event_loop: &LoopHandle<'static, State>,
+        mut config: ClockModuleConfig,
+    ) -> Result<Self> {
+        // Fall back to the default formats, since invalid ones panic while rendering.
+        let default_config = ClockModuleConfig::default();
+        for (format, default) in [
+            (&mut config.format, default_config.format),
+            (&mut config.seconds_format, default_config.seconds_format),
+        ] {
+            if StrftimeItems::new(format).any(|item| item == Item::Error) {
+                warn!(module = "clock", "Invalid clock format {format:?}");
+                *format = default;
+            }
+        }
 
-            // Calculate seconds until next minute. We add one second just to be sure.
-            let total_secs = UNIX_EPOCH.elapsed().unwrap().as_secs();
-            let remaining = Duration::from_secs(60 - (total_secs % 60) + 1);
+        let timer = event_loop.insert_source(Timer::immediate(), update_clock)?;
 
-            TimeoutAction::ToInstant(now + remaining)
+        // Since timers use the monotonic clock, which stops during suspend, the minute
+        // timer must be re-armed after resume.
+        let rx = logind::resume_listener();
+        event_loop.insert_source(rx, |event, _, state| {
+            if let Event::Msg(BackendEvent::Update(())) = event {
+                state.modules.clock.resync();
+            }
         })?;
 
-        Ok(Self { _new: () })
+        // Re-arm the minute timer when the wall clock is set, like after NTP syncs.
+        match ClockChangeTimer::new() {
+            Ok(clock_change_timer) => {
+                let source = Generic::new(clock_change_timer, Interest::READ, Mode::Level);
+                event_loop.insert_source(source, |_, timer, state| {
+                    if timer.clock_changed()? {
+                        state.modules.clock.resync();
+                    }
+                    Ok(PostAction::Continue)
+                })?;
+            },
+            Err(err) => warn!(module = "clock", "Could not watch for wall clock changes: {err}"),
+        }
+
+        Ok(Self {
+            event_loop: event_loop.clone(),
+            config,
+            seconds_until: None,
+            timer: Some(timer),
+            latency: Duration::ZERO,
+            latency_requested: Default::default(),
+        })
+    }
+
+    /// Immediately update the clock and re-arm its update timer.
+    fn resync(&mut self) {
+        if let Some(timer) = self.timer.take() {
+            self.event_loop.remove(timer);
+        }
+
+        let timer = self.event_loop.insert_source(Timer::immediate(), update_clock);
+        self.timer = timer.ok();
+    }
+
+    /// Check if seconds are currently shown.
+    fn seconds_visible(&self) -> bool {
+        self.seconds_until.is_some_and(|until| Instant::now() < until)
+    }
+
+    /// Update the panel's presentation latency.
+    pub fn set_latency(&mut self, latency: Duration) {
+        self.latency = latency;
     }
+
+    /// Check if the presentation latency should be measured, resetting the
+    /// request.
+    pub fn take_latency_request(&mut self) -> bool {
+        mem::take(&mut self.latency_requested)
+    }
+}
+
+/// Redraw the clock and schedule the next update.
+fn update_clock(now: Instant, _: &mut (), state: &mut State) -> TimeoutAction {
+    state.request_frame();
+
+    // Measure the latency of this update for scheduling the next one.
+    let clock = &mut state.modules.clock;
+    clock.latency_requested = true;
+
+    // Fall back to minute updates once seconds have expired.
+    let interval = if clock.seconds_visible() {
+        SECOND
+    } else {
+        clock.seconds_until = None;
+        MINUTE
+    };
+
+    // Calculate time until the next interval is presented on screen.
+    let presented = UNIX_EPOCH.elapsed().unwrap() + clock.latency;
+    let since_interval = Duration::from_nanos((presented.as_nanos() % interval.as_nanos()) as u64);
+    let remaining = interval - since_interval + PRESENTATION_MARGIN;
+
+    TimeoutAction::ToInstant(now + remaining)
 }
 
 impl Module for Clock {
     fn panel_module(&self) -> Option<&dyn PanelModule> {
         Some(self)
     }
+
+    fn panel_tap(&mut self) -> bool {
+        // Temporarily show seconds, switching to per-second updates.
+        self.seconds_until = Some(Instant::now() + SECONDS_DURATION);
+        self.resync();
+
+        true
+    }
+
+    fn panel_long_press(&mut self) -> Option<String> {
+        Some(Local::now().format("%Y-%m-%d %H:%M:%S").to_string())
+    }
 }
 
 impl PanelModule for Clock {
+    fn name(&self) -> &'static str {
+        "clock"
+    }
+
     fn alignment(&self) -> Alignment {
         Alignment::Center
     }
 
     fn content(&self) -> PanelModuleContent {
-        PanelModuleContent::Text(Local::now().format("%H:%M").to_string())
+        // Show the time at which the frame will be visible.
+        let latency =
+            chrono::Duration::from_std(self.latency).unwrap_or_else(|_| chrono::Duration::zero());
+        let format =
+            if self.seconds_visible() { &self.config.seconds_format } else { &self.config.format };
+        PanelModuleContent::Text((Local::now() + latency).format(format).to_string())
     }
 }
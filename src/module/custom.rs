@@ -0,0 +1,79 @@
+//! User-defined DBus modules.
+
+use std::time::Duration;
+
+use calloop::channel::Event;
+use calloop::LoopHandle;
+
+use crate::config::DbusModuleConfig;
+use crate::dbus::custom;
+use crate::module::{DrawerModule, Freshness, Info, Module};
+use crate::supervisor::BackendEvent;
+use crate::{Result, State};
+
+pub struct CustomDbus {
+    config: DbusModuleConfig,
+    freshness: Freshness,
+    text: String,
+}
+
+impl CustomDbus {
+    /// Create a custom module at the specified index of the custom modules.
+    pub fn new(
+        event_loop: &LoopHandle<'static, State>,
+        config: DbusModuleConfig,
+        index: usize,
+        max_age: Duration,
+    ) -> Result<Self> {
+        // Subscribe to property changes.
+        let rx = custom::property_listener(config.clone());
+        event_loop.insert_source(rx, move |event, _, state| {
+            let text = match event {
+                Event::Msg(BackendEvent::Update(text)) => text,
+                Event::Msg(BackendEvent::Stopped) | Event::Closed => {
+                    state.modules.custom[index].freshness.stop(&state.event_loop);
+                    return;
+                },
+            };
+
+            // Redraw modules which were outdated before this update.
+            let freshness = &mut state.modules.custom[index].freshness;
+            let outdated = freshness.is_outdated();
+            freshness.update();
+            if outdated {
+                state.request_frame();
+            }
+
+            let module = &mut state.modules.custom[index];
+            if module.text != text {
+                module.text = text;
+                state.request_frame();
+            }
+        })?;
+
+        Ok(Self { config, freshness: Freshness::new(max_age), text: Default::default() })
+    }
+}
+
+impl Module for CustomDbus {
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::Info(self))
+    }
+}
+
+impl Info for CustomDbus {
+    fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn tap(&mut self) -> Result<()> {
+        if self.config.method.is_some() {
+            custom::call_method(self.config.clone());
+        }
+        Ok(())
+    }
+
+    fn stale(&self) -> bool {
+        self.freshness.is_outdated()
+    }
+}
@@ -0,0 +1,168 @@
+//! System uptime and suspend statistics, and epitaph's own resource usage.
+
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::module::{DrawerModule, Info, Module};
+use crate::renderer;
+use crate::time::clock_time;
+use crate::Result;
+
+/// Suspend statistics sysfs directory.
+const SUSPEND_STATS_PATH: &str = "/sys/power/suspend_stats";
+
+/// Process status file.
+const STATUS_PATH: &str = "/proc/self/status";
+
+/// Directory with all open file descriptors.
+const FD_PATH: &str = "/proc/self/fd";
+
+#[derive(Default)]
+pub struct Diagnostics {
+    text: String,
+
+    /// Show epitaph's resource usage instead of the system statistics.
+    show_usage: bool,
+
+    /// Resource usage can be shown by tapping the module.
+    usage_enabled: bool,
+}
+
+impl Diagnostics {
+    pub fn new(usage_enabled: bool) -> Self {
+        let mut diagnostics = Self { usage_enabled, ..Self::default() };
+        diagnostics.refresh();
+        diagnostics
+    }
+
+    /// Reload the shown statistics.
+    pub fn refresh(&mut self) {
+        if self.show_usage {
+            self.refresh_usage();
+        } else {
+            self.refresh_system();
+        }
+    }
+
+    /// Reload uptime and suspend statistics.
+    fn refresh_system(&mut self) {
+        // Time suspended is the difference between the boot and monotonic clocks,
+        // since the latter stops during suspend.
+        let boottime = clock_time(libc::CLOCK_BOOTTIME);
+        let monotonic = clock_time(libc::CLOCK_MONOTONIC);
+        let suspended = boottime.saturating_sub(monotonic);
+
+        let mut text = format!("Uptime {}", format_duration(boottime));
+
+        if !boottime.is_zero() {
+            let percentage = suspended.as_secs_f64() / boottime.as_secs_f64() * 100.;
+            text += &format!("\nSuspended {} ({percentage:.0}%)", format_duration(suspended));
+        }
+
+        // Add suspend attempt counts.
+        let stats = Path::new(SUSPEND_STATS_PATH);
+        if let Some((success, fail)) =
+            read_stat(&stats.join("success")).zip(read_stat(&stats.join("fail")))
+        {
+            text += &format!(", {success} ok, {fail} failed");
+        }
+
+        self.text = text;
+    }
+
+    /// Reload epitaph's resource usage.
+    fn refresh_usage(&mut self) {
+        let status = fs::read_to_string(STATUS_PATH).unwrap_or_default();
+        let rss = status_value(&status, "VmRSS").map(|rss| rss * 1024);
+        let threads = status_value(&status, "Threads");
+
+        // Exclude the descriptor used for reading the directory itself.
+        let fds = fs::read_dir(FD_PATH).ok().map(|fds| fds.count().saturating_sub(1));
+
+        let unknown = || String::from("?");
+        self.text = format!(
+            "Memory {}, GPU ~{}\nThreads {}, FDs {}",
+            rss.map_or_else(unknown, format_bytes),
+            format_bytes(renderer::texture_memory() as u64),
+            threads.map_or_else(unknown, |threads| threads.to_string()),
+            fds.map_or_else(unknown, |fds| fds.to_string()),
+        );
+    }
+}
+
+impl Module for Diagnostics {
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::Info(self))
+    }
+
+    fn drawer_visibility_changed(&mut self, visible: bool) {
+        // Update statistics only when opening the drawer.
+        if visible {
+            self.refresh();
+        }
+    }
+}
+
+impl Info for Diagnostics {
+    fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Switch between system statistics and epitaph's resource usage.
+    fn tap(&mut self) -> Result<()> {
+        if self.usage_enabled {
+            self.show_usage = !self.show_usage;
+            self.refresh();
+        }
+        Ok(())
+    }
+}
+
+/// Read a numeric suspend statistic.
+fn read_stat(path: &Path) -> Option<u64> {
+    let stat = fs::read_to_string(path).ok()?;
+    u64::from_str(stat.trim()).ok()
+}
+
+/// Format a duration as days, hours and minutes.
+fn format_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    let (days, hours, minutes) = (minutes / 1440, minutes / 60 % 24, minutes % 60);
+
+    if days > 0 {
+        format!("{days}d {hours}h {minutes}m")
+    } else if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else {
+        format!("{minutes}m")
+    }
+}
+
+/// Read a numeric value from the process status file.
+///
+/// Units like `kB` are ignored.
+fn status_value(status: &str, key: &str) -> Option<u64> {
+    let line = status.lines().find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))?;
+    let value = line.split_whitespace().next()?;
+    u64::from_str(value).ok()
+}
+
+/// Format a size in bytes with a binary unit.
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024. && unit + 1 < UNITS.len() {
+        size /= 1024.;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
@@ -0,0 +1,76 @@
+//! Notification popup suppression.
+
+use crate::module::{Alignment, DrawerModule, Module, PanelModule, PanelModuleContent, Toggle};
+use crate::persistence::PersistentState;
+use crate::text::Svg;
+use crate::Result;
+
+pub struct DoNotDisturb {
+    enabled: bool,
+}
+
+impl DoNotDisturb {
+    pub fn new() -> Self {
+        let enabled = PersistentState::load().do_not_disturb.unwrap_or_default();
+        Self { enabled }
+    }
+
+    /// Check if notification popups are suppressed.
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Enable or disable notification popups.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        PersistentState::update(|state| state.do_not_disturb = Some(enabled));
+    }
+}
+
+impl Module for DoNotDisturb {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        // Only show the indicator while popups are suppressed.
+        self.enabled.then_some(self)
+    }
+
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::Toggle(self))
+    }
+}
+
+impl PanelModule for DoNotDisturb {
+    fn name(&self) -> &'static str {
+        "do_not_disturb"
+    }
+
+    fn alignment(&self) -> Alignment {
+        Alignment::Right
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        PanelModuleContent::Svg(Svg::DoNotDisturb)
+    }
+
+    fn priority(&self) -> u8 {
+        5
+    }
+}
+
+impl Toggle for DoNotDisturb {
+    fn set_enabled(&mut self, enabled: bool) -> Result<()> {
+        DoNotDisturb::set_enabled(self, enabled);
+        Ok(())
+    }
+
+    fn svg(&self) -> Svg {
+        Svg::DoNotDisturb
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn title(&self) -> &'static str {
+        "Quiet"
+    }
+}
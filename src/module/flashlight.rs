@@ -1,22 +1,194 @@
 //! Screen brightness.
 
-use std::ops::{Deref, DerefMut};
-use std::str::FromStr;
+use std::time::Duration;
 
-use udev::{Device, Enumerator};
+use calloop::timer::{TimeoutAction, Timer};
+use calloop::{LoopHandle, RegistrationToken};
+use tracing::{error, warn};
 
+use crate::config::FlashlightModuleConfig;
+use crate::dbus::logind::{self, SleepInhibitor};
+use crate::led::{Led, LedClaim};
 use crate::module::{DrawerModule, Module, Toggle};
+use crate::persistence::PersistentState;
 use crate::text::Svg;
-use crate::Result;
+use crate::{Result, State};
+
+/// Selectable flashlight intensities, relative to its maximum brightness.
+///
+/// Levels must be in ascending order.
+const INTENSITY_LEVELS: [f64; 3] = [0.25, 0.5, 1.];
+
+/// Duration of one morse code unit in the SOS pattern.
+const SOS_UNIT: Duration = Duration::from_millis(200);
+
+/// SOS morse code pattern, as LED state and duration in units.
+#[rustfmt::skip]
+const SOS_PATTERN: [(bool, u32); 18] = [
+    // S
+    (true, 1), (false, 1), (true, 1), (false, 1), (true, 1), (false, 3),
+    // O
+    (true, 3), (false, 1), (true, 3), (false, 1), (true, 3), (false, 3),
+    // S, followed by a word gap
+    (true, 1), (false, 1), (true, 1), (false, 1), (true, 1), (false, 7),
+];
 
-#[derive(Default)]
 pub struct Flashlight {
+    event_loop: LoopHandle<'static, State>,
+
+    /// Timer driving the SOS blink pattern.
+    sos_timer: Option<RegistrationToken>,
+
+    /// Index of the next SOS pattern step.
+    sos_step: usize,
+
+    /// Intensity while running on battery.
+    battery_intensity: f64,
+
+    /// Intensity while the battery is charging.
+    charging_intensity: f64,
+
+    charging: bool,
     enabled: bool,
+
+    /// Suspend inhibitor held while the flashlight is on.
+    inhibitor: Option<SleepInhibitor>,
+
+    /// Flash LED controlled while the flashlight is on.
+    led: Option<LedClaim>,
 }
 
 impl Flashlight {
-    pub fn new() -> Self {
-        Self::default()
+    pub fn new(event_loop: &LoopHandle<'static, State>, config: FlashlightModuleConfig) -> Self {
+        let default_config = FlashlightModuleConfig::default();
+        let battery_intensity =
+            valid_intensity(config.battery_intensity, default_config.battery_intensity);
+        let charging_intensity =
+            valid_intensity(config.charging_intensity, default_config.charging_intensity);
+
+        // Prefer intensities selected in a previous run.
+        let persistent_state = PersistentState::load();
+        let persisted_battery = persistent_state.flashlight_battery_intensity;
+        let persisted_charging = persistent_state.flashlight_charging_intensity;
+
+        Self {
+            event_loop: event_loop.clone(),
+            battery_intensity: persisted_battery.map_or(battery_intensity, |intensity| {
+                valid_intensity(intensity, battery_intensity)
+            }),
+            charging_intensity: persisted_charging.map_or(charging_intensity, |intensity| {
+                valid_intensity(intensity, charging_intensity)
+            }),
+            inhibitor: Default::default(),
+            led: Default::default(),
+            sos_timer: Default::default(),
+            sos_step: Default::default(),
+            charging: Default::default(),
+            enabled: Default::default(),
+        }
+    }
+
+    /// Update the power source, switching to its intensity.
+    pub fn set_charging(&mut self, charging: bool) {
+        if self.charging == charging {
+            return;
+        }
+        self.charging = charging;
+
+        if self.enabled {
+            if let Err(err) = self.update_brightness() {
+                error!(module = "flashlight", "Could not update flashlight brightness: {err}");
+            }
+        }
+    }
+
+    /// Intensity for the current power source.
+    fn intensity_mut(&mut self) -> &mut f64 {
+        if self.charging {
+            &mut self.charging_intensity
+        } else {
+            &mut self.battery_intensity
+        }
+    }
+
+    /// Update the flashlight's LED brightness.
+    fn update_brightness(&mut self) -> Result<()> {
+        // Keep the system awake while the flashlight is on.
+        if !self.enabled {
+            self.inhibitor = None;
+        } else if self.inhibitor.is_none() {
+            self.inhibitor = Some(logind::inhibit_sleep("Flashlight is on"));
+        }
+
+        // Release the LED, restoring the state of other LEDs on its controller.
+        if !self.enabled {
+            self.led = None;
+            return Ok(());
+        }
+
+        // Claim the flash LED when turning on the flashlight.
+        if self.led.is_none() {
+            self.led = match Led::find("white:flash")? {
+                Some(led) => Some(led.claim()?),
+                None => return Ok(()),
+            };
+        }
+
+        // Leave brightness control to the SOS timer while it is active.
+        if self.sos_timer.is_some() {
+            return Ok(());
+        }
+
+        // Update flashlight brightness.
+        self.set_lit(true)
+    }
+
+    /// Switch the claimed LED on or off, using the current intensity.
+    fn set_lit(&mut self, lit: bool) -> Result<()> {
+        let intensity = *self.intensity_mut();
+        if let Some(led) = &mut self.led {
+            let brightness = if lit {
+                ((led.max_brightness() as f64 * intensity).round() as u32).max(1)
+            } else {
+                0
+            };
+            led.set_brightness(brightness)?;
+        }
+
+        Ok(())
+    }
+
+    /// Start blinking an SOS pattern.
+    fn start_sos(&mut self) -> Result<()> {
+        self.enabled = true;
+        self.update_brightness()?;
+
+        self.sos_step = 0;
+        let timer = self.event_loop.insert_source(Timer::immediate(), |_, _, state| {
+            state.modules.flashlight.advance_sos()
+        })?;
+        self.sos_timer = Some(timer);
+
+        Ok(())
+    }
+
+    /// Stop the SOS pattern.
+    fn stop_sos(&mut self) {
+        if let Some(timer) = self.sos_timer.take() {
+            self.event_loop.remove(timer);
+        }
+    }
+
+    /// Apply the next step of the SOS pattern.
+    fn advance_sos(&mut self) -> TimeoutAction {
+        let (lit, units) = SOS_PATTERN[self.sos_step];
+        self.sos_step = (self.sos_step + 1) % SOS_PATTERN.len();
+
+        if let Err(err) = self.set_lit(lit) {
+            error!(module = "flashlight", "Could not update flashlight brightness: {err}");
+        }
+
+        TimeoutAction::ToDuration(SOS_UNIT * units)
     }
 }
 
@@ -27,26 +199,40 @@ impl Module for Flashlight {
 }
 
 impl Toggle for Flashlight {
-    fn toggle(&mut self) -> Result<()> {
-        self.enabled = !self.enabled;
+    fn set_enabled(&mut self, enabled: bool) -> Result<()> {
+        self.stop_sos();
+        self.enabled = enabled;
+        self.update_brightness()
+    }
+
+    /// Start the SOS pattern while the flashlight is off, otherwise cycle
+    /// through flashlight intensities of the current power source.
+    fn long_press(&mut self) -> Result<bool> {
+        if !self.enabled {
+            self.start_sos()?;
+            return Ok(true);
+        }
 
-        // Get all LED devices.
-        let mut enumerator = Enumerator::new()?;
-        enumerator.match_subsystem("leds")?;
-        enumerator.match_sysname("white:flash")?;
-        let devices = enumerator.scan_devices()?;
+        // Stay on with the next intensity if the SOS pattern was active.
+        self.stop_sos();
 
-        // Find any flashlight device.
-        let mut flash = match devices.into_iter().find_map(Flash::from_device) {
-            Some(flash) => flash,
-            None => return Ok(()),
-        };
+        let intensity = self.intensity_mut();
+        *intensity = next_intensity(*intensity);
+        let intensity = *intensity;
 
-        // Toggle flashlight brightness.
-        let new_value = if flash.enabled() { 0 } else { flash.max_brightness };
-        flash.set_attribute_value("brightness", new_value.to_string())?;
+        // Persist the selection for the current power source.
+        let charging = self.charging;
+        PersistentState::update(|state| {
+            if charging {
+                state.flashlight_charging_intensity = Some(intensity);
+            } else {
+                state.flashlight_battery_intensity = Some(intensity);
+            }
+        });
 
-        Ok(())
+        self.enabled = true;
+        self.update_brightness()?;
+        Ok(true)
     }
 
     fn svg(&self) -> Svg {
@@ -60,42 +246,59 @@ impl Toggle for Flashlight {
     fn enabled(&self) -> bool {
         self.enabled
     }
+
+    fn title(&self) -> &'static str {
+        "Torch"
+    }
 }
 
-/// Flashlight udev device.
-struct Flash {
-    max_brightness: usize,
-    brightness: usize,
-    device: Device,
+/// Get the lowest intensity level above the current intensity.
+///
+/// This wraps around to the lowest level once the highest one is reached.
+fn next_intensity(intensity: f64) -> f64 {
+    let next = INTENSITY_LEVELS.iter().find(|level| **level > intensity);
+    next.copied().unwrap_or(INTENSITY_LEVELS[0])
 }
 
-impl Flash {
-    /// Check if flashlight is on.
-    fn enabled(&self) -> bool {
-        self.brightness > 0
+/// Clamp a configured intensity to the valid range.
+///
+/// Invalid values are replaced with the fallback.
+fn valid_intensity(intensity: f64, fallback: f64) -> f64 {
+    if (0.0..=1.).contains(&intensity) {
+        return intensity;
     }
 
-    /// Convert udev device to flashlight.
-    fn from_device(device: Device) -> Option<Flash> {
-        let max_brightness_str = device.attribute_value("max_brightness")?.to_string_lossy();
-        let max_brightness = usize::from_str(&max_brightness_str).ok()?;
-        let brightness_str = device.attribute_value("brightness")?.to_string_lossy();
-        let brightness = usize::from_str(&brightness_str).ok()?;
-
-        Some(Self { max_brightness, brightness, device })
+    warn!(module = "flashlight", "Flashlight intensity {intensity} is outside of 0.0..=1.0");
+    if intensity.is_nan() {
+        fallback
+    } else {
+        intensity.clamp(0., 1.)
     }
 }
 
-impl Deref for Flash {
-    type Target = Device;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    fn deref(&self) -> &Self::Target {
-        &self.device
+    #[test]
+    fn cycle_intensity_levels() {
+        assert_eq!(next_intensity(0.25), 0.5);
+        assert_eq!(next_intensity(0.5), 1.);
+        assert_eq!(next_intensity(1.), 0.25);
+    }
+
+    #[test]
+    fn cycle_custom_intensity() {
+        assert_eq!(next_intensity(0.3), 0.5);
+        assert_eq!(next_intensity(0.), 0.25);
+        assert_eq!(next_intensity(0.9), 1.);
     }
-}
 
-impl DerefMut for Flash {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.device
+    #[test]
+    fn clamp_intensity() {
+        assert_eq!(valid_intensity(0.7, 1.), 0.7);
+        assert_eq!(valid_intensity(1.5, 0.5), 1.);
+        assert_eq!(valid_intensity(-1., 0.5), 0.);
+        assert_eq!(valid_intensity(f64::NAN, 0.5), 0.5);
     }
 }
@@ -0,0 +1,47 @@
+//! High-contrast color scheme.
+
+use crate::module::{DrawerModule, Module, ModuleGroup, Toggle};
+use crate::text::Svg;
+use crate::theme::Theme;
+use crate::Result;
+
+#[derive(Default)]
+pub struct HighContrast {
+    enabled: bool,
+}
+
+impl HighContrast {
+    /// Get the selected color scheme.
+    pub fn theme(&self) -> &'static Theme {
+        Theme::select(self.enabled)
+    }
+}
+
+impl Module for HighContrast {
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::Toggle(self))
+    }
+
+    fn group(&self) -> ModuleGroup {
+        ModuleGroup::Display
+    }
+}
+
+impl Toggle for HighContrast {
+    fn set_enabled(&mut self, enabled: bool) -> Result<()> {
+        self.enabled = enabled;
+        Ok(())
+    }
+
+    fn svg(&self) -> Svg {
+        Svg::Contrast
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn title(&self) -> &'static str {
+        "Contrast"
+    }
+}
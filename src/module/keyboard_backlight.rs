@@ -0,0 +1,73 @@
+//! Keyboard backlight brightness.
+
+use tracing::warn;
+
+use crate::led::Led;
+use crate::module::{DrawerModule, Module, ModuleGroup, Slider};
+use crate::text::Svg;
+use crate::Result;
+
+/// Name pattern of keyboard backlight LEDs.
+const LED_NAME: &str = "*::kbd_backlight";
+
+pub struct KeyboardBacklight {
+    led: Option<Led>,
+    brightness: f64,
+}
+
+impl KeyboardBacklight {
+    pub fn new() -> Self {
+        let led = Led::find(LED_NAME).unwrap_or_else(|err| {
+            warn!(module = "keyboard_backlight", "Could not find keyboard backlight: {err}");
+            None
+        });
+
+        let brightness = led
+            .as_ref()
+            .and_then(|led| Some(led.brightness()? as f64 / led.max_brightness() as f64))
+            .unwrap_or_default();
+
+        Self { led, brightness }
+    }
+}
+
+impl Module for KeyboardBacklight {
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        // Hide the slider on devices without a keyboard backlight.
+        if self.led.is_some() {
+            Some(DrawerModule::Slider(self))
+        } else {
+            None
+        }
+    }
+
+    fn group(&self) -> ModuleGroup {
+        ModuleGroup::Display
+    }
+}
+
+impl Slider for KeyboardBacklight {
+    fn set_value(&mut self, value: f64) -> Result<()> {
+        let led = match &mut self.led {
+            Some(led) => led,
+            None => return Ok(()),
+        };
+
+        // Snap to the LED's brightness levels, which are often just a handful.
+        let max_brightness = led.max_brightness();
+        let brightness = (value * max_brightness as f64).round() as u32;
+        led.set_brightness(brightness)?;
+
+        self.brightness = brightness as f64 / max_brightness as f64;
+
+        Ok(())
+    }
+
+    fn get_value(&self) -> f64 {
+        self.brightness
+    }
+
+    fn svg(&self) -> Svg {
+        Svg::KeyboardBacklight
+    }
+}
@@ -0,0 +1,69 @@
+//! Modem location access indicator.
+
+use calloop::channel::Event;
+use calloop::LoopHandle;
+
+use crate::dbus::modem_manager;
+use crate::module::{Alignment, Module, PanelModule, PanelModuleContent};
+use crate::supervisor::BackendEvent;
+use crate::text::Svg;
+use crate::{Result, State};
+
+pub struct Location {
+    /// Modem GPS is enabled.
+    enabled: bool,
+}
+
+impl Location {
+    pub fn new(event_loop: &LoopHandle<'static, State>) -> Result<Self> {
+        // Subscribe to ModemManager location changes.
+        let rx = modem_manager::location_listener();
+        event_loop.insert_source(rx, |event, _, state| {
+            let enabled = match event {
+                Event::Msg(BackendEvent::Update(enabled)) => enabled,
+                Event::Msg(BackendEvent::Stopped) | Event::Closed => return,
+            };
+
+            let module = &mut state.modules.location;
+            if module.enabled != enabled {
+                module.enabled = enabled;
+                state.request_frame();
+            }
+        })?;
+
+        Ok(Self { enabled: false })
+    }
+}
+
+impl Module for Location {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        // Only show the indicator while location is accessible.
+        if self.enabled {
+            Some(self)
+        } else {
+            None
+        }
+    }
+}
+
+impl PanelModule for Location {
+    fn name(&self) -> &'static str {
+        "location"
+    }
+
+    fn alignment(&self) -> Alignment {
+        Alignment::Right
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        PanelModuleContent::Svg(Svg::Location)
+    }
+
+    fn priority(&self) -> u8 {
+        25
+    }
+
+    fn summary(&self) -> Option<String> {
+        Some("Location on".into())
+    }
+}
@@ -0,0 +1,105 @@
+//! Metered connection indicator.
+
+use calloop::channel::Event;
+use calloop::LoopHandle;
+
+use crate::dbus::network_manager;
+use crate::module::{
+    Alignment, DrawerModule, Module, ModuleGroup, PanelModule, PanelModuleContent, Toggle,
+};
+use crate::supervisor::BackendEvent;
+use crate::text::Svg;
+use crate::{Result, State};
+
+pub struct Metered {
+    /// Primary connection is metered, `None` if unknown.
+    metered: Option<bool>,
+}
+
+impl Metered {
+    pub fn new(event_loop: &LoopHandle<'static, State>) -> Result<Self> {
+        // Subscribe to NetworkManager metered changes.
+        let rx = network_manager::metered_listener();
+        event_loop.insert_source(rx, |event, _, state| {
+            let metered = match event {
+                Event::Msg(BackendEvent::Update(metered)) => metered,
+                Event::Msg(BackendEvent::Stopped) | Event::Closed => return,
+            };
+
+            let module = &mut state.modules.metered;
+            if module.metered != metered {
+                module.metered = metered;
+                state.request_frame();
+            }
+        })?;
+
+        Ok(Self { metered: None })
+    }
+}
+
+impl Module for Metered {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        // Only show the indicator while the connection is metered.
+        if self.metered == Some(true) {
+            Some(self)
+        } else {
+            None
+        }
+    }
+
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        // Hide the toggle without any connection to update.
+        if self.metered.is_some() {
+            Some(DrawerModule::Toggle(self))
+        } else {
+            None
+        }
+    }
+
+    fn group(&self) -> ModuleGroup {
+        ModuleGroup::Connectivity
+    }
+}
+
+impl PanelModule for Metered {
+    fn name(&self) -> &'static str {
+        "metered"
+    }
+
+    fn alignment(&self) -> Alignment {
+        Alignment::Right
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        PanelModuleContent::Svg(Svg::Metered)
+    }
+
+    fn priority(&self) -> u8 {
+        5
+    }
+
+    fn summary(&self) -> Option<String> {
+        Some("Metered".into())
+    }
+}
+
+impl Toggle for Metered {
+    fn set_enabled(&mut self, enabled: bool) -> Result<()> {
+        network_manager::set_metered(enabled);
+        self.metered = Some(enabled);
+
+        Ok(())
+    }
+
+    fn svg(&self) -> Svg {
+        Svg::Metered
+    }
+
+    fn enabled(&self) -> bool {
+        self.metered == Some(true)
+    }
+
+    fn title(&self) -> &'static str {
+        "Metered"
+    }
+}
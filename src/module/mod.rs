@@ -1,15 +1,37 @@
 //! Panel modules.
 
+use std::borrow::Cow;
+use std::time::{Duration, Instant};
+
+use calloop::timer::{TimeoutAction, Timer};
+use calloop::LoopHandle;
+use serde::{Deserialize, Serialize};
+
 use crate::text::Svg;
-use crate::Result;
+use crate::{Result, State};
 
 pub mod battery;
 pub mod brightness;
 pub mod cellular;
+pub mod cellular_profile;
+pub mod charge_limit;
 pub mod clock;
+pub mod custom;
+pub mod diagnostics;
+pub mod do_not_disturb;
 pub mod flashlight;
+pub mod high_contrast;
+pub mod keyboard_backlight;
+pub mod location;
+pub mod metered;
+pub mod network_mode;
 pub mod orientation;
+pub mod playback;
+pub mod plugin;
+pub mod ring_mode;
 pub mod scale;
+pub mod sim_slot;
+pub mod task;
 pub mod wifi;
 
 /// Panel module.
@@ -23,6 +45,91 @@ pub trait Module {
     fn drawer_module(&mut self) -> Option<DrawerModule> {
         None
     }
+
+    /// Handle taps on the panel module.
+    ///
+    /// Returns `false` if the module has no tap action, in which case the tap
+    /// toggles the drawer instead.
+    fn panel_tap(&mut self) -> bool {
+        false
+    }
+
+    /// Handle long-presses on the panel module.
+    ///
+    /// Returns text which should be copied to the clipboard, or `None` if the
+    /// module has no long-press action.
+    fn panel_long_press(&mut self) -> Option<String> {
+        None
+    }
+
+    /// Handle the drawer being shown or hidden.
+    ///
+    /// This can be used to limit expensive updates to while the drawer is
+    /// visible.
+    fn drawer_visibility_changed(&mut self, _visible: bool) {}
+
+    /// Handle all panels being concealed or revealed.
+    ///
+    /// Panels are concealed while fullscreen windows cover their output.
+    fn panel_visibility_changed(&mut self, _visible: bool) {}
+
+    /// Drawer group of the module.
+    fn group(&self) -> ModuleGroup {
+        ModuleGroup::System
+    }
+}
+
+/// Sorted list of modules, addressed by their index.
+pub trait ModuleList {
+    /// Iterate over all modules.
+    fn iter(&self) -> impl Iterator<Item = &dyn Module>;
+
+    /// Iterate mutably over all modules.
+    fn iter_mut(&mut self) -> impl Iterator<Item = &mut dyn Module>;
+
+    /// Get the module at an index.
+    fn get_mut(&mut self, index: usize) -> Option<&mut dyn Module> {
+        self.iter_mut().nth(index)
+    }
+
+    /// Get the drawer module at an index.
+    fn drawer_module(&mut self, index: usize) -> Option<DrawerModule> {
+        self.get_mut(index)?.drawer_module()
+    }
+}
+
+impl ModuleList for Vec<Box<dyn Module>> {
+    fn iter(&self) -> impl Iterator<Item = &dyn Module> {
+        self.as_slice().iter().map(|module| module.as_ref())
+    }
+
+    fn iter_mut(&mut self) -> impl Iterator<Item = &mut dyn Module> {
+        self.as_mut_slice().iter_mut().map(|module| &mut **module as &mut dyn Module)
+    }
+}
+
+/// Category of drawer modules.
+#[derive(Deserialize, Serialize, Copy, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ModuleGroup {
+    Connectivity,
+    Display,
+    Audio,
+    System,
+    Windows,
+}
+
+impl ModuleGroup {
+    /// Group header text.
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::Connectivity => "Connectivity",
+            Self::Display => "Display",
+            Self::Audio => "Audio",
+            Self::System => "System",
+            Self::Windows => "Windows",
+        }
+    }
 }
 
 /// Module alignment.
@@ -34,23 +141,77 @@ pub enum Alignment {
 
 /// Module in the panel.
 pub trait PanelModule {
+    /// Module name used for configuration.
+    fn name(&self) -> &'static str;
+
     /// Module alignment.
     fn alignment(&self) -> Alignment;
 
     /// Renderable panel content.
     fn content(&self) -> PanelModuleContent;
+
+    /// Request the user's attention by pulsing the module.
+    fn attention(&self) -> bool {
+        false
+    }
+
+    /// Current frame of a time-based animation.
+    ///
+    /// The panel is only redrawn for animations once the frame changed.
+    fn animation_frame(&self) -> Option<usize> {
+        None
+    }
+
+    /// Time at which the next animation frame is due.
+    ///
+    /// While this returns `Some`, visible panels are redrawn at this instant.
+    fn next_animation_frame(&self) -> Option<Instant> {
+        None
+    }
+
+    /// Hide the module from the panel.
+    ///
+    /// Hidden modules are still included in the drawer's summary.
+    fn hidden(&self) -> bool {
+        false
+    }
+
+    /// Content is restored from the previous run and not yet confirmed.
+    fn stale(&self) -> bool {
+        false
+    }
+
+    /// Importance of the module when the panel is overflowing.
+    ///
+    /// Modules with the lowest priority are hidden first.
+    fn priority(&self) -> u8 {
+        0
+    }
+
+    /// Text description of the module's state.
+    ///
+    /// This is shown in the drawer's summary, providing details which cannot
+    /// be conveyed by icons alone.
+    fn summary(&self) -> Option<String> {
+        None
+    }
 }
 
 /// Panel module renderable.
 pub enum PanelModuleContent {
     Text(String),
     Svg(Svg),
+    /// Icon followed by text.
+    Composite(Svg, String),
 }
 
 /// Module in the drawer.
 pub enum DrawerModule<'a> {
     Toggle(&'a mut dyn Toggle),
     Slider(&'a mut dyn Slider),
+    Cycle(&'a mut dyn Cycle),
+    Info(&'a mut dyn Info),
+    Media(&'a mut dyn Media),
 }
 
 /// Drawer slider module.
@@ -75,12 +236,153 @@ pub trait Slider {
 
 /// Drawer toggle button module.
 pub trait Toggle {
+    /// Apply the button status.
+    ///
+    /// This should apply the state even if it matches the current status,
+    /// allowing failed actions to be retried.
+    fn set_enabled(&mut self, enabled: bool) -> Result<()>;
+
     /// Toggle button status.
-    fn toggle(&mut self) -> Result<()>;
+    fn toggle(&mut self) -> Result<()> {
+        self.set_enabled(!self.enabled())
+    }
+
+    /// Secondary action triggered by long-pressing the button.
+    ///
+    /// Returns `false` if the module has no secondary action, in which case
+    /// the touch is handled like a regular tap.
+    fn long_press(&mut self) -> Result<bool> {
+        Ok(false)
+    }
+
+    /// Recent values in the range `0.0..=1.0`, graphed below the button's
+    /// symbol.
+    fn history(&self) -> Option<&[f32]> {
+        None
+    }
 
     /// Get button status.
     fn enabled(&self) -> bool;
 
+    /// Short hint rendered below the button's symbol.
+    fn label(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Name of the button, shown below it.
+    fn title(&self) -> &'static str;
+
     /// Get renderable SVG.
     fn svg(&self) -> Svg;
 }
+
+/// Drawer button module cycling through multiple states.
+pub trait Cycle {
+    /// Get all states of the button.
+    ///
+    /// The first state is the default, which is rendered like an inactive
+    /// toggle button unless [`Cycle::enabled`] is overridden.
+    fn states(&self) -> &[CycleState];
+
+    /// Get the index of the active state.
+    fn active(&self) -> usize;
+
+    /// Render the button like an active toggle button.
+    fn enabled(&self) -> bool {
+        self.active() != 0
+    }
+
+    /// Switch to a different state.
+    fn set_active(&mut self, index: usize) -> Result<()>;
+}
+
+/// State of a cycle button.
+#[derive(Clone)]
+pub struct CycleState {
+    /// Button symbol.
+    pub svg: Svg,
+
+    /// Button label.
+    pub label: Cow<'static, str>,
+}
+
+/// Drawer module displaying read-only text.
+pub trait Info {
+    /// Get the text content.
+    ///
+    /// Lines are separated by newline characters.
+    fn text(&self) -> &str;
+
+    /// Handle taps on the module.
+    fn tap(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Handle horizontal swipes over the module.
+    fn swipe(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Text is outdated, since its backend stopped.
+    fn stale(&self) -> bool {
+        false
+    }
+}
+
+/// Drawer module controlling media playback.
+pub trait Media {
+    /// Title of the current track.
+    fn title(&self) -> &str;
+
+    /// Check if the media is currently playing.
+    fn playing(&self) -> bool;
+
+    /// Send a playback command to the player.
+    fn control(&mut self, action: MediaAction) -> Result<()>;
+}
+
+/// Media playback command.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum MediaAction {
+    Previous,
+    PlayPause,
+    Next,
+}
+
+/// Age tracking for data received from a module's backend.
+#[derive(Copy, Clone, Debug)]
+pub struct Freshness {
+    last_update: Instant,
+    max_age: Duration,
+    running: bool,
+}
+
+impl Freshness {
+    pub fn new(max_age: Duration) -> Self {
+        Self { max_age, last_update: Instant::now(), running: true }
+    }
+
+    /// Record new data from the backend.
+    pub fn update(&mut self) {
+        self.last_update = Instant::now();
+        self.running = true;
+    }
+
+    /// Check if the backend stopped and its last data exceeded the maximum age.
+    pub fn is_outdated(&self) -> bool {
+        !self.running && self.last_update.elapsed() > self.max_age
+    }
+
+    /// Handle backend shutdown.
+    ///
+    /// This schedules a redraw for once the last received data is outdated.
+    pub fn stop(&mut self, event_loop: &LoopHandle<'static, State>) {
+        self.running = false;
+
+        let remaining = self.max_age.saturating_sub(self.last_update.elapsed());
+        let _ = event_loop.insert_source(Timer::from_duration(remaining), |_, _, state| {
+            state.request_frame();
+            TimeoutAction::Drop
+        });
+    }
+}
@@ -0,0 +1,69 @@
+//! Preferred cellular network mode.
+
+use std::borrow::Cow;
+
+use crate::dbus::modem_manager;
+use crate::module::{Cycle, CycleState, DrawerModule, Module, ModuleGroup};
+use crate::text::Svg;
+use crate::Result;
+
+/// ModemManager modes of each cycle state.
+const MODES: [modem_manager::NetworkMode; 5] = [
+    modem_manager::NetworkMode::Auto,
+    modem_manager::NetworkMode::FiveG,
+    modem_manager::NetworkMode::FourG,
+    modem_manager::NetworkMode::ThreeG,
+    modem_manager::NetworkMode::TwoG,
+];
+
+/// Cycle button states of each mode.
+const STATES: [CycleState; 5] = [
+    CycleState { svg: Svg::NetworkMode, label: Cow::Borrowed("Auto") },
+    CycleState { svg: Svg::NetworkMode, label: Cow::Borrowed("5G") },
+    CycleState { svg: Svg::NetworkMode, label: Cow::Borrowed("4G") },
+    CycleState { svg: Svg::NetworkMode, label: Cow::Borrowed("3G") },
+    CycleState { svg: Svg::NetworkMode, label: Cow::Borrowed("2G") },
+];
+
+#[derive(Default)]
+pub struct NetworkMode {
+    mode: modem_manager::NetworkMode,
+}
+
+impl NetworkMode {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Update the mode reported by the modem.
+    pub fn set_current(&mut self, mode: modem_manager::NetworkMode) {
+        self.mode = mode;
+    }
+}
+
+impl Module for NetworkMode {
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::Cycle(self))
+    }
+
+    fn group(&self) -> ModuleGroup {
+        ModuleGroup::Connectivity
+    }
+}
+
+impl Cycle for NetworkMode {
+    fn states(&self) -> &[CycleState] {
+        &STATES
+    }
+
+    fn active(&self) -> usize {
+        MODES.iter().position(|mode| *mode == self.mode).unwrap_or_default()
+    }
+
+    fn set_active(&mut self, index: usize) -> Result<()> {
+        self.mode = MODES[index];
+        modem_manager::set_network_mode(self.mode);
+
+        Ok(())
+    }
+}
@@ -2,7 +2,7 @@
 
 use catacomb_ipc::{self, IpcMessage};
 
-use crate::module::{DrawerModule, Module, Toggle};
+use crate::module::{DrawerModule, Module, ModuleGroup, Toggle};
 use crate::text::Svg;
 use crate::Result;
 
@@ -20,11 +20,15 @@ impl Module for Orientation {
     fn drawer_module(&mut self) -> Option<DrawerModule> {
         Some(DrawerModule::Toggle(self))
     }
+
+    fn group(&self) -> ModuleGroup {
+        ModuleGroup::Display
+    }
 }
 
 impl Toggle for Orientation {
-    fn toggle(&mut self) -> Result<()> {
-        self.locked = !self.locked;
+    fn set_enabled(&mut self, enabled: bool) -> Result<()> {
+        self.locked = enabled;
 
         let msg = IpcMessage::Orientation { lock: None, unlock: !self.locked };
         catacomb_ipc::send_message(&msg)?;
@@ -43,4 +47,8 @@ impl Toggle for Orientation {
     fn enabled(&self) -> bool {
         self.locked
     }
+
+    fn title(&self) -> &'static str {
+        "Rotation"
+    }
 }
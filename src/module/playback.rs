@@ -0,0 +1,80 @@
+//! Media playback controls.
+
+use calloop::channel::Event;
+use calloop::LoopHandle;
+
+use crate::dbus::mpris::{self, MediaControl, MediaPlayer, MprisEvent};
+use crate::module::{DrawerModule, Media, MediaAction, Module, ModuleGroup};
+use crate::supervisor::BackendEvent;
+use crate::{Result, State};
+
+pub struct Playback {
+    /// Player controlled by the drawer.
+    player: Option<MediaPlayer>,
+
+    /// Sender for playback commands.
+    control: MediaControl,
+}
+
+impl Playback {
+    pub fn new(event_loop: &LoopHandle<'static, State>) -> Result<Self> {
+        // Subscribe to MPRIS player changes.
+        let (rx, control) = mpris::player_listener();
+        event_loop.insert_source(rx, |event, _, state| {
+            let player = match event {
+                Event::Msg(BackendEvent::Update(MprisEvent::Player(player))) => player,
+                Event::Msg(BackendEvent::Update(MprisEvent::ControlFailed(err))) => {
+                    state.media_control_failed(err);
+                    return;
+                },
+                Event::Msg(BackendEvent::Stopped) | Event::Closed => None,
+            };
+
+            let module = &mut state.modules.playback;
+            if module.player != player {
+                module.player = player;
+                state.drawer().request_frame();
+            }
+        })?;
+
+        Ok(Self { player: None, control })
+    }
+}
+
+impl Module for Playback {
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        // Only show controls while a player is running.
+        self.player.as_ref()?;
+        Some(DrawerModule::Media(self))
+    }
+
+    fn group(&self) -> ModuleGroup {
+        ModuleGroup::Audio
+    }
+}
+
+impl Media for Playback {
+    fn title(&self) -> &str {
+        self.player.as_ref().map_or("", |player| &player.title)
+    }
+
+    fn playing(&self) -> bool {
+        self.player.as_ref().is_some_and(|player| player.playing)
+    }
+
+    fn control(&mut self, action: MediaAction) -> Result<()> {
+        let player = match &mut self.player {
+            Some(player) => player,
+            None => return Ok(()),
+        };
+
+        // Optimistically update the button until the player confirms the change.
+        if action == MediaAction::PlayPause {
+            player.playing = !player.playing;
+        }
+
+        self.control.send(player.name.clone(), action)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+//! Out-of-tree drawer modules loaded from shared libraries.
+
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::mem;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+use crate::module::{DrawerModule, Info, Module};
+use crate::Result;
+
+/// Version of the plugin interface.
+///
+/// This must be incremented with every incompatible change to
+/// [`PluginVTable`].
+pub const PLUGIN_API_VERSION: u32 = 1;
+
+/// Plugin entry point, returning a pointer to its [`PluginVTable`].
+const INIT_SYMBOL: &[u8] = b"epitaph_plugin_init\0";
+
+/// Plugin interface exposed over the C ABI.
+#[repr(C)]
+struct PluginVTable {
+    /// Version of the interface implemented by the plugin.
+    api_version: u32,
+
+    /// Opaque plugin state passed to all callbacks.
+    data: *mut c_void,
+
+    /// Get the module's text, which must stay valid until the next call.
+    text: Option<unsafe extern "C" fn(data: *mut c_void) -> *const c_char>,
+
+    /// Handle taps on the module, returning zero on success.
+    tap: Option<unsafe extern "C" fn(data: *mut c_void) -> c_int>,
+}
+
+pub struct Plugin {
+    vtable: &'static PluginVTable,
+    text: String,
+}
+
+impl Plugin {
+    /// Load a plugin from a shared library.
+    pub fn load(path: &Path) -> Result<Self> {
+        let c_path = CString::new(path.as_os_str().as_bytes())?;
+        let handle = unsafe { libc::dlopen(c_path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        if handle.is_null() {
+            return Err(dl_error().into());
+        }
+
+        let vtable = unsafe { Self::init(handle) };
+        let vtable = match vtable {
+            Ok(vtable) => vtable,
+            Err(err) => {
+                unsafe { libc::dlclose(handle) };
+                return Err(err);
+            },
+        };
+
+        // The library is never unloaded, since its vtable must outlive the module.
+        let mut plugin = Self { vtable, text: Default::default() };
+        plugin.refresh();
+
+        Ok(plugin)
+    }
+
+    /// Initialize the plugin and validate its vtable.
+    unsafe fn init(handle: *mut c_void) -> Result<&'static PluginVTable> {
+        let init = libc::dlsym(handle, INIT_SYMBOL.as_ptr().cast());
+        if init.is_null() {
+            return Err("missing epitaph_plugin_init symbol".into());
+        }
+
+        let init: unsafe extern "C" fn() -> *const PluginVTable = mem::transmute(init);
+        let vtable = init().as_ref().ok_or("plugin initialization failed")?;
+
+        if vtable.api_version != PLUGIN_API_VERSION {
+            let version = vtable.api_version;
+            return Err(format!(
+                "unsupported plugin API version {version}, expected {PLUGIN_API_VERSION}"
+            )
+            .into());
+        }
+
+        Ok(vtable)
+    }
+
+    /// Reload the module's text.
+    fn refresh(&mut self) {
+        let text = match self.vtable.text {
+            Some(text) => unsafe { text(self.vtable.data) },
+            None => return,
+        };
+
+        self.text = if text.is_null() {
+            String::new()
+        } else {
+            unsafe { CStr::from_ptr(text).to_string_lossy().into_owned() }
+        };
+    }
+}
+
+impl Module for Plugin {
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::Info(self))
+    }
+
+    fn drawer_visibility_changed(&mut self, visible: bool) {
+        if visible {
+            self.refresh();
+        }
+    }
+}
+
+impl Info for Plugin {
+    fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn tap(&mut self) -> Result<()> {
+        if let Some(tap) = self.vtable.tap {
+            let status = unsafe { tap(self.vtable.data) };
+            self.refresh();
+
+            if status != 0 {
+                return Err(format!("plugin tap failed with status {status}").into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Get the last dynamic linker error.
+fn dl_error() -> String {
+    let error = unsafe { libc::dlerror() };
+    if error.is_null() {
+        String::from("unknown dlopen error")
+    } else {
+        unsafe { CStr::from_ptr(error).to_string_lossy().into_owned() }
+    }
+}
@@ -0,0 +1,123 @@
+//! Ring, vibrate and silent mode.
+
+use std::borrow::Cow;
+use std::time::Duration;
+
+use calloop::channel::Event;
+use calloop::LoopHandle;
+
+use crate::dbus::feedbackd::{self, FeedbackProfile};
+use crate::module::{
+    Alignment, Cycle, CycleState, DrawerModule, Freshness, Module, ModuleGroup, PanelModule,
+    PanelModuleContent,
+};
+use crate::supervisor::BackendEvent;
+use crate::text::Svg;
+use crate::{Result, State};
+
+/// Feedbackd profiles of each mode.
+const PROFILES: [FeedbackProfile; 3] =
+    [FeedbackProfile::Full, FeedbackProfile::Quiet, FeedbackProfile::Silent];
+
+/// Cycle button states of each mode.
+const STATES: [CycleState; 3] = [
+    CycleState { svg: Svg::Ring, label: Cow::Borrowed("Ring") },
+    CycleState { svg: Svg::RingVibrate, label: Cow::Borrowed("Vibrate") },
+    CycleState { svg: Svg::RingSilent, label: Cow::Borrowed("Silent") },
+];
+
+pub struct RingMode {
+    profile: FeedbackProfile,
+
+    /// Age of the feedbackd data.
+    freshness: Freshness,
+}
+
+impl RingMode {
+    pub fn new(event_loop: &LoopHandle<'static, State>, max_age: Duration) -> Result<Self> {
+        // Subscribe to feedbackd profile changes.
+        let rx = feedbackd::profile_listener();
+        event_loop.insert_source(rx, |event, _, state| {
+            let profile = match event {
+                Event::Msg(BackendEvent::Update(profile)) => profile,
+                Event::Msg(BackendEvent::Stopped) | Event::Closed => {
+                    state.modules.ring_mode.freshness.stop(&state.event_loop);
+                    return;
+                },
+            };
+
+            // Redraw modules which were outdated before this update.
+            let freshness = &mut state.modules.ring_mode.freshness;
+            let outdated = freshness.is_outdated();
+            freshness.update();
+            if outdated {
+                state.request_frame();
+            }
+
+            let module = &mut state.modules.ring_mode;
+            if module.profile != profile {
+                module.profile = profile;
+                state.request_frame();
+            }
+        })?;
+
+        Ok(Self { profile: FeedbackProfile::default(), freshness: Freshness::new(max_age) })
+    }
+}
+
+impl Module for RingMode {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        // Only show a badge when sounds are muted.
+        match self.profile {
+            FeedbackProfile::Full => None,
+            FeedbackProfile::Quiet | FeedbackProfile::Silent => Some(self),
+        }
+    }
+
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::Cycle(self))
+    }
+
+    fn group(&self) -> ModuleGroup {
+        ModuleGroup::Audio
+    }
+}
+
+impl PanelModule for RingMode {
+    fn name(&self) -> &'static str {
+        "ring_mode"
+    }
+
+    fn alignment(&self) -> Alignment {
+        Alignment::Right
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        PanelModuleContent::Svg(STATES[self.active()].svg)
+    }
+
+    fn stale(&self) -> bool {
+        self.freshness.is_outdated()
+    }
+
+    fn priority(&self) -> u8 {
+        10
+    }
+}
+
+impl Cycle for RingMode {
+    fn states(&self) -> &[CycleState] {
+        &STATES
+    }
+
+    fn active(&self) -> usize {
+        PROFILES.iter().position(|profile| *profile == self.profile).unwrap_or_default()
+    }
+
+    fn set_active(&mut self, index: usize) -> Result<()> {
+        self.profile = PROFILES[index];
+        feedbackd::set_profile(self.profile);
+
+        Ok(())
+    }
+}
@@ -2,7 +2,7 @@
 
 use catacomb_ipc::{self, IpcMessage, WindowScale};
 
-use crate::module::{DrawerModule, Module, Slider};
+use crate::module::{DrawerModule, Module, ModuleGroup, Slider};
 use crate::text::Svg;
 use crate::Result;
 
@@ -20,6 +20,10 @@ impl Module for Scale {
     fn drawer_module(&mut self) -> Option<DrawerModule> {
         Some(DrawerModule::Slider(self))
     }
+
+    fn group(&self) -> ModuleGroup {
+        ModuleGroup::Display
+    }
 }
 
 impl Slider for Scale {
@@ -0,0 +1,98 @@
+//! Primary SIM slot selection.
+
+use std::borrow::Cow;
+
+use crate::dbus::modem_manager;
+use crate::module::{
+    Alignment, Cycle, CycleState, DrawerModule, Module, ModuleGroup, PanelModule,
+    PanelModuleContent,
+};
+use crate::text::Svg;
+use crate::Result;
+
+#[derive(Default)]
+pub struct SimSlot {
+    /// Cycle button states of each SIM slot.
+    states: Vec<CycleState>,
+
+    /// Index of the active SIM slot.
+    primary: usize,
+}
+
+impl SimSlot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Update the SIM slots reported by the modem.
+    ///
+    /// The primary slot is the 1-based slot number used by ModemManager.
+    pub fn set_current(&mut self, slots: usize, primary: u32) {
+        self.primary = (primary as usize).saturating_sub(1);
+
+        if self.states.len() != slots {
+            self.states = (1..=slots)
+                .map(|slot| CycleState { svg: Svg::Sim, label: Cow::Owned(format!("SIM {slot}")) })
+                .collect();
+        }
+    }
+
+    /// Check if there are multiple SIM slots to choose from.
+    fn has_multiple_slots(&self) -> bool {
+        self.states.len() > 1
+    }
+}
+
+impl Module for SimSlot {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        self.has_multiple_slots().then_some(self as &dyn PanelModule)
+    }
+
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        self.has_multiple_slots().then_some(DrawerModule::Cycle(self))
+    }
+
+    fn group(&self) -> ModuleGroup {
+        ModuleGroup::Connectivity
+    }
+}
+
+impl PanelModule for SimSlot {
+    fn name(&self) -> &'static str {
+        "sim_slot"
+    }
+
+    fn alignment(&self) -> Alignment {
+        Alignment::Right
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        PanelModuleContent::Text((self.primary + 1).to_string())
+    }
+
+    fn priority(&self) -> u8 {
+        15
+    }
+}
+
+impl Cycle for SimSlot {
+    fn states(&self) -> &[CycleState] {
+        &self.states
+    }
+
+    fn active(&self) -> usize {
+        self.primary
+    }
+
+    // Every slot is a valid choice, so the first one isn't rendered as off.
+    fn enabled(&self) -> bool {
+        true
+    }
+
+    fn set_active(&mut self, index: usize) -> Result<()> {
+        self.primary = index;
+        modem_manager::set_primary_sim_slot(index as u32 + 1);
+
+        Ok(())
+    }
+}
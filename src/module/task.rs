@@ -0,0 +1,54 @@
+//! Open windows of other clients.
+
+use smithay_client_toolkit::reexports::client::protocol::wl_seat::WlSeat;
+use smithay_client_toolkit::reexports::protocols_wlr::foreign_toplevel::v1::client::zwlr_foreign_toplevel_handle_v1::ZwlrForeignToplevelHandleV1;
+
+use crate::module::{DrawerModule, Info, Module, ModuleGroup};
+use crate::protocols::foreign_toplevel::Toplevel;
+use crate::Result;
+
+pub struct Task {
+    handle: ZwlrForeignToplevelHandleV1,
+    seat: Option<WlSeat>,
+    text: String,
+}
+
+impl Task {
+    pub fn new(toplevel: &Toplevel, seat: Option<WlSeat>) -> Self {
+        // Fall back to the app ID for windows without title.
+        let text = if toplevel.title.is_empty() {
+            toplevel.app_id.clone()
+        } else {
+            toplevel.title.clone()
+        };
+
+        Self { handle: toplevel.handle.clone(), seat, text }
+    }
+}
+
+impl Module for Task {
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::Info(self))
+    }
+
+    fn group(&self) -> ModuleGroup {
+        ModuleGroup::Windows
+    }
+}
+
+impl Info for Task {
+    fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn tap(&mut self) -> Result<()> {
+        let seat = self.seat.as_ref().ok_or("no seat available")?;
+        self.handle.activate(seat);
+        Ok(())
+    }
+
+    fn swipe(&mut self) -> Result<()> {
+        self.handle.close();
+        Ok(())
+    }
+}
@@ -1,13 +1,27 @@
 //! WiFi status and signal strength.
 
-use calloop::channel::Event;
+use std::time::Duration;
+
+use calloop::channel::{self, Event, Sender};
+use calloop::timer::{TimeoutAction, Timer};
 use calloop::LoopHandle;
 
-use crate::dbus::network_manager::{self, WifiConnection};
-use crate::module::{Alignment, DrawerModule, Module, PanelModule, PanelModuleContent, Toggle};
+use crate::dbus::network_manager::{self, WifiConnection, WifiNetwork, WifiScan};
+use crate::module::{
+    Alignment, DrawerModule, Freshness, Module, ModuleGroup, PanelModule, PanelModuleContent,
+    Toggle,
+};
+use crate::persistence::PersistentState;
+use crate::supervisor::BackendEvent;
 use crate::text::Svg;
 use crate::{Result, State};
 
+/// Number of signal strength samples shown in the drawer.
+const STRENGTH_HISTORY_LEN: usize = 24;
+
+/// Interval between signal strength samples.
+const STRENGTH_SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+
 #[derive(Debug)]
 pub struct Wifi {
     /// Current connection state.
@@ -15,18 +29,57 @@ pub struct Wifi {
 
     /// Desired connectivity state.
     desired_enabled: bool,
+
+    /// Desired connectivity state from the previous run.
+    persisted_enabled: Option<bool>,
+
+    /// Connection state is restored from the previous run.
+    stale: bool,
+
+    /// Icon is hidden from the panel in favor of another connection.
+    hidden: bool,
+
+    /// Age of the NetworkManager data.
+    freshness: Freshness,
+
+    /// Recent signal strength samples in the range `0.0..=1.0`, oldest first.
+    strength_history: Vec<f32>,
+
+    /// Networks found by the last scan.
+    networks: Vec<WifiNetwork>,
+
+    /// Sender for WiFi scan results.
+    scan_tx: Sender<Vec<WifiNetwork>>,
+
+    /// Scan running while the drawer is open.
+    scan: Option<WifiScan>,
 }
 
 impl Wifi {
-    pub fn new(event_loop: &LoopHandle<'static, State>) -> Result<Self> {
+    pub fn new(
+        event_loop: &LoopHandle<'static, State>,
+        max_age: Duration,
+        reduced_motion: bool,
+    ) -> Result<Self> {
         // Subscribe to NetworkManager DBus events.
-        let rx = network_manager::wifi_listener()?;
+        let rx = network_manager::wifi_listener();
         event_loop.insert_source(rx, move |event, _, state| {
             let connection = match event {
-                Event::Msg(connection) => connection,
-                Event::Closed => return,
+                Event::Msg(BackendEvent::Update(connection)) => connection,
+                Event::Msg(BackendEvent::Stopped) | Event::Closed => {
+                    state.modules.wifi.freshness.stop(&state.event_loop);
+                    return;
+                },
             };
 
+            // Redraw modules which were outdated before this update.
+            let freshness = &mut state.modules.wifi.freshness;
+            let outdated = freshness.is_outdated();
+            freshness.update();
+            if outdated {
+                state.request_frame();
+            }
+
             // Ignore updates that change nothing.
             let module = &mut state.modules.wifi;
             if connection == module.connection {
@@ -34,19 +87,115 @@ impl Wifi {
             }
 
             let old_enabled = module.desired_enabled;
+            let old_stale = module.stale;
             let old_svg = module.svg();
 
             // Update connection status.
             module.desired_enabled = connection.enabled;
             module.connection = connection;
+            module.stale = false;
+            state.update_connectivity_icons();
+            let module = &mut state.modules.wifi;
+
+            // Restore the desired state from the previous run once the wireless device is
+            // available.
+            if connection.available {
+                if let Some(enabled) = module.persisted_enabled.take() {
+                    if enabled != connection.enabled {
+                        module.desired_enabled = enabled;
+                        network_manager::set_enabled(enabled);
+                    }
+                }
+            }
+
+            // Cache the icon's status for the next startup.
+            let svg_changed = old_svg != state.modules.wifi.svg();
+            if svg_changed {
+                state.status_cache.update(move |state| {
+                    state.wifi_strength = Some(connection.strength);
+                    state.wifi_connected = Some(connection.connected);
+                });
+            }
 
             // Request redraw only if SVG changed.
-            if old_svg != state.modules.wifi.svg() || old_enabled != connection.enabled {
+            if svg_changed || old_enabled != state.modules.wifi.desired_enabled || old_stale {
                 state.request_frame();
             }
         })?;
 
-        Ok(Self { connection: WifiConnection::default(), desired_enabled: false })
+        // Sample signal strength periodically, avoiding a constantly shifting
+        // graph with reduced motion.
+        if !reduced_motion {
+            let timer = Timer::from_duration(STRENGTH_SAMPLE_INTERVAL);
+            event_loop.insert_source(timer, |_, _, state| {
+                state.modules.wifi.record_strength();
+                state.drawer().request_frame();
+                TimeoutAction::ToDuration(STRENGTH_SAMPLE_INTERVAL)
+            })?;
+        }
+
+        // Cache WiFi scan results.
+        let (scan_tx, scan_rx) = channel::channel();
+        event_loop.insert_source(scan_rx, |event, _, state| {
+            if let Event::Msg(networks) = event {
+                let module = &mut state.modules.wifi;
+                let old_summary = module.summary();
+                module.networks = networks;
+
+                if module.summary() != old_summary {
+                    state.drawer().request_frame();
+                }
+            }
+        })?;
+
+        // Show the last known status until NetworkManager reports the connection.
+        let persistent_state = PersistentState::load();
+        let persisted_enabled = persistent_state.wifi_enabled;
+        let connection = WifiConnection {
+            enabled: persisted_enabled.unwrap_or_default(),
+            strength: persistent_state.wifi_strength.unwrap_or_default(),
+            connected: persistent_state.wifi_connected.unwrap_or_default(),
+            available: false,
+        };
+
+        Ok(Self {
+            connection,
+            persisted_enabled,
+            desired_enabled: connection.enabled,
+            stale: persistent_state.wifi_strength.is_some(),
+            freshness: Freshness::new(max_age),
+            hidden: false,
+            strength_history: Default::default(),
+            networks: Default::default(),
+            scan_tx,
+            scan: None,
+        })
+    }
+
+    /// Current connection state.
+    pub fn connection(&self) -> WifiConnection {
+        self.connection
+    }
+
+    /// Hide the icon from the panel.
+    ///
+    /// Returns `true` if the visibility changed.
+    pub fn set_hidden(&mut self, hidden: bool) -> bool {
+        let changed = self.hidden != hidden;
+        self.hidden = hidden;
+        changed
+    }
+
+    /// Add a signal strength sample to the history.
+    ///
+    /// Disconnects are recorded as zero strength, showing them as gaps.
+    fn record_strength(&mut self) {
+        let strength = if self.connection.enabled { self.connection.strength } else { 0 };
+
+        if self.strength_history.len() >= STRENGTH_HISTORY_LEN {
+            self.strength_history.remove(0);
+        }
+        self.strength_history.push(strength as f32 / 100.);
     }
 }
 
@@ -58,9 +207,23 @@ impl Module for Wifi {
     fn drawer_module(&mut self) -> Option<DrawerModule> {
         Some(DrawerModule::Toggle(self))
     }
+
+    fn group(&self) -> ModuleGroup {
+        ModuleGroup::Connectivity
+    }
+
+    fn drawer_visibility_changed(&mut self, visible: bool) {
+        // Scan for networks while the drawer is open.
+        self.scan = (visible && self.connection.enabled)
+            .then(|| network_manager::request_scan(self.scan_tx.clone()));
+    }
 }
 
 impl PanelModule for Wifi {
+    fn name(&self) -> &'static str {
+        "wifi"
+    }
+
     fn alignment(&self) -> Alignment {
         Alignment::Right
     }
@@ -68,12 +231,51 @@ impl PanelModule for Wifi {
     fn content(&self) -> PanelModuleContent {
         PanelModuleContent::Svg(self.svg())
     }
+
+    fn hidden(&self) -> bool {
+        self.hidden
+    }
+
+    fn stale(&self) -> bool {
+        self.stale || self.freshness.is_outdated()
+    }
+
+    fn priority(&self) -> u8 {
+        20
+    }
+
+    fn summary(&self) -> Option<String> {
+        let connection = &self.connection;
+        let summary = if !connection.available {
+            return None;
+        } else if !connection.enabled {
+            "WiFi off".into()
+        } else if connection.strength == 0 {
+            // Show networks available for connecting.
+            let open = self.networks.iter().filter(|network| !network.secured).count();
+            match self.networks.len() {
+                0 => "WiFi disconnected".into(),
+                1 if open == 1 => "WiFi disconnected, 1 open network".into(),
+                1 => "WiFi disconnected, 1 network".into(),
+                count => format!("WiFi disconnected, {count} networks ({open} open)"),
+            }
+        } else if !connection.connected {
+            format!("WiFi {}%, no internet", connection.strength)
+        } else {
+            format!("WiFi {}%", connection.strength)
+        };
+        Some(summary)
+    }
 }
 
 impl Toggle for Wifi {
-    fn toggle(&mut self) -> Result<()> {
-        self.desired_enabled = !self.desired_enabled;
-        network_manager::set_enabled(self.desired_enabled);
+    fn set_enabled(&mut self, enabled: bool) -> Result<()> {
+        self.desired_enabled = enabled;
+        network_manager::set_enabled(enabled);
+
+        // Persist desired state for restoring it after reboot.
+        PersistentState::update(|state| state.wifi_enabled = Some(enabled));
+
         Ok(())
     }
 
@@ -98,4 +300,17 @@ impl Toggle for Wifi {
     fn enabled(&self) -> bool {
         self.desired_enabled
     }
+
+    fn history(&self) -> Option<&[f32]> {
+        // Require multiple samples to show a trend.
+        if self.strength_history.len() < 2 {
+            return None;
+        }
+
+        Some(&self.strength_history)
+    }
+
+    fn title(&self) -> &'static str {
+        "Wi-Fi"
+    }
 }
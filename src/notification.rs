@@ -0,0 +1,501 @@
+//! Notification popup windows.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::time::Duration;
+
+use calloop::channel::Event;
+use calloop::timer::{TimeoutAction, Timer};
+use calloop::{LoopHandle, RegistrationToken};
+use smithay_client_toolkit::reexports::client::protocol::wl_surface::WlSurface;
+use smithay_client_toolkit::reexports::client::QueueHandle;
+use smithay_client_toolkit::shell::wlr_layer::{Anchor, Layer, LayerSurfaceConfigure};
+use smithay_client_toolkit::shell::WaylandSurface;
+use tracing::error;
+
+use crate::dbus::notifications::{
+    self, CloseReason, Notification, NotificationCloser, NotificationEvent,
+};
+use crate::renderer::Renderer;
+use crate::surface::WindowSurface;
+use crate::theme::Theme;
+use crate::{geometry, gl, ProtocolStates, Result, State};
+
+/// Popup height in pixels with a scale factor of 1.
+const POPUP_HEIGHT: u32 = 76;
+
+/// Spacing between popups and the screen edges.
+const POPUP_MARGIN: i32 = 8;
+
+/// Padding between popup edges and text.
+const TEXT_PADDING: f64 = 10.;
+
+/// Maximum number of lines for the notification summary.
+const SUMMARY_LINES: usize = 1;
+
+/// Maximum number of lines for the notification body.
+const BODY_LINES: usize = 2;
+
+/// Maximum number of simultaneously visible popups.
+const MAX_VISIBLE: usize = 3;
+
+/// Expiry time for notifications without explicit timeout.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Minimum horizontal swipe distance to dismiss a popup.
+const DISMISS_DISTANCE: f64 = 100.;
+
+/// IDs above this are used for notifications created by epitaph itself.
+///
+/// Server IDs count up from 1, so local IDs count down from the maximum.
+const LOCAL_ID_MIN: u32 = u32::MAX / 2;
+
+/// Notification popup manager.
+pub struct Notifications {
+    event_loop: LoopHandle<'static, State>,
+    pending: VecDeque<Notification>,
+    closer: NotificationCloser,
+    queue: QueueHandle<State>,
+    touch: Option<PopupTouch>,
+    popups: Vec<Popup>,
+    reduced_motion: bool,
+    renderer: Rc<RefCell<Renderer>>,
+    next_local_id: u32,
+}
+
+impl Notifications {
+    pub fn new(
+        event_loop: &LoopHandle<'static, State>,
+        queue: QueueHandle<State>,
+        renderer: Rc<RefCell<Renderer>>,
+        reduced_motion: bool,
+    ) -> Result<Self> {
+        // Subscribe to incoming notifications.
+        let (rx, closer) = notifications::notification_listener()?;
+        event_loop.insert_source(rx, |event, _, state| {
+            let event = match event {
+                Event::Msg(event) => event,
+                Event::Closed => return,
+            };
+
+            let protocol_states = &state.protocol_states;
+            let notifications = state.notifications.as_mut().unwrap();
+            match event {
+                // Reject notifications immediately while popups are suppressed.
+                NotificationEvent::Notify(notification)
+                    if state.modules.do_not_disturb.enabled() =>
+                {
+                    notifications.report_closed(notification.id, CloseReason::Dismissed)
+                },
+                NotificationEvent::Notify(notification) => {
+                    notifications.notify(protocol_states, notification)
+                },
+                NotificationEvent::Close(id) => {
+                    notifications.close(protocol_states, id, CloseReason::Closed)
+                },
+            }
+        })?;
+
+        Ok(Self {
+            renderer,
+            closer,
+            queue,
+            reduced_motion,
+            event_loop: event_loop.clone(),
+            next_local_id: u32::MAX,
+            pending: Default::default(),
+            popups: Default::default(),
+            touch: Default::default(),
+        })
+    }
+
+    /// Show a new notification, or update an existing one.
+    pub fn notify(&mut self, protocol_states: &ProtocolStates, notification: Notification) {
+        // Replace visible notifications in place.
+        if let Some(index) = self.popup_index(notification.id) {
+            self.popups[index].notification = notification;
+            self.stage_expiry(index);
+            self.popups[index].surface.request_frame();
+            return;
+        }
+
+        // Replace queued notifications.
+        let pending = self.pending.iter_mut().find(|pending| pending.id == notification.id);
+        if let Some(pending) = pending {
+            *pending = notification;
+            return;
+        }
+
+        self.pending.push_back(notification);
+        self.show_pending(protocol_states);
+    }
+
+    /// Show a notification which was not received over DBus.
+    pub fn notify_local(
+        &mut self,
+        protocol_states: &ProtocolStates,
+        summary: String,
+        body: String,
+    ) {
+        let id = self.local_id();
+        let notification = Notification { id, summary, body, timeout: None, persistent: false };
+        self.notify(protocol_states, notification);
+    }
+
+    /// Show a local notification which stays open until it is dismissed.
+    ///
+    /// Returns the notification's ID, which can be used to update or close it.
+    pub fn notify_local_persistent(
+        &mut self,
+        protocol_states: &ProtocolStates,
+        summary: String,
+        body: String,
+    ) -> u32 {
+        let id = self.local_id();
+        let notification = Notification { id, summary, body, timeout: None, persistent: true };
+        self.notify(protocol_states, notification);
+        id
+    }
+
+    /// Allocate an ID for a local notification.
+    fn local_id(&mut self) -> u32 {
+        let id = self.next_local_id;
+        self.next_local_id -= 1;
+        if self.next_local_id <= LOCAL_ID_MIN {
+            self.next_local_id = u32::MAX;
+        }
+        id
+    }
+
+    /// Check if a notification is visible or queued.
+    pub fn is_open(&self, id: u32) -> bool {
+        self.popup_index(id).is_some() || self.pending.iter().any(|pending| pending.id == id)
+    }
+
+    /// Close a notification.
+    pub fn close(&mut self, protocol_states: &ProtocolStates, id: u32, reason: CloseReason) {
+        if let Some(index) = self.pending.iter().position(|pending| pending.id == id) {
+            self.pending.remove(index);
+        } else if let Some(index) = self.popup_index(id) {
+            let popup = self.popups.remove(index);
+            if let Some(expiry) = popup.expiry {
+                self.event_loop.remove(expiry);
+            }
+
+            // Reset swipe if the touched popup was closed.
+            if self.touch.as_ref().is_some_and(|touch| touch.notification_id == id) {
+                self.touch = None;
+            }
+
+            // Fill the space with queued notifications.
+            self.show_pending(protocol_states);
+        } else {
+            return;
+        }
+
+        self.report_closed(id, reason);
+    }
+
+    /// Notify DBus clients that one of their notifications was closed.
+    fn report_closed(&self, id: u32, reason: CloseReason) {
+        if id <= LOCAL_ID_MIN {
+            self.closer.closed(id, reason);
+        }
+    }
+
+    /// Get notification ID of the popup owning this surface.
+    pub fn owns_surface(&self, surface: &WlSurface) -> Option<u32> {
+        let popup = self.popups.iter().find(|popup| popup.surface.owns_surface(surface))?;
+        Some(popup.notification.id)
+    }
+
+    /// Render the popup owning this surface.
+    pub fn draw(&mut self, surface: &WlSurface, theme: &Theme) -> Result<()> {
+        match self.popup_mut(surface) {
+            Some(popup) => popup.draw(theme),
+            None => Ok(()),
+        }
+    }
+
+    /// Update the DPI scale factor of a popup.
+    pub fn set_scale_factor(&mut self, surface: &WlSurface, scale_factor: f64) {
+        if let Some(popup) = self.popup_mut(surface) {
+            popup.surface.set_scale_factor(scale_factor);
+        }
+    }
+
+    /// Reconfigure a popup window.
+    pub fn reconfigure(&mut self, surface: &WlSurface, configure: LayerSurfaceConfigure) {
+        if let Some(popup) = self.popup_mut(surface) {
+            popup.surface.reconfigure(configure, None);
+        }
+    }
+
+    /// Handle touch press events.
+    pub fn touch_down(&mut self, notification_id: u32, touch_id: i32, position: (f64, f64)) {
+        let index = match self.popup_index(notification_id) {
+            Some(index) if self.touch.is_none() => index,
+            _ => return,
+        };
+
+        // Keep the popup around while it's touched.
+        if let Some(expiry) = self.popups[index].expiry.take() {
+            self.event_loop.remove(expiry);
+        }
+
+        let start_x = position.0;
+        self.touch = Some(PopupTouch { notification_id, touch_id, start_x, offset: 0. });
+    }
+
+    /// Handle touch motion events.
+    ///
+    /// Returns `true` if the touch belongs to a popup.
+    pub fn touch_motion(&mut self, touch_id: i32, position: (f64, f64)) -> bool {
+        let touch = match &mut self.touch {
+            Some(touch) if touch.touch_id == touch_id => touch,
+            _ => return false,
+        };
+        touch.offset = position.0 - touch.start_x;
+
+        // Keep popups in place with reduced motion, dismissing them on release.
+        let (notification_id, offset) = (touch.notification_id, touch.offset);
+        if let Some(index) = self.popup_index(notification_id).filter(|_| !self.reduced_motion) {
+            let popup = &mut self.popups[index];
+            popup.offset = offset;
+            popup.surface.request_frame();
+        }
+
+        true
+    }
+
+    /// Handle touch release events.
+    ///
+    /// Returns `true` if the touch belongs to a popup.
+    pub fn touch_up(&mut self, protocol_states: &ProtocolStates, touch_id: i32) -> bool {
+        let touch = match self.touch.take() {
+            Some(touch) if touch.touch_id == touch_id => touch,
+            touch => {
+                self.touch = touch;
+                return false;
+            },
+        };
+
+        let index = match self.popup_index(touch.notification_id) {
+            Some(index) => index,
+            None => return true,
+        };
+
+        // Dismiss popup once swiped far enough, otherwise snap it back.
+        if touch.offset.abs() >= DISMISS_DISTANCE {
+            self.close(protocol_states, touch.notification_id, CloseReason::Dismissed);
+        } else {
+            self.popups[index].offset = 0.;
+            self.popups[index].surface.request_frame();
+            self.stage_expiry(index);
+        }
+
+        true
+    }
+
+    /// Handle touch cancellation.
+    ///
+    /// This snaps the swiped popup back into place.
+    pub fn touch_cancel(&mut self) {
+        let touch = match self.touch.take() {
+            Some(touch) => touch,
+            None => return,
+        };
+
+        if let Some(index) = self.popup_index(touch.notification_id) {
+            self.popups[index].offset = 0.;
+            self.popups[index].surface.request_frame();
+            self.stage_expiry(index);
+        }
+    }
+
+    /// Create popups for queued notifications while there's space available.
+    fn show_pending(&mut self, protocol_states: &ProtocolStates) {
+        while self.popups.len() < MAX_VISIBLE {
+            let notification = match self.pending.pop_front() {
+                Some(notification) => notification,
+                None => break,
+            };
+
+            let id = notification.id;
+            match Popup::new(
+                protocol_states,
+                self.queue.clone(),
+                self.renderer.clone(),
+                notification,
+            ) {
+                Ok(popup) => {
+                    self.popups.push(popup);
+                    self.stage_expiry(self.popups.len() - 1);
+                },
+                Err(err) => {
+                    error!(module = "notifications", "Could not create notification popup: {err}");
+                    self.report_closed(id, CloseReason::Dismissed);
+                },
+            }
+        }
+
+        self.layout();
+    }
+
+    /// Stack all visible popups below each other.
+    fn layout(&mut self) {
+        for (i, popup) in self.popups.iter().enumerate() {
+            let window = match popup.surface.window() {
+                Some(window) => window,
+                None => continue,
+            };
+
+            let top = POPUP_MARGIN + i as i32 * (POPUP_HEIGHT as i32 + POPUP_MARGIN);
+            window.set_margin(top, POPUP_MARGIN, 0, POPUP_MARGIN);
+            window.commit();
+        }
+    }
+
+    /// Restart the expiry timer of a popup.
+    fn stage_expiry(&mut self, index: usize) {
+        let popup = &mut self.popups[index];
+
+        if let Some(expiry) = popup.expiry.take() {
+            self.event_loop.remove(expiry);
+        }
+
+        if popup.notification.persistent {
+            return;
+        }
+
+        let id = popup.notification.id;
+        let timeout = popup.notification.timeout.unwrap_or(DEFAULT_TIMEOUT);
+        let timer = Timer::from_duration(timeout);
+        let expiry = self.event_loop.insert_source(timer, move |_, _, state| {
+            let protocol_states = &state.protocol_states;
+            let notifications = state.notifications.as_mut().unwrap();
+
+            // Clear token, since the source is dropped automatically.
+            if let Some(index) = notifications.popup_index(id) {
+                notifications.popups[index].expiry = None;
+            }
+            notifications.close(protocol_states, id, CloseReason::Expired);
+
+            TimeoutAction::Drop
+        });
+        popup.expiry = expiry.ok();
+    }
+
+    /// Get index of a visible notification.
+    fn popup_index(&self, id: u32) -> Option<usize> {
+        self.popups.iter().position(|popup| popup.notification.id == id)
+    }
+
+    /// Get the popup owning this surface.
+    fn popup_mut(&mut self, surface: &WlSurface) -> Option<&mut Popup> {
+        self.popups.iter_mut().find(|popup| popup.surface.owns_surface(surface))
+    }
+}
+
+/// Notification popup window.
+struct Popup {
+    notification: Notification,
+    expiry: Option<RegistrationToken>,
+    surface: WindowSurface,
+
+    /// Horizontal swipe offset.
+    offset: f64,
+}
+
+impl Popup {
+    fn new(
+        protocol_states: &ProtocolStates,
+        queue: QueueHandle<State>,
+        renderer: Rc<RefCell<Renderer>>,
+        notification: Notification,
+    ) -> Result<Self> {
+        let mut surface = WindowSurface::new(queue.clone(), renderer);
+
+        // Create the window.
+        let wl_surface = protocol_states.compositor.create_surface(&queue);
+        let window = protocol_states.layer.create_layer_surface(
+            &queue,
+            wl_surface,
+            Layer::Overlay,
+            Some("notification"),
+            None,
+        );
+        window.set_anchor(Anchor::LEFT | Anchor::TOP | Anchor::RIGHT);
+        window.set_size(0, POPUP_HEIGHT);
+        surface.show(&protocol_states.fractional_scale, &protocol_states.viewporter, window);
+
+        Ok(Self { notification, surface, expiry: Default::default(), offset: Default::default() })
+    }
+
+    /// Render the popup.
+    fn draw(&mut self, theme: &Theme) -> Result<()> {
+        self.surface.frame_done();
+
+        let scale_factor = self.surface.scale_factor();
+        let size = self.surface.size();
+        let offset = geometry::to_physical(self.offset, scale_factor);
+        let summary = &self.notification.summary;
+        let body = &self.notification.body;
+
+        self.surface.draw(|renderer| unsafe {
+            // Transparently clear entire window.
+            gl::Disable(gl::SCISSOR_TEST);
+            gl::Viewport(0, 0, size.width, size.height);
+            gl::ClearColor(0.0, 0.0, 0.0, 0.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+
+            // Setup popup to render at its swipe offset.
+            gl::Enable(gl::SCISSOR_TEST);
+            gl::Scissor(offset, 0, size.width, size.height);
+            gl::Viewport(offset, 0, size.width, size.height);
+
+            // Draw popup background.
+            theme.set_clear_color();
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+
+            // Layout summary and body below each other.
+            let padding = geometry::to_physical(TEXT_PADDING, scale_factor) as i16;
+            let max_width = size.width as i16 - 2 * padding;
+            let rasterizer = &mut renderer.rasterizer;
+            let mut lines = rasterizer.layout_string(summary, max_width, SUMMARY_LINES);
+            lines.append(&mut rasterizer.layout_string(body, max_width, BODY_LINES));
+
+            // Batch all text lines.
+            let metrics = renderer.rasterizer.metrics()?;
+            let baseline = (metrics.line_height + metrics.descent as f64) as i16;
+            for (i, line) in lines.iter().enumerate() {
+                let y = padding + (i as f64 * metrics.line_height) as i16 + baseline;
+                let mut x = padding;
+                for glyph in line {
+                    if let Some(quad) = glyph.quad(x, y) {
+                        renderer.text_batcher.push(glyph.texture_id, quad);
+                    }
+                    x += glyph.advance.0 as i16;
+                }
+            }
+
+            // Draw batched text.
+            renderer.rasterizer.flush_uploads();
+            let mut batches = renderer.text_batcher.batches();
+            while let Some(batch) = batches.next() {
+                batch.draw();
+            }
+
+            Ok(())
+        })
+    }
+}
+
+/// Active popup swipe.
+struct PopupTouch {
+    notification_id: u32,
+    touch_id: i32,
+    start_x: f64,
+    offset: f64,
+}
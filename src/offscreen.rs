@@ -0,0 +1,301 @@
+//! Offscreen rendering without a Wayland compositor.
+
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::fs;
+use std::path::Path;
+
+use glutin::api::egl::device::Device;
+use glutin::api::egl::display::Display;
+use glutin::config::{ConfigSurfaceTypes, ConfigTemplateBuilder};
+use glutin::prelude::*;
+use resvg::tiny_skia::{IntSize, Pixmap};
+
+use crate::cli::ScreenshotOptions;
+use crate::config::Config;
+use crate::drawer::Drawer;
+use crate::module::{
+    Alignment, DrawerModule, Module, ModuleGroup, PanelModule, PanelModuleContent, Slider, Toggle,
+};
+use crate::panel::Panel;
+use crate::renderer::{self, Renderer};
+use crate::text::Svg;
+use crate::theme::Theme;
+use crate::{geometry, gl, Result, Size};
+
+/// Render the panel and drawer into PNG files.
+///
+/// This writes `panel.png` and `drawer.png` into the output directory, using
+/// fixed module states to produce reproducible images.
+pub fn screenshot(options: &ScreenshotOptions, config: &Config) -> Result<()> {
+    let mut renderer = create_renderer(options.scale_factor, config)?;
+    render(&mut renderer, options, config)
+}
+
+/// Render the fixed modules with an existing renderer.
+fn render(renderer: &mut Renderer, options: &ScreenshotOptions, config: &Config) -> Result<()> {
+    let mut modules = fixed_modules();
+    let theme = Theme::select(false);
+
+    fs::create_dir_all(&options.dir)?;
+
+    // Render the panel by itself.
+    let scale_factor = options.scale_factor;
+    let panel_height = geometry::to_physical(config.panel.height, scale_factor);
+    renderer.set_offscreen(Size::new(options.size.width, panel_height), scale_factor)?;
+    renderer.draw(|renderer| unsafe {
+        theme.set_clear_color();
+        gl::Clear(gl::COLOR_BUFFER_BIT);
+
+        Panel::draw_modules(renderer, &modules, renderer.size, 1., &config.panel, None)?;
+
+        Ok(())
+    })?;
+    write_png(renderer, &options.dir.join("panel.png"))?;
+
+    // Render the fully opened drawer below the panel.
+    renderer.set_offscreen(options.size, scale_factor)?;
+    renderer.draw(|renderer| unsafe {
+        theme.set_clear_color();
+        gl::Clear(gl::COLOR_BUFFER_BIT);
+
+        let panel_size = Size::new(renderer.size.width, panel_height as f32);
+        Panel::draw_modules(renderer, &modules, panel_size, 1., &config.panel, None)?;
+
+        Drawer::draw_modules(
+            renderer,
+            &mut modules,
+            &config.drawer,
+            theme,
+            config.panel.height,
+            &HashMap::new(),
+        );
+
+        Ok(())
+    })?;
+    write_png(renderer, &options.dir.join("drawer.png"))?;
+
+    Ok(())
+}
+
+/// Modules with fixed states, independent of the system's hardware.
+fn fixed_modules() -> Vec<Box<dyn Module>> {
+    vec![
+        Box::new(FixedPanel {
+            name: "clock",
+            alignment: Alignment::Center,
+            content: || PanelModuleContent::Text("12:34".into()),
+        }),
+        Box::new(FixedPanel {
+            name: "cellular",
+            alignment: Alignment::Right,
+            content: || PanelModuleContent::Svg(Svg::Cellular80),
+        }),
+        Box::new(FixedPanel {
+            name: "wifi",
+            alignment: Alignment::Right,
+            content: || PanelModuleContent::Svg(Svg::WifiConnected75),
+        }),
+        Box::new(FixedPanel {
+            name: "battery",
+            alignment: Alignment::Right,
+            content: || PanelModuleContent::Svg(Svg::Battery80),
+        }),
+        Box::new(FixedSlider { svg: Svg::Brightness, value: 0.6 }),
+        Box::new(FixedToggle {
+            title: "WiFi",
+            svg: Svg::WifiConnected100,
+            group: ModuleGroup::Connectivity,
+            enabled: true,
+        }),
+        Box::new(FixedToggle {
+            title: "Cellular",
+            svg: Svg::Cellular100,
+            group: ModuleGroup::Connectivity,
+            enabled: false,
+        }),
+        Box::new(FixedToggle {
+            title: "Flashlight",
+            svg: Svg::FlashlightOff,
+            group: ModuleGroup::System,
+            enabled: false,
+        }),
+        Box::new(FixedToggle {
+            title: "Orientation",
+            svg: Svg::OrientationLocked,
+            group: ModuleGroup::Display,
+            enabled: true,
+        }),
+    ]
+}
+
+/// Panel module with fixed content.
+struct FixedPanel {
+    name: &'static str,
+    alignment: Alignment,
+    content: fn() -> PanelModuleContent,
+}
+
+impl Module for FixedPanel {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        Some(self)
+    }
+}
+
+impl PanelModule for FixedPanel {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn alignment(&self) -> Alignment {
+        self.alignment
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        (self.content)()
+    }
+}
+
+/// Drawer slider with a fixed value.
+struct FixedSlider {
+    svg: Svg,
+    value: f64,
+}
+
+impl Module for FixedSlider {
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::Slider(self))
+    }
+
+    fn group(&self) -> ModuleGroup {
+        ModuleGroup::Display
+    }
+}
+
+impl Slider for FixedSlider {
+    fn set_value(&mut self, _value: f64) -> Result<()> {
+        Ok(())
+    }
+
+    fn get_value(&self) -> f64 {
+        self.value
+    }
+
+    fn svg(&self) -> Svg {
+        self.svg
+    }
+}
+
+/// Drawer toggle with a fixed state.
+struct FixedToggle {
+    title: &'static str,
+    svg: Svg,
+    group: ModuleGroup,
+    enabled: bool,
+}
+
+impl Module for FixedToggle {
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::Toggle(self))
+    }
+
+    fn group(&self) -> ModuleGroup {
+        self.group
+    }
+}
+
+impl Toggle for FixedToggle {
+    fn set_enabled(&mut self, _enabled: bool) -> Result<()> {
+        Ok(())
+    }
+
+    fn svg(&self) -> Svg {
+        self.svg
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn title(&self) -> &'static str {
+        self.title
+    }
+}
+
+/// Create a renderer on a surfaceless EGL display.
+fn create_renderer(scale_factor: f64, config: &Config) -> Result<Renderer> {
+    let device = Device::query_devices()?.next().ok_or("no EGL device found")?;
+    let display = unsafe { Display::with_device(&device, None)? };
+
+    let template = ConfigTemplateBuilder::new()
+        .with_alpha_size(8)
+        .with_stencil_size(0)
+        .with_depth_size(0)
+        .with_surface_type(ConfigSurfaceTypes::empty())
+        .build();
+    let egl_config =
+        unsafe { display.find_configs(template)?.next().ok_or("no suitable EGL configs found")? };
+
+    // Load the OpenGL symbols.
+    gl::load_with(|symbol| {
+        let symbol = CString::new(symbol).unwrap();
+        display.get_proc_address(symbol.as_c_str()).cast()
+    });
+
+    let egl_context = renderer::create_context(&display, &egl_config)?;
+
+    let font = config.panel.font.clone();
+    Renderer::new(egl_context, scale_factor, font, config.text.dual_source_blending)
+}
+
+/// Write the renderer's framebuffer to a PNG file.
+fn write_png(renderer: &Renderer, path: &Path) -> Result<()> {
+    let pixels = renderer.read_pixels()?;
+
+    let size = renderer.size;
+    let size = IntSize::from_wh(size.width as u32, size.height as u32).ok_or("invalid size")?;
+    let pixmap = Pixmap::from_vec(pixels, size).ok_or("invalid framebuffer")?;
+    pixmap.save_png(path)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn reproducible_screenshots() {
+        let config = Config::default();
+        let dir = env::temp_dir().join(format!("epitaph-screenshot-{}", std::process::id()));
+        let options =
+            ScreenshotOptions { dir: dir.clone(), size: Size::new(360, 720), scale_factor: 2. };
+
+        // Offscreen rendering requires an EGL device.
+        let mut renderer = match create_renderer(options.scale_factor, &config) {
+            Ok(renderer) => renderer,
+            Err(err) => {
+                eprintln!("Skipping offscreen rendering test: {err}");
+                return;
+            },
+        };
+
+        render(&mut renderer, &options, &config).unwrap();
+        let panel = fs::read(dir.join("panel.png")).unwrap();
+        let drawer = fs::read(dir.join("drawer.png")).unwrap();
+
+        let panel_image = Pixmap::decode_png(&panel).unwrap();
+        assert_eq!((panel_image.width(), panel_image.height()), (360, 40));
+        let drawer_image = Pixmap::decode_png(&drawer).unwrap();
+        assert_eq!((drawer_image.width(), drawer_image.height()), (360, 720));
+
+        // Rendering the same modules again must produce identical images.
+        render(&mut renderer, &options, &config).unwrap();
+        assert_eq!(fs::read(dir.join("panel.png")).unwrap(), panel);
+        assert_eq!(fs::read(dir.join("drawer.png")).unwrap(), drawer);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
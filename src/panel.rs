@@ -1,192 +1,492 @@
 //! Panel window state.
 
-use std::num::NonZeroU32;
-use std::ptr::NonNull;
-
-use crossfont::Metrics;
-use glutin::api::egl::config::Config;
-use glutin::context::{ContextApi, ContextAttributesBuilder, Version};
-use glutin::display::GetGlDisplay;
-use glutin::prelude::*;
-use glutin::surface::{SurfaceAttributesBuilder, WindowSurface};
-use raw_window_handle::{RawWindowHandle, WaylandWindowHandle};
-use smithay_client_toolkit::compositor::{CompositorState, Region};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::ops::Range;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use smithay_client_toolkit::compositor::CompositorState;
+use smithay_client_toolkit::reexports::client::protocol::wl_output::WlOutput;
 use smithay_client_toolkit::reexports::client::protocol::wl_surface::WlSurface;
-use smithay_client_toolkit::reexports::client::{Proxy, QueueHandle};
-use smithay_client_toolkit::reexports::protocols::wp::viewporter::client::wp_viewport::WpViewport;
-use smithay_client_toolkit::shell::wlr_layer::{
-    Anchor, Layer, LayerShell, LayerSurface, LayerSurfaceConfigure,
-};
+use smithay_client_toolkit::reexports::client::QueueHandle;
+use smithay_client_toolkit::shell::wlr_layer::{Anchor, Layer, LayerSurfaceConfigure};
 use smithay_client_toolkit::shell::WaylandSurface;
 
-use crate::module::{Alignment, Module, PanelModuleContent};
+use crate::config::{FontConfig, PanelConfig};
+use crate::module::{Alignment, ModuleList, PanelModule, PanelModuleContent};
 use crate::protocols::fractional_scale::FractionalScaleManager;
-use crate::protocols::viewporter::Viewporter;
 use crate::renderer::{Renderer, TextRenderer};
+use crate::surface::WindowSurface;
 use crate::text::{GlRasterizer, Svg};
+use crate::theme::Theme;
 use crate::vertex::VertexBatcher;
-use crate::{gl, Result, Size, State};
-
-/// Panel height in pixels with a scale factor of 1.
-pub const PANEL_HEIGHT: i32 = 20;
+use crate::{geometry, gl, ProtocolStates, Result, Size, State};
 
 /// Panel SVG width.
 const MODULE_WIDTH: u32 = 20;
 
-/// Padding between panel modules.
-const MODULE_PADDING: f64 = 5.;
+/// Minimum panel SVG height, to keep icons readable.
+const MIN_ICON_HEIGHT: f64 = 8.;
+
+/// Duration of one attention pulse in seconds.
+const ATTENTION_PERIOD: f64 = 1.5;
+
+/// Minimum opacity of modules requesting attention.
+const ATTENTION_MIN_ALPHA: f64 = 0.25;
+
+/// Opacity of modules with stale content.
+const STALE_ALPHA: f32 = 0.5;
+
+/// Duration of the cross-fade between changing panel icons.
+const ICON_FADE_DURATION: Duration = Duration::from_millis(150);
 
-/// Panel padding to the screen edges.
-const EDGE_PADDING: f64 = 5.;
+/// Duration of the panel flash confirming gesture actions.
+const FLASH_DURATION: Duration = Duration::from_millis(250);
+
+/// Background brightness at the start of the panel flash.
+const FLASH_BRIGHTNESS: f32 = 0.5;
+
+/// Indicator for modules hidden due to lack of space.
+const OVERFLOW_INDICATOR: &str = "…";
 
 pub struct Panel {
-    queue: QueueHandle<State>,
-    viewport: WpViewport,
-    window: LayerSurface,
-    frame_pending: bool,
-    renderer: Renderer,
-    scale_factor: f64,
-    size: Size,
+    /// Module indices and their horizontal extent in logical coordinates.
+    module_bounds: Vec<(usize, Range<f64>)>,
+
+    /// Last drawn frame of every time-based module animation.
+    animation_frames: Vec<usize>,
+
+    /// Panel content changed since the last draw.
+    damaged: bool,
+
+    animation_start: Instant,
+    flash_start: Option<Instant>,
+    icon_fades: IconFades,
+    output: Option<WlOutput>,
+    surface: WindowSurface,
+    config: PanelConfig,
+    concealed: bool,
+    reduced_motion: bool,
 }
 
 impl Panel {
     pub fn new(
-        fractional_scale: &FractionalScaleManager,
-        compositor: &CompositorState,
-        viewporter: &Viewporter,
+        protocol_states: &ProtocolStates,
         queue: QueueHandle<State>,
-        layer: &LayerShell,
-        egl_config: &Config,
+        renderer: Rc<RefCell<Renderer>>,
+        config: &PanelConfig,
+        output: Option<WlOutput>,
+        mapped: bool,
+        reduced_motion: bool,
     ) -> Result<Self> {
-        // Default to 1x1 initial size since 0x0 EGL surfaces are illegal.
-        let size = Size { width: 1, height: 1 };
+        let surface = WindowSurface::new(queue.clone(), renderer);
+
+        let mut panel = Self {
+            output,
+            config: config.clone(),
+            animation_start: Instant::now(),
+            animation_frames: Default::default(),
+            module_bounds: Default::default(),
+            damaged: true,
+            icon_fades: Default::default(),
+            flash_start: Default::default(),
+            concealed: Default::default(),
+            reduced_motion,
+            surface,
+        };
 
-        // Initialize EGL context.
-        let context_attribules = ContextAttributesBuilder::new()
-            .with_context_api(ContextApi::Gles(Some(Version::new(2, 0))))
-            .build(None);
+        // Skip window creation in drawer-only mode.
+        if !mapped {
+            return Ok(panel);
+        }
 
-        let egl_display = egl_config.display();
-        let egl_context = unsafe { egl_display.create_context(egl_config, &context_attribules)? };
+        // Create the window.
+        let wl_surface = protocol_states.compositor.create_surface(&queue);
+        let window = protocol_states.layer.create_layer_surface(
+            &queue,
+            wl_surface,
+            config.layer.into(),
+            Some("panel"),
+            panel.output.as_ref(),
+        );
+        window.set_anchor(Anchor::LEFT | Anchor::TOP | Anchor::RIGHT);
+        window.set_size(0, config.height);
+        window.set_exclusive_zone(panel.exclusive_zone());
+        let fractional_scale = &protocol_states.fractional_scale;
+        panel.surface.show(fractional_scale, &protocol_states.viewporter, window);
 
-        // Create the Wayland surface.
-        let surface = compositor.create_surface(&queue);
+        Ok(panel)
+    }
 
-        let window = NonNull::new(surface.id().as_ptr().cast()).unwrap();
-        let wayland_window_handle = WaylandWindowHandle::new(window);
-        let raw_window_handle = RawWindowHandle::Wayland(wayland_window_handle);
+    /// Render the panel.
+    pub fn draw(&mut self, modules: &impl ModuleList, theme: &Theme) -> Result<()> {
+        self.surface.frame_done();
 
-        // Create the EGL surface.
-        let surface_attributes = SurfaceAttributesBuilder::<WindowSurface>::new().build(
-            raw_window_handle,
-            NonZeroU32::new(size.width as u32).unwrap(),
-            NonZeroU32::new(size.height as u32).unwrap(),
-        );
+        // Keep requesting frames while modules are animated.
+        let animated = self.config.attention_pulse
+            && modules
+                .iter()
+                .filter_map(|module| module.panel_module())
+                .any(|module| module.attention());
+        let flash = self.flash_intensity();
+        let animation_frames: Vec<_> =
+            modules.iter().filter_map(|module| module.panel_module()?.animation_frame()).collect();
+
+        // Only redraw time-based animations once their frame changed.
+        let continuous = animated || flash > 0. || self.icon_fades.active();
+        if !self.damaged && !continuous && animation_frames == self.animation_frames {
+            return Ok(());
+        }
+        self.animation_frames = animation_frames;
+        self.damaged = false;
 
-        // Create the EGL surface.
-        let egl_surface =
-            unsafe { egl_config.display().create_window_surface(egl_config, &surface_attributes)? };
+        if animated || flash > 0. {
+            self.surface.queue_frame();
+        }
 
-        // Create the window.
-        let window =
-            layer.create_layer_surface(&queue, surface, Layer::Bottom, Some("panel"), None);
-        window.set_anchor(Anchor::LEFT | Anchor::TOP | Anchor::RIGHT);
-        window.set_size(0, PANEL_HEIGHT as u32);
-        window.set_exclusive_zone(PANEL_HEIGHT);
+        let attention_alpha = self.attention_alpha();
+        let background = theme.background.map(|c| c + (FLASH_BRIGHTNESS - c) * flash);
+        let mut icon_fades = self.config.icon_fade.then_some(&mut self.icon_fades);
+        let module_bounds = &mut self.module_bounds;
+        let config = &self.config;
+        self.surface.draw(|renderer| unsafe {
+            gl::ClearColor(background[0], background[1], background[2], 1.);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
 
-        // Initialize the renderer.
-        let mut renderer = Renderer::new(egl_context, 1.)?;
-        renderer.set_surface(Some(egl_surface));
+            *module_bounds = Self::draw_modules(
+                renderer,
+                modules,
+                renderer.size,
+                attention_alpha,
+                config,
+                icon_fades.as_deref_mut(),
+            )?;
+
+            Ok(())
+        })?;
+
+        // Continue drawing until all icon transitions are completed.
+        if self.icon_fades.active() {
+            self.surface.request_frame();
+        }
 
-        // Initialize fractional scaling protocol.
-        fractional_scale.fractional_scaling(&queue, window.wl_surface());
+        Ok(())
+    }
 
-        // Initialize viewporter protocol.
-        let viewport = viewporter.viewport(&queue, window.wl_surface());
+    /// Panel size in logical pixels.
+    pub fn logical_size(&self) -> Size {
+        self.surface.logical_size()
+    }
 
-        Ok(Self { viewport, renderer, window, queue, size, frame_pending: false, scale_factor: 1. })
+    /// Current DPI scale factor.
+    pub fn scale_factor(&self) -> f64 {
+        self.surface.scale_factor()
     }
 
-    /// Render the panel.
-    pub fn draw(&mut self, modules: &[&dyn Module]) -> Result<()> {
-        self.frame_pending = false;
+    /// Output the panel is shown on.
+    ///
+    /// This is `None` if the compositor picked the output.
+    pub fn output(&self) -> Option<&WlOutput> {
+        self.output.as_ref()
+    }
 
-        self.renderer.draw(|renderer| unsafe {
-            gl::Clear(gl::COLOR_BUFFER_BIT);
+    /// Check if the panel is shown on an output.
+    ///
+    /// This also covers the compositor's choice of output, once the panel
+    /// surface entered it.
+    pub fn on_output(&self, output: &WlOutput) -> bool {
+        self.output.as_ref() == Some(output) || self.surface.on_output(output)
+    }
 
-            Self::draw_modules(renderer, modules, renderer.size)
-        })
+    /// Update the outputs the panel surface is visible on.
+    pub fn set_entered(&mut self, output: &WlOutput, entered: bool) {
+        self.surface.set_entered(output, entered);
+    }
+
+    /// Get the index of the module at the specified X coordinate.
+    pub fn module_at(&self, x: f64) -> Option<usize> {
+        self.module_bounds.iter().find(|(_, bounds)| bounds.contains(&x)).map(|(index, _)| *index)
     }
 
     /// Render just the panel modules.
+    ///
+    /// Returns the indices of all visible modules, with their horizontal
+    /// extent in logical coordinates.
+    ///
+    /// Icon changes are cross-faded when `icon_fades` is provided.
     pub fn draw_modules(
         renderer: &mut Renderer,
-        modules: &[&dyn Module],
+        modules: &impl ModuleList,
         size: Size<f32>,
-    ) -> Result<()> {
+        attention_alpha: f32,
+        config: &PanelConfig,
+        mut icon_fades: Option<&mut IconFades>,
+    ) -> Result<Vec<(usize, Range<f64>)>> {
+        let scale_factor = renderer.scale_factor;
+        let mut module_bounds = Vec::new();
+
+        // Right modules may only use the space next to the center modules.
+        let mut center_end = 0;
+
         for alignment in [Alignment::Center, Alignment::Right] {
-            let mut run = PanelRun::new(renderer, size, alignment)?;
-            for module in modules
+            let mut run = PanelRun::new(renderer, size, alignment, config);
+
+            let mut panel_modules: Vec<_> = modules
                 .iter()
-                .filter_map(|module| module.panel_module())
-                .filter(|module| module.alignment() == alignment)
-            {
-                run.batch(module.content());
+                .enumerate()
+                .filter_map(|(index, module)| Some((index, module.panel_module()?)))
+                .filter(|(_, module)| module.alignment() == alignment && !module.hidden())
+                .map(|(index, module)| (index, module, module.content()))
+                .collect();
+
+            // Hide modules which do not fit into the panel.
+            let max_width = match alignment {
+                Alignment::Center => size.width as i16 - 2 * run.edge_padding(),
+                Alignment::Right => {
+                    size.width as i16 - run.edge_padding() - center_end - run.module_padding()
+                },
+            };
+            let overflowing = run.fit(&mut panel_modules, max_width, &config.priorities);
+            if overflowing {
+                let indicator = PanelModuleContent::Text(OVERFLOW_INDICATOR.into());
+                run.batch(indicator, &config.font, 1.)?;
+            }
+
+            let mut run_bounds = Vec::new();
+            for (index, module, content) in panel_modules {
+                let mut alpha = if module.attention() { attention_alpha } else { 1. };
+                if module.stale() {
+                    alpha *= STALE_ALPHA;
+                }
+
+                let fade =
+                    icon_fades.as_deref_mut().and_then(|fades| fades.update(index, &content));
+
+                let start = run.width;
+                match fade {
+                    Some((previous, progress)) => {
+                        run.batch_overlay(previous, alpha * (1. - progress))?;
+                        run.batch(content, config.font(module.name()), alpha * progress)?;
+                    },
+                    None => run.batch(content, config.font(module.name()), alpha)?,
+                }
+                run_bounds.push((index, start..run.width));
             }
-            run.draw();
+
+            let (x_offset, run_end) = run.draw();
+            center_end = run_end;
+
+            // Convert module bounds to logical coordinates.
+            module_bounds.extend(run_bounds.into_iter().map(|(index, bounds)| {
+                let start = geometry::to_logical(x_offset + bounds.start, scale_factor);
+                let end = geometry::to_logical(x_offset + bounds.end, scale_factor);
+                (index, start..end)
+            }));
         }
-        Ok(())
+
+        // Restore the default font for other text.
+        renderer.rasterizer.reset_font();
+
+        Ok(module_bounds)
     }
 
     /// Check if the panel owns this surface.
     pub fn owns_surface(&self, surface: &WlSurface) -> bool {
-        self.window.wl_surface() == surface
+        self.surface.owns_surface(surface)
     }
 
     /// Update the DPI scale factor.
     pub fn set_scale_factor(&mut self, compositor: &CompositorState, scale_factor: f64) {
-        let factor_change = scale_factor / self.scale_factor;
-        self.scale_factor = scale_factor;
+        self.damaged = true;
+        self.surface.set_scale_factor(scale_factor);
+        self.update_opaque_region(compositor);
+    }
 
-        self.resize(compositor, self.size * factor_change);
+    /// Resize and redraw the panel after its output changed.
+    ///
+    /// The `output_width` is the new logical width of the output, if known,
+    /// and `rescale` indicates a change of its mode, scale or transform.
+    pub fn output_changed(
+        &mut self,
+        fractional_scale: &FractionalScaleManager,
+        compositor: &CompositorState,
+        output_width: Option<i32>,
+        rescale: bool,
+    ) {
+        let size = output_width.map(|width| Size::new(width, self.height()));
+        self.surface.output_changed(fractional_scale, size, rescale);
+        self.update_opaque_region(compositor);
+        self.request_frame();
     }
 
     /// Reconfigure the window.
     pub fn reconfigure(&mut self, compositor: &CompositorState, configure: LayerSurfaceConfigure) {
-        // Update size.
-        let new_width = configure.new_size.0 as i32;
-        let size = Size::new(new_width, PANEL_HEIGHT) * self.scale_factor;
-        self.resize(compositor, size);
+        self.damaged = true;
+        self.surface.reconfigure(configure, Some(self.height()));
+        self.update_opaque_region(compositor);
     }
 
     /// Request a new frame.
     pub fn request_frame(&mut self) {
-        if self.frame_pending {
+        self.damaged = true;
+
+        // Avoid rendering while hidden behind fullscreen windows.
+        if !self.concealed {
+            self.surface.request_frame();
+        }
+    }
+
+    /// Hide the panel behind fullscreen windows.
+    ///
+    /// This moves the panel to the background layer, releases its exclusive
+    /// zone and stops redraws until the panel is revealed again.
+    pub fn set_concealed(&mut self, concealed: bool) {
+        if !self.config.auto_conceal || self.concealed == concealed {
             return;
         }
-        self.frame_pending = true;
+        self.concealed = concealed;
+
+        // Move the panel below the fullscreen window, since its content is
+        // not updated while concealed.
+        if let Some(window) = self.surface.window() {
+            let layer = if concealed { Layer::Background } else { self.config.layer.into() };
+            window.set_layer(layer);
+            window.set_exclusive_zone(self.exclusive_zone());
+            window.commit();
+        }
+
+        self.request_frame();
+    }
+
+    /// Check if the panel is hidden behind fullscreen windows.
+    pub fn concealed(&self) -> bool {
+        self.concealed
+    }
+
+    /// Configured panel height in logical pixels.
+    fn height(&self) -> i32 {
+        self.config.height as i32
+    }
+
+    /// Height of the space reserved for the panel.
+    pub fn exclusive_zone(&self) -> i32 {
+        if self.config.exclusive_zone && !self.concealed {
+            self.height()
+        } else {
+            0
+        }
+    }
+
+    /// Handle a frame callback from the compositor.
+    pub fn frame_received(&mut self) {
+        self.surface.frame_received();
+    }
+
+    /// Recreate the window's EGL surface if it stopped receiving frames.
+    pub fn recover_stall(&mut self) -> Option<WlSurface> {
+        // Frames are expected to be withheld while behind fullscreen windows.
+        if self.concealed {
+            return None;
+        }
 
-        let surface = self.window.wl_surface();
-        surface.frame(&self.queue, surface.clone());
-        surface.commit();
+        let surface = self.surface.recover_stall()?;
+        self.damaged = true;
+        Some(surface)
     }
 
-    /// Resize the window.
-    fn resize(&mut self, compositor: &CompositorState, size: Size) {
-        self.size = size;
+    /// Briefly flash the panel background.
+    pub fn flash(&mut self) {
+        self.flash_start = Some(Instant::now());
+        self.request_frame();
+    }
 
-        // Update viewporter buffer target size.
-        let logical_size = size / self.scale_factor;
-        self.viewport.set_destination(logical_size.width, logical_size.height);
+    /// Intensity of the panel flash at the current time.
+    fn flash_intensity(&mut self) -> f32 {
+        let elapsed = match self.flash_start {
+            Some(flash_start) => flash_start.elapsed(),
+            None => return 0.,
+        };
 
-        // Update opaque region.
-        if let Ok(region) = Region::new(compositor) {
-            region.add(0, 0, logical_size.width, logical_size.height);
-            self.window.wl_surface().set_opaque_region(Some(region.wl_region()));
+        if elapsed >= FLASH_DURATION {
+            self.flash_start = None;
+            return 0.;
         }
 
-        let scale_factor = self.scale_factor;
-        let _ = self.renderer.resize(size, scale_factor);
+        // Hold the full brightness, instead of fading out.
+        if self.reduced_motion {
+            return 1.;
+        }
+
+        1. - elapsed.as_secs_f32() / FLASH_DURATION.as_secs_f32()
+    }
+
+    /// Opacity of modules requesting attention at the current time.
+    fn attention_alpha(&self) -> f32 {
+        if !self.config.attention_pulse {
+            return 1.;
+        }
+
+        let elapsed = self.animation_start.elapsed().as_secs_f64();
+        let phase = (elapsed * 2. * PI / ATTENTION_PERIOD).cos() / 2. + 0.5;
+        (ATTENTION_MIN_ALPHA + (1. - ATTENTION_MIN_ALPHA) * phase) as f32
+    }
+
+    /// Mark the entire panel as opaque.
+    fn update_opaque_region(&self, compositor: &CompositorState) {
+        let logical_size = self.surface.logical_size();
+        self.surface.set_opaque_region(compositor, 0, 0, logical_size.width, logical_size.height);
+    }
+}
+
+/// Cross-fade state of panel module icons.
+#[derive(Default)]
+pub struct IconFades {
+    /// Last drawn icon of each module.
+    icons: HashMap<usize, Svg>,
+
+    /// Previous icon and transition start of modules which are fading.
+    fades: HashMap<usize, (Svg, Instant)>,
+}
+
+impl IconFades {
+    /// Update a module's content.
+    ///
+    /// Returns the previous icon and the progress of the transition to the
+    /// new one, while the module is fading.
+    fn update(&mut self, index: usize, content: &PanelModuleContent) -> Option<(Svg, f32)> {
+        let svg = match content {
+            PanelModuleContent::Svg(svg) => *svg,
+            // Fading composite modules would also fade their text.
+            PanelModuleContent::Text(_) | PanelModuleContent::Composite(..) => {
+                self.icons.remove(&index);
+                self.fades.remove(&index);
+                return None;
+            },
+        };
+
+        let now = Instant::now();
+        match self.icons.insert(index, svg) {
+            Some(previous) if previous != svg => {
+                self.fades.insert(index, (previous, now));
+            },
+            _ => (),
+        }
+
+        let (previous, start) = *self.fades.get(&index)?;
+        let progress = now.duration_since(start).as_secs_f32() / ICON_FADE_DURATION.as_secs_f32();
+        if progress >= 1. {
+            self.fades.remove(&index);
+            return None;
+        }
+
+        Some((previous, progress))
+    }
+
+    /// Check if any icon is still fading.
+    fn active(&self) -> bool {
+        !self.fades.is_empty()
     }
 }
 
@@ -194,101 +494,221 @@ impl Panel {
 struct PanelRun<'a> {
     batcher: &'a mut VertexBatcher<TextRenderer>,
     rasterizer: &'a mut GlRasterizer,
+    config: &'a PanelConfig,
     alignment: Alignment,
     scale_factor: f64,
-    metrics: Metrics,
+    module_padding: f64,
+    edge_padding: f64,
     size: Size<f32>,
     width: i16,
 }
 
 impl<'a> PanelRun<'a> {
-    fn new(renderer: &'a mut Renderer, size: Size<f32>, alignment: Alignment) -> Result<Self> {
-        Ok(Self {
+    fn new(
+        renderer: &'a mut Renderer,
+        size: Size<f32>,
+        alignment: Alignment,
+        config: &'a PanelConfig,
+    ) -> Self {
+        Self {
+            module_padding: config.module_padding(alignment),
+            edge_padding: config.edge_padding(alignment),
             alignment,
             size,
             scale_factor: renderer.scale_factor,
-            metrics: renderer.rasterizer.metrics()?,
             rasterizer: &mut renderer.rasterizer,
             batcher: &mut renderer.text_batcher,
+            config,
             width: 0,
-        })
+        }
     }
 
     /// Draw all modules in this run.
-    fn draw(mut self) {
+    ///
+    /// Returns the X coordinates of the run's left and right edge.
+    fn draw(mut self) -> (i16, i16) {
         // Trim last module padding.
         self.width = self.width.saturating_sub(self.module_padding());
 
-        // Determine vertex offset from left screen edge.
+        // Determine quad offset from left screen edge.
         let x_offset = match self.alignment {
             Alignment::Center => (self.size.width as i16 - self.width) / 2,
             Alignment::Right => self.size.width as i16 - self.width - self.edge_padding(),
         };
 
-        // Update vertex position based on text alignment.
-        for vertex in self.batcher.pending() {
-            vertex.x += x_offset;
+        // Update quad position based on text alignment.
+        for quad in self.batcher.pending() {
+            quad.x += x_offset;
         }
 
-        // Draw all batched vertices.
+        // Draw all batched quads.
+        self.rasterizer.flush_uploads();
         let mut batches = self.batcher.batches();
         while let Some(batch) = batches.next() {
             batch.draw();
         }
+
+        (x_offset, x_offset + self.width)
+    }
+
+    /// Hide the lowest priority modules until the run fits into `max_width`.
+    ///
+    /// Returns `true` if any module was hidden.
+    fn fit(
+        &mut self,
+        modules: &mut Vec<(usize, &dyn PanelModule, PanelModuleContent)>,
+        max_width: i16,
+        priorities: &HashMap<String, u8>,
+    ) -> bool {
+        let config = self.config;
+        let mut widths: Vec<_> = modules
+            .iter()
+            .map(|(_, module, content)| self.measure(content, config.font(module.name())))
+            .collect();
+        let mut width = widths.iter().sum::<i16>() - self.module_padding();
+
+        // Reserve space for the overflow indicator once the first module is hidden.
+        let indicator = PanelModuleContent::Text(OVERFLOW_INDICATOR.into());
+        let indicator_width = self.measure(&indicator, &config.font);
+
+        let mut overflowing = false;
+        while width > max_width && !modules.is_empty() {
+            if !overflowing {
+                width += indicator_width;
+                overflowing = true;
+            }
+
+            // Remove the leftmost module with the lowest priority.
+            let index = (0..modules.len())
+                .min_by_key(|i| {
+                    let module = modules[*i].1;
+                    priorities.get(module.name()).copied().unwrap_or_else(|| module.priority())
+                })
+                .unwrap();
+            modules.remove(index);
+            width -= widths.remove(index);
+        }
+
+        overflowing
+    }
+
+    /// Width of a module, including its padding.
+    fn measure(&mut self, content: &PanelModuleContent, font: &FontConfig) -> i16 {
+        let width = match content {
+            PanelModuleContent::Text(text) => self.measure_string(text, font),
+            PanelModuleContent::Svg(svg) => self.measure_svg(*svg),
+            PanelModuleContent::Composite(svg, text) => {
+                self.measure_svg(*svg) + self.measure_string(text, font)
+            },
+        };
+
+        width + self.module_padding()
+    }
+
+    /// Width of a text, without padding.
+    fn measure_string(&mut self, text: &str, font: &FontConfig) -> i16 {
+        self.rasterizer.set_font(font);
+        self.rasterizer.rasterize_string(text).map(|glyph| glyph.advance.0 as i16).sum()
+    }
+
+    /// Width of an SVG icon, without padding.
+    fn measure_svg(&mut self, svg: Svg) -> i16 {
+        let width = self.icon_width(svg);
+        self.rasterizer.rasterize_svg(svg, width, None).map_or(0, |svg| svg.advance.0 as i16)
     }
 
     /// Add a panel module to the run.
-    fn batch(&mut self, module: PanelModuleContent) {
+    fn batch(&mut self, module: PanelModuleContent, font: &FontConfig, alpha: f32) -> Result<()> {
+        let quad_start = self.batcher.pending().len();
+
         match module {
-            PanelModuleContent::Text(text) => self.batch_string(&text),
-            PanelModuleContent::Svg(svg) => {
-                let _ = self.batch_svg(svg);
+            PanelModuleContent::Text(text) => self.batch_string(&text, font)?,
+            PanelModuleContent::Svg(svg) => self.batch_svg(svg)?,
+            PanelModuleContent::Composite(svg, text) => {
+                self.batch_svg(svg)?;
+                self.batch_string(&text, font)?;
             },
         }
+
+        self.width += self.module_padding();
+
+        // Apply module opacity.
+        for quad in &mut self.batcher.pending()[quad_start..] {
+            quad.alpha = alpha;
+        }
+
+        Ok(())
     }
 
-    /// Add text module to this run.
-    fn batch_string(&mut self, text: &str) {
+    /// Add an SVG on top of the next module, without reserving space for it.
+    fn batch_overlay(&mut self, svg: Svg, alpha: f32) -> Result<()> {
+        let width = self.width;
+        let config = self.config;
+        self.batch(PanelModuleContent::Svg(svg), &config.font, alpha)?;
+        self.width = width;
+        Ok(())
+    }
+
+    /// Add text to this run, without padding.
+    fn batch_string(&mut self, text: &str, font: &FontConfig) -> Result<()> {
+        self.rasterizer.set_font(font);
+
         // Calculate Y to center text.
-        let y = ((self.size.height as f64 - self.metrics.line_height) / 2.
-            + (self.metrics.line_height + self.metrics.descent as f64)) as i16;
+        let metrics = self.rasterizer.metrics()?;
+        let y = ((self.size.height as f64 - metrics.line_height) / 2.
+            + (metrics.line_height + metrics.descent as f64)) as i16;
 
-        // Batch vertices for all glyphs.
+        // Batch quads for all glyphs.
         for glyph in self.rasterizer.rasterize_string(text) {
-            for vertex in glyph.vertices(self.width, y).into_iter().flatten() {
-                self.batcher.push(glyph.texture_id, vertex);
+            if let Some(quad) = glyph.quad(self.width, y) {
+                self.batcher.push(glyph.texture_id, quad);
             }
 
             self.width += glyph.advance.0 as i16;
         }
 
-        self.width += self.module_padding();
+        Ok(())
     }
 
-    /// Add SVG module to this run.
+    /// Add SVG to this run, without padding.
     fn batch_svg(&mut self, svg: Svg) -> Result<()> {
-        let svg = self.rasterizer.rasterize_svg(svg, MODULE_WIDTH, None)?;
+        let width = self.icon_width(svg);
+        let svg = self.rasterizer.rasterize_svg(svg, width, None)?;
 
         // Calculate Y to center SVG.
         let y = (self.size.height as i16 - svg.height) / 2;
 
-        for vertex in svg.vertices(self.width, y).into_iter().flatten() {
-            self.batcher.push(svg.texture_id, vertex);
+        if let Some(quad) = svg.quad(self.width, y) {
+            self.batcher.push(svg.texture_id, quad);
         }
         self.width += svg.advance.0 as i16;
 
-        self.width += self.module_padding();
-
         Ok(())
     }
 
+    /// Target width of an SVG icon.
+    ///
+    /// Icons are shrunk while preserving their aspect ratio when they would
+    /// overflow the panel vertically at `MODULE_WIDTH`, but never below a
+    /// readable size.
+    fn icon_width(&self, svg: Svg) -> u32 {
+        let (width, height) = svg.size();
+        let aspect_ratio = width as f64 / height as f64;
+
+        let panel_height = geometry::to_logical(self.size.height, self.scale_factor);
+        let max_height = panel_height.max(MIN_ICON_HEIGHT);
+        let max_width = (max_height * aspect_ratio).floor() as u32;
+
+        MODULE_WIDTH.min(max_width)
+    }
+
     /// Module padding with scale factor applied.
     fn module_padding(&self) -> i16 {
-        (MODULE_PADDING * self.scale_factor).round() as i16
+        geometry::to_physical(self.module_padding, self.scale_factor) as i16
     }
 
     /// Edge padding with scale factor applied.
     fn edge_padding(&self) -> i16 {
-        (EDGE_PADDING * self.scale_factor).round() as i16
+        geometry::to_physical(self.edge_padding, self.scale_factor) as i16
     }
 }
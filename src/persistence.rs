@@ -0,0 +1,224 @@
+//! State persisted across restarts.
+
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+use std::{env, fs, mem};
+
+use calloop::timer::{TimeoutAction, Timer};
+use calloop::LoopHandle;
+use tracing::error;
+
+use crate::State;
+
+/// Name of the persistent state file.
+const STATE_FILE: &str = "state";
+
+/// Delay before cached module status is written to disk.
+const STATUS_CACHE_DELAY: Duration = Duration::from_secs(60);
+
+/// Pending modification of the persistent state.
+type Update = Box<dyn FnOnce(&mut PersistentState)>;
+
+/// State persisted across restarts.
+#[derive(Default, Debug)]
+pub struct PersistentState {
+    /// Desired cellular connectivity state.
+    pub cellular_enabled: Option<bool>,
+
+    /// Desired WiFi connectivity state.
+    pub wifi_enabled: Option<bool>,
+
+    /// Last known battery capacity.
+    pub battery_capacity: Option<u8>,
+
+    /// Last known battery charging state.
+    pub battery_charging: Option<bool>,
+
+    /// Last known WiFi signal strength.
+    pub wifi_strength: Option<u8>,
+
+    /// Last known WiFi internet connectivity.
+    pub wifi_connected: Option<bool>,
+
+    /// Last known cellular signal strength.
+    pub cellular_strength: Option<u8>,
+
+    /// Last known cellular registration state.
+    pub cellular_registered: Option<bool>,
+
+    /// Charger input current limit before slow charging was enabled.
+    pub charge_current_limit: Option<u32>,
+
+    /// Boot during which the charger's input current limit was recorded.
+    pub charge_limit_boot_id: Option<String>,
+
+    /// Notification popups are suppressed.
+    pub do_not_disturb: Option<bool>,
+
+    /// Flashlight intensity selected while running on battery.
+    pub flashlight_battery_intensity: Option<f64>,
+
+    /// Flashlight intensity selected while charging.
+    pub flashlight_charging_intensity: Option<f64>,
+}
+
+impl PersistentState {
+    /// Load the persistent state from disk.
+    ///
+    /// Missing or malformed entries are ignored.
+    pub fn load() -> Self {
+        let mut state = Self::default();
+
+        let content = match state_path().and_then(|path| fs::read_to_string(path).ok()) {
+            Some(content) => content,
+            None => return state,
+        };
+
+        for line in content.lines() {
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => continue,
+            };
+
+            match key {
+                "cellular_enabled" => state.cellular_enabled = bool::from_str(value).ok(),
+                "wifi_enabled" => state.wifi_enabled = bool::from_str(value).ok(),
+                "battery_capacity" => state.battery_capacity = u8::from_str(value).ok(),
+                "battery_charging" => state.battery_charging = bool::from_str(value).ok(),
+                "wifi_strength" => state.wifi_strength = u8::from_str(value).ok(),
+                "wifi_connected" => state.wifi_connected = bool::from_str(value).ok(),
+                "cellular_strength" => state.cellular_strength = u8::from_str(value).ok(),
+                "cellular_registered" => state.cellular_registered = bool::from_str(value).ok(),
+                "charge_current_limit" => state.charge_current_limit = u32::from_str(value).ok(),
+                "charge_limit_boot_id" => state.charge_limit_boot_id = Some(value.into()),
+                "do_not_disturb" => state.do_not_disturb = bool::from_str(value).ok(),
+                "flashlight_battery_intensity" => {
+                    state.flashlight_battery_intensity = f64::from_str(value).ok()
+                },
+                "flashlight_charging_intensity" => {
+                    state.flashlight_charging_intensity = f64::from_str(value).ok()
+                },
+                _ => (),
+            }
+        }
+
+        state
+    }
+
+    /// Write the persistent state to disk.
+    pub fn save(&self) {
+        let path = match state_path() {
+            Some(path) => path,
+            None => return,
+        };
+
+        let entries = [
+            ("cellular_enabled", self.cellular_enabled.map(|value| value.to_string())),
+            ("wifi_enabled", self.wifi_enabled.map(|value| value.to_string())),
+            ("battery_capacity", self.battery_capacity.map(|value| value.to_string())),
+            ("battery_charging", self.battery_charging.map(|value| value.to_string())),
+            ("wifi_strength", self.wifi_strength.map(|value| value.to_string())),
+            ("wifi_connected", self.wifi_connected.map(|value| value.to_string())),
+            ("cellular_strength", self.cellular_strength.map(|value| value.to_string())),
+            ("cellular_registered", self.cellular_registered.map(|value| value.to_string())),
+            ("charge_current_limit", self.charge_current_limit.map(|value| value.to_string())),
+            ("charge_limit_boot_id", self.charge_limit_boot_id.clone()),
+            ("do_not_disturb", self.do_not_disturb.map(|value| value.to_string())),
+            (
+                "flashlight_battery_intensity",
+                self.flashlight_battery_intensity.map(|value| value.to_string()),
+            ),
+            (
+                "flashlight_charging_intensity",
+                self.flashlight_charging_intensity.map(|value| value.to_string()),
+            ),
+        ];
+
+        let mut content = String::new();
+        for (key, value) in entries {
+            if let Some(value) = value {
+                content.push_str(&format!("{key}={value}\n"));
+            }
+        }
+
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        if let Err(err) = fs::write(&path, content) {
+            error!(module = "persistence", "Could not write state file {path:?}: {err}");
+        }
+    }
+
+    /// Modify the state on disk.
+    pub fn update<F: FnOnce(&mut Self)>(fun: F) {
+        let mut state = Self::load();
+        fun(&mut state);
+        state.save();
+    }
+}
+
+/// Batched writer for frequently changing module status.
+///
+/// Updates are written to disk together after a delay, or once this is
+/// dropped on shutdown.
+pub struct StatusCache {
+    event_loop: LoopHandle<'static, State>,
+    pending: Vec<Update>,
+}
+
+impl StatusCache {
+    pub fn new(event_loop: &LoopHandle<'static, State>) -> Self {
+        Self { event_loop: event_loop.clone(), pending: Default::default() }
+    }
+
+    /// Modify the state on disk after a delay.
+    pub fn update<F: FnOnce(&mut PersistentState) + 'static>(&mut self, fun: F) {
+        self.pending.push(Box::new(fun));
+        if self.pending.len() > 1 {
+            return;
+        }
+
+        let timer = Timer::from_duration(STATUS_CACHE_DELAY);
+        let result = self.event_loop.insert_source(timer, |_, _, state| {
+            state.status_cache.flush();
+            TimeoutAction::Drop
+        });
+
+        // Write immediately if the update cannot be delayed.
+        if let Err(err) = result {
+            error!(module = "persistence", "Could not schedule status cache write: {err}");
+            self.flush();
+        }
+    }
+
+    /// Write all pending updates to disk.
+    pub fn flush(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        let pending = mem::take(&mut self.pending);
+        PersistentState::update(|state| {
+            for update in pending {
+                update(state);
+            }
+        });
+    }
+}
+
+impl Drop for StatusCache {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Location of the persistent state file.
+fn state_path() -> Option<PathBuf> {
+    let state_home = match env::var_os("XDG_STATE_HOME") {
+        Some(state_home) => PathBuf::from(state_home),
+        None => PathBuf::from(env::var_os("HOME")?).join(".local/state"),
+    };
+
+    Some(state_home.join("epitaph").join(STATE_FILE))
+}
@@ -0,0 +1,73 @@
+//! Handling of the cursor shape protocol.
+//!
+//! Protocol events are dispatched by SCTK's pointer delegate.
+
+use smithay_client_toolkit::globals::GlobalData;
+use smithay_client_toolkit::reexports::client::globals::{BindError, GlobalList};
+use smithay_client_toolkit::reexports::client::protocol::wl_pointer::WlPointer;
+use smithay_client_toolkit::reexports::client::QueueHandle;
+use smithay_client_toolkit::reexports::protocols::wp::cursor_shape::v1::client::wp_cursor_shape_device_v1::{
+    Shape, WpCursorShapeDeviceV1,
+};
+use smithay_client_toolkit::reexports::protocols::wp::cursor_shape::v1::client::wp_cursor_shape_manager_v1::WpCursorShapeManagerV1;
+
+use crate::State;
+
+/// Cursor shape manager.
+#[derive(Debug)]
+pub struct CursorShapeManager {
+    manager: WpCursorShapeManagerV1,
+}
+
+impl CursorShapeManager {
+    /// Create new cursor shape manager.
+    pub fn new(globals: &GlobalList, queue_handle: &QueueHandle<State>) -> Result<Self, BindError> {
+        let manager = globals.bind(queue_handle, 1..=1, GlobalData)?;
+        Ok(Self { manager })
+    }
+
+    /// Get the cursor shape device for a pointer.
+    pub fn device(
+        &self,
+        queue_handle: &QueueHandle<State>,
+        pointer: &WlPointer,
+    ) -> WpCursorShapeDeviceV1 {
+        self.manager.get_pointer(pointer, queue_handle, GlobalData)
+    }
+}
+
+/// Cursor shape of a pointer.
+#[derive(Debug)]
+pub struct PointerCursor {
+    device: WpCursorShapeDeviceV1,
+    shape: Option<Shape>,
+    serial: u32,
+}
+
+impl PointerCursor {
+    pub fn new(device: WpCursorShapeDeviceV1) -> Self {
+        Self { device, shape: None, serial: 0 }
+    }
+
+    /// Handle the pointer entering a surface.
+    ///
+    /// The cursor shape must be set again after every enter event.
+    pub fn enter(&mut self, serial: u32) {
+        self.serial = serial;
+        self.shape = None;
+    }
+
+    /// Update the cursor shape.
+    pub fn set_shape(&mut self, shape: Shape) {
+        if self.shape != Some(shape) {
+            self.device.set_shape(self.serial, shape);
+            self.shape = Some(shape);
+        }
+    }
+}
+
+impl Drop for PointerCursor {
+    fn drop(&mut self) {
+        self.device.destroy();
+    }
+}
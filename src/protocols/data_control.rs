@@ -0,0 +1,154 @@
+//! Handling of the wlr data control protocol.
+
+use std::fs::File;
+use std::io::Write;
+
+use smithay_client_toolkit::globals::GlobalData;
+use smithay_client_toolkit::reexports::client::globals::{BindError, GlobalList};
+use smithay_client_toolkit::reexports::client::protocol::wl_seat::WlSeat;
+use smithay_client_toolkit::reexports::client::{
+    delegate_dispatch, event_created_child, Connection, Dispatch, QueueHandle,
+};
+use smithay_client_toolkit::reexports::protocols_wlr::data_control::v1::client::zwlr_data_control_device_v1::{
+    self, Event as DeviceEvent, ZwlrDataControlDeviceV1,
+};
+use smithay_client_toolkit::reexports::protocols_wlr::data_control::v1::client::zwlr_data_control_manager_v1::{
+    self, ZwlrDataControlManagerV1,
+};
+use smithay_client_toolkit::reexports::protocols_wlr::data_control::v1::client::zwlr_data_control_offer_v1::{
+    self, ZwlrDataControlOfferV1,
+};
+use smithay_client_toolkit::reexports::protocols_wlr::data_control::v1::client::zwlr_data_control_source_v1::{
+    Event as SourceEvent, ZwlrDataControlSourceV1,
+};
+use tracing::error;
+
+use crate::State;
+
+/// MIME types offered for copied text.
+const TEXT_MIME_TYPES: [&str; 5] =
+    ["text/plain;charset=utf-8", "text/plain", "UTF8_STRING", "TEXT", "STRING"];
+
+/// Data control manager.
+#[derive(Debug)]
+pub struct DataControlManager {
+    manager: ZwlrDataControlManagerV1,
+    queue: QueueHandle<State>,
+    device: Option<ZwlrDataControlDeviceV1>,
+}
+
+impl DataControlManager {
+    /// Create new data control manager.
+    pub fn new(globals: &GlobalList, queue_handle: &QueueHandle<State>) -> Result<Self, BindError> {
+        let manager = globals.bind(queue_handle, 1..=2, GlobalData)?;
+        Ok(Self { manager, queue: queue_handle.clone(), device: None })
+    }
+
+    /// Setup clipboard access for a seat.
+    ///
+    /// This does nothing if a seat was already setup.
+    pub fn add_seat(&mut self, seat: &WlSeat) {
+        if self.device.is_none() {
+            self.device = Some(self.manager.get_data_device(seat, &self.queue, GlobalData));
+        }
+    }
+
+    /// Replace the clipboard's content with text.
+    ///
+    /// Returns `false` if no seat is available.
+    pub fn copy(&self, text: String) -> bool {
+        let device = match &self.device {
+            Some(device) => device,
+            None => return false,
+        };
+
+        let source = self.manager.create_data_source(&self.queue, text);
+        for mime_type in TEXT_MIME_TYPES {
+            source.offer(mime_type.into());
+        }
+        device.set_selection(Some(&source));
+
+        true
+    }
+}
+
+impl Dispatch<ZwlrDataControlManagerV1, GlobalData, State> for DataControlManager {
+    fn event(
+        _: &mut State,
+        _: &ZwlrDataControlManagerV1,
+        _: zwlr_data_control_manager_v1::Event,
+        _: &GlobalData,
+        _: &Connection,
+        _: &QueueHandle<State>,
+    ) {
+        // No events.
+    }
+}
+
+impl Dispatch<ZwlrDataControlDeviceV1, GlobalData, State> for DataControlManager {
+    event_created_child!(State, ZwlrDataControlDeviceV1, [
+        zwlr_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ZwlrDataControlOfferV1, GlobalData),
+    ]);
+
+    fn event(
+        state: &mut State,
+        device: &ZwlrDataControlDeviceV1,
+        event: DeviceEvent,
+        _: &GlobalData,
+        _: &Connection,
+        _: &QueueHandle<State>,
+    ) {
+        match event {
+            // Clipboard content of other clients is never read.
+            DeviceEvent::Selection { id: Some(offer) }
+            | DeviceEvent::PrimarySelection { id: Some(offer) } => offer.destroy(),
+            DeviceEvent::Finished => {
+                device.destroy();
+                if let Some(manager) = &mut state.protocol_states.data_control {
+                    manager.device = None;
+                }
+            },
+            _ => (),
+        }
+    }
+}
+
+impl Dispatch<ZwlrDataControlOfferV1, GlobalData, State> for DataControlManager {
+    fn event(
+        _: &mut State,
+        _: &ZwlrDataControlOfferV1,
+        _: zwlr_data_control_offer_v1::Event,
+        _: &GlobalData,
+        _: &Connection,
+        _: &QueueHandle<State>,
+    ) {
+        // Offered MIME types are irrelevant, since offers are never read.
+    }
+}
+
+impl Dispatch<ZwlrDataControlSourceV1, String, State> for DataControlManager {
+    fn event(
+        _: &mut State,
+        source: &ZwlrDataControlSourceV1,
+        event: SourceEvent,
+        text: &String,
+        _: &Connection,
+        _: &QueueHandle<State>,
+    ) {
+        match event {
+            SourceEvent::Send { fd, .. } => {
+                let mut file = File::from(fd);
+                if let Err(err) = file.write_all(text.as_bytes()) {
+                    error!(module = "clipboard", "Could not write clipboard content: {err}");
+                }
+            },
+            SourceEvent::Cancelled => source.destroy(),
+            _ => (),
+        }
+    }
+}
+
+delegate_dispatch!(State: [ZwlrDataControlManagerV1: GlobalData] => DataControlManager);
+delegate_dispatch!(State: [ZwlrDataControlDeviceV1: GlobalData] => DataControlManager);
+delegate_dispatch!(State: [ZwlrDataControlOfferV1: GlobalData] => DataControlManager);
+delegate_dispatch!(State: [ZwlrDataControlSourceV1: String] => DataControlManager);
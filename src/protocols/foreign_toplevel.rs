@@ -0,0 +1,233 @@
+//! Handling of the foreign toplevel management protocol.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use smithay_client_toolkit::globals::GlobalData;
+use smithay_client_toolkit::reexports::client::backend::ObjectId;
+use smithay_client_toolkit::reexports::client::globals::{BindError, GlobalList};
+use smithay_client_toolkit::reexports::client::protocol::wl_output::WlOutput;
+use smithay_client_toolkit::reexports::client::protocol::wl_seat::WlSeat;
+use smithay_client_toolkit::reexports::client::{
+    delegate_dispatch, event_created_child, Connection, Dispatch, Proxy, QueueHandle,
+};
+use smithay_client_toolkit::reexports::protocols_wlr::foreign_toplevel::v1::client::zwlr_foreign_toplevel_handle_v1::{
+    Event as HandleEvent, State as ToplevelState, ZwlrForeignToplevelHandleV1,
+};
+use smithay_client_toolkit::reexports::protocols_wlr::foreign_toplevel::v1::client::zwlr_foreign_toplevel_manager_v1::{
+    self, ZwlrForeignToplevelManagerV1,
+};
+
+use crate::State;
+
+/// Foreign toplevel manager.
+#[derive(Debug)]
+pub struct ForeignToplevelManager {
+    _manager: ZwlrForeignToplevelManagerV1,
+    fullscreen: HashMap<ObjectId, Vec<WlOutput>>,
+    toplevels: Vec<Toplevel>,
+    seat: Option<WlSeat>,
+}
+
+impl ForeignToplevelManager {
+    /// Create new foreign toplevel manager.
+    pub fn new(globals: &GlobalList, queue_handle: &QueueHandle<State>) -> Result<Self, BindError> {
+        let manager = globals.bind(queue_handle, 1..=3, GlobalData)?;
+        Ok(Self {
+            _manager: manager,
+            fullscreen: Default::default(),
+            toplevels: Default::default(),
+            seat: Default::default(),
+        })
+    }
+
+    /// Set the seat used for activating toplevels.
+    ///
+    /// This does nothing if a seat was already set.
+    pub fn add_seat(&mut self, seat: &WlSeat) {
+        if self.seat.is_none() {
+            self.seat = Some(seat.clone());
+        }
+    }
+
+    /// Get the seat used for activating toplevels.
+    pub fn seat(&self) -> Option<&WlSeat> {
+        self.seat.as_ref()
+    }
+
+    /// Get all open toplevels, in the order they were opened.
+    pub fn toplevels(&self) -> &[Toplevel] {
+        &self.toplevels
+    }
+
+    /// Check if an active toplevel is fullscreen on an output.
+    ///
+    /// Without an output, this checks for fullscreen toplevels on any output.
+    pub fn has_fullscreen(&self, output: Option<&WlOutput>) -> bool {
+        match output {
+            Some(output) => self.fullscreen.values().flatten().any(|o| o == output),
+            None => !self.fullscreen.is_empty(),
+        }
+    }
+
+    /// Update a toplevel's fullscreen outputs.
+    ///
+    /// Returns `true` if the toplevel's fullscreen state changed.
+    fn set_fullscreen(&mut self, toplevel: ObjectId, outputs: Option<Vec<WlOutput>>) -> bool {
+        match outputs {
+            Some(outputs) => self.fullscreen.insert(toplevel, outputs.clone()) != Some(outputs),
+            None => self.fullscreen.remove(&toplevel).is_some(),
+        }
+    }
+
+    /// Apply a toplevel's pending title and app ID.
+    ///
+    /// Returns `true` if the toplevel list changed.
+    fn update_toplevel(
+        &mut self,
+        handle: &ZwlrForeignToplevelHandleV1,
+        data: &ToplevelData,
+    ) -> bool {
+        let title = data.title.lock().unwrap().clone();
+        let app_id = data.app_id.lock().unwrap().clone();
+
+        match self.toplevels.iter_mut().find(|toplevel| &toplevel.handle == handle) {
+            Some(toplevel) if toplevel.title == title && toplevel.app_id == app_id => false,
+            Some(toplevel) => {
+                toplevel.title = title;
+                toplevel.app_id = app_id;
+                true
+            },
+            None => {
+                self.toplevels.push(Toplevel { handle: handle.clone(), title, app_id });
+                true
+            },
+        }
+    }
+
+    /// Remove a closed toplevel.
+    ///
+    /// Returns `true` if the toplevel list changed.
+    fn remove_toplevel(&mut self, handle: &ZwlrForeignToplevelHandleV1) -> bool {
+        let len = self.toplevels.len();
+        self.toplevels.retain(|toplevel| &toplevel.handle != handle);
+        len != self.toplevels.len()
+    }
+}
+
+/// Open toplevel of another client.
+#[derive(Debug)]
+pub struct Toplevel {
+    pub handle: ZwlrForeignToplevelHandleV1,
+    pub title: String,
+    pub app_id: String,
+}
+
+/// Foreign toplevel handler.
+pub trait ForeignToplevelHandler {
+    /// Active toplevel entered or left fullscreen on any output.
+    fn fullscreen_changed(&mut self);
+
+    /// Toplevel was opened, closed, or changed its title.
+    fn toplevels_changed(&mut self);
+}
+
+/// Pending state of a toplevel.
+#[derive(Default, Debug)]
+pub struct ToplevelData {
+    fullscreen: AtomicBool,
+    outputs: Mutex<Vec<WlOutput>>,
+    title: Mutex<String>,
+    app_id: Mutex<String>,
+}
+
+impl Dispatch<ZwlrForeignToplevelManagerV1, GlobalData, State> for ForeignToplevelManager {
+    event_created_child!(State, ZwlrForeignToplevelManagerV1, [
+        zwlr_foreign_toplevel_manager_v1::EVT_TOPLEVEL_OPCODE => (ZwlrForeignToplevelHandleV1, ToplevelData::default()),
+    ]);
+
+    fn event(
+        _: &mut State,
+        _: &ZwlrForeignToplevelManagerV1,
+        _: zwlr_foreign_toplevel_manager_v1::Event,
+        _: &GlobalData,
+        _: &Connection,
+        _: &QueueHandle<State>,
+    ) {
+        // New toplevels are handled through their own events.
+    }
+}
+
+impl Dispatch<ZwlrForeignToplevelHandleV1, ToplevelData, State> for ForeignToplevelManager {
+    fn event(
+        state: &mut State,
+        toplevel: &ZwlrForeignToplevelHandleV1,
+        event: HandleEvent,
+        data: &ToplevelData,
+        _: &Connection,
+        _: &QueueHandle<State>,
+    ) {
+        let closed = match event {
+            HandleEvent::Title { title } => {
+                *data.title.lock().unwrap() = title;
+                return;
+            },
+            HandleEvent::AppId { app_id } => {
+                *data.app_id.lock().unwrap() = app_id;
+                return;
+            },
+            HandleEvent::OutputEnter { output } => {
+                data.outputs.lock().unwrap().push(output);
+                return;
+            },
+            HandleEvent::OutputLeave { output } => {
+                data.outputs.lock().unwrap().retain(|o| o != &output);
+                return;
+            },
+            // Only consider the focused toplevel, since others are hidden behind it.
+            HandleEvent::State { state } => {
+                let states: Vec<_> = state
+                    .chunks_exact(4)
+                    .map(|state| u32::from_ne_bytes([state[0], state[1], state[2], state[3]]))
+                    .collect();
+                let fullscreen = states.contains(&(ToplevelState::Fullscreen as u32))
+                    && states.contains(&(ToplevelState::Activated as u32));
+                data.fullscreen.store(fullscreen, Ordering::Relaxed);
+                return;
+            },
+            HandleEvent::Done => false,
+            HandleEvent::Closed => true,
+            _ => return,
+        };
+
+        let manager = match &mut state.protocol_states.foreign_toplevel {
+            Some(manager) => manager,
+            None => return,
+        };
+
+        let (fullscreen, toplevels_changed) = if closed {
+            (false, manager.remove_toplevel(toplevel))
+        } else {
+            (data.fullscreen.load(Ordering::Relaxed), manager.update_toplevel(toplevel, data))
+        };
+
+        let outputs = fullscreen.then(|| data.outputs.lock().unwrap().clone());
+        let fullscreen_changed = manager.set_fullscreen(toplevel.id(), outputs);
+
+        if closed {
+            toplevel.destroy();
+        }
+
+        if fullscreen_changed {
+            state.fullscreen_changed();
+        }
+
+        if toplevels_changed {
+            state.toplevels_changed();
+        }
+    }
+}
+
+delegate_dispatch!(State: [ZwlrForeignToplevelManagerV1: GlobalData] => ForeignToplevelManager);
+delegate_dispatch!(State: [ZwlrForeignToplevelHandleV1: ToplevelData] => ForeignToplevelManager);
@@ -1,2 +1,6 @@
+pub mod cursor_shape;
+pub mod data_control;
+pub mod foreign_toplevel;
 pub mod fractional_scale;
+pub mod presentation_time;
 pub mod viewporter;
@@ -0,0 +1,101 @@
+//! Handling of the presentation time protocol.
+
+use std::time::Duration;
+
+use smithay_client_toolkit::globals::GlobalData;
+use smithay_client_toolkit::reexports::client::globals::{BindError, GlobalList};
+use smithay_client_toolkit::reexports::client::protocol::wl_surface::WlSurface;
+use smithay_client_toolkit::reexports::client::{
+    delegate_dispatch, Connection, Dispatch, QueueHandle,
+};
+use smithay_client_toolkit::reexports::protocols::wp::presentation_time::client::wp_presentation::{
+    Event as PresentationEvent, WpPresentation,
+};
+use smithay_client_toolkit::reexports::protocols::wp::presentation_time::client::wp_presentation_feedback::{
+    Event as FeedbackEvent, WpPresentationFeedback,
+};
+
+use crate::time::clock_time;
+use crate::State;
+
+/// Presentation time manager.
+#[derive(Debug)]
+pub struct Presentation {
+    queue_handle: QueueHandle<State>,
+    presentation: WpPresentation,
+    clock_id: Option<libc::clockid_t>,
+}
+
+impl Presentation {
+    /// Create new presentation time manager.
+    pub fn new(globals: &GlobalList, queue_handle: &QueueHandle<State>) -> Result<Self, BindError> {
+        let presentation = globals.bind(queue_handle, 1..=1, GlobalData)?;
+        Ok(Self { presentation, queue_handle: queue_handle.clone(), clock_id: None })
+    }
+
+    /// Request presentation feedback for the next commit of a surface.
+    pub fn feedback(&self, surface: &WlSurface) {
+        // Presentation timestamps are meaningless without their clock.
+        let clock_id = match self.clock_id {
+            Some(clock_id) => clock_id,
+            None => return,
+        };
+
+        let data = FeedbackData { surface: surface.clone(), commit_time: clock_time(clock_id) };
+        self.presentation.feedback(surface, &self.queue_handle, data);
+    }
+}
+
+/// Presentation feedback handler.
+pub trait PresentationHandler {
+    /// Surface content was shown on screen.
+    ///
+    /// The latency is the time between the surface commit and its
+    /// presentation.
+    fn presented(&mut self, surface: &WlSurface, latency: Duration);
+}
+
+/// Surface state at the time of the feedback request.
+#[derive(Debug)]
+pub struct FeedbackData {
+    surface: WlSurface,
+    commit_time: Duration,
+}
+
+impl Dispatch<WpPresentation, GlobalData, State> for Presentation {
+    fn event(
+        state: &mut State,
+        _: &WpPresentation,
+        event: PresentationEvent,
+        _: &GlobalData,
+        _: &Connection,
+        _: &QueueHandle<State>,
+    ) {
+        if let PresentationEvent::ClockId { clk_id } = event {
+            if let Some(presentation) = &mut state.protocol_states.presentation {
+                presentation.clock_id = Some(clk_id as libc::clockid_t);
+            }
+        }
+    }
+}
+
+impl Dispatch<WpPresentationFeedback, FeedbackData, State> for Presentation {
+    fn event(
+        state: &mut State,
+        _: &WpPresentationFeedback,
+        event: FeedbackEvent,
+        data: &FeedbackData,
+        _: &Connection,
+        _: &QueueHandle<State>,
+    ) {
+        if let FeedbackEvent::Presented { tv_sec_hi, tv_sec_lo, tv_nsec, .. } = event {
+            let secs = ((tv_sec_hi as u64) << 32) | tv_sec_lo as u64;
+            let presentation_time = Duration::new(secs, tv_nsec);
+            let latency = presentation_time.saturating_sub(data.commit_time);
+            state.presented(&data.surface, latency);
+        }
+    }
+}
+
+delegate_dispatch!(State: [WpPresentation: GlobalData] => Presentation);
+delegate_dispatch!(State: [WpPresentationFeedback: FeedbackData] => Presentation);
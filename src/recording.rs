@@ -0,0 +1,172 @@
+//! Touch input recording and playback.
+//!
+//! Recordings are plain text files with one event per line, prefixed by the
+//! milliseconds elapsed since the start of the recording:
+//!
+//! ```text
+//! 0 down panel 0 100.5 10
+//! 16 motion 0 100.5 42
+//! 32 up 0
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::result::Result as StdResult;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use calloop::timer::{TimeoutAction, Timer};
+use calloop::LoopHandle;
+use tracing::error;
+
+use crate::{Result, State};
+
+/// Environment variable with the file touch events are recorded to.
+pub const RECORD_ENV: &str = "EPITAPH_TOUCH_RECORD";
+
+/// Environment variable with the file touch events are replayed from.
+pub const REPLAY_ENV: &str = "EPITAPH_TOUCH_REPLAY";
+
+/// Surface receiving a touch press.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TouchTarget {
+    Panel,
+    Drawer,
+    Notification(u32),
+}
+
+impl Display for TouchTarget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Panel => write!(f, "panel"),
+            Self::Drawer => write!(f, "drawer"),
+            Self::Notification(id) => write!(f, "notification:{id}"),
+        }
+    }
+}
+
+impl FromStr for TouchTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> StdResult<Self, Self::Err> {
+        match s {
+            "panel" => Ok(Self::Panel),
+            "drawer" => Ok(Self::Drawer),
+            _ => match s.strip_prefix("notification:") {
+                Some(id) => Ok(Self::Notification(parse_field(id)?)),
+                None => Err(format!("invalid touch target: {s:?}")),
+            },
+        }
+    }
+}
+
+/// Recordable touch event.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum TouchEvent {
+    Down { target: TouchTarget, id: i32, position: (f64, f64) },
+    Up { id: i32 },
+    Motion { id: i32, position: (f64, f64) },
+    Cancel,
+}
+
+impl Display for TouchEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Down { target, id, position: (x, y) } => write!(f, "down {target} {id} {x} {y}"),
+            Self::Up { id } => write!(f, "up {id}"),
+            Self::Motion { id, position: (x, y) } => write!(f, "motion {id} {x} {y}"),
+            Self::Cancel => write!(f, "cancel"),
+        }
+    }
+}
+
+impl FromStr for TouchEvent {
+    type Err = String;
+
+    fn from_str(s: &str) -> StdResult<Self, Self::Err> {
+        let mut fields = s.split_whitespace();
+        let mut next = || fields.next().ok_or_else(|| format!("incomplete touch event: {s:?}"));
+
+        let kind = next()?;
+        match kind {
+            "down" => {
+                let target = next()?.parse()?;
+                let id = parse_field(next()?)?;
+                let position = (parse_field(next()?)?, parse_field(next()?)?);
+                Ok(Self::Down { target, id, position })
+            },
+            "up" => Ok(Self::Up { id: parse_field(next()?)? }),
+            "motion" => {
+                let id = parse_field(next()?)?;
+                let position = (parse_field(next()?)?, parse_field(next()?)?);
+                Ok(Self::Motion { id, position })
+            },
+            "cancel" => Ok(Self::Cancel),
+            _ => Err(format!("invalid touch event: {kind:?}")),
+        }
+    }
+}
+
+/// Touch event recorder.
+pub struct TouchRecorder {
+    file: File,
+    start: Instant,
+}
+
+impl TouchRecorder {
+    pub fn new(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self { file, start: Instant::now() })
+    }
+
+    /// Append an event to the recording.
+    pub fn record(&mut self, event: TouchEvent) {
+        let millis = self.start.elapsed().as_millis();
+        if let Err(err) = writeln!(self.file, "{millis} {event}") {
+            error!(module = "recording", "Could not record touch event: {err}");
+        }
+    }
+}
+
+/// Schedule playback of a touch event recording.
+pub fn replay(event_loop: &LoopHandle<'static, State>, path: impl AsRef<Path>) -> Result<()> {
+    let content = fs::read_to_string(path)?;
+
+    // Parse all events upfront, to avoid replaying partial recordings.
+    let mut events = VecDeque::new();
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        let (millis, event) = line.trim().split_once(' ').ok_or("invalid touch recording")?;
+        let offset = Duration::from_millis(millis.parse()?);
+        events.push_back((offset, event.parse::<TouchEvent>()?));
+    }
+
+    let first_offset = match events.front() {
+        Some((offset, _)) => *offset,
+        None => return Ok(()),
+    };
+
+    let start = Instant::now();
+    let timer = Timer::from_deadline(start + first_offset);
+    event_loop.insert_source(timer, move |now, _, state| {
+        // Dispatch all events which are due.
+        while events.front().is_some_and(|(offset, _)| start + *offset <= now) {
+            let (_, event) = events.pop_front().unwrap();
+            state.replay_touch(event);
+        }
+
+        match events.front() {
+            Some((offset, _)) => TimeoutAction::ToInstant(start + *offset),
+            None => TimeoutAction::Drop,
+        }
+    })?;
+
+    Ok(())
+}
+
+/// Parse a single touch event field.
+fn parse_field<T: FromStr>(field: &str) -> StdResult<T, String> {
+    field.parse().map_err(|_| format!("invalid touch event field: {field:?}"))
+}
@@ -1,26 +1,34 @@
 //! OpenGL rendering.
 
-use std::num::NonZeroU32;
+use std::cell::Cell;
+use std::ffi::CStr;
 use std::ops::Deref;
-use std::{mem, ptr};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{cmp, mem, ptr};
 
 use crossfont::Size as FontSize;
+use glutin::api::egl::config::Config;
 use glutin::api::egl::context::{NotCurrentContext, PossiblyCurrentContext};
+use glutin::api::egl::display::Display;
 use glutin::api::egl::surface::Surface;
+use glutin::context::{ContextApi, ContextAttributesBuilder, Version};
 use glutin::prelude::*;
 use glutin::surface::WindowSurface;
+use tracing::{error, warn};
 
-use crate::gl::types::{GLenum, GLfloat, GLshort, GLuint};
+use crate::config::FontConfig;
+use crate::gl::types::{GLboolean, GLchar, GLenum, GLfloat, GLint, GLshort, GLuint};
 use crate::text::GlRasterizer;
-use crate::vertex::{GlyphVertex, RectVertex, VertexBatcher};
+use crate::vertex::{GlyphQuad, GlyphVertex, RectQuad, RectVertex, VertexBatcher};
 use crate::{gl, Result, Size};
 
-/// Default font.
-const FONT: &str = "Sans";
-
 /// Default font size.
 const FONT_SIZE: f32 = 12.;
 
+/// Estimated GPU memory used by all textures, in bytes.
+static TEXTURE_MEMORY: AtomicUsize = AtomicUsize::new(0);
+
 /// Maximum items to be drawn in a batch.
 ///
 /// We use the closest number to `u16::MAX` dividable by 4 (amount of vertices
@@ -30,10 +38,53 @@ const BATCH_MAX: usize = (u16::MAX - u16::MAX % 4) as usize;
 
 const TEXT_VERTEX_SHADER: &str = include_str!("../shaders/text.v.glsl");
 const TEXT_FRAGMENT_SHADER: &str = include_str!("../shaders/text.f.glsl");
+const TEXT_FALLBACK_FRAGMENT_SHADER: &str = include_str!("../shaders/text_fallback.f.glsl");
 const RECT_VERTEX_SHADER: &str = include_str!("../shaders/rect.v.glsl");
 const RECT_FRAGMENT_SHADER: &str = include_str!("../shaders/rect.f.glsl");
+const TEXT_INSTANCED_VERTEX_SHADER: &str = include_str!("../shaders/text_instanced.v.glsl");
+const RECT_INSTANCED_VERTEX_SHADER: &str = include_str!("../shaders/rect_instanced.v.glsl");
+
+/// Vertex attribute layout: component count, type and normalization.
+type Attribute = (GLint, GLenum, GLboolean);
+
+/// Per-instance attributes of [`GlyphQuad`].
+const GLYPH_QUAD_ATTRIBUTES: [Attribute; 4] = [
+    (4, gl::SHORT, gl::FALSE),
+    (4, gl::FLOAT, gl::FALSE),
+    (1, gl::FLOAT, gl::FALSE),
+    (1, gl::FLOAT, gl::FALSE),
+];
+
+/// Per-instance attributes of [`RectQuad`].
+const RECT_QUAD_ATTRIBUTES: [Attribute; 3] =
+    [(4, gl::FLOAT, gl::FALSE), (4, gl::UNSIGNED_BYTE, gl::TRUE), (3, gl::FLOAT, gl::FALSE)];
+
+/// Create an OpenGL ES context.
+///
+/// GLES3 is preferred for instanced rendering, falling back to GLES2 if the
+/// driver does not support it.
+pub fn create_context(display: &Display, config: &Config) -> Result<NotCurrentContext> {
+    let attributes = |version| {
+        ContextAttributesBuilder::new()
+            .with_context_api(ContextApi::Gles(Some(version)))
+            .build(None)
+    };
+
+    unsafe {
+        match display.create_context(config, &attributes(Version::new(3, 0))) {
+            Ok(context) => Ok(context),
+            Err(err) => {
+                warn!(module = "renderer", "Falling back to GLES2 rendering: {err}");
+                Ok(display.create_context(config, &attributes(Version::new(2, 0)))?)
+            },
+        }
+    }
+}
 
 /// OpenGL renderer.
+///
+/// A single renderer is shared between all windows, which avoids duplicating
+/// the glyph and SVG textures for every EGL context.
 pub struct Renderer {
     pub text_batcher: VertexBatcher<TextRenderer>,
     pub rect_batcher: VertexBatcher<RectRenderer>,
@@ -41,50 +92,63 @@ pub struct Renderer {
     pub scale_factor: f64,
     pub size: Size<f32>,
 
-    egl_surface: Option<Surface<WindowSurface>>,
     egl_context: PossiblyCurrentContext,
+    framebuffer: Option<Framebuffer>,
 }
 
 impl Renderer {
     /// Initialize a new renderer.
-    pub fn new(egl_context: NotCurrentContext, scale_factor: f64) -> Result<Self> {
+    ///
+    /// Text is rendered with `font`, unless another font is selected.
+    /// Dual-source text blending is used if `dual_source_blending` is unset and
+    /// the driver supports it.
+    pub fn new(
+        egl_context: NotCurrentContext,
+        scale_factor: f64,
+        font: FontConfig,
+        dual_source_blending: Option<bool>,
+    ) -> Result<Self> {
         unsafe {
             // Enable the OpenGL context.
             let egl_context = egl_context.make_current_surfaceless()?;
 
-            // Set background color and blending.
-            gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+            let text_blending = match dual_source_blending {
+                Some(true) => TextBlending::DualSource,
+                Some(false) => TextBlending::Premultiplied,
+                None => TextBlending::detect(),
+            };
+
+            // Enable blending.
             gl::Enable(gl::BLEND);
 
             let font_size = FontSize::new(FONT_SIZE);
 
+            // Share the static index buffer between all programs.
+            let index_buffer = Rc::new(IndexBuffer::new());
+
+            // Draw quads through instancing on GLES3 contexts.
+            let corner_buffer = instancing_supported().then(|| Rc::new(CornerBuffer::new()));
+
             Ok(Renderer {
                 scale_factor,
                 egl_context,
-                rasterizer: GlRasterizer::new(FONT, font_size, scale_factor)?,
-                text_batcher: Default::default(),
-                rect_batcher: Default::default(),
-                egl_surface: Default::default(),
+                rasterizer: GlRasterizer::new(font, font_size, scale_factor)?,
+                text_batcher: VertexBatcher::new(TextRenderer::new(
+                    index_buffer.clone(),
+                    corner_buffer.clone(),
+                    text_blending,
+                )),
+                rect_batcher: VertexBatcher::new(RectRenderer::new(index_buffer, corner_buffer)),
+                framebuffer: Default::default(),
                 size: Default::default(),
             })
         }
     }
 
     /// Update viewport size.
-    pub fn resize(&mut self, size: Size, scale_factor: f64) -> Result<()> {
-        // XXX: Resize here **must** be performed before making the EGL context current,
-        // to avoid locking the back buffer and delaying the resize by one
-        // frame.
-        if let Some(egl_surface) = &self.egl_surface {
-            egl_surface.resize(
-                &self.egl_context,
-                NonZeroU32::new(size.width as u32).unwrap(),
-                NonZeroU32::new(size.height as u32).unwrap(),
-            );
-        }
-
-        self.bind()?;
-
+    ///
+    /// This must be called with the target surface bound.
+    fn update_viewport(&mut self, size: Size, scale_factor: f64) {
         unsafe { gl::Viewport(0, 0, size.width, size.height) };
         self.size = size.into();
 
@@ -95,19 +159,18 @@ impl Renderer {
         let offset_y = 1.;
 
         // Update the text renderer's uniform.
-        self.text_batcher.renderer().bind();
-        unsafe {
-            gl::Uniform4f(0, offset_x, offset_y, scale_x, scale_y);
+        let text_renderer = self.text_batcher.renderer();
+        if text_renderer.is_valid() {
+            text_renderer.bind();
+            unsafe { gl::Uniform4f(0, offset_x, offset_y, scale_x, scale_y) };
         }
 
         // Update rasterizer's scale factor.
         self.rasterizer.set_scale_factor(scale_factor);
         self.scale_factor = scale_factor;
-
-        Ok(())
     }
 
-    /// Perform drawing with this renderer.
+    /// Perform drawing into the offscreen framebuffer.
     pub fn draw<F: FnMut(&mut Renderer) -> Result<()>>(&mut self, mut fun: F) -> Result<()> {
         self.bind()?;
 
@@ -115,9 +178,28 @@ impl Renderer {
 
         unsafe { gl::Flush() };
 
-        if let Some(egl_surface) = &self.egl_surface {
-            egl_surface.swap_buffers(&self.egl_context)?;
-        }
+        Ok(())
+    }
+
+    /// Perform drawing into a window's EGL surface.
+    pub fn draw_surface<F: FnMut(&mut Renderer) -> Result<()>>(
+        &mut self,
+        egl_surface: &Surface<WindowSurface>,
+        size: Size,
+        scale_factor: f64,
+        mut fun: F,
+    ) -> Result<()> {
+        self.egl_context.make_current(egl_surface)?;
+
+        // Reset state modified by other windows, since the context is shared.
+        unsafe { gl::Disable(gl::SCISSOR_TEST) };
+        self.update_viewport(size, scale_factor);
+
+        fun(self)?;
+
+        unsafe { gl::Flush() };
+
+        egl_surface.swap_buffers(&self.egl_context)?;
 
         Ok(())
     }
@@ -127,48 +209,137 @@ impl Renderer {
         &self.egl_context
     }
 
-    /// Update the renderer's active EGL surface.
-    pub fn set_surface(&mut self, egl_surface: Option<Surface<WindowSurface>>) {
-        self.egl_surface = egl_surface;
+    /// Render into an offscreen framebuffer instead of an EGL surface.
+    pub fn set_offscreen(&mut self, size: Size, scale_factor: f64) -> Result<()> {
+        self.egl_context.make_current_surfaceless()?;
+        self.framebuffer = Some(Framebuffer::new(size)?);
+        self.bind()?;
+        self.update_viewport(size, scale_factor);
+        Ok(())
     }
 
-    /// Check if the EGL surface is initialized.
-    pub fn has_surface(&self) -> bool {
-        self.egl_surface.is_some()
+    /// Read the offscreen framebuffer's RGBA pixels, starting at the top.
+    pub fn read_pixels(&self) -> Result<Vec<u8>> {
+        let framebuffer = match &self.framebuffer {
+            Some(framebuffer) => framebuffer,
+            None => return Err("Attempted to read pixels without framebuffer".into()),
+        };
+        self.bind()?;
+
+        let size = framebuffer.size;
+        let stride = size.width as usize * 4;
+        let mut pixels = vec![0; stride * size.height as usize];
+        unsafe {
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::ReadPixels(
+                0,
+                0,
+                size.width,
+                size.height,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_mut_ptr() as *mut _,
+            );
+        }
+
+        // Flip rows, since OpenGL's origin is at the bottom.
+        let mut flipped = Vec::with_capacity(pixels.len());
+        for row in pixels.chunks_exact(stride).rev() {
+            flipped.extend_from_slice(row);
+        }
+
+        Ok(flipped)
     }
 
-    /// Bind this renderer's program and buffers.
-    fn bind(&self) -> Result<&Surface<WindowSurface>> {
-        let egl_surface = match &self.egl_surface {
-            Some(egl_surface) => egl_surface,
-            None => return Err("Attempted to bind EGL context without surface".into()),
+    /// Bind the offscreen framebuffer.
+    fn bind(&self) -> Result<()> {
+        let framebuffer = match &self.framebuffer {
+            Some(framebuffer) => framebuffer,
+            None => return Err("Attempted to bind EGL context without framebuffer".into()),
         };
 
-        self.egl_context.make_current(egl_surface)?;
+        self.egl_context.make_current_surfaceless()?;
+        unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer.id) };
+
+        Ok(())
+    }
+}
+
+/// Offscreen render target.
+struct Framebuffer {
+    _texture: Texture,
+    size: Size,
+    id: GLuint,
+}
+
+impl Framebuffer {
+    fn new(size: Size) -> Result<Self> {
+        let texture = Texture::new(size.width, size.height);
+
+        unsafe {
+            let mut id = 0;
+            gl::GenFramebuffers(1, &mut id);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, id);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                texture.id,
+                0,
+            );
+
+            let framebuffer = Self { _texture: texture, size, id };
+
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                return Err(format!("Incomplete framebuffer: {status:#x}").into());
+            }
+
+            Ok(framebuffer)
+        }
+    }
+}
 
-        Ok(egl_surface)
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteFramebuffers(1, &self.id) };
     }
 }
 
 /// Abstraction over shader programs.
-pub trait RenderProgram: Default {
+pub trait RenderProgram {
     /// Type of the vertex used for this program.
     type Vertex;
 
+    /// Type of the quads batched for this program.
+    type Quad: Copy;
+
+    /// Expand a quad into its corner vertices, for drawing without instancing.
+    fn vertices(quad: &Self::Quad) -> [Self::Vertex; 4];
+
+    /// Check if quads are drawn as instances, instead of expanding them.
+    fn instanced(&self) -> bool;
+
     /// Make this renderer active for drawing.
     fn bind(&self);
+
+    /// Check if the shader program was built successfully.
+    ///
+    /// Batches of invalid programs are skipped, leaving only the solid
+    /// background color.
+    fn is_valid(&self) -> bool;
+
+    /// Get the program's vertex buffer.
+    fn vertex_buffer(&self) -> &VertexBuffer;
 }
 
-/// Renderer for glyphs and SVGs.
-pub struct TextRenderer {
-    id: GLuint,
-    vao: GLuint,
-    vbo: GLuint,
+/// Element buffer with the indices of every possible quad in a batch.
+pub struct IndexBuffer {
     ebo: GLuint,
 }
 
-impl Default for TextRenderer {
-    fn default() -> Self {
+impl IndexBuffer {
+    fn new() -> Self {
         // Create buffer with all possible vertex indices.
         let mut vertex_indices = Vec::with_capacity(BATCH_MAX / 4 * 6);
         for index in 0..(BATCH_MAX / 4) as u16 {
@@ -183,25 +354,6 @@ impl Default for TextRenderer {
         }
 
         unsafe {
-            // Create vertex shader.
-            let vertex_shader = Shader::new(gl::VERTEX_SHADER, TEXT_VERTEX_SHADER);
-
-            // Create fragment shader.
-            let fragment_shader = Shader::new(gl::FRAGMENT_SHADER, TEXT_FRAGMENT_SHADER);
-
-            // Create shader program.
-            let id = gl::CreateProgram();
-            gl::AttachShader(id, *vertex_shader);
-            gl::AttachShader(id, *fragment_shader);
-            gl::LinkProgram(id);
-            gl::UseProgram(id);
-
-            // Generate VAO.
-            let mut vao = 0;
-            gl::GenVertexArraysOES(1, &mut vao);
-            gl::BindVertexArrayOES(vao);
-
-            // Generate EBO.
             let mut ebo = 0;
             gl::GenBuffers(1, &mut ebo);
             gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
@@ -212,17 +364,225 @@ impl Default for TextRenderer {
                 gl::STATIC_DRAW,
             );
 
-            // Generate VBO.
+            Self { ebo }
+        }
+    }
+}
+
+impl Drop for IndexBuffer {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteBuffers(1, &self.ebo) };
+    }
+}
+
+/// Vertex buffer with the corners of a quad, shared by all instanced programs.
+pub struct CornerBuffer {
+    vbo: GLuint,
+}
+
+impl CornerBuffer {
+    fn new() -> Self {
+        // Corners in triangle strip order, relative to the quad's top-left.
+        let corners: [GLfloat; 8] = [0., 0., 0., 1., 1., 0., 1., 1.];
+
+        unsafe {
             let mut vbo = 0;
             gl::GenBuffers(1, &mut vbo);
             gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
             gl::BufferData(
                 gl::ARRAY_BUFFER,
-                (BATCH_MAX * mem::size_of::<GlyphVertex>()) as isize,
-                ptr::null(),
-                gl::STREAM_DRAW,
+                mem::size_of_val(&corners) as isize,
+                corners.as_ptr() as *const _,
+                gl::STATIC_DRAW,
             );
 
+            Self { vbo }
+        }
+    }
+}
+
+impl Drop for CornerBuffer {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteBuffers(1, &self.vbo) };
+    }
+}
+
+/// Setup the attributes of an instanced program.
+///
+/// The first attribute is the quad corner, followed by the per-instance
+/// `attributes` read from the `vertex_buffer`.
+unsafe fn setup_instancing(
+    corner_buffer: &CornerBuffer,
+    vertex_buffer: &VertexBuffer,
+    attributes: &[Attribute],
+    stride: usize,
+) {
+    // Quad corners, shared by all instances.
+    gl::BindBuffer(gl::ARRAY_BUFFER, corner_buffer.vbo);
+    gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, 0, ptr::null());
+    gl::EnableVertexAttribArray(0);
+
+    // Per-instance quad attributes.
+    gl::BindBuffer(gl::ARRAY_BUFFER, vertex_buffer.vbo);
+    let mut offset = 0;
+    for (i, &(size, kind, normalized)) in attributes.iter().enumerate() {
+        let index = i as GLuint + 1;
+        gl::VertexAttribPointer(index, size, kind, normalized, stride as i32, offset as *const _);
+        gl::VertexAttribDivisor(index, 1);
+        gl::EnableVertexAttribArray(index);
+
+        let type_size = match kind {
+            gl::UNSIGNED_BYTE => mem::size_of::<u8>(),
+            gl::SHORT => mem::size_of::<GLshort>(),
+            _ => mem::size_of::<GLfloat>(),
+        };
+        offset += size as usize * type_size;
+    }
+}
+
+/// Check if the current context supports instanced rendering.
+///
+/// This requires a current OpenGL context.
+unsafe fn instancing_supported() -> bool {
+    let version = gl::GetString(gl::VERSION);
+    if version.is_null() {
+        return false;
+    }
+
+    let version = CStr::from_ptr(version as *const _).to_string_lossy();
+    version.strip_prefix("OpenGL ES ").is_some_and(|version| !version.starts_with('2'))
+}
+
+/// Vertex buffer growing to the largest batch size.
+pub struct VertexBuffer {
+    vbo: GLuint,
+    capacity: Cell<usize>,
+}
+
+impl VertexBuffer {
+    fn new() -> Self {
+        let mut vbo = 0;
+        unsafe {
+            gl::GenBuffers(1, &mut vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+        }
+
+        Self { vbo, capacity: Default::default() }
+    }
+
+    /// Upload vertices to the start of the buffer.
+    ///
+    /// The buffer must be bound already. If the vertices do not fit into
+    /// the buffer, it is reallocated with enough space for them.
+    pub fn upload<T>(&self, vertices: &[T]) {
+        let size = mem::size_of_val(vertices);
+
+        unsafe {
+            // Grow buffer to the next power of two, to limit reallocations.
+            if size > self.capacity.get() {
+                let capacity = cmp::min(size.next_power_of_two(), BATCH_MAX * mem::size_of::<T>());
+                gl::BufferData(gl::ARRAY_BUFFER, capacity as isize, ptr::null(), gl::STREAM_DRAW);
+                self.capacity.set(capacity);
+            }
+
+            gl::BufferSubData(gl::ARRAY_BUFFER, 0, size as isize, vertices.as_ptr() as *const _);
+        }
+    }
+}
+
+impl Drop for VertexBuffer {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteBuffers(1, &self.vbo) };
+    }
+}
+
+/// Blending mode of the text renderer.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TextBlending {
+    /// Per-channel subpixel blending, using `GL_EXT_blend_func_extended`.
+    DualSource,
+    /// Grayscale blending with premultiplied alpha.
+    Premultiplied,
+}
+
+impl TextBlending {
+    /// Pick the best blending mode supported by the driver.
+    ///
+    /// This requires a current OpenGL context.
+    unsafe fn detect() -> Self {
+        let extensions = gl::GetString(gl::EXTENSIONS);
+        if extensions.is_null() {
+            return Self::Premultiplied;
+        }
+
+        let extensions = CStr::from_ptr(extensions as *const _).to_string_lossy();
+        if extensions.split_whitespace().any(|extension| extension == "GL_EXT_blend_func_extended")
+        {
+            Self::DualSource
+        } else {
+            Self::Premultiplied
+        }
+    }
+}
+
+/// Renderer for glyphs and SVGs.
+pub struct TextRenderer {
+    blending: TextBlending,
+    corner_buffer: Option<Rc<CornerBuffer>>,
+    index_buffer: Rc<IndexBuffer>,
+    vertex_buffer: VertexBuffer,
+    id: GLuint,
+    vao: GLuint,
+}
+
+impl TextRenderer {
+    pub fn new(
+        index_buffer: Rc<IndexBuffer>,
+        corner_buffer: Option<Rc<CornerBuffer>>,
+        mut blending: TextBlending,
+    ) -> Self {
+        unsafe {
+            let vertex_shader = match corner_buffer {
+                Some(_) => TEXT_INSTANCED_VERTEX_SHADER,
+                None => TEXT_VERTEX_SHADER,
+            };
+
+            // Create shader program, falling back to premultiplied blending.
+            let mut id = 0;
+            if blending == TextBlending::DualSource {
+                match try_create_program(vertex_shader, TEXT_FRAGMENT_SHADER) {
+                    Ok(program) => id = program,
+                    Err(err) => {
+                        warn!(
+                            module = "renderer",
+                            "Falling back to premultiplied text blending: {err}"
+                        );
+                        blending = TextBlending::Premultiplied;
+                    },
+                }
+            }
+            if blending == TextBlending::Premultiplied {
+                id = create_program("text", vertex_shader, TEXT_FALLBACK_FRAGMENT_SHADER);
+            }
+            gl::UseProgram(id);
+
+            // Generate VAO.
+            let mut vao = 0;
+            gl::GenVertexArraysOES(1, &mut vao);
+            gl::BindVertexArrayOES(vao);
+
+            // Use the shared EBO.
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, index_buffer.ebo);
+
+            // Generate VBO, which is allocated on first draw.
+            let vertex_buffer = VertexBuffer::new();
+
+            if let Some(buffer) = &corner_buffer {
+                let stride = mem::size_of::<GlyphQuad>();
+                setup_instancing(buffer, &vertex_buffer, &GLYPH_QUAD_ATTRIBUTES, stride);
+                return Self { blending, corner_buffer, index_buffer, vertex_buffer, id, vao };
+            }
+
             // Glyph position.
             let mut offset = 0;
             gl::VertexAttribPointer(
@@ -258,69 +618,84 @@ impl Default for TextRenderer {
                 offset as *const _,
             );
             gl::EnableVertexAttribArray(2);
+            offset += mem::size_of::<GLfloat>();
+
+            // Glyph opacity.
+            gl::VertexAttribPointer(
+                3,
+                1,
+                gl::FLOAT,
+                gl::FALSE,
+                mem::size_of::<GlyphVertex>() as i32,
+                offset as *const _,
+            );
+            gl::EnableVertexAttribArray(3);
 
-            Self { id, vao, vbo, ebo }
+            Self { blending, corner_buffer, index_buffer, vertex_buffer, id, vao }
         }
     }
 }
 
 impl RenderProgram for TextRenderer {
+    type Quad = GlyphQuad;
     type Vertex = GlyphVertex;
 
+    fn vertices(quad: &GlyphQuad) -> [GlyphVertex; 4] {
+        quad.vertices()
+    }
+
+    fn instanced(&self) -> bool {
+        self.corner_buffer.is_some()
+    }
+
     fn bind(&self) {
         unsafe {
             gl::UseProgram(self.id);
             gl::BindVertexArrayOES(self.vao);
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.ebo);
-            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
-            gl::BlendFunc(gl::SRC1_COLOR_EXT, gl::ONE_MINUS_SRC1_COLOR_EXT);
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.index_buffer.ebo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vertex_buffer.vbo);
+            match self.blending {
+                TextBlending::DualSource => {
+                    gl::BlendFunc(gl::SRC1_COLOR_EXT, gl::ONE_MINUS_SRC1_COLOR_EXT)
+                },
+                TextBlending::Premultiplied => gl::BlendFunc(gl::ONE, gl::ONE_MINUS_SRC_ALPHA),
+            }
         }
     }
+
+    fn is_valid(&self) -> bool {
+        self.id != 0
+    }
+
+    fn vertex_buffer(&self) -> &VertexBuffer {
+        &self.vertex_buffer
+    }
 }
 
 impl Drop for TextRenderer {
     fn drop(&mut self) {
-        unsafe {
-            gl::DeleteBuffers(1, &self.vbo);
-            gl::DeleteBuffers(1, &self.ebo);
-            gl::DeleteVertexArraysOES(1, &self.vao);
-        }
+        unsafe { gl::DeleteVertexArraysOES(1, &self.vao) };
     }
 }
 
 /// Renderer for single-color rectangles.
 pub struct RectRenderer {
+    corner_buffer: Option<Rc<CornerBuffer>>,
+    index_buffer: Rc<IndexBuffer>,
+    vertex_buffer: VertexBuffer,
     id: GLuint,
     vao: GLuint,
-    vbo: GLuint,
-    ebo: GLuint,
 }
 
-impl Default for RectRenderer {
-    fn default() -> Self {
-        // Create buffer with all possible vertex indices.
-        let mut vertex_indices = Vec::with_capacity(BATCH_MAX / 4 * 6);
-        for index in 0..(BATCH_MAX / 4) as u16 {
-            let index = index * 4;
-            vertex_indices.push(index);
-            vertex_indices.push(index + 1);
-            vertex_indices.push(index + 3);
-
-            vertex_indices.push(index + 1);
-            vertex_indices.push(index + 2);
-            vertex_indices.push(index + 3);
-        }
-
+impl RectRenderer {
+    pub fn new(index_buffer: Rc<IndexBuffer>, corner_buffer: Option<Rc<CornerBuffer>>) -> Self {
         unsafe {
-            // Create shaders.
-            let vertex_shader = Shader::new(gl::VERTEX_SHADER, RECT_VERTEX_SHADER);
-            let fragment_shader = Shader::new(gl::FRAGMENT_SHADER, RECT_FRAGMENT_SHADER);
-
             // Create shader program.
-            let id = gl::CreateProgram();
-            gl::AttachShader(id, *vertex_shader);
-            gl::AttachShader(id, *fragment_shader);
-            gl::LinkProgram(id);
+            let vertex_shader = match corner_buffer {
+                Some(_) => RECT_INSTANCED_VERTEX_SHADER,
+                None => RECT_VERTEX_SHADER,
+            };
+            let id = create_program("rect", vertex_shader, RECT_FRAGMENT_SHADER);
             gl::UseProgram(id);
 
             // Generate VAO.
@@ -328,27 +703,17 @@ impl Default for RectRenderer {
             gl::GenVertexArraysOES(1, &mut vao);
             gl::BindVertexArrayOES(vao);
 
-            // Generate EBO.
-            let mut ebo = 0;
-            gl::GenBuffers(1, &mut ebo);
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
-            gl::BufferData(
-                gl::ELEMENT_ARRAY_BUFFER,
-                (vertex_indices.capacity() * mem::size_of::<u16>()) as isize,
-                vertex_indices.as_ptr() as *const _,
-                gl::STATIC_DRAW,
-            );
+            // Use the shared EBO.
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, index_buffer.ebo);
 
-            // Generate VBO.
-            let mut vbo = 0;
-            gl::GenBuffers(1, &mut vbo);
-            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                (BATCH_MAX * mem::size_of::<GlyphVertex>()) as isize,
-                ptr::null(),
-                gl::STREAM_DRAW,
-            );
+            // Generate VBO, which is allocated on first draw.
+            let vertex_buffer = VertexBuffer::new();
+
+            if let Some(buffer) = &corner_buffer {
+                let stride = mem::size_of::<RectQuad>();
+                setup_instancing(buffer, &vertex_buffer, &RECT_QUAD_ATTRIBUTES, stride);
+                return Self { corner_buffer, index_buffer, vertex_buffer, id, vao };
+            }
 
             // Rectangle position.
             let mut offset = 0;
@@ -373,33 +738,70 @@ impl Default for RectRenderer {
                 offset as *const _,
             );
             gl::EnableVertexAttribArray(1);
+            offset += mem::size_of::<u8>() * 4;
+
+            // Position relative to the rectangle center.
+            gl::VertexAttribPointer(
+                2,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                mem::size_of::<RectVertex>() as i32,
+                offset as *const _,
+            );
+            gl::EnableVertexAttribArray(2);
+            offset += mem::size_of::<GLfloat>() * 2;
 
-            Self { id, vao, vbo, ebo }
+            // Rectangle half size and corner radius.
+            gl::VertexAttribPointer(
+                3,
+                3,
+                gl::FLOAT,
+                gl::FALSE,
+                mem::size_of::<RectVertex>() as i32,
+                offset as *const _,
+            );
+            gl::EnableVertexAttribArray(3);
+
+            Self { corner_buffer, index_buffer, vertex_buffer, id, vao }
         }
     }
 }
 
 impl RenderProgram for RectRenderer {
+    type Quad = RectQuad;
     type Vertex = RectVertex;
 
+    fn vertices(quad: &RectQuad) -> [RectVertex; 4] {
+        quad.vertices()
+    }
+
+    fn instanced(&self) -> bool {
+        self.corner_buffer.is_some()
+    }
+
     fn bind(&self) {
         unsafe {
             gl::UseProgram(self.id);
             gl::BindVertexArrayOES(self.vao);
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.ebo);
-            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.index_buffer.ebo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vertex_buffer.vbo);
             gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
         }
     }
+
+    fn is_valid(&self) -> bool {
+        self.id != 0
+    }
+
+    fn vertex_buffer(&self) -> &VertexBuffer {
+        &self.vertex_buffer
+    }
 }
 
 impl Drop for RectRenderer {
     fn drop(&mut self) {
-        unsafe {
-            gl::DeleteBuffers(1, &self.vbo);
-            gl::DeleteBuffers(1, &self.ebo);
-            gl::DeleteVertexArraysOES(1, &self.vao);
-        }
+        unsafe { gl::DeleteVertexArraysOES(1, &self.vao) };
     }
 }
 
@@ -416,7 +818,7 @@ impl Deref for Shader {
 }
 
 impl Shader {
-    fn new(shader_type: GLenum, source: &str) -> Self {
+    fn new(shader_type: GLenum, source: &str) -> Result<Self> {
         unsafe {
             let id = gl::CreateShader(shader_type);
             gl::ShaderSource(
@@ -427,16 +829,90 @@ impl Shader {
             );
             gl::CompileShader(id);
 
-            Self { id }
+            // Ensure the shader is deleted on error.
+            let shader = Self { id };
+
+            let mut success: GLint = 0;
+            gl::GetShaderiv(id, gl::COMPILE_STATUS, &mut success);
+            if success != GLint::from(gl::TRUE) {
+                let log = info_log(id, gl::GetShaderiv, gl::GetShaderInfoLog);
+                return Err(format!("shader compilation failed: {log}").into());
+            }
+
+            Ok(shader)
         }
     }
 }
 
+impl Drop for Shader {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteShader(self.id) };
+    }
+}
+
+/// Create a shader program.
+///
+/// Returns the invalid program `0` if the shaders could not be compiled or
+/// linked.
+fn create_program(name: &str, vertex_source: &str, fragment_source: &str) -> GLuint {
+    match try_create_program(vertex_source, fragment_source) {
+        Ok(id) => id,
+        Err(err) => {
+            error!(
+                module = "renderer",
+                program = name,
+                "Falling back to solid-color rendering: {err}"
+            );
+            0
+        },
+    }
+}
+
+/// Compile and link a shader program.
+fn try_create_program(vertex_source: &str, fragment_source: &str) -> Result<GLuint> {
+    let vertex_shader = Shader::new(gl::VERTEX_SHADER, vertex_source)?;
+    let fragment_shader = Shader::new(gl::FRAGMENT_SHADER, fragment_source)?;
+
+    unsafe {
+        let id = gl::CreateProgram();
+        gl::AttachShader(id, *vertex_shader);
+        gl::AttachShader(id, *fragment_shader);
+        gl::LinkProgram(id);
+
+        let mut success: GLint = 0;
+        gl::GetProgramiv(id, gl::LINK_STATUS, &mut success);
+        if success != GLint::from(gl::TRUE) {
+            let log = info_log(id, gl::GetProgramiv, gl::GetProgramInfoLog);
+            gl::DeleteProgram(id);
+            return Err(format!("program linking failed: {log}").into());
+        }
+
+        Ok(id)
+    }
+}
+
+/// Get the info log of a shader or program.
+unsafe fn info_log(
+    id: GLuint,
+    get_iv: unsafe fn(GLuint, GLenum, *mut GLint),
+    get_info_log: unsafe fn(GLuint, GLint, *mut GLint, *mut GLchar),
+) -> String {
+    let mut max_length: GLint = 0;
+    get_iv(id, gl::INFO_LOG_LENGTH, &mut max_length);
+
+    let mut log = vec![0u8; max_length.max(0) as usize];
+    let mut length: GLint = 0;
+    get_info_log(id, max_length, &mut length, log.as_mut_ptr() as *mut GLchar);
+    log.truncate(length.max(0) as usize);
+
+    String::from_utf8_lossy(&log).trim().into()
+}
+
 /// OpenGL texture.
 pub struct Texture {
     pub id: GLuint,
-    pub _width: i32,
-    pub _height: i32,
+    pub width: i32,
+    pub height: i32,
 }
 
 impl Texture {
@@ -465,7 +941,9 @@ impl Texture {
             gl::BindTexture(gl::TEXTURE_2D, 0);
         }
 
-        Self { id, _width: width, _height: height }
+        TEXTURE_MEMORY.fetch_add(texture_size(width, height), Ordering::Relaxed);
+
+        Self { id, width, height }
     }
 
     /// Upload buffer to texture.
@@ -497,5 +975,17 @@ impl Drop for Texture {
         unsafe {
             gl::DeleteTextures(1, &self.id);
         }
+
+        TEXTURE_MEMORY.fetch_sub(texture_size(self.width, self.height), Ordering::Relaxed);
     }
 }
+
+/// Estimated GPU memory used by all textures, in bytes.
+pub fn texture_memory() -> usize {
+    TEXTURE_MEMORY.load(Ordering::Relaxed)
+}
+
+/// Size of an RGBA texture in bytes.
+fn texture_size(width: i32, height: i32) -> usize {
+    width as usize * height as usize * 4
+}
@@ -0,0 +1,101 @@
+//! Supervision of backend threads.
+
+use std::any::Any;
+use std::error::Error;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::SendError;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use calloop::channel::{self, Channel, Sender};
+use tokio::runtime::Builder;
+use tracing::{error, info_span, warn};
+
+/// Delay before the first restart of a stopped backend.
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Maximum delay between backend restarts.
+///
+/// Backends running for longer than this will be restarted with the minimum
+/// delay again.
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Event sent by a supervised backend.
+pub enum BackendEvent<T> {
+    /// Data update from the backend.
+    Update(T),
+    /// Backend stopped and will be restarted.
+    Stopped,
+}
+
+/// Sender for backend data updates.
+pub struct BackendSender<T>(Sender<BackendEvent<T>>);
+
+impl<T> BackendSender<T> {
+    /// Send a data update to the module.
+    pub fn send(&self, update: T) -> Result<(), SendError<BackendEvent<T>>> {
+        self.0.send(BackendEvent::Update(update))
+    }
+}
+
+/// Spawn a supervised backend thread.
+///
+/// The backend is restarted with exponential backoff whenever it exits,
+/// regardless of whether it failed, panicked, or stopped successfully.
+/// Supervision ends once the receiving channel is dropped.
+pub fn spawn<T, F, Fut>(name: &'static str, backend: F) -> Channel<BackendEvent<T>>
+where
+    T: Send + 'static,
+    F: Fn(BackendSender<T>) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<(), Box<dyn Error>>>,
+{
+    let (tx, rx) = channel::channel();
+    thread::spawn(move || {
+        // Tag all log messages of this thread with the backend name.
+        let _span = info_span!("backend", backend = name).entered();
+
+        let mut backoff = MIN_BACKOFF;
+        loop {
+            let start = Instant::now();
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                // Use a new runtime for every attempt, to drop tasks of panicked backends.
+                let runtime = Builder::new_current_thread().enable_all().build()?;
+                runtime.block_on(backend(BackendSender(tx.clone())))
+            }));
+
+            // Reset backoff for backends which were running successfully for a while.
+            if start.elapsed() >= MAX_BACKOFF {
+                backoff = MIN_BACKOFF;
+            }
+
+            match result {
+                Ok(Ok(())) => warn!(module = name, "Backend stopped, restarting in {backoff:?}"),
+                Ok(Err(err)) => {
+                    error!(module = name, "Backend failed, restarting in {backoff:?}: {err}")
+                },
+                Err(panic) => {
+                    let msg = panic_message(&*panic);
+                    error!(module = name, "Backend panicked, restarting in {backoff:?}: {msg}")
+                },
+            }
+
+            // Notify the module, stopping supervision once it's gone.
+            if tx.send(BackendEvent::Stopped).is_err() {
+                return;
+            }
+
+            thread::sleep(backoff);
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    });
+    rx
+}
+
+/// Extract the message of a caught panic.
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    match panic.downcast_ref::<&str>() {
+        Some(msg) => msg,
+        None => panic.downcast_ref::<String>().map_or("unknown panic", |msg| msg.as_str()),
+    }
+}
@@ -0,0 +1,481 @@
+//! Shared layer shell window state.
+
+use std::cell::RefCell;
+use std::mem;
+use std::num::NonZeroU32;
+use std::ptr::NonNull;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use glutin::api::egl::surface::Surface;
+use glutin::config::GetGlConfig;
+use glutin::display::GetGlDisplay;
+use glutin::prelude::*;
+use glutin::surface::SurfaceAttributesBuilder;
+use raw_window_handle::{RawWindowHandle, WaylandWindowHandle};
+use smithay_client_toolkit::compositor::{CompositorState, Region};
+use smithay_client_toolkit::reexports::client::protocol::wl_output::WlOutput;
+use smithay_client_toolkit::reexports::client::protocol::wl_surface::WlSurface;
+use smithay_client_toolkit::reexports::client::{Proxy, QueueHandle};
+use smithay_client_toolkit::reexports::protocols::wp::fractional_scale::v1::client::wp_fractional_scale_v1::WpFractionalScaleV1;
+use smithay_client_toolkit::reexports::protocols::wp::viewporter::client::wp_viewport::WpViewport;
+use smithay_client_toolkit::shell::wlr_layer::{LayerSurface, LayerSurfaceConfigure};
+use smithay_client_toolkit::shell::WaylandSurface;
+use tracing::warn;
+
+use crate::protocols::fractional_scale::FractionalScaleManager;
+use crate::protocols::viewporter::Viewporter;
+use crate::renderer::Renderer;
+use crate::{geometry, Result, Size, State};
+
+/// Time without frame callbacks before a window is considered frozen.
+const FRAME_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Layer shell window with an EGL surface.
+///
+/// This handles surface creation, scaling and frame callbacks, which are
+/// identical for all of Epitaph's windows.
+pub struct WindowSurface {
+    /// Renderer shared between all windows.
+    renderer: Rc<RefCell<Renderer>>,
+
+    egl_surface: Option<Surface<glutin::surface::WindowSurface>>,
+    fractional_scale: Option<WpFractionalScaleV1>,
+    viewport: Option<WpViewport>,
+    window: Option<LayerSurface>,
+    queue: QueueHandle<State>,
+    outputs: Vec<WlOutput>,
+    frames: FrameTracker,
+    scale_factor: f64,
+    logical_size: Size,
+    size: Size,
+}
+
+impl WindowSurface {
+    pub fn new(queue: QueueHandle<State>, renderer: Rc<RefCell<Renderer>>) -> Self {
+        // Default to 1x1 initial size since 0x0 EGL surfaces are illegal.
+        let size = Size { width: 1, height: 1 };
+
+        Self {
+            renderer,
+            queue,
+            size,
+            logical_size: size,
+            scale_factor: 1.,
+            frames: Default::default(),
+            egl_surface: Default::default(),
+            fractional_scale: Default::default(),
+            viewport: Default::default(),
+            outputs: Default::default(),
+            window: Default::default(),
+        }
+    }
+
+    /// Attach a layer shell window.
+    ///
+    /// The EGL surface is created lazily once the window's size is known.
+    pub fn show(
+        &mut self,
+        fractional_scale: &FractionalScaleManager,
+        viewporter: &Viewporter,
+        window: LayerSurface,
+    ) {
+        // Initialize fractional scaling protocol.
+        let fractional_scale =
+            fractional_scale.fractional_scaling(&self.queue, window.wl_surface());
+        self.fractional_scale = Some(fractional_scale);
+
+        // Initialize viewporter protocol.
+        let viewport = viewporter.viewport(&self.queue, window.wl_surface());
+
+        // Set initial viewport size based on last resize.
+        let destination = geometry::viewport_destination(self.size, self.scale_factor);
+        viewport.set_destination(destination.width, destination.height);
+
+        // Reset frame request tracking since we created a new surface.
+        self.frames = Default::default();
+        self.outputs.clear();
+
+        self.viewport = Some(viewport);
+        self.window = Some(window);
+    }
+
+    /// Destroy the window.
+    pub fn hide(&mut self) {
+        if let Some(fractional_scale) = self.fractional_scale.take() {
+            fractional_scale.destroy();
+        }
+        self.egl_surface = None;
+        self.viewport = None;
+        self.window = None;
+        self.outputs.clear();
+    }
+
+    /// Draw into the window using the shared renderer.
+    pub fn draw<F: FnMut(&mut Renderer) -> Result<()>>(&mut self, fun: F) -> Result<()> {
+        // Request a frame callback for the resized buffer, to detect stalls.
+        if self.frames.resize_pending {
+            self.queue_frame();
+        }
+
+        let egl_surface = match &self.egl_surface {
+            Some(egl_surface) => egl_surface,
+            None => return Err("Attempted to draw without EGL surface".into()),
+        };
+
+        let mut renderer = self.renderer.borrow_mut();
+        renderer.draw_surface(egl_surface, self.size, self.scale_factor, fun)
+    }
+
+    /// Get the layer shell window, if it is mapped.
+    pub fn window(&self) -> Option<&LayerSurface> {
+        self.window.as_ref()
+    }
+
+    /// Check if the window owns this surface.
+    pub fn owns_surface(&self, surface: &WlSurface) -> bool {
+        self.window.as_ref().is_some_and(|window| window.wl_surface() == surface)
+    }
+
+    /// Track the outputs the window is visible on.
+    pub fn set_entered(&mut self, output: &WlOutput, entered: bool) {
+        self.outputs.retain(|entered_output| entered_output != output);
+        if entered {
+            self.outputs.push(output.clone());
+        }
+    }
+
+    /// Check if the window is visible on an output.
+    pub fn on_output(&self, output: &WlOutput) -> bool {
+        self.outputs.contains(output)
+    }
+
+    /// Window size in physical pixels.
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// Window size in logical pixels.
+    pub fn logical_size(&self) -> Size {
+        self.logical_size
+    }
+
+    /// Current DPI scale factor.
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// Update the DPI scale factor.
+    pub fn set_scale_factor(&mut self, scale_factor: f64) {
+        self.scale_factor = scale_factor;
+
+        // Scale from the logical size, to avoid accumulating rounding errors.
+        self.resize(self.logical_size);
+    }
+
+    /// Resize the window based on a layer shell configure.
+    ///
+    /// The `fixed_height` is used instead of the configured height for windows
+    /// with a static logical height.
+    pub fn reconfigure(&mut self, configure: LayerSurfaceConfigure, fixed_height: Option<i32>) {
+        let size = configured_size(configure.new_size, fixed_height, self.logical_size);
+        self.resize(size);
+    }
+
+    /// Update the window after its output's mode or transform changed.
+    ///
+    /// This resizes the window ahead of the next layer shell configure. With
+    /// `rescale`, the fractional scale is re-requested too, since the
+    /// preferred scale might have changed with the output's mode, scale or
+    /// transform.
+    pub fn output_changed(
+        &mut self,
+        fractional_scale: &FractionalScaleManager,
+        logical_size: Option<Size>,
+        rescale: bool,
+    ) {
+        if let Some(logical_size) = logical_size.filter(|size| *size != self.logical_size) {
+            self.resize(logical_size);
+        }
+
+        let window = match &self.window {
+            Some(window) if rescale => window,
+            _ => return,
+        };
+
+        // Only one fractional scale object may exist per surface.
+        if let Some(fractional_scale) = self.fractional_scale.take() {
+            fractional_scale.destroy();
+        }
+        let fractional_scale =
+            fractional_scale.fractional_scaling(&self.queue, window.wl_surface());
+        self.fractional_scale = Some(fractional_scale);
+    }
+
+    /// Resize the window to a new logical size.
+    pub fn resize(&mut self, logical_size: Size) {
+        self.frames.resize();
+        self.logical_size = logical_size;
+        self.size = geometry::buffer_size(logical_size, self.scale_factor);
+
+        self.resize_surface(self.size);
+
+        // Update viewporter buffer target size.
+        let destination = geometry::viewport_destination(self.size, self.scale_factor);
+        if let Some(viewport) = &self.viewport {
+            viewport.set_destination(destination.width, destination.height);
+        }
+    }
+
+    /// Update the window's opaque region in logical coordinates.
+    pub fn set_opaque_region(&self, compositor: &CompositorState, x: i32, y: i32, w: i32, h: i32) {
+        let region = Region::new(compositor).ok();
+        if let Some((window, region)) = self.window.as_ref().zip(region) {
+            region.add(x, y, w, h);
+            window.wl_surface().set_opaque_region(Some(region.wl_region()));
+        }
+    }
+
+    /// Request a new frame.
+    pub fn request_frame(&mut self) {
+        if self.frames.frame_pending {
+            return;
+        }
+
+        if let Some(surface) = self.queue_frame() {
+            surface.commit();
+        }
+    }
+
+    /// Request a frame callback without committing the surface.
+    ///
+    /// This should be used while drawing, since the buffer swap will commit
+    /// the surface.
+    pub fn queue_frame(&mut self) -> Option<&WlSurface> {
+        let surface = self.window.as_ref()?.wl_surface();
+        self.frames.queue_frame(Instant::now());
+
+        surface.frame(&self.queue, surface.clone());
+
+        Some(surface)
+    }
+
+    /// Mark the pending frame as completed.
+    pub fn frame_done(&mut self) {
+        self.frames.frame_done();
+    }
+
+    /// Handle a frame callback from the compositor.
+    ///
+    /// Unlike [`Self::frame_done`], this is not called for redraws caused by
+    /// other events, since only callbacks confirm that the compositor is
+    /// still processing the window's commits.
+    pub fn frame_received(&mut self) {
+        self.frames.frame_received();
+    }
+
+    /// Recreate the EGL surface if frame callbacks stopped arriving.
+    ///
+    /// Returns the surface which needs to be redrawn after recovery.
+    pub fn recover_stall(&mut self) -> Option<WlSurface> {
+        let surface = self.window.as_ref()?.wl_surface().clone();
+        let elapsed = self.frames.recover_stall(Instant::now())?;
+
+        warn!(
+            module = "surface",
+            surface = %surface.id(),
+            size = ?self.size,
+            scale_factor = self.scale_factor,
+            "No frame callback for {elapsed:?}, recreating EGL surface"
+        );
+
+        self.egl_surface = None;
+        self.resize_surface(self.size);
+
+        Some(surface)
+    }
+
+    /// Resize EGL surface, dynamically initializing it on first resize.
+    fn resize_surface(&mut self, size: Size) {
+        let renderer = self.renderer.borrow();
+        let width = NonZeroU32::new(size.width as u32).unwrap_or(NonZeroU32::MIN);
+        let height = NonZeroU32::new(size.height as u32).unwrap_or(NonZeroU32::MIN);
+
+        // XXX: Resize here **must** be performed before making the EGL context current,
+        // to avoid locking the back buffer and delaying the resize by one
+        // frame.
+        if let Some(egl_surface) = &self.egl_surface {
+            egl_surface.resize(renderer.egl_context(), width, height);
+            return;
+        }
+
+        let window = match &self.window {
+            Some(window) => window,
+            None => return,
+        };
+
+        // Create a new EGL surface if there is none yet.
+        let window = NonNull::new(window.wl_surface().id().as_ptr().cast()).unwrap();
+        let wayland_window_handle = WaylandWindowHandle::new(window);
+        let raw_window_handle = RawWindowHandle::Wayland(wayland_window_handle);
+
+        let config = renderer.egl_context().config();
+        let surface_attributes = SurfaceAttributesBuilder::<glutin::surface::WindowSurface>::new()
+            .build(raw_window_handle, width, height);
+
+        let display = config.display();
+        let egl_surface = unsafe { display.create_window_surface(&config, &surface_attributes) };
+        self.egl_surface = egl_surface.ok();
+    }
+}
+
+/// Frame callback bookkeeping for detecting frozen render loops.
+#[derive(Default)]
+struct FrameTracker {
+    /// Time of the first frame request committed after a resize.
+    ///
+    /// This is cleared once any frame callback arrives.
+    resize_frame_time: Option<Instant>,
+
+    /// The window was resized without committing a frame request.
+    resize_pending: bool,
+
+    /// A frame was requested and not drawn yet.
+    frame_pending: bool,
+
+    stall_recovered: bool,
+}
+
+impl FrameTracker {
+    /// Mark the window as resized.
+    fn resize(&mut self) {
+        self.resize_pending = true;
+    }
+
+    /// Track a new frame request.
+    fn queue_frame(&mut self, now: Instant) {
+        self.frame_pending = true;
+
+        // Wait for the first callback after the resized buffer is committed.
+        if mem::take(&mut self.resize_pending) {
+            self.resize_frame_time.get_or_insert(now);
+        }
+    }
+
+    /// Mark the pending frame as drawn.
+    fn frame_done(&mut self) {
+        self.frame_pending = false;
+    }
+
+    /// Track a frame callback from the compositor.
+    fn frame_received(&mut self) {
+        self.resize_frame_time = None;
+        self.stall_recovered = false;
+    }
+
+    /// Check if frame callbacks stopped arriving after a resize.
+    ///
+    /// Returns the time since the first frame request following the resize.
+    /// Recovery is only attempted once until the next frame callback, to
+    /// avoid recreating the surface while the compositor is withholding
+    /// frames.
+    ///
+    /// Only frames following a resize are considered, since compositors
+    /// legitimately withhold frame callbacks from occluded surfaces.
+    fn recover_stall(&mut self, now: Instant) -> Option<Duration> {
+        let elapsed = now.saturating_duration_since(self.resize_frame_time?);
+        if self.stall_recovered || elapsed < FRAME_TIMEOUT {
+            return None;
+        }
+
+        self.stall_recovered = true;
+        self.frame_pending = false;
+
+        Some(elapsed)
+    }
+}
+
+/// Get the logical window size requested by a layer shell configure.
+///
+/// Dimensions of zero leave the size up to the client, so the current size is
+/// kept for them.
+fn configured_size(new_size: (u32, u32), fixed_height: Option<i32>, current: Size) -> Size {
+    let width = if new_size.0 == 0 { current.width } else { new_size.0 as i32 };
+    let height = match fixed_height {
+        Some(fixed_height) => fixed_height,
+        None if new_size.1 == 0 => current.height,
+        None => new_size.1 as i32,
+    };
+    Size::new(width, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stall_after_resize() {
+        let mut frames = FrameTracker::default();
+        let start = Instant::now();
+
+        // Resize, then draw the resized buffer without receiving a callback.
+        frames.resize();
+        frames.frame_done();
+        frames.queue_frame(start);
+
+        // Redraws must not hide the missing callback.
+        frames.frame_done();
+
+        assert_eq!(frames.recover_stall(start), None);
+        assert_eq!(frames.recover_stall(start + FRAME_TIMEOUT), Some(FRAME_TIMEOUT));
+
+        // Recovery is only attempted once.
+        assert_eq!(frames.recover_stall(start + FRAME_TIMEOUT * 2), None);
+    }
+
+    #[test]
+    fn stall_recovery_resets_on_frame_callback() {
+        let mut frames = FrameTracker::default();
+        let start = Instant::now();
+
+        frames.resize();
+        frames.queue_frame(start);
+        assert!(frames.recover_stall(start + FRAME_TIMEOUT).is_some());
+
+        // Callbacks for the resized buffer end the stall.
+        frames.frame_received();
+        assert_eq!(frames.recover_stall(start + FRAME_TIMEOUT * 2), None);
+
+        // Stalls after the next resize are recovered again.
+        let resize = start + FRAME_TIMEOUT * 2;
+        frames.resize();
+        frames.queue_frame(resize);
+        assert!(frames.recover_stall(resize + FRAME_TIMEOUT).is_some());
+    }
+
+    #[test]
+    fn no_stall_without_resize() {
+        let mut frames = FrameTracker::default();
+        let start = Instant::now();
+
+        // Occluded windows legitimately stop receiving frame callbacks.
+        frames.queue_frame(start);
+        assert_eq!(frames.recover_stall(start + FRAME_TIMEOUT * 2), None);
+
+        // Frames drawn after a resize was acknowledged are not stalls either.
+        frames.resize();
+        frames.queue_frame(start);
+        frames.frame_received();
+        frames.queue_frame(start);
+        assert_eq!(frames.recover_stall(start + FRAME_TIMEOUT * 2), None);
+    }
+
+    #[test]
+    fn zero_size_configure() {
+        let current = Size::new(360, 20);
+        assert_eq!(configured_size((0, 0), None, current), current);
+        assert_eq!(configured_size((0, 30), None, current), Size::new(360, 30));
+        assert_eq!(configured_size((720, 0), Some(20), current), Size::new(720, 20));
+        assert_eq!(configured_size((720, 40), None, current), Size::new(720, 40));
+    }
+}
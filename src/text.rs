@@ -6,15 +6,17 @@ use std::collections::HashMap;
 use std::{cmp, mem};
 
 use crossfont::{
-    BitmapBuffer, FontDesc, FontKey, GlyphKey, Metrics, Rasterize, RasterizedGlyph, Rasterizer,
-    Size as FontSize, Slant, Style, Weight,
+    BitmapBuffer, Error as FontError, FontDesc, FontKey, GlyphKey, Metrics, Rasterize,
+    RasterizedGlyph, Rasterizer, Size as FontSize, Slant, Style, Weight,
 };
-use resvg::tiny_skia::{Pixmap, Transform};
+use resvg::tiny_skia::{FilterQuality, IntSize, Pixmap, PixmapPaint, Transform};
 use resvg::usvg::{Options, Tree};
+use tracing::warn;
 
+use crate::config::FontConfig;
 use crate::gl::types::GLuint;
 use crate::renderer::Texture;
-use crate::Result;
+use crate::{geometry, Result};
 
 /// Width and height of the glyph atlas texture.
 ///
@@ -22,15 +24,22 @@ use crate::Result;
 const ATLAS_SIZE: i32 = 4096;
 
 /// Cached OpenGL rasterization.
+///
+/// Glyphs and SVGs of all scale factors share the same cache and atlas, so
+/// windows on outputs with different scales do not invalidate each other's
+/// textures.
 pub struct GlRasterizer {
     // OpenGL subtexture caching.
     cache: HashMap<CacheKey, GlSubTexture>,
     atlas: Atlas,
 
     // FreeType font rasterization.
-    metrics: Option<Metrics>,
+    fonts: HashMap<(FontConfig, FontSize), FontKey>,
+    metrics: HashMap<(FontKey, FontSize), Metrics>,
+    default_font: FontConfig,
     rasterizer: Rasterizer,
-    font_name: String,
+    active_font: FontConfig,
+    active_size: FontSize,
     size: FontSize,
     font: FontKey,
 
@@ -39,26 +48,26 @@ pub struct GlRasterizer {
 }
 
 impl GlRasterizer {
-    pub fn new(
-        font_name: impl Into<String>,
-        size: impl Into<FontSize>,
-        scale_factor: f64,
-    ) -> Result<Self> {
-        let font_name = font_name.into();
+    pub fn new(font: FontConfig, size: impl Into<FontSize>, scale_factor: f64) -> Result<Self> {
         let size = size.into();
 
         // Create FreeType rasterizer.
         let mut rasterizer = Rasterizer::new()?;
 
         // Load font at the requested size.
-        let font = Self::load_font(&mut rasterizer, &font_name, size, scale_factor)?;
+        let scaled_size = size.scale(scale_factor as f32);
+        let font_key = Self::load_font(&mut rasterizer, &font, scaled_size)?;
+        let fonts = HashMap::from([((font.clone(), scaled_size), font_key)]);
 
         Ok(Self {
             scale_factor,
             rasterizer,
-            font_name,
-            font,
+            fonts,
             size,
+            active_size: size,
+            default_font: font.clone(),
+            active_font: font,
+            font: font_key,
             metrics: Default::default(),
             atlas: Default::default(),
             cache: Default::default(),
@@ -67,22 +76,52 @@ impl GlRasterizer {
 
     /// Update the DPI scale factor.
     pub fn set_scale_factor(&mut self, scale_factor: f64) {
-        // Avoid clearing all caches when factor didn't change.
         if self.scale_factor == scale_factor {
             return;
         }
         self.scale_factor = scale_factor;
 
-        // Load font at new size.
-        self.font = Self::load_font(&mut self.rasterizer, &self.font_name, self.size, scale_factor)
-            .unwrap_or(self.font);
+        // Switch to the active font's variant for this scale factor.
+        self.update_font();
+    }
+
+    /// Change the font used for rasterizing text.
+    ///
+    /// Fonts are loaded on first use. If the font cannot be loaded, the
+    /// default font is used instead.
+    pub fn set_font(&mut self, font: &FontConfig) {
+        if self.active_font == *font {
+            return;
+        }
+        self.active_font = font.clone();
+
+        self.update_font();
+    }
+
+    /// Restore the font the rasterizer was created with.
+    pub fn reset_font(&mut self) {
+        let font = self.default_font.clone();
+        self.set_font(&font);
+    }
 
-        // Clear glyph cache and drop all atlas textures.
-        self.atlas = Atlas::default();
-        self.cache = HashMap::new();
+    /// Font the rasterizer was created with.
+    pub fn default_font(&self) -> &FontConfig {
+        &self.default_font
+    }
 
-        // Clear font metrics.
-        self.metrics = None;
+    /// Change the font size used for rasterizing text.
+    ///
+    /// Passing `None` restores the default size.
+    pub fn set_size(&mut self, size: Option<f32>) {
+        self.active_size = size.map_or(self.size, FontSize::new);
+    }
+
+    /// Upload all new rasterizations to OpenGL.
+    ///
+    /// This must be called before drawing any vertices referencing glyphs or
+    /// SVGs rasterized since the last upload.
+    pub fn flush_uploads(&mut self) {
+        self.atlas.flush();
     }
 
     /// Rasterize each glyph in a string.
@@ -114,16 +153,63 @@ impl GlRasterizer {
         let glyph_key = self.glyph_key(character);
 
         // Try to load glyph from cache.
-        let entry = match self.cache.entry(character.into()) {
-            Entry::Occupied(entry) => return Ok(*entry.get()),
-            Entry::Vacant(entry) => entry,
-        };
+        let cache_key = CacheKey::Character((self.font, self.font_size(), character));
+        if let Some(glyph) = self.cache.get(&cache_key) {
+            return Ok(*glyph);
+        }
+
+        // Skip invisible emoji modifiers, which often have no glyph in the font.
+        if is_zero_width(character) {
+            return Ok(*self.cache.entry(cache_key).or_default());
+        }
 
         // Rasterize the glyph if it's missing.
-        let rasterized_glyph = self.rasterizer.get_glyph(glyph_key)?;
+        //
+        // If no font has the glyph, its placeholder is rendered instead.
+        let mut rasterized_glyph = match self.rasterizer.get_glyph(glyph_key) {
+            Err(FontError::MissingGlyph(glyph)) => glyph,
+            glyph => glyph?,
+        };
+
+        // Scale color glyphs like emojis to fit the line height.
+        if let BitmapBuffer::Rgba(_) = rasterized_glyph.buffer {
+            let line_height = self.metrics()?.line_height;
+            if rasterized_glyph.height as f64 > line_height {
+                let scale = line_height / rasterized_glyph.height as f64;
+                rasterized_glyph = scale_glyph(rasterized_glyph, scale)?;
+            }
+        }
+
         let glyph = self.atlas.insert(&rasterized_glyph)?;
 
-        Ok(*entry.insert(glyph))
+        Ok(*self.cache.entry(cache_key).or_insert(glyph))
+    }
+
+    /// Rasterize a string, wrapping it into lines.
+    ///
+    /// Lines are wrapped at whitespace when exceeding `max_width`, or in the
+    /// middle of a word if it doesn't fit on a line by itself. Text exceeding
+    /// `max_lines` is truncated with an ellipsis.
+    ///
+    /// The glyphs of each line should be positioned using their advance.
+    pub fn layout_string(
+        &mut self,
+        text: &str,
+        max_width: i16,
+        max_lines: usize,
+    ) -> Vec<Vec<GlSubTexture>> {
+        let glyphs: Vec<_> = text
+            .chars()
+            .zip(self.rasterize_string(text))
+            .map(|(character, glyph)| (character, glyph.advance.0 as i16, glyph))
+            .collect();
+
+        let ellipsis = || {
+            let ellipsis = self.rasterize_char('…').ok()?;
+            Some((ellipsis.advance.0 as i16, ellipsis))
+        };
+
+        wrap_lines(glyphs, max_width, max_lines, ellipsis)
     }
 
     /// Rasterize an SVG from its text.
@@ -144,8 +230,8 @@ impl GlRasterizer {
         };
 
         // Calculate target dimensions.
-        width = (width as f64 * self.scale_factor * x_scale) as u32;
-        height = (height as f64 * self.scale_factor * y_scale) as u32;
+        width = geometry::to_physical(width as f64 * x_scale, self.scale_factor) as u32;
+        height = geometry::to_physical(height as f64 * y_scale, self.scale_factor) as u32;
 
         // Try to load svg from cache.
         let entry = match self.cache.entry(CacheKey::Svg((svg, width, height))) {
@@ -174,12 +260,13 @@ impl GlRasterizer {
 
     /// Get font metrics.
     pub fn metrics(&mut self) -> Result<Metrics> {
-        match &mut self.metrics {
+        let metrics_key = (self.font, self.font_size());
+        match self.metrics.get(&metrics_key) {
             Some(metrics) => Ok(*metrics),
             None => {
                 let _ = self.rasterize_char(' ');
                 let new_metrics = self.rasterizer.metrics(self.font, self.font_size())?;
-                Ok(*self.metrics.insert(new_metrics))
+                Ok(*self.metrics.entry(metrics_key).or_insert(new_metrics))
             },
         }
     }
@@ -189,21 +276,51 @@ impl GlRasterizer {
         GlyphKey { font_key: self.font, size: self.font_size(), character }
     }
 
-    /// Load a new font.
+    /// Update the active font key for the current font and scale factor.
+    fn update_font(&mut self) {
+        let font = self.active_font.clone();
+        self.font = match self.font_key(&font) {
+            Ok(font_key) => font_key,
+            Err(err) => {
+                warn!(module = "fonts", "Could not load font {:?}: {err}", font.family);
+
+                // Fall back to the default font, without retrying the broken one.
+                let default_font = self.default_font.clone();
+                let font_key = self.font_key(&default_font).unwrap_or(self.font);
+                self.fonts.insert((font, self.size.scale(self.scale_factor as f32)), font_key);
+                font_key
+            },
+        };
+    }
+
+    /// Get the key of a font at the current scale factor, loading it on first use.
+    fn font_key(&mut self, font: &FontConfig) -> Result<FontKey> {
+        let size = self.size.scale(self.scale_factor as f32);
+        if let Some(font_key) = self.fonts.get(&(font.clone(), size)) {
+            return Ok(*font_key);
+        }
+
+        let font_key = Self::load_font(&mut self.rasterizer, font, size)?;
+        self.fonts.insert((font.clone(), size), font_key);
+        Ok(font_key)
+    }
+
+    /// Load a new font at an already scaled size.
     fn load_font(
         rasterizer: &mut Rasterizer,
-        font_name: &str,
+        font: &FontConfig,
         size: FontSize,
-        scale_factor: f64,
     ) -> Result<FontKey> {
-        let font_style = Style::Description { slant: Slant::Normal, weight: Weight::Normal };
-        let font_desc = FontDesc::new(font_name, font_style);
-        Ok(rasterizer.load_font(&font_desc, size.scale(scale_factor as f32))?)
+        let slant = if font.italic { Slant::Italic } else { Slant::Normal };
+        let weight = if font.bold { Weight::Bold } else { Weight::Normal };
+        let font_style = Style::Description { slant, weight };
+        let font_desc = FontDesc::new(&font.family, font_style);
+        Ok(rasterizer.load_font(&font_desc, size)?)
     }
 
     /// Scaled font size.
     fn font_size(&self) -> FontSize {
-        self.size.scale(self.scale_factor as f32)
+        self.active_size.scale(self.scale_factor as f32)
     }
 }
 
@@ -234,6 +351,8 @@ pub struct Atlas {
     cursor_x: i32,
     /// Y position for writing new glyphs.
     cursor_y: i32,
+    /// Entries in the current row waiting for upload.
+    pending: Vec<PendingUpload>,
 }
 
 impl Default for Atlas {
@@ -243,6 +362,7 @@ impl Default for Atlas {
             row_height: Default::default(),
             cursor_x: Default::default(),
             cursor_y: Default::default(),
+            pending: Default::default(),
         }
     }
 }
@@ -259,27 +379,28 @@ impl Atlas {
 
         // Create new row if entry doesn't fit into current one.
         if self.cursor_x + entry.width > ATLAS_SIZE {
+            self.flush();
             self.cursor_y += mem::take(&mut self.row_height);
             self.cursor_x = 0;
         }
 
         // Create a new texture if the row's available height is too little.
         if self.cursor_y + entry.height > ATLAS_SIZE {
+            self.flush();
             self.textures.push(Texture::new(ATLAS_SIZE, ATLAS_SIZE));
             self.row_height = 0;
             self.cursor_x = 0;
             self.cursor_y = 0;
         }
 
-        // Upload entry's buffer to OpenGL.
+        // Stage entry's buffer for upload to OpenGL.
+        self.pending.push(PendingUpload {
+            x: self.cursor_x,
+            width: entry.width,
+            height: entry.height,
+            buffer: entry.buffer.into_owned(),
+        });
         let active_texture = &self.textures[self.textures.len() - 1];
-        active_texture.upload_buffer(
-            self.cursor_x,
-            self.cursor_y,
-            entry.width,
-            entry.height,
-            &entry.buffer,
-        );
 
         // Generate UV coordinates.
         let uv_bot = self.cursor_y as f32 / ATLAS_SIZE as f32;
@@ -305,10 +426,56 @@ impl Atlas {
             top: entry.top as i16,
         })
     }
+
+    /// Upload all pending entries to OpenGL.
+    ///
+    /// Since pending entries are always adjacent within the current row, they
+    /// are combined into a single upload. The space above entries shorter than
+    /// the tallest one is never written to by other entries, so it can safely
+    /// be overwritten.
+    fn flush(&mut self) {
+        let (first, last) = match (self.pending.first(), self.pending.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return,
+        };
+        let active_texture = &self.textures[self.textures.len() - 1];
+
+        // Avoid copying the buffer for a single entry.
+        if self.pending.len() == 1 {
+            let y = self.cursor_y;
+            active_texture.upload_buffer(first.x, y, first.width, first.height, &first.buffer);
+            self.pending.clear();
+            return;
+        }
+
+        // Combine all entries into one buffer.
+        let x = first.x;
+        let width = last.x + last.width - x;
+        let height = self.pending.iter().map(|entry| entry.height).max().unwrap_or(0);
+        let mut buffer = vec![0; (width * height * 4) as usize];
+        for entry in self.pending.drain(..) {
+            let entry_stride = (entry.width * 4) as usize;
+            let offset = ((entry.x - x) * 4) as usize;
+            for (row, entry_row) in entry.buffer.chunks_exact(entry_stride).enumerate() {
+                let start = row * (width * 4) as usize + offset;
+                buffer[start..start + entry_stride].copy_from_slice(entry_row);
+            }
+        }
+
+        active_texture.upload_buffer(x, self.cursor_y, width, height, &buffer);
+    }
+}
+
+/// Atlas entry waiting for upload.
+struct PendingUpload {
+    x: i32,
+    width: i32,
+    height: i32,
+    buffer: Vec<u8>,
 }
 
 /// Subtexture cached inside an [`Atlas`].
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Default, Debug)]
 pub struct GlSubTexture {
     pub texture_id: GLuint,
     pub multicolor: bool,
@@ -323,6 +490,125 @@ pub struct GlSubTexture {
     pub advance: (i32, i32),
 }
 
+/// Wrap glyphs with their horizontal advance into lines.
+///
+/// See [`GlRasterizer::layout_string`] for the wrapping rules.
+fn wrap_lines<T>(
+    glyphs: impl IntoIterator<Item = (char, i16, T)>,
+    max_width: i16,
+    max_lines: usize,
+    ellipsis: impl FnOnce() -> Option<(i16, T)>,
+) -> Vec<Vec<T>> {
+    let mut lines: Vec<Vec<(char, i16, T)>> = vec![Vec::new()];
+    let mut line_break = false;
+    let mut truncated = false;
+    let mut width = 0;
+
+    for (character, advance, glyph) in glyphs {
+        // Start a new line after explicit line breaks or when the line is full.
+        //
+        // Explicit line breaks are only applied once they're followed by more
+        // text, so trailing line breaks neither add lines nor truncate text.
+        let line_full = !line_break
+            && width + advance > max_width
+            && !lines.last().is_some_and(|line| line.is_empty());
+        if line_break || line_full {
+            if lines.len() >= max_lines {
+                truncated = true;
+                break;
+            }
+
+            // Move the last word to the next line, unless a space overflowed.
+            let line = lines.last_mut().unwrap();
+            let mut next_line = Vec::new();
+            if line_full {
+                if !character.is_whitespace() {
+                    if let Some(index) = line.iter().rposition(|(c, ..)| c.is_whitespace()) {
+                        next_line = line.split_off(index + 1);
+                    }
+                }
+
+                // Remove whitespace at the end of wrapped lines.
+                while line.last().is_some_and(|(c, ..)| c.is_whitespace()) {
+                    line.pop();
+                }
+            }
+
+            width = next_line.iter().map(|(_, advance, _)| advance).sum();
+            lines.push(next_line);
+            line_break = false;
+        }
+
+        if character == '\n' {
+            line_break = true;
+            continue;
+        }
+
+        // Skip whitespace at the start of wrapped lines.
+        let line = lines.last_mut().unwrap();
+        if line_full && line.is_empty() && character.is_whitespace() {
+            continue;
+        }
+
+        line.push((character, advance, glyph));
+        width += advance;
+    }
+
+    // Truncate the last line to make space for the ellipsis.
+    if let Some((ellipsis_advance, ellipsis)) = truncated.then(ellipsis).flatten() {
+        let line = lines.last_mut().unwrap();
+        while let Some((character, advance, _)) = line.last() {
+            if width + ellipsis_advance <= max_width && !character.is_whitespace() {
+                break;
+            }
+
+            width -= advance;
+            line.pop();
+        }
+        line.push(('…', ellipsis_advance, ellipsis));
+    }
+
+    lines.into_iter().map(|line| line.into_iter().map(|(_, _, glyph)| glyph).collect()).collect()
+}
+
+/// Scale a color glyph's bitmap and metrics.
+fn scale_glyph(glyph: RasterizedGlyph, scale: f64) -> Result<RasterizedGlyph> {
+    let buffer = match glyph.buffer {
+        BitmapBuffer::Rgba(buffer) => buffer,
+        BitmapBuffer::Rgb(_) => return Err("only color glyphs can be scaled".into()),
+    };
+
+    let scale_dimension = |value: i32| (value as f64 * scale).round() as i32;
+    let width = scale_dimension(glyph.width).max(1);
+    let height = scale_dimension(glyph.height).max(1);
+
+    // Load the premultiplied glyph bitmap.
+    let size = IntSize::from_wh(glyph.width as u32, glyph.height as u32)
+        .ok_or("invalid color glyph size")?;
+    let source = Pixmap::from_vec(buffer, size).ok_or("invalid color glyph buffer")?;
+
+    // Draw the glyph into a smaller buffer.
+    let mut target = Pixmap::new(width as u32, height as u32).ok_or("invalid color glyph size")?;
+    let paint = PixmapPaint { quality: FilterQuality::Bilinear, ..PixmapPaint::default() };
+    let transform = Transform::from_scale(scale as f32, scale as f32);
+    target.draw_pixmap(0, 0, source.as_ref(), &paint, transform, None);
+
+    Ok(RasterizedGlyph {
+        width,
+        height,
+        character: glyph.character,
+        top: scale_dimension(glyph.top),
+        left: scale_dimension(glyph.left),
+        advance: (scale_dimension(glyph.advance.0), scale_dimension(glyph.advance.1)),
+        buffer: BitmapBuffer::Rgba(target.take()),
+    })
+}
+
+/// Check if a character is an invisible formatting character.
+fn is_zero_width(character: char) -> bool {
+    matches!(character, '\u{200D}' | '\u{FE00}'..='\u{FE0F}' | '\u{E0020}'..='\u{E007F}')
+}
+
 fn rgb_to_rgba(rgb: &[u8]) -> Vec<u8> {
     let rgb_len = rgb.len();
     debug_assert_eq!(rgb_len % 3, 0);
@@ -385,16 +671,10 @@ impl<'a> From<&'a RasterizedGlyph> for AtlasEntry<'a> {
 /// Key for caching atlas entries.
 #[derive(Copy, Clone, Hash, PartialEq, Eq)]
 enum CacheKey {
-    Character(char),
+    Character((FontKey, FontSize, char)),
     Svg((Svg, u32, u32)),
 }
 
-impl From<char> for CacheKey {
-    fn from(c: char) -> Self {
-        Self::Character(c)
-    }
-}
-
 /// Built-in SVGs.
 #[derive(Copy, Clone, Hash, PartialEq, Eq)]
 pub enum Svg {
@@ -408,6 +688,7 @@ pub enum Svg {
     Battery60,
     Battery40,
     Battery20,
+    BatteryLimited,
     WifiConnected100,
     WifiConnected75,
     WifiConnected50,
@@ -424,12 +705,27 @@ pub enum Svg {
     Cellular20,
     Cellular0,
     CellularDisabled,
+    CellularRestricted,
+    Sim,
     Brightness,
+    KeyboardBacklight,
     FlashlightOn,
     FlashlightOff,
     OrientationLocked,
     OrientationUnlocked,
     Scale,
+    Ring,
+    RingVibrate,
+    RingSilent,
+    NetworkMode,
+    Location,
+    Metered,
+    Contrast,
+    DoNotDisturb,
+    MediaPlay,
+    MediaPause,
+    MediaNext,
+    MediaPrevious,
     ArrowUp,
     ArrowDown,
 }
@@ -444,6 +740,7 @@ impl Svg {
             Self::BatteryCharging40 => (20, 13),
             Self::BatteryCharging20 => (20, 13),
             Self::Battery100 => (20, 7),
+            Self::BatteryLimited => (20, 7),
             Self::Battery80 => (20, 7),
             Self::Battery60 => (20, 7),
             Self::Battery40 => (20, 7),
@@ -464,12 +761,27 @@ impl Svg {
             Self::Cellular20 => (20, 15),
             Self::Cellular0 => (20, 15),
             Self::CellularDisabled => (20, 18),
+            Self::CellularRestricted => (20, 15),
+            Self::Sim => (16, 20),
             Self::Brightness => (1, 1),
+            Self::KeyboardBacklight => (1, 1),
             Self::FlashlightOn => (45, 75),
             Self::FlashlightOff => (45, 75),
             Self::OrientationLocked => (73, 65),
             Self::OrientationUnlocked => (73, 65),
             Self::Scale => (11, 7),
+            Self::Ring => (20, 14),
+            Self::RingVibrate => (20, 14),
+            Self::RingSilent => (20, 14),
+            Self::NetworkMode => (20, 20),
+            Self::Location => (14, 20),
+            Self::Metered => (20, 13),
+            Self::Contrast => (16, 16),
+            Self::DoNotDisturb => (16, 16),
+            Self::MediaPlay => (16, 16),
+            Self::MediaPause => (16, 16),
+            Self::MediaNext => (16, 16),
+            Self::MediaPrevious => (16, 16),
             Self::ArrowUp => (64, 64),
             Self::ArrowDown => (64, 64),
         }
@@ -484,6 +796,7 @@ impl Svg {
             Self::BatteryCharging40 => include_str!("../svgs/battery/battery_charging_40.svg"),
             Self::BatteryCharging20 => include_str!("../svgs/battery/battery_charging_20.svg"),
             Self::Battery100 => include_str!("../svgs/battery/battery_100.svg"),
+            Self::BatteryLimited => include_str!("../svgs/battery/battery_limited.svg"),
             Self::Battery80 => include_str!("../svgs/battery/battery_80.svg"),
             Self::Battery60 => include_str!("../svgs/battery/battery_60.svg"),
             Self::Battery40 => include_str!("../svgs/battery/battery_40.svg"),
@@ -504,7 +817,12 @@ impl Svg {
             Self::Cellular20 => include_str!("../svgs/cellular/cellular_20.svg"),
             Self::Cellular0 => include_str!("../svgs/cellular/cellular_0.svg"),
             Self::CellularDisabled => include_str!("../svgs/cellular/cellular_disabled.svg"),
+            Self::CellularRestricted => include_str!("../svgs/cellular/cellular_restricted.svg"),
+            Self::Sim => include_str!("../svgs/sim/sim.svg"),
             Self::Brightness => include_str!("../svgs/brightness/brightness.svg"),
+            Self::KeyboardBacklight => {
+                include_str!("../svgs/keyboard_backlight/keyboard_backlight.svg")
+            },
             Self::FlashlightOn => include_str!("../svgs/flashlight/flashlight_on.svg"),
             Self::FlashlightOff => include_str!("../svgs/flashlight/flashlight_off.svg"),
             Self::OrientationLocked => include_str!("../svgs/orientation/orientation_locked.svg"),
@@ -512,8 +830,65 @@ impl Svg {
                 include_str!("../svgs/orientation/orientation_unlocked.svg")
             },
             Self::Scale => include_str!("../svgs/scale/scale.svg"),
+            Self::Ring => include_str!("../svgs/ring/ring.svg"),
+            Self::RingVibrate => include_str!("../svgs/ring/ring_vibrate.svg"),
+            Self::RingSilent => include_str!("../svgs/ring/ring_silent.svg"),
+            Self::NetworkMode => include_str!("../svgs/network_mode/network_mode.svg"),
+            Self::Location => include_str!("../svgs/location/location.svg"),
+            Self::Metered => include_str!("../svgs/metered/metered.svg"),
+            Self::Contrast => include_str!("../svgs/contrast/contrast.svg"),
+            Self::DoNotDisturb => include_str!("../svgs/do_not_disturb/do_not_disturb.svg"),
+            Self::MediaPlay => include_str!("../svgs/media/media_play.svg"),
+            Self::MediaPause => include_str!("../svgs/media/media_pause.svg"),
+            Self::MediaNext => include_str!("../svgs/media/media_next.svg"),
+            Self::MediaPrevious => include_str!("../svgs/media/media_previous.svg"),
             Self::ArrowUp => include_str!("../svgs/arrow_up.svg"),
             Self::ArrowDown => include_str!("../svgs/arrow_down.svg"),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wrap text with a fixed advance of one per character.
+    fn wrap(text: &str, max_width: i16, max_lines: usize) -> Vec<String> {
+        let glyphs = text.chars().map(|character| (character, 1, character));
+        let lines = wrap_lines(glyphs, max_width, max_lines, || Some((1, '…')));
+        lines.into_iter().map(|line| line.into_iter().collect()).collect()
+    }
+
+    #[test]
+    fn wrap_at_whitespace() {
+        assert_eq!(wrap("hello world", 8, 3), ["hello", "world"]);
+        assert_eq!(wrap("ab cd ef", 5, 3), ["ab cd", "ef"]);
+        assert_eq!(wrap("ab  cd", 3, 3), ["ab", "cd"]);
+    }
+
+    #[test]
+    fn wrap_long_words() {
+        assert_eq!(wrap("abcdefgh", 3, 5), ["abc", "def", "gh"]);
+        assert_eq!(wrap("a bcdefgh", 3, 5), ["a", "bcd", "efg", "h"]);
+    }
+
+    #[test]
+    fn hard_line_breaks() {
+        assert_eq!(wrap("a\nb", 5, 5), ["a", "b"]);
+        assert_eq!(wrap("a\n\nb", 5, 5), ["a", "", "b"]);
+        assert_eq!(wrap("a\n b", 5, 5), ["a", " b"]);
+    }
+
+    #[test]
+    fn trailing_line_break() {
+        assert_eq!(wrap("abc\n", 5, 1), ["abc"]);
+        assert_eq!(wrap("abc\n", 5, 2), ["abc"]);
+    }
+
+    #[test]
+    fn truncate_with_ellipsis() {
+        assert_eq!(wrap("abc def ghi", 5, 2), ["abc", "def…"]);
+        assert_eq!(wrap("a\nb\nc", 5, 2), ["a", "b…"]);
+        assert_eq!(wrap("abcdefgh", 3, 2), ["abc", "de…"]);
+    }
+}
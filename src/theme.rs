@@ -0,0 +1,57 @@
+//! Color schemes.
+
+use crate::drawer::MODULE_SIZE;
+use crate::gl;
+
+/// Default color scheme.
+const DEFAULT: Theme = Theme {
+    background: [0.1, 0.1, 0.1],
+    module_fg: [85, 85, 85, 255],
+    module_bg: [51, 51, 51, 255],
+    slider_height: (MODULE_SIZE - 16) as f64,
+};
+
+/// High-contrast color scheme for outdoor visibility.
+///
+/// The active color must contrast with both the white icons drawn on top of
+/// it and the black background.
+const HIGH_CONTRAST: Theme = Theme {
+    background: [0., 0., 0.],
+    module_fg: [0, 95, 204, 255],
+    module_bg: [64, 64, 64, 255],
+    slider_height: (MODULE_SIZE - 4) as f64,
+};
+
+/// Window color scheme.
+pub struct Theme {
+    /// Window background color.
+    pub background: [f32; 3],
+
+    /// Color of slider handle and active buttons.
+    pub module_fg: [u8; 4],
+
+    /// Color of the slider tray and inactive buttons.
+    pub module_bg: [u8; 4],
+
+    /// Slider module height with a scale factor of 1.
+    ///
+    /// This should be less than `MODULE_SIZE`.
+    pub slider_height: f64,
+}
+
+impl Theme {
+    /// Get the default or high-contrast color scheme.
+    pub fn select(high_contrast: bool) -> &'static Self {
+        if high_contrast {
+            &HIGH_CONTRAST
+        } else {
+            &DEFAULT
+        }
+    }
+
+    /// Set the OpenGL clear color to the background color.
+    pub fn set_clear_color(&self) {
+        let [r, g, b] = self.background;
+        unsafe { gl::ClearColor(r, g, b, 1.) };
+    }
+}
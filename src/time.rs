@@ -0,0 +1,93 @@
+//! System clock helpers.
+
+use std::io;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd};
+use std::time::Duration;
+
+/// Get the current time of a clock.
+///
+/// Returns zero if the clock is not supported.
+pub fn clock_time(clock: libc::clockid_t) -> Duration {
+    let mut time = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    if unsafe { libc::clock_gettime(clock, &mut time) } != 0 {
+        return Duration::ZERO;
+    }
+    Duration::new(time.tv_sec as u64, time.tv_nsec as u32)
+}
+
+/// Timer file descriptor which becomes readable when the wall clock is set.
+///
+/// This uses a realtime timer which never expires, relying on
+/// `TFD_TIMER_CANCEL_ON_SET` to cancel it on discontinuous clock changes.
+#[derive(Debug)]
+pub struct ClockChangeTimer {
+    fd: OwnedFd,
+}
+
+impl ClockChangeTimer {
+    pub fn new() -> io::Result<Self> {
+        let flags = libc::TFD_NONBLOCK | libc::TFD_CLOEXEC;
+        let fd = unsafe { libc::timerfd_create(libc::CLOCK_REALTIME, flags) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let timer = Self { fd: unsafe { OwnedFd::from_raw_fd(fd) } };
+        timer.arm()?;
+
+        Ok(timer)
+    }
+
+    /// Check if the wall clock was set since the last check.
+    ///
+    /// The timer is re-armed after every clock change.
+    pub fn clock_changed(&self) -> io::Result<bool> {
+        let mut expirations = 0u64;
+        let buffer = &mut expirations as *mut u64 as *mut libc::c_void;
+        if unsafe { libc::read(self.fd.as_raw_fd(), buffer, 8) } >= 0 {
+            return Ok(false);
+        }
+
+        let err = io::Error::last_os_error();
+        match err.raw_os_error() {
+            Some(libc::ECANCELED) => {
+                self.arm()?;
+                Ok(true)
+            },
+            _ if err.kind() == io::ErrorKind::WouldBlock => Ok(false),
+            _ => Err(err),
+        }
+    }
+
+    /// Arm the timer with the latest possible expiration time.
+    fn arm(&self) -> io::Result<()> {
+        let zero = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+        let never = libc::timespec { tv_sec: libc::time_t::MAX, tv_nsec: 0 };
+        let spec = libc::itimerspec { it_interval: zero, it_value: never };
+
+        let flags = libc::TFD_TIMER_ABSTIME | libc::TFD_TIMER_CANCEL_ON_SET;
+        let fd = self.fd.as_raw_fd();
+        if unsafe { libc::timerfd_settime(fd, flags, &spec, std::ptr::null_mut()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+}
+
+impl AsFd for ClockChangeTimer {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unchanged_clock() {
+        let timer = ClockChangeTimer::new().unwrap();
+        assert!(!timer.clock_changed().unwrap());
+    }
+}
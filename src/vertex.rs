@@ -1,4 +1,4 @@
-//! OpenGL vertex batching.
+//! OpenGL quad batching.
 
 use std::{cmp, mem, ptr};
 
@@ -6,56 +6,50 @@ use crate::gl;
 use crate::gl::types::GLuint;
 use crate::renderer::RenderProgram;
 use crate::text::GlSubTexture;
+use crate::Size;
 
-/// Maximum items to be drawn in a batch.
+/// Maximum quads to be drawn in a batch.
 ///
-/// We use the closest number to `u16::MAX` dividable by 4 (amount of vertices
-/// we push for a subtexture), since it's the maximum possible index in
-/// `glDrawElements` in GLES2.
-const MAX_BATCH_SIZE: usize = (u16::MAX - u16::MAX % 4) as usize;
+/// Each quad is expanded into 4 vertices without instancing, and `u16::MAX`
+/// is the maximum possible index in `glDrawElements` in GLES2.
+const MAX_BATCH_SIZE: usize = (u16::MAX / 4) as usize;
 
-/// Batch vertices by texture ID.
+/// Batch quads by texture ID.
 ///
-/// Groups together multiple vertices with the same texture ID into a rendering
+/// Groups together multiple quads with the same texture ID into a rendering
 /// batch and limits the maximum size of each batch.
 pub struct VertexBatcher<R: RenderProgram> {
     texture_ids: Vec<GLuint>,
-    vertices: Vec<R::Vertex>,
+    quads: Vec<R::Quad>,
     renderer: R,
 }
 
-impl<R: RenderProgram> Default for VertexBatcher<R> {
-    fn default() -> Self {
-        Self {
-            texture_ids: Default::default(),
-            vertices: Default::default(),
-            renderer: Default::default(),
-        }
+impl<R: RenderProgram> VertexBatcher<R> {
+    pub fn new(renderer: R) -> Self {
+        Self { renderer, texture_ids: Default::default(), quads: Default::default() }
     }
-}
 
-impl<R: RenderProgram> VertexBatcher<R> {
-    /// Add a vertex to the batcher.
-    pub fn push(&mut self, texture_id: GLuint, vertex: R::Vertex) {
+    /// Add a quad to the batcher.
+    pub fn push(&mut self, texture_id: GLuint, quad: R::Quad) {
         self.texture_ids.push(texture_id);
-        self.vertices.push(vertex);
+        self.quads.push(quad);
     }
 
-    /// Get all vertex batches.
+    /// Get all quad batches.
     pub fn batches(&mut self) -> VertexBatches<'_, R> {
-        sort_multiple(&mut self.texture_ids, &mut self.vertices);
+        sort_multiple(&mut self.texture_ids, &mut self.quads);
 
         VertexBatches {
             texture_ids: &mut self.texture_ids,
-            vertices: &mut self.vertices,
+            quads: &mut self.quads,
             renderer: &self.renderer,
             offset: 0,
         }
     }
 
-    /// Get pending vertices.
-    pub fn pending(&mut self) -> &mut [R::Vertex] {
-        &mut self.vertices
+    /// Get pending quads.
+    pub fn pending(&mut self) -> &mut [R::Quad] {
+        &mut self.quads
     }
 
     /// Get the batcher's renderer.
@@ -64,10 +58,10 @@ impl<R: RenderProgram> VertexBatcher<R> {
     }
 }
 
-/// Iterator over batched vertex groups.
+/// Iterator over batched quad groups.
 pub struct VertexBatches<'a, R: RenderProgram> {
     texture_ids: &'a mut Vec<GLuint>,
-    vertices: &'a mut Vec<R::Vertex>,
+    quads: &'a mut Vec<R::Quad>,
     offset: usize,
     renderer: &'a R,
 }
@@ -75,21 +69,21 @@ pub struct VertexBatches<'a, R: RenderProgram> {
 impl<R: RenderProgram> Drop for VertexBatches<'_, R> {
     fn drop(&mut self) {
         self.texture_ids.clear();
-        self.vertices.clear();
+        self.quads.clear();
     }
 }
 
 impl<R: RenderProgram> VertexBatches<'_, R> {
-    /// Get the next vertex batch.
+    /// Get the next quad batch.
     pub fn next(&mut self) -> Option<VertexBatch<'_, R>> {
-        let vertex_count = self.vertices.len();
-        if self.offset >= vertex_count {
+        let quad_count = self.quads.len();
+        if self.offset >= quad_count {
             return None;
         }
 
-        // Group all vertices up to `MAX_BATCH_SIZE` with identical texture ID.
+        // Group all quads up to `MAX_BATCH_SIZE` with identical texture ID.
         let texture_id = self.texture_ids[self.offset];
-        let max_size = cmp::min(vertex_count - self.offset, MAX_BATCH_SIZE);
+        let max_size = cmp::min(quad_count - self.offset, MAX_BATCH_SIZE);
         let batch_size = self.texture_ids[self.offset..self.offset + max_size]
             .iter()
             .position(|id| id != &texture_id)
@@ -100,84 +94,113 @@ impl<R: RenderProgram> VertexBatches<'_, R> {
 
         Some(VertexBatch {
             texture_id,
-            vertices: &self.vertices[old_offset..self.offset],
+            quads: &self.quads[old_offset..self.offset],
             renderer: self.renderer,
         })
     }
 }
 
-/// Batch of vertices with consistent resource ID.
+/// Batch of quads with consistent resource ID.
 pub struct VertexBatch<'a, R: RenderProgram> {
     texture_id: GLuint,
-    vertices: &'a [R::Vertex],
+    quads: &'a [R::Quad],
     renderer: &'a R,
 }
 
 impl<R: RenderProgram> VertexBatch<'_, R> {
     /// Render this batch.
     pub fn draw(&self) {
+        // Skip rendering with broken shaders.
+        if !self.renderer.is_valid() {
+            return;
+        }
+
         self.renderer.bind();
 
-        let vertex_count = self.vertices.len();
+        let quad_count = self.quads.len();
         unsafe {
             gl::BindTexture(gl::TEXTURE_2D, self.texture_id);
 
-            gl::BufferSubData(
-                gl::ARRAY_BUFFER,
-                0,
-                mem::size_of_val(self.vertices) as isize,
-                self.vertices.as_ptr() as *const _,
-            );
+            // Draw quads as instances of a shared triangle strip.
+            if self.renderer.instanced() {
+                self.renderer.vertex_buffer().upload(self.quads);
+                gl::DrawArraysInstanced(gl::TRIANGLE_STRIP, 0, 4, quad_count as i32);
+                return;
+            }
+
+            let vertices: Vec<_> = self.quads.iter().flat_map(R::vertices).collect();
+            self.renderer.vertex_buffer().upload(&vertices);
 
-            let num_indices = (vertex_count / 4 * 6) as i32;
+            let num_indices = (quad_count * 6) as i32;
             gl::DrawElements(gl::TRIANGLES, num_indices, gl::UNSIGNED_SHORT, ptr::null());
         }
     }
 }
 
 impl GlSubTexture {
-    /// OpenGL vertices for this subtexture.
-    pub fn vertices(&self, x: i16, y: i16) -> Option<[GlyphVertex; 4]> {
+    /// OpenGL quad for this subtexture.
+    pub fn quad(&self, x: i16, y: i16) -> Option<GlyphQuad> {
         if self.width == 0 || self.height == 0 {
             return None;
         }
 
-        let x = x + self.left;
-        let y = y - self.top;
+        Some(GlyphQuad {
+            x: x + self.left,
+            y: y - self.top,
+            width: self.width,
+            height: self.height,
+            uv_left: self.uv_left,
+            uv_top: self.uv_bot,
+            uv_width: self.uv_width,
+            uv_height: self.uv_height,
+            flags: if self.multicolor { 1. } else { 0. },
+            alpha: 1.,
+        })
+    }
+}
+
+/// Textured quad for the text shader.
+///
+/// This is used directly as instance data, or expanded into 4 vertices
+/// without instancing support.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct GlyphQuad {
+    // Top-left corner position and size.
+    pub x: i16,
+    pub y: i16,
+    pub width: i16,
+    pub height: i16,
 
-        let flags = if self.multicolor { 1. } else { 0. };
+    // Area in the Atlas.
+    pub uv_left: f32,
+    pub uv_top: f32,
+    pub uv_width: f32,
+    pub uv_height: f32,
 
-        // Bottom-Left vertex.
-        let bottom_left = GlyphVertex {
-            x,
-            y: y + self.height,
-            u: self.uv_left,
-            v: self.uv_bot + self.uv_height,
-            flags,
-        };
+    // Quad flags.
+    pub flags: f32,
 
-        // Top-Left vertex.
-        let top_left = GlyphVertex { x, y, u: self.uv_left, v: self.uv_bot, flags };
+    // Quad opacity.
+    pub alpha: f32,
+}
 
-        // Top-Right vertex.
-        let top_right = GlyphVertex {
-            x: x + self.width,
-            y,
-            u: self.uv_left + self.uv_width,
-            v: self.uv_bot,
-            flags,
-        };
+impl GlyphQuad {
+    /// Vertices of the quad's corners.
+    pub fn vertices(&self) -> [GlyphVertex; 4] {
+        let vertex = |x, y, u, v| GlyphVertex { x, y, u, v, flags: self.flags, alpha: self.alpha };
 
-        // Bottom-Right vertex.
-        let bottom_right = GlyphVertex {
-            x: x + self.width,
-            y: y + self.height,
-            u: self.uv_left + self.uv_width,
-            v: self.uv_bot + self.uv_height,
-            flags,
-        };
+        let right = self.x + self.width;
+        let bottom = self.y + self.height;
+        let uv_right = self.uv_left + self.uv_width;
+        let uv_bottom = self.uv_top + self.uv_height;
 
-        Some([bottom_left, top_left, top_right, bottom_right])
+        [
+            vertex(self.x, bottom, self.uv_left, uv_bottom),
+            vertex(self.x, self.y, self.uv_left, self.uv_top),
+            vertex(right, self.y, uv_right, self.uv_top),
+            vertex(right, bottom, uv_right, uv_bottom),
+        ]
     }
 }
 
@@ -195,6 +218,9 @@ pub struct GlyphVertex {
 
     // Vertex flags.
     pub flags: f32,
+
+    // Vertex opacity.
+    pub alpha: f32,
 }
 
 /// Vertex for the rectangle shader.
@@ -209,37 +235,105 @@ pub struct RectVertex {
     pub g: u8,
     pub b: u8,
     pub a: u8,
+
+    // Vertex position relative to the rectangle center, in pixels.
+    pub local_x: f32,
+    pub local_y: f32,
+
+    // Half rectangle size and corner radius, in pixels.
+    pub half_width: f32,
+    pub half_height: f32,
+    pub radius: f32,
+}
+
+/// Rounded rectangle for the rectangle shader.
+///
+/// This is used directly as instance data, or expanded into 4 vertices
+/// without instancing support.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct RectQuad {
+    // Top-left corner position and size, in normalized device coordinates.
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+
+    // Rectangle color.
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+
+    // Half rectangle size and corner radius, in pixels.
+    pub half_width: f32,
+    pub half_height: f32,
+    pub radius: f32,
 }
 
-impl RectVertex {
-    pub fn new(
-        window_width: i16,
-        window_height: i16,
-        x: i16,
-        y: i16,
-        width: i16,
-        height: i16,
-        color: &[u8; 4],
-    ) -> [Self; 4] {
+impl RectQuad {
+    pub fn new(window_size: Size<i16>, rect: Rect, radius: i16, color: &[u8; 4]) -> Self {
+        // Clamp radius to fit into the rectangle.
+        let half_width = rect.width as f32 / 2.;
+        let half_height = rect.height as f32 / 2.;
+        let radius = (radius as f32).min(half_width).min(half_height).max(0.);
+
         // Calculate rectangle vertex positions in normalized device coordinates.
         // NDC range from -1 to +1, with Y pointing up.
-        let half_width = window_width as f32 / 2.;
-        let half_height = window_height as f32 / 2.;
-        let x = x as f32 / half_width - 1.;
-        let y = -y as f32 / half_height + 1.;
-        let width = width as f32 / half_width;
-        let height = height as f32 / half_height;
+        let half_window_width = window_size.width as f32 / 2.;
+        let half_window_height = window_size.height as f32 / 2.;
+        let x = rect.x as f32 / half_window_width - 1.;
+        let y = -rect.y as f32 / half_window_height + 1.;
+        let width = rect.width as f32 / half_window_width;
+        let height = rect.height as f32 / half_window_height;
 
         let [r, g, b, a] = *color;
+        Self { x, y, width, height, r, g, b, a, half_width, half_height, radius }
+    }
+
+    /// Vertices of the quad's corners.
+    pub fn vertices(&self) -> [RectVertex; 4] {
+        let (half_width, half_height) = (self.half_width, self.half_height);
+        let vertex = |x, y, local_x, local_y| RectVertex {
+            x,
+            y,
+            r: self.r,
+            g: self.g,
+            b: self.b,
+            a: self.a,
+            local_x,
+            local_y,
+            half_width,
+            half_height,
+            radius: self.radius,
+        };
+
+        let right = self.x + self.width;
+        let bottom = self.y - self.height;
         [
-            RectVertex { x, y, r, g, b, a },
-            RectVertex { x, y: y - height, r, g, b, a },
-            RectVertex { x: x + width, y: y - height, r, g, b, a },
-            RectVertex { x: x + width, y, r, g, b, a },
+            vertex(self.x, self.y, -half_width, -half_height),
+            vertex(self.x, bottom, -half_width, half_height),
+            vertex(right, bottom, half_width, half_height),
+            vertex(right, self.y, half_width, -half_height),
         ]
     }
 }
 
+/// Rectangle in physical pixels, relative to the window's top-left corner.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i16,
+    pub y: i16,
+    pub width: i16,
+    pub height: i16,
+}
+
+impl Rect {
+    pub fn new(x: i16, y: i16, width: i16, height: i16) -> Self {
+        Self { x, y, width, height }
+    }
+}
+
 /// Insertion sort for multiple arrays.
 ///
 /// This will use `v1` as a discriminant for sorting and perform the same
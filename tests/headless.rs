@@ -0,0 +1,399 @@
+//! Integration tests against a headless Sway compositor.
+//!
+//! Window state is queried through Epitaph's IPC socket, while gestures are
+//! injected with the wlroots virtual pointer protocol, which Epitaph handles
+//! like touch input.
+//!
+//! These tests require `sway` and `swaymsg` in the `PATH` and a working
+//! software EGL implementation. Without Sway, they are skipped.
+
+use std::io::Read;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+use std::{env, fs, process, thread};
+
+use serde_json::Value;
+use smithay_client_toolkit::reexports::client::globals::{self, GlobalListContents};
+use smithay_client_toolkit::reexports::client::protocol::wl_pointer::ButtonState;
+use smithay_client_toolkit::reexports::client::protocol::wl_registry::{self, WlRegistry};
+use smithay_client_toolkit::reexports::client::{
+    delegate_noop, Connection, Dispatch, EventQueue, QueueHandle,
+};
+use smithay_client_toolkit::reexports::protocols_wlr::virtual_pointer::v1::client::zwlr_virtual_pointer_manager_v1::ZwlrVirtualPointerManagerV1;
+use smithay_client_toolkit::reexports::protocols_wlr::virtual_pointer::v1::client::zwlr_virtual_pointer_v1::ZwlrVirtualPointerV1;
+
+/// Maximum time to wait for the compositor to become ready.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time Epitaph is given to process all pending events.
+const SETTLE_DURATION: Duration = Duration::from_secs(2);
+
+/// Maximum time to wait for Epitaph to reach an expected state.
+const STATE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Name of the headless output created by Sway.
+const OUTPUT: &str = "HEADLESS-1";
+
+/// Initial output size.
+const OUTPUT_WIDTH: i32 = 720;
+const OUTPUT_HEIGHT: i32 = 1440;
+
+/// Linux input event code of the left mouse button.
+const BTN_LEFT: u32 = 0x110;
+
+#[test]
+fn startup() {
+    let compositor = match Compositor::new("startup") {
+        Some(compositor) => compositor,
+        None => return,
+    };
+    let mut epitaph = compositor.spawn_epitaph();
+
+    // Ensure the panel surface was created and configured to the output width.
+    epitaph.wait_for("panel configure", |status| {
+        panels(status).as_slice() == [(OUTPUT_WIDTH as f64, 1.)]
+    });
+
+    // Ensure the drawer is not mapped without user interaction.
+    thread::sleep(SETTLE_DURATION);
+    let status = epitaph.status();
+    assert_eq!(status["drawer"], "hidden", "unexpected status: {status}");
+}
+
+#[test]
+fn output_reconfigure() {
+    let compositor = match Compositor::new("output_reconfigure") {
+        Some(compositor) => compositor,
+        None => return,
+    };
+    let mut epitaph = compositor.spawn_epitaph();
+    epitaph.wait_for("panel configure", |status| !panels(status).is_empty());
+
+    // Force a new configure with a different panel width.
+    compositor.swaymsg(&["output", OUTPUT, "resolution", "640x480"]);
+    epitaph.wait_for("panel resize", |status| panels(status).as_slice() == [(640., 1.)]);
+
+    // Force a new configure with a different scale factor.
+    compositor.swaymsg(&["output", OUTPUT, "scale", "2"]);
+    epitaph.wait_for("panel rescale", |status| panels(status).as_slice() == [(320., 2.)]);
+}
+
+#[test]
+fn drawer_gestures() {
+    let compositor = match Compositor::new("drawer_gestures") {
+        Some(compositor) => compositor,
+        None => return,
+    };
+    let mut epitaph = compositor.spawn_epitaph();
+    epitaph.wait_for("panel configure", |status| !panels(status).is_empty());
+
+    let mut pointer = VirtualPointer::new(&compositor);
+    let center_x = OUTPUT_WIDTH as f64 / 2.;
+    let height = OUTPUT_HEIGHT as f64;
+
+    // Swipe down from the panel to open the drawer.
+    pointer.swipe((center_x, 5.), (center_x, height * 0.75));
+    epitaph.wait_for("drawer open", |status| status["drawer"] == "open");
+
+    // Swipe up from below the drawer modules to close it again.
+    pointer.swipe((center_x, height * 0.9), (center_x, height * 0.1));
+    epitaph.wait_for("drawer close", |status| status["drawer"] == "hidden");
+}
+
+/// Headless Sway instance.
+struct Compositor {
+    runtime_dir: PathBuf,
+    wayland_display: String,
+    sway_socket: PathBuf,
+    child: Child,
+}
+
+impl Compositor {
+    /// Start a new compositor.
+    ///
+    /// Returns `None` if Sway is not installed.
+    fn new(name: &str) -> Option<Self> {
+        if !installed("sway") || !installed("swaymsg") {
+            eprintln!("Skipping {name}: sway is not available");
+            return None;
+        }
+
+        // Use a dedicated runtime directory to avoid conflicts with other sessions.
+        let runtime_dir = env::temp_dir().join(format!("epitaph-test-{}-{name}", process::id()));
+        let _ = fs::remove_dir_all(&runtime_dir);
+        fs::create_dir_all(&runtime_dir).expect("create runtime directory");
+        set_permissions(&runtime_dir);
+
+        let config = runtime_dir.join("sway.conf");
+        let output_mode = format!("output {OUTPUT} mode {OUTPUT_WIDTH}x{OUTPUT_HEIGHT}\n");
+        fs::write(&config, output_mode).expect("write sway config");
+
+        let child = Command::new("sway")
+            .arg("--config")
+            .arg(&config)
+            .env("XDG_RUNTIME_DIR", &runtime_dir)
+            .env("WLR_BACKENDS", "headless")
+            .env("WLR_LIBINPUT_NO_DEVICES", "1")
+            .env_remove("WAYLAND_DISPLAY")
+            .env_remove("SWAYSOCK")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("spawn sway");
+
+        // Wait for Wayland and IPC sockets.
+        let start = Instant::now();
+        let (wayland_display, sway_socket) = loop {
+            assert!(start.elapsed() < STARTUP_TIMEOUT, "sway did not start in time");
+
+            let sockets = Self::sockets(&runtime_dir);
+            if let (Some(wayland_display), Some(sway_socket)) = sockets {
+                break (wayland_display, sway_socket);
+            }
+
+            thread::sleep(Duration::from_millis(50));
+        };
+
+        Some(Self { runtime_dir, wayland_display, sway_socket, child })
+    }
+
+    /// Launch Epitaph on this compositor.
+    fn spawn_epitaph(&self) -> Epitaph {
+        let child = Command::new(env!("CARGO_BIN_EXE_epitaph"))
+            .env("XDG_RUNTIME_DIR", &self.runtime_dir)
+            .env("XDG_CONFIG_HOME", &self.runtime_dir)
+            .env("XDG_STATE_HOME", &self.runtime_dir)
+            .env("WAYLAND_DISPLAY", &self.wayland_display)
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("spawn epitaph");
+
+        Epitaph {
+            child,
+            runtime_dir: self.runtime_dir.clone(),
+            wayland_display: self.wayland_display.clone(),
+        }
+    }
+
+    /// Send an IPC message to the compositor.
+    fn swaymsg(&self, args: &[&str]) {
+        let status = Command::new("swaymsg")
+            .arg("--socket")
+            .arg(&self.sway_socket)
+            .args(args)
+            .stdout(Stdio::null())
+            .status()
+            .expect("run swaymsg");
+        assert!(status.success(), "swaymsg {args:?} failed");
+    }
+
+    /// Find the Wayland and Sway IPC sockets.
+    fn sockets(runtime_dir: &Path) -> (Option<String>, Option<PathBuf>) {
+        let mut wayland_display = None;
+        let mut sway_socket = None;
+
+        for entry in fs::read_dir(runtime_dir).into_iter().flatten().flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if file_name.starts_with("wayland-") && !file_name.ends_with(".lock") {
+                wayland_display = Some(file_name);
+            } else if file_name.starts_with("sway-ipc.") {
+                sway_socket = Some(entry.path());
+            }
+        }
+
+        (wayland_display, sway_socket)
+    }
+}
+
+impl Drop for Compositor {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = fs::remove_dir_all(&self.runtime_dir);
+    }
+}
+
+/// Running Epitaph instance.
+struct Epitaph {
+    runtime_dir: PathBuf,
+    wayland_display: String,
+    child: Child,
+}
+
+impl Epitaph {
+    /// Query window state through the IPC socket.
+    ///
+    /// Returns `Value::Null` if the IPC socket is not ready yet.
+    fn status(&mut self) -> Value {
+        self.assert_running();
+
+        let output = Command::new(env!("CARGO_BIN_EXE_epitaph"))
+            .args(["msg", "status"])
+            .env("XDG_RUNTIME_DIR", &self.runtime_dir)
+            .env("WAYLAND_DISPLAY", &self.wayland_display)
+            .stderr(Stdio::null())
+            .output()
+            .expect("run epitaph msg");
+
+        if !output.status.success() {
+            return Value::Null;
+        }
+        serde_json::from_slice(&output.stdout).expect("parse status")
+    }
+
+    /// Wait until the window state matches a condition.
+    fn wait_for<F: FnMut(&Value) -> bool>(&mut self, description: &str, mut condition: F) {
+        let start = Instant::now();
+        loop {
+            let status = self.status();
+            if condition(&status) {
+                return;
+            }
+
+            assert!(start.elapsed() < STATE_TIMEOUT, "timeout waiting for {description}: {status}");
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    /// Assert that Epitaph is still running.
+    fn assert_running(&mut self) {
+        if let Some(status) = self.child.try_wait().expect("poll epitaph") {
+            let mut stderr = String::new();
+            if let Some(mut child_stderr) = self.child.stderr.take() {
+                let _ = child_stderr.read_to_string(&mut stderr);
+            }
+            panic!("epitaph exited with {status}:\n{stderr}");
+        }
+    }
+}
+
+impl Drop for Epitaph {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Virtual pointer device for injecting input.
+struct VirtualPointer {
+    queue: EventQueue<VirtualPointerState>,
+    pointer: ZwlrVirtualPointerV1,
+    start: Instant,
+}
+
+impl VirtualPointer {
+    fn new(compositor: &Compositor) -> Self {
+        let socket = compositor.runtime_dir.join(&compositor.wayland_display);
+        let stream = UnixStream::connect(socket).expect("connect to compositor");
+        let connection = Connection::from_socket(stream).expect("create wayland connection");
+
+        let (globals, mut queue) =
+            globals::registry_queue_init(&connection).expect("initialize registry");
+        let queue_handle = queue.handle();
+        let manager: ZwlrVirtualPointerManagerV1 =
+            globals.bind(&queue_handle, 1..=2, ()).expect("bind virtual pointer manager");
+        let pointer = manager.create_virtual_pointer(None, &queue_handle, ());
+        queue.roundtrip(&mut VirtualPointerState).expect("create virtual pointer");
+
+        Self { queue, pointer, start: Instant::now() }
+    }
+
+    /// Press the left button and drag the pointer between two output positions.
+    fn swipe(&mut self, from: (f64, f64), to: (f64, f64)) {
+        const STEPS: u32 = 20;
+
+        self.motion(from);
+        self.button(ButtonState::Pressed);
+
+        for step in 1..=STEPS {
+            let progress = step as f64 / STEPS as f64;
+            let x = from.0 + (to.0 - from.0) * progress;
+            let y = from.1 + (to.1 - from.1) * progress;
+            self.motion((x, y));
+        }
+
+        self.button(ButtonState::Released);
+    }
+
+    /// Move the pointer to an absolute output position.
+    fn motion(&mut self, position: (f64, f64)) {
+        let time = self.time();
+        let (width, height) = (OUTPUT_WIDTH as u32, OUTPUT_HEIGHT as u32);
+        self.pointer.motion_absolute(time, position.0 as u32, position.1 as u32, width, height);
+        self.frame();
+    }
+
+    /// Change the left button's state.
+    fn button(&mut self, state: ButtonState) {
+        let time = self.time();
+        self.pointer.button(time, BTN_LEFT, state);
+        self.frame();
+    }
+
+    /// Submit the pending events and give Epitaph time to process them.
+    fn frame(&mut self) {
+        self.pointer.frame();
+        self.queue.roundtrip(&mut VirtualPointerState).expect("send pointer events");
+        thread::sleep(Duration::from_millis(16));
+    }
+
+    /// Event timestamp in milliseconds.
+    fn time(&self) -> u32 {
+        self.start.elapsed().as_millis() as u32
+    }
+}
+
+impl Drop for VirtualPointer {
+    fn drop(&mut self) {
+        self.pointer.destroy();
+        let _ = self.queue.flush();
+    }
+}
+
+/// Wayland state of the virtual pointer connection.
+struct VirtualPointerState;
+
+impl Dispatch<WlRegistry, GlobalListContents> for VirtualPointerState {
+    fn event(
+        _: &mut Self,
+        _: &WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+delegate_noop!(VirtualPointerState: ignore ZwlrVirtualPointerManagerV1);
+delegate_noop!(VirtualPointerState: ignore ZwlrVirtualPointerV1);
+
+/// Logical width and scale factor of all panels.
+fn panels(status: &Value) -> Vec<(f64, f64)> {
+    let panels = status["panels"].as_array().map(Vec::as_slice).unwrap_or_default();
+    panels
+        .iter()
+        .filter_map(|panel| Some((panel["width"].as_f64()?, panel["scale_factor"].as_f64()?)))
+        .collect()
+}
+
+/// Check if a program is available in the `PATH`.
+fn installed(program: &str) -> bool {
+    Command::new(program)
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Restrict runtime directory permissions, as required by libwayland.
+fn set_permissions(path: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+
+    let permissions = fs::Permissions::from_mode(0o700);
+    fs::set_permissions(path, permissions).expect("set runtime directory permissions");
+}